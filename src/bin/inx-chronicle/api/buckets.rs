@@ -0,0 +1,174 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use chronicle::{
+    db::mongodb::collections::MilestoneCollection,
+    model::tangle::{MilestoneIndex, MilestoneIndexTimestamp, MilestoneTimestamp},
+};
+use serde::{Deserialize, Serialize};
+
+/// The length of a time bucket.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BucketInterval {
+    Hour,
+    Day,
+    Week,
+}
+
+impl BucketInterval {
+    /// The length of the interval in seconds.
+    pub fn seconds(self) -> u32 {
+        match self {
+            Self::Hour => 60 * 60,
+            Self::Day => 24 * 60 * 60,
+            Self::Week => 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// A time bucket together with the milestones that were issued within it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MilestoneBucket {
+    pub start_timestamp: MilestoneTimestamp,
+    /// The inclusive end of the bucket.
+    pub end_timestamp: MilestoneTimestamp,
+    /// The first and last milestone of the bucket, or `None` if no milestone was issued within it.
+    pub milestones: Option<(MilestoneIndex, MilestoneIndex)>,
+}
+
+/// Splits a time range into buckets of equal length and finds the milestones that belong to each of them. Bucketed
+/// analytics should use this, so that all of them handle the bucket boundaries the same way.
+#[derive(Copy, Clone, Debug)]
+pub struct MilestoneBucketer {
+    start_timestamp: MilestoneTimestamp,
+    end_timestamp: MilestoneTimestamp,
+    interval: BucketInterval,
+}
+
+impl MilestoneBucketer {
+    /// Creates a bucketer for the inclusive time range. The first bucket starts at `start_timestamp`, and the last one
+    /// is cut short at `end_timestamp` if the range is not a multiple of the interval.
+    pub fn new(
+        (start_timestamp, end_timestamp): (MilestoneTimestamp, MilestoneTimestamp),
+        interval: BucketInterval,
+    ) -> Self {
+        Self {
+            start_timestamp,
+            end_timestamp,
+            interval,
+        }
+    }
+
+    /// The number of buckets.
+    pub fn len(&self) -> usize {
+        if self.end_timestamp < self.start_timestamp {
+            0
+        } else {
+            ((self.end_timestamp.0 - self.start_timestamp.0) / self.interval.seconds()) as usize + 1
+        }
+    }
+
+    /// The inclusive start and end timestamp of each bucket.
+    pub fn bounds(&self) -> impl Iterator<Item = (MilestoneTimestamp, MilestoneTimestamp)> {
+        let Self {
+            start_timestamp,
+            end_timestamp,
+            interval,
+        } = *self;
+        (0..self.len() as u32).map(move |i| {
+            let start = start_timestamp.0 + i * interval.seconds();
+            let end = start.saturating_add(interval.seconds() - 1).min(end_timestamp.0);
+            (start.into(), end.into())
+        })
+    }
+
+    /// Finds the first and last milestone of each bucket.
+    pub async fn buckets(
+        &self,
+        collection: &MilestoneCollection,
+    ) -> Result<Vec<MilestoneBucket>, mongodb::error::Error> {
+        let mut buckets = Vec::with_capacity(self.len());
+        for (start_timestamp, end_timestamp) in self.bounds() {
+            let first = collection.find_first_milestone(start_timestamp).await?;
+            // Skip the second lookup if there is no milestone in the bucket anyway.
+            let last = match first {
+                Some(first) if first.milestone_timestamp <= end_timestamp => {
+                    collection.find_last_milestone(end_timestamp).await?
+                }
+                _ => None,
+            };
+            buckets.push(MilestoneBucket {
+                start_timestamp,
+                end_timestamp,
+                milestones: bucket_milestones((start_timestamp, end_timestamp), first, last),
+            });
+        }
+        Ok(buckets)
+    }
+}
+
+/// Given the first milestone at or after the start of a bucket and the last milestone at or before its end, returns the
+/// range of milestones within the bucket.
+fn bucket_milestones(
+    (start_timestamp, end_timestamp): (MilestoneTimestamp, MilestoneTimestamp),
+    first: Option<MilestoneIndexTimestamp>,
+    last: Option<MilestoneIndexTimestamp>,
+) -> Option<(MilestoneIndex, MilestoneIndex)> {
+    match (first, last) {
+        (Some(first), Some(last))
+            if first.milestone_timestamp <= end_timestamp
+                && last.milestone_timestamp >= start_timestamp
+                && first.milestone_index <= last.milestone_index =>
+        {
+            Some((first.milestone_index, last.milestone_index))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(milestone_index: u32, milestone_timestamp: u32) -> Option<MilestoneIndexTimestamp> {
+        Some(MilestoneIndexTimestamp {
+            milestone_index: milestone_index.into(),
+            milestone_timestamp: milestone_timestamp.into(),
+        })
+    }
+
+    #[test]
+    fn bounds_cover_range() {
+        let hour = BucketInterval::Hour.seconds();
+        let bounds = |start: u32, end: u32| (MilestoneTimestamp(start), MilestoneTimestamp(end));
+
+        let bucketer = MilestoneBucketer::new(bounds(1000, 1000 + 2 * hour - 1), BucketInterval::Hour);
+        assert_eq!(bucketer.len(), 2);
+        assert_eq!(
+            bucketer.bounds().collect::<Vec<_>>(),
+            vec![bounds(1000, 1000 + hour - 1), bounds(1000 + hour, 1000 + 2 * hour - 1)]
+        );
+
+        // The trailing bucket is cut short at the end of the range.
+        let bucketer = MilestoneBucketer::new(bounds(1000, 1000 + hour + 10), BucketInterval::Hour);
+        assert_eq!(bucketer.bounds().last(), Some(bounds(1000 + hour, 1000 + hour + 10)));
+
+        // A single timestamp is a single bucket, and an inverted range has none.
+        assert_eq!(MilestoneBucketer::new(bounds(5, 5), BucketInterval::Day).len(), 1);
+        assert_eq!(MilestoneBucketer::new(bounds(5, 4), BucketInterval::Day).len(), 0);
+    }
+
+    #[test]
+    fn empty_buckets_have_no_milestones() {
+        let bounds = (MilestoneTimestamp(100), MilestoneTimestamp(199));
+        assert_eq!(
+            bucket_milestones(bounds, at(10, 100), at(12, 199)),
+            Some((MilestoneIndex(10), MilestoneIndex(12)))
+        );
+        // The next milestone was issued after the bucket.
+        assert_eq!(bucket_milestones(bounds, at(13, 250), at(12, 99)), None);
+        // There are no milestones after the start of the bucket.
+        assert_eq!(bucket_milestones(bounds, None, at(9, 50)), None);
+    }
+}
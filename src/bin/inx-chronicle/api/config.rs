@@ -1,23 +1,42 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use derive_more::From;
 use regex::RegexSet;
 use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
 use tower_http::cors::AllowOrigin;
 
-use super::{error::ConfigError, SecretKey};
+use super::{error::ConfigError, metrics::CollectionStatsCache, stale::StaleResponses, SecretKey};
 
 pub const DEFAULT_ENABLED: bool = true;
 pub const DEFAULT_PORT: u16 = 8042;
 pub const DEFAULT_ALLOW_ORIGINS: &str = "0.0.0.0";
 pub const DEFAULT_PUBLIC_ROUTES: &str = "api/core/v2/*";
 pub const DEFAULT_MAX_PAGE_SIZE: usize = 1000;
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
 pub const DEFAULT_JWT_PASSWORD: &str = "password";
 pub const DEFAULT_JWT_SALT: &str = "saltines";
 pub const DEFAULT_JWT_EXPIRATION: &str = "72h";
+pub const DEFAULT_CONFIRMATION_DEPTH: u32 = 0;
+pub const DEFAULT_WARMUP_MILESTONES: usize = 0;
+pub const DEFAULT_MAX_CONCURRENT_AGGREGATIONS: usize = 8;
+pub const DEFAULT_MAX_QUEUED_AGGREGATIONS: usize = 32;
+pub const DEFAULT_AGGREGATION_TIMEOUT: &str = "5s";
+pub const DEFAULT_MAX_TIME_RANGE: &str = "0s";
+pub const DEFAULT_MAX_TOTAL_COUNT: u64 = 10_000;
+pub const DEFAULT_SERVE_STALE_ON_OUTAGE: bool = false;
+// Similar to Hornet, we enforce that the latest known milestone is newer than 5 minutes. This should give Chronicle
+// sufficient time to catch up with the node that it is connected too. The current milestone interval is 5 seconds.
+pub const DEFAULT_HEALTH_GRACE_PERIOD: &str = "5m";
+pub const DEFAULT_MAX_MILESTONE_LAG: u32 = 0;
+pub const DEFAULT_BASE_PATH: &str = "";
+pub const DEFAULT_REQUEST_TIMEOUT: &str = "30s";
+pub const DEFAULT_MAX_REQUEST_BODY_SIZE: usize = 1024 * 1024;
+pub const DEFAULT_SHUTDOWN_GRACE_PERIOD: &str = "10s";
+pub const DEFAULT_METRICS_CACHE_INTERVAL: &str = "1m";
 
 /// API configuration
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -28,11 +47,33 @@ pub struct ApiConfig {
     pub allow_origins: SingleOrMultiple<String>,
     pub public_routes: Vec<String>,
     pub max_page_size: usize,
+    pub max_batch_size: usize,
     pub jwt_password: String,
     pub jwt_salt: String,
     pub jwt_identity_file: Option<String>,
     #[serde(with = "humantime_serde")]
     pub jwt_expiration: Duration,
+    pub confirmation_depth: u32,
+    pub warmup_milestones: usize,
+    pub max_concurrent_aggregations: usize,
+    pub max_queued_aggregations: usize,
+    #[serde(with = "humantime_serde")]
+    pub aggregation_timeout: Duration,
+    #[serde(with = "humantime_serde")]
+    pub max_time_range: Duration,
+    pub max_total_count: u64,
+    pub serve_stale_on_outage: bool,
+    #[serde(with = "humantime_serde")]
+    pub health_grace_period: Duration,
+    pub max_milestone_lag: u32,
+    pub base_path: String,
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+    pub max_request_body_size: usize,
+    #[serde(with = "humantime_serde")]
+    pub shutdown_grace_period: Duration,
+    #[serde(with = "humantime_serde")]
+    pub metrics_cache_interval: Duration,
 }
 
 impl Default for ApiConfig {
@@ -43,10 +84,38 @@ impl Default for ApiConfig {
             allow_origins: SingleOrMultiple::Single(DEFAULT_ALLOW_ORIGINS.to_string()),
             public_routes: vec![DEFAULT_PUBLIC_ROUTES.to_string()],
             max_page_size: DEFAULT_MAX_PAGE_SIZE,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
             jwt_identity_file: None,
             jwt_password: DEFAULT_JWT_PASSWORD.to_string(),
             jwt_salt: DEFAULT_JWT_SALT.to_string(),
             jwt_expiration: DEFAULT_JWT_EXPIRATION.parse::<humantime::Duration>().unwrap().into(),
+            confirmation_depth: DEFAULT_CONFIRMATION_DEPTH,
+            warmup_milestones: DEFAULT_WARMUP_MILESTONES,
+            max_concurrent_aggregations: DEFAULT_MAX_CONCURRENT_AGGREGATIONS,
+            max_queued_aggregations: DEFAULT_MAX_QUEUED_AGGREGATIONS,
+            aggregation_timeout: DEFAULT_AGGREGATION_TIMEOUT
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            max_time_range: DEFAULT_MAX_TIME_RANGE.parse::<humantime::Duration>().unwrap().into(),
+            max_total_count: DEFAULT_MAX_TOTAL_COUNT,
+            serve_stale_on_outage: DEFAULT_SERVE_STALE_ON_OUTAGE,
+            health_grace_period: DEFAULT_HEALTH_GRACE_PERIOD
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            max_milestone_lag: DEFAULT_MAX_MILESTONE_LAG,
+            base_path: DEFAULT_BASE_PATH.to_string(),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT.parse::<humantime::Duration>().unwrap().into(),
+            max_request_body_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+            shutdown_grace_period: DEFAULT_SHUTDOWN_GRACE_PERIOD
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            metrics_cache_interval: DEFAULT_METRICS_CACHE_INTERVAL
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
         }
     }
 }
@@ -57,11 +126,43 @@ pub struct ApiConfigData {
     pub allow_origins: AllowOrigin,
     pub public_routes: RegexSet,
     pub max_page_size: usize,
+    /// The maximum number of ids that can be looked up in a single request.
+    pub max_batch_size: usize,
     pub jwt_password_hash: Vec<u8>,
     pub jwt_password_salt: String,
     pub jwt_secret_key: SecretKey,
     pub jwt_expiration: Duration,
     pub jwt_argon_config: JwtArgonConfig,
+    pub confirmation_depth: u32,
+    pub warmup_milestones: usize,
+    /// Limits the number of expensive analytics aggregations that run at the same time.
+    pub aggregation_permits: Arc<Semaphore>,
+    /// Limits the number of analytics requests that run or wait for an aggregation permit, so that requests are
+    /// rejected right away once the queue is full.
+    pub aggregation_admissions: Arc<Semaphore>,
+    pub aggregation_timeout: Duration,
+    /// The longest time range that an analytics request may cover. Zero allows any range.
+    pub max_time_range: Duration,
+    /// The number of matches at which counting the total of a list response stops. `0` counts exactly.
+    pub max_total_count: u64,
+    /// The last successful analytics responses, served while the database is unreachable. Only set if enabled.
+    pub stale_responses: Option<StaleResponses>,
+    /// How old the newest milestone may become before Chronicle reports itself as unhealthy.
+    pub health_grace_period: Duration,
+    /// How many milestones the newest stored milestone may be behind the node before Chronicle reports itself as
+    /// unhealthy. Zero disables the check.
+    pub max_milestone_lag: u32,
+    /// The path prefix under which all routes are served, or an empty string to serve them at the root.
+    pub base_path: String,
+    /// How long a request may take, including reading its body, before it is cancelled.
+    pub request_timeout: Duration,
+    /// The maximum size of a request body in bytes.
+    pub max_request_body_size: usize,
+    /// How long the requests in flight are given to complete once the API shuts down.
+    pub shutdown_grace_period: Duration,
+    /// The collection sizes reported by the metrics route, which are only gathered again once they are older than the
+    /// configured interval.
+    pub collection_stats: CollectionStatsCache,
 }
 
 impl ApiConfigData {
@@ -78,6 +179,7 @@ impl TryFrom<ApiConfig> for ApiConfigData {
             allow_origins: AllowOrigin::try_from(config.allow_origins)?,
             public_routes: RegexSet::new(config.public_routes.iter().map(route_to_regex).collect::<Vec<_>>())?,
             max_page_size: config.max_page_size,
+            max_batch_size: config.max_batch_size,
             jwt_password_hash: argon2::hash_raw(
                 config.jwt_password.as_bytes(),
                 config.jwt_salt.as_bytes(),
@@ -98,10 +200,37 @@ impl TryFrom<ApiConfig> for ApiConfigData {
             },
             jwt_expiration: config.jwt_expiration,
             jwt_argon_config: JwtArgonConfig::default(),
+            confirmation_depth: config.confirmation_depth,
+            warmup_milestones: config.warmup_milestones,
+            aggregation_permits: Arc::new(Semaphore::new(config.max_concurrent_aggregations)),
+            aggregation_admissions: Arc::new(Semaphore::new(
+                config.max_concurrent_aggregations + config.max_queued_aggregations,
+            )),
+            aggregation_timeout: config.aggregation_timeout,
+            max_time_range: config.max_time_range,
+            max_total_count: config.max_total_count,
+            stale_responses: config.serve_stale_on_outage.then(StaleResponses::default),
+            health_grace_period: config.health_grace_period,
+            max_milestone_lag: config.max_milestone_lag,
+            base_path: validate_base_path(config.base_path)?,
+            request_timeout: config.request_timeout,
+            max_request_body_size: config.max_request_body_size,
+            shutdown_grace_period: config.shutdown_grace_period,
+            collection_stats: CollectionStatsCache::new(config.metrics_cache_interval),
         })
     }
 }
 
+/// Checks that a non-empty base path starts with a slash and does not end with one, so that it can be used to nest
+/// the routes.
+fn validate_base_path(base_path: String) -> Result<String, ConfigError> {
+    if base_path.is_empty() || (base_path.starts_with('/') && !base_path.ends_with('/')) {
+        Ok(base_path)
+    } else {
+        Err(ConfigError::InvalidBasePath(base_path))
+    }
+}
+
 fn route_to_regex(route: &impl AsRef<str>) -> String {
     // Escape the string to make sure a regex can be built from it.
     // Existing wildcards `*` get escaped to `\\*`.
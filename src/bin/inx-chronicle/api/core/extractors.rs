@@ -0,0 +1,359 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt::Display, str::FromStr};
+
+use async_trait::async_trait;
+use axum::{
+    body::HttpBody,
+    extract::{FromRequest, Json, Query},
+    BoxError, Extension,
+};
+use chronicle::model::{tangle::MilestoneIndex, utxo::OutputId};
+use serde::Deserialize;
+
+use crate::api::{
+    config::ApiConfigData,
+    error::RequestError,
+    extractors::{parse_output_id, parse_output_kind},
+    ApiError, DEFAULT_PAGE_SIZE,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputsByKindPagination {
+    pub kind: &'static str,
+    pub ledger_index: Option<MilestoneIndex>,
+    pub page_size: usize,
+    pub cursor: Option<OutputId>,
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct OutputsByKindPaginationQuery {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub at: Option<MilestoneIndex>,
+    pub page_size: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+/// A cursor for paging through outputs ordered by [`OutputId`].
+#[derive(Clone)]
+pub struct OutputIdCursor {
+    pub output_id: OutputId,
+    pub page_size: usize,
+}
+
+impl FromStr for OutputIdCursor {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split('.').collect();
+        Ok(match parts[..] {
+            [o, ps] => OutputIdCursor {
+                output_id: o.parse().map_err(RequestError::from)?,
+                page_size: ps.parse().map_err(RequestError::from)?,
+            },
+            _ => return Err(ApiError::from(RequestError::BadPagingState)),
+        })
+    }
+}
+
+impl Display for OutputIdCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.output_id.to_hex(), self.page_size)
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for OutputsByKindPagination {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<OutputsByKindPaginationQuery>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        let Extension(config) = Extension::<ApiConfigData>::from_request(req).await?;
+
+        let kind = parse_output_kind(query.kind.as_deref().unwrap_or_default())?;
+
+        let (page_size, cursor) = if let Some(cursor) = query.cursor {
+            let cursor: OutputIdCursor = cursor.parse()?;
+            (cursor.page_size, Some(cursor.output_id))
+        } else {
+            (query.page_size.unwrap_or(DEFAULT_PAGE_SIZE), None)
+        };
+
+        Ok(OutputsByKindPagination {
+            kind,
+            ledger_index: query.at,
+            page_size: page_size.min(config.max_page_size),
+            cursor,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenesisOutputsPagination {
+    pub page_size: usize,
+    pub cursor: Option<OutputId>,
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct GenesisOutputsPaginationQuery {
+    pub page_size: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for GenesisOutputsPagination {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<GenesisOutputsPaginationQuery>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        let Extension(config) = Extension::<ApiConfigData>::from_request(req).await?;
+
+        let (page_size, cursor) = if let Some(cursor) = query.cursor {
+            let cursor: OutputIdCursor = cursor.parse()?;
+            (cursor.page_size, Some(cursor.output_id))
+        } else {
+            (query.page_size.unwrap_or(DEFAULT_PAGE_SIZE), None)
+        };
+
+        Ok(GenesisOutputsPagination {
+            page_size: page_size.min(config.max_page_size),
+            cursor,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputsByAddressPagination {
+    pub include_spent: bool,
+    pub ledger_index: Option<MilestoneIndex>,
+    pub page_size: usize,
+    pub cursor: Option<OutputId>,
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct OutputsByAddressPaginationQuery {
+    pub unspent_only: bool,
+    pub at: Option<MilestoneIndex>,
+    pub page_size: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for OutputsByAddressPagination {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<OutputsByAddressPaginationQuery>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        let Extension(config) = Extension::<ApiConfigData>::from_request(req).await?;
+
+        let (page_size, cursor) = if let Some(cursor) = query.cursor {
+            let cursor: OutputIdCursor = cursor.parse()?;
+            (cursor.page_size, Some(cursor.output_id))
+        } else {
+            (query.page_size.unwrap_or(DEFAULT_PAGE_SIZE), None)
+        };
+
+        Ok(OutputsByAddressPagination {
+            include_spent: !query.unspent_only,
+            ledger_index: query.at,
+            page_size: page_size.min(config.max_page_size),
+            cursor,
+        })
+    }
+}
+
+/// A batch of output ids given as a JSON array in the request body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputIdsBatch(pub Vec<OutputId>);
+
+#[async_trait]
+impl<B> FromRequest<B> for OutputIdsBatch
+where
+    B: HttpBody + Send,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Json(ids) = Json::<Vec<String>>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        let Extension(config) = Extension::<ApiConfigData>::from_request(req).await?;
+
+        if ids.len() > config.max_batch_size {
+            return Err(ApiError::from(RequestError::BatchTooLarge {
+                found: ids.len(),
+                max: config.max_batch_size,
+            }));
+        }
+
+        // Report all malformed ids at once rather than only the first one.
+        let mut output_ids = Vec::with_capacity(ids.len());
+        let mut invalid = Vec::new();
+        for id in ids {
+            match parse_output_id(&id) {
+                Ok(output_id) => output_ids.push(output_id),
+                Err(_) => invalid.push(id),
+            }
+        }
+        if !invalid.is_empty() {
+            return Err(ApiError::from(RequestError::InvalidOutputIds(invalid)));
+        }
+
+        Ok(OutputIdsBatch(output_ids))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{body::Body, extract::RequestParts, http::Request};
+    use chronicle::model::utxo::NftOutput;
+
+    use super::*;
+    use crate::api::ApiConfig;
+
+    #[test]
+    fn output_id_cursor_from_to_str() {
+        let output_id_str = "0xfa0de75d225cca2799395e5fc340702fc7eac821d2bdd79911126f131ae097a20100";
+        let page_size_str = "1337";
+
+        let cursor = format!("{output_id_str}.{page_size_str}",);
+        let parsed: OutputIdCursor = cursor.parse().unwrap();
+        assert_eq!(parsed.to_string(), cursor);
+    }
+
+    #[tokio::test]
+    async fn outputs_by_address_filters_spent() {
+        let request = |uri: &str| {
+            RequestParts::new(
+                Request::builder()
+                    .method("GET")
+                    .uri(uri)
+                    .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                    .body(())
+                    .unwrap(),
+            )
+        };
+
+        // Spent outputs are included unless they are excluded explicitly.
+        assert_eq!(
+            OutputsByAddressPagination::from_request(&mut request("/outputs/by-address/addr"))
+                .await
+                .unwrap(),
+            OutputsByAddressPagination {
+                include_spent: true,
+                ledger_index: None,
+                page_size: DEFAULT_PAGE_SIZE,
+                cursor: None,
+            }
+        );
+
+        let output_id = "0xfa0de75d225cca2799395e5fc340702fc7eac821d2bdd79911126f131ae097a20100";
+        assert_eq!(
+            OutputsByAddressPagination::from_request(&mut request(&format!(
+                "/outputs/by-address/addr?unspentOnly=true&at=10&cursor={output_id}.20"
+            )))
+            .await
+            .unwrap(),
+            OutputsByAddressPagination {
+                include_spent: false,
+                ledger_index: Some(10.into()),
+                page_size: 20,
+                cursor: Some(output_id.parse().unwrap()),
+            }
+        );
+
+        assert!(
+            OutputsByAddressPagination::from_request(&mut request("/outputs/by-address/addr?unspentOnly=maybe"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn outputs_by_kind_validates_type() {
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/outputs?type=nft&at=10")
+                .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                .body(())
+                .unwrap(),
+        );
+        assert_eq!(
+            OutputsByKindPagination::from_request(&mut req).await.unwrap(),
+            OutputsByKindPagination {
+                kind: NftOutput::KIND,
+                ledger_index: Some(10.into()),
+                page_size: DEFAULT_PAGE_SIZE,
+                cursor: None,
+            }
+        );
+
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/outputs?type=treasury")
+                .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                .body(())
+                .unwrap(),
+        );
+        assert!(OutputsByKindPagination::from_request(&mut req).await.is_err());
+    }
+
+    fn output_ids_request(body: &str) -> RequestParts<Body> {
+        RequestParts::new(
+            Request::builder()
+                .method("POST")
+                .uri("/outputs/by-ids")
+                .header("content-type", "application/json")
+                .extension(
+                    ApiConfigData::try_from(ApiConfig {
+                        max_batch_size: 3,
+                        ..Default::default()
+                    })
+                    .unwrap(),
+                )
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn output_ids_batch_is_validated() {
+        let known = "0xfa0de75d225cca2799395e5fc340702fc7eac821d2bdd79911126f131ae097a20100";
+        let unknown = "0xfa0de75d225cca2799395e5fc340702fc7eac821d2bdd79911126f131ae097a20200";
+
+        let mut req = output_ids_request(&format!(r#"["{known}", "{unknown}"]"#));
+        assert_eq!(
+            OutputIdsBatch::from_request(&mut req).await.unwrap(),
+            OutputIdsBatch(vec![known.parse().unwrap(), unknown.parse().unwrap()])
+        );
+
+        let mut req = output_ids_request(&format!(r#"["{known}", "0x1234", "foo"]"#));
+        let err = OutputIdsBatch::from_request(&mut req).await.unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<RequestError>(),
+            Some(RequestError::InvalidOutputIds(ids)) if ids == &["0x1234", "foo"]
+        ));
+
+        let mut req = output_ids_request(&format!(r#"["{known}", "{known}", "{known}", "{known}"]"#));
+        let err = OutputIdsBatch::from_request(&mut req).await.unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<RequestError>(),
+            Some(RequestError::BatchTooLarge { found: 4, max: 3 })
+        ));
+    }
+}
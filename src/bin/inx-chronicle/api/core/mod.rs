@@ -1,7 +1,9 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+mod extractors;
 mod responses;
 mod routes;
 
+pub(crate) use self::routes::create_block_metadata_response;
 pub use self::routes::routes;
@@ -1,7 +1,13 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use iota_types::{api::core::response as iota, block::protocol::dto::ProtocolParametersDto};
+use std::collections::BTreeMap;
+
+use chronicle::model::tangle::MilestoneIndex;
+use iota_types::{
+    api::core::response as iota,
+    block::{output::dto::OutputMetadataDto, protocol::dto::ProtocolParametersDto},
+};
 use serde::{Deserialize, Serialize};
 
 use crate::api::responses::impl_success_response;
@@ -19,6 +25,126 @@ pub struct InfoResponse {
 
 impl_success_response!(InfoResponse);
 
+/// Response of `GET /api/core/v2/protocol-parameters`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolParametersResponse {
+    /// The milestone from which on the parameters are active.
+    pub milestone_index: MilestoneIndex,
+    pub parameters: ProtocolParametersDto,
+}
+
+impl_success_response!(ProtocolParametersResponse);
+
+/// Response of `GET /api/core/v2/transactions/<transaction_id>/milestone`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionMilestoneResponse {
+    pub milestone_index: u32,
+    pub milestone_timestamp: u32,
+    pub status: FinalityStatus,
+}
+
+/// Whether a milestone is buried deep enough below the latest milestone to be treated as final.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FinalityStatus {
+    Confirmed,
+    Pending,
+}
+
+impl FinalityStatus {
+    /// Determines the status of `booked` given the `latest` stored milestone and the configured confirmation
+    /// `depth`. A depth of `0` treats every stored milestone as confirmed.
+    pub fn new(booked: MilestoneIndex, latest: MilestoneIndex, depth: u32) -> Self {
+        if booked.0.saturating_add(depth) <= latest.0 {
+            Self::Confirmed
+        } else {
+            Self::Pending
+        }
+    }
+}
+
+impl_success_response!(TransactionMilestoneResponse);
+
+/// Response of `GET /api/core/v2/outputs/<output_id>/history`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputHistoryResponse {
+    pub output_id: String,
+    pub booked: OutputBookedDto,
+    /// `null` if the output is unspent.
+    pub spent: Option<OutputSpentDto>,
+    pub ledger_index: MilestoneIndex,
+}
+
+impl_success_response!(OutputHistoryResponse);
+
+/// The block and milestone that created an output.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputBookedDto {
+    pub block_id: String,
+    pub milestone_index: u32,
+    pub milestone_timestamp: u32,
+}
+
+/// The transaction and milestone that spent an output.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputSpentDto {
+    pub transaction_id: String,
+    pub milestone_index: u32,
+    pub milestone_timestamp: u32,
+}
+
+/// Response of `GET /api/core/v2/outputs?type=<kind>`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputsByKindResponse {
+    pub ledger_index: MilestoneIndex,
+    pub items: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl_success_response!(OutputsByKindResponse);
+
+/// Response of `GET /api/core/v2/outputs/by-address/<address>`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputsByAddressResponse {
+    pub address: String,
+    pub ledger_index: MilestoneIndex,
+    pub items: Vec<iota::OutputWithMetadataResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl_success_response!(OutputsByAddressResponse);
+
+/// Response of `GET /api/core/v2/genesis/outputs`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisOutputsResponse {
+    pub items: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl_success_response!(GenesisOutputsResponse);
+
+/// Response of `POST /api/core/v2/outputs/by-ids`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputsByIdsResponse {
+    pub ledger_index: MilestoneIndex,
+    /// The metadata of every requested output by its id, or `null` if the output is unknown.
+    pub items: BTreeMap<String, Option<OutputMetadataDto>>,
+}
+
+impl_success_response!(OutputsByIdsResponse);
+
 /// A wrapper struct that allows us to implement [`IntoResponse`](axum::response::IntoResponse) for the foreign
 /// responses from [`iota_types`](iota_types::api::core::response).
 #[derive(Clone, Debug, Serialize, derive_more::From)]
@@ -47,3 +173,18 @@ impl<T: Serialize> axum::response::IntoResponse for IotaRawResponse<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::FinalityStatus;
+
+    #[test]
+    fn finality_respects_confirmation_depth() {
+        assert_eq!(FinalityStatus::new(99.into(), 100.into(), 3), FinalityStatus::Pending);
+        assert_eq!(FinalityStatus::new(97.into(), 100.into(), 3), FinalityStatus::Confirmed);
+        assert_eq!(
+            FinalityStatus::new(100.into(), 100.into(), 0),
+            FinalityStatus::Confirmed
+        );
+    }
+}
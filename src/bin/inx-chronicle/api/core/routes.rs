@@ -1,20 +1,20 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::str::FromStr;
+use std::{collections::BTreeMap, str::FromStr};
 
 use axum::{
     extract::{Extension, Path},
     handler::Handler,
     http::header::HeaderMap,
-    routing::get,
+    routing::{get, post},
 };
 use chronicle::{
     db::{
         mongodb::collections::{
-            BlockCollection, ConfigurationUpdateCollection, MilestoneCollection, OutputCollection,
-            OutputMetadataResult, OutputWithMetadataResult, ProtocolUpdateCollection, TreasuryCollection,
-            UtxoChangesResult,
+            ApplicationStateCollection, BlockCollection, ConfigurationUpdateCollection, MilestoneCollection,
+            MilestonePayloadPart, OutputCollection, OutputMetadataResult, OutputPart, OutputWithMetadataResult,
+            ProtocolUpdateCollection, ProtocolUpdateDocument, TreasuryCollection, UtxoChangesResult,
         },
         MongoDb,
     },
@@ -22,8 +22,8 @@ use chronicle::{
         metadata::BlockMetadata,
         payload::{MilestoneId, TransactionId},
         tangle::MilestoneIndex,
-        utxo::OutputId,
-        BlockId, TryFromWithContext,
+        utxo::{Address, OutputId},
+        BlockId, ProtocolParameters, TryFromWithContext,
     },
 };
 use futures::TryStreamExt;
@@ -37,7 +37,7 @@ use iota_types::{
         },
     },
     block::{
-        output::dto::{OutputMetadataDto, RentStructureDto},
+        output::dto::{OutputDto, OutputMetadataDto, RentStructureDto},
         payload::{dto::MilestonePayloadDto, milestone::option::dto::MilestoneOptionDto},
         protocol::dto::ProtocolParametersDto,
         BlockDto,
@@ -45,17 +45,79 @@ use iota_types::{
 };
 use packable::PackableExt;
 
-use super::responses::{InfoResponse, IotaRawResponse, IotaResponse};
+use super::{
+    extractors::{
+        GenesisOutputsPagination, OutputIdCursor, OutputIdsBatch, OutputsByAddressPagination, OutputsByKindPagination,
+    },
+    responses::{
+        FinalityStatus, GenesisOutputsResponse, InfoResponse, IotaRawResponse, IotaResponse, OutputBookedDto,
+        OutputHistoryResponse, OutputSpentDto, OutputsByAddressResponse, OutputsByIdsResponse, OutputsByKindResponse,
+        ProtocolParametersResponse, TransactionMilestoneResponse,
+    },
+};
 use crate::api::{
-    error::{ApiError, CorruptStateError, MissingError, RequestError},
+    config::ApiConfigData,
+    error::{ApiError, CorruptStateError, MissingError, PrunedError, RequestError},
+    extractors::{parse_output_id, FieldSelection, ResponseFormat},
+    responses::Negotiated,
     router::Router,
     routes::{is_healthy, not_implemented, BYTE_CONTENT_HEADER},
     ApiResult,
 };
 
+/// The fields of an output that can be selected via `?fields=`. The `metadata` field selects the output metadata.
+const OUTPUT_FIELDS: &[&str] = &[
+    "metadata",
+    "type",
+    "amount",
+    "nativeTokens",
+    "aliasId",
+    "nftId",
+    "stateIndex",
+    "stateMetadata",
+    "foundryCounter",
+    "serialNumber",
+    "tokenScheme",
+    "unlockConditions",
+    "features",
+    "immutableFeatures",
+];
+
+/// The output fields that the database leaves out if they are not selected.
+const OUTPUT_PARTS: &[(&str, OutputPart)] = &[
+    ("nativeTokens", OutputPart::NativeTokens),
+    ("stateMetadata", OutputPart::StateMetadata),
+    ("features", OutputPart::Features),
+    ("immutableFeatures", OutputPart::ImmutableFeatures),
+];
+
+/// The fields of a milestone payload that can be selected via `?fields=`.
+const MILESTONE_FIELDS: &[&str] = &[
+    "type",
+    "index",
+    "timestamp",
+    "protocolVersion",
+    "previousMilestoneId",
+    "parents",
+    "inclusionMerkleRoot",
+    "appliedMerkleRoot",
+    "options",
+    "metadata",
+    "signatures",
+];
+
+/// The milestone payload fields that the database leaves out if they are not selected.
+const MILESTONE_PARTS: &[(&str, MilestonePayloadPart)] = &[
+    ("parents", MilestonePayloadPart::Parents),
+    ("metadata", MilestonePayloadPart::Metadata),
+    ("options", MilestonePayloadPart::Options),
+    ("signatures", MilestonePayloadPart::Signatures),
+];
+
 pub fn routes() -> Router {
     Router::new()
         .route("/info", get(info))
+        .route("/protocol-parameters", get(protocol_parameters))
         .route("/tips", not_implemented.into_service())
         .nest(
             "/blocks",
@@ -67,8 +129,12 @@ pub fn routes() -> Router {
         .nest(
             "/outputs",
             Router::new()
+                .route("/", get(outputs_by_kind))
+                .route("/by-ids", post(outputs_by_ids))
+                .route("/by-address/:address", get(outputs_by_address))
                 .route("/:output_id", get(output))
-                .route("/:output_id/metadata", get(output_metadata)),
+                .route("/:output_id/metadata", get(output_metadata))
+                .route("/:output_id/history", get(output_history)),
         )
         .nest(
             "/receipts",
@@ -77,11 +143,13 @@ pub fn routes() -> Router {
                 .route("/:migrated_at", get(receipts_migrated_at)),
         )
         .route("/treasury", get(treasury))
+        .route("/genesis/outputs", get(genesis_outputs))
         .nest(
             "/transactions",
             Router::new()
                 .route("/:transaction_id/included-block", get(included_block))
-                .route("/:transaction_id/included-block/metadata", get(included_block_metadata)),
+                .route("/:transaction_id/included-block/metadata", get(included_block_metadata))
+                .route("/:transaction_id/milestone", get(transaction_milestone)),
         )
         .nest(
             "/milestones",
@@ -101,7 +169,10 @@ pub fn routes() -> Router {
         .route("/control/snapshot/create", not_implemented.into_service())
 }
 
-pub async fn info(database: Extension<MongoDb>) -> ApiResult<InfoResponse> {
+pub async fn info(
+    database: Extension<MongoDb>,
+    Extension(config): Extension<ApiConfigData>,
+) -> ApiResult<InfoResponse> {
     let protocol = database
         .collection::<ProtocolUpdateCollection>()
         .get_latest_protocol_parameters()
@@ -109,10 +180,12 @@ pub async fn info(database: Extension<MongoDb>) -> ApiResult<InfoResponse> {
         .ok_or(CorruptStateError::ProtocolParams)?
         .parameters;
 
-    let is_healthy = is_healthy(&database).await.unwrap_or_else(|ApiError { error, .. }| {
-        tracing::error!("An error occured during health check: {error}");
-        false
-    });
+    let is_healthy = is_healthy(&database, config.health_grace_period, config.max_milestone_lag)
+        .await
+        .unwrap_or_else(|ApiError { error, .. }| {
+            tracing::error!("An error occured during health check: {error}");
+            false
+        });
 
     let newest_milestone = database
         .collection::<MilestoneCollection>()
@@ -164,19 +237,7 @@ pub async fn info(database: Extension<MongoDb>) -> ApiResult<InfoResponse> {
             confirmed_milestone,
             pruning_index: oldest_milestone.milestone_index.0 - 1,
         },
-        protocol: ProtocolParametersDto {
-            protocol_version: protocol.version,
-            network_name: protocol.network_name,
-            below_max_depth: protocol.below_max_depth,
-            bech32_hrp: protocol.bech32_hrp,
-            min_pow_score: protocol.min_pow_score,
-            rent_structure: RentStructureDto {
-                v_byte_cost: protocol.rent_structure.v_byte_cost,
-                v_byte_factor_data: protocol.rent_structure.v_byte_factor_data,
-                v_byte_factor_key: protocol.rent_structure.v_byte_factor_key,
-            },
-            token_supply: protocol.token_supply.to_string(),
-        },
+        protocol: protocol_parameters_dto(protocol),
         base_token: BaseTokenResponse {
             name: base_token.name,
             ticker_symbol: base_token.ticker_symbol,
@@ -188,6 +249,39 @@ pub async fn info(database: Extension<MongoDb>) -> ApiResult<InfoResponse> {
     })
 }
 
+/// Returns the latest protocol parameters, together with the milestone from which on they are active.
+async fn protocol_parameters(database: Extension<MongoDb>) -> ApiResult<ProtocolParametersResponse> {
+    let update = database
+        .collection::<ProtocolUpdateCollection>()
+        .get_latest_protocol_parameters()
+        .await?
+        .ok_or(MissingError::NoResults)?;
+    Ok(protocol_parameters_response(update))
+}
+
+fn protocol_parameters_response(update: ProtocolUpdateDocument) -> ProtocolParametersResponse {
+    ProtocolParametersResponse {
+        milestone_index: update.tangle_index,
+        parameters: protocol_parameters_dto(update.parameters),
+    }
+}
+
+fn protocol_parameters_dto(protocol: ProtocolParameters) -> ProtocolParametersDto {
+    ProtocolParametersDto {
+        protocol_version: protocol.version,
+        network_name: protocol.network_name,
+        below_max_depth: protocol.below_max_depth,
+        bech32_hrp: protocol.bech32_hrp,
+        min_pow_score: protocol.min_pow_score,
+        rent_structure: RentStructureDto {
+            v_byte_cost: protocol.rent_structure.v_byte_cost,
+            v_byte_factor_data: protocol.rent_structure.v_byte_factor_data,
+            v_byte_factor_key: protocol.rent_structure.v_byte_factor_key,
+        },
+        token_supply: protocol.token_supply.to_string(),
+    }
+}
+
 async fn block(
     database: Extension<MongoDb>,
     Path(block_id): Path<String>,
@@ -197,24 +291,29 @@ async fn block(
 
     if matches!(headers.get(axum::http::header::ACCEPT), Some(header) if header == BYTE_CONTENT_HEADER) {
         return Ok(IotaRawResponse::Raw(
-            database
+            match database
                 .collection::<BlockCollection>()
                 .get_block_raw(&block_id)
                 .await?
-                .ok_or(MissingError::NoResults)?,
+            {
+                Some(raw) => raw,
+                None => return Err(missing_block(&database, &block_id).await),
+            },
         ));
     }
 
-    let block = database
-        .collection::<BlockCollection>()
-        .get_block(&block_id)
-        .await?
-        .ok_or(MissingError::NoResults)?;
+    let block = match database.collection::<BlockCollection>().get_block(&block_id).await? {
+        Some(block) => block,
+        None => return Err(missing_block(&database, &block_id).await),
+    };
 
     Ok(IotaRawResponse::Json(block.into()))
 }
 
-fn create_block_metadata_response(block_id: BlockId, metadata: BlockMetadata) -> iota::BlockMetadataResponse {
+pub(crate) fn create_block_metadata_response(
+    block_id: BlockId,
+    metadata: BlockMetadata,
+) -> iota::BlockMetadataResponse {
     iota::BlockMetadataResponse {
         block_id: block_id.to_hex(),
         parents: metadata.parents.iter().map(BlockId::to_hex).collect(),
@@ -234,11 +333,14 @@ async fn block_metadata(
     Path(block_id_str): Path<String>,
 ) -> ApiResult<IotaResponse<BlockMetadataResponse>> {
     let block_id = BlockId::from_str(&block_id_str).map_err(RequestError::from)?;
-    let metadata = database
+    let metadata = match database
         .collection::<BlockCollection>()
         .get_block_metadata(&block_id)
         .await?
-        .ok_or(MissingError::NoResults)?;
+    {
+        Some(metadata) => metadata,
+        None => return Err(missing_block(&database, &block_id).await),
+    };
 
     Ok(create_block_metadata_response(block_id, metadata).into())
 }
@@ -270,22 +372,30 @@ fn create_output_metadata_response(metadata: OutputMetadataResult, ledger_index:
 async fn output(
     database: Extension<MongoDb>,
     Path(output_id): Path<String>,
+    fields: FieldSelection,
     headers: HeaderMap,
-) -> ApiResult<IotaRawResponse<OutputWithMetadataResponse>> {
+) -> ApiResult<IotaRawResponse<serde_json::Value>> {
+    fields.validate(OUTPUT_FIELDS)?;
     let ledger_index = database
         .collection::<MilestoneCollection>()
         .get_ledger_index()
         .await?
         .ok_or(MissingError::NoResults)?;
-    let output_id = OutputId::from_str(&output_id).map_err(RequestError::from)?;
+    let output_id = parse_output_id(&output_id)?;
+    let raw = matches!(headers.get(axum::http::header::ACCEPT), Some(header) if header == BYTE_CONTENT_HEADER);
+    // The raw output is always packed in full.
+    let omitted = if raw { Vec::new() } else { fields.omitted(OUTPUT_PARTS) };
 
-    let OutputWithMetadataResult { output, metadata } = database
+    let OutputWithMetadataResult { output, metadata } = match database
         .collection::<OutputCollection>()
-        .get_output_with_metadata(&output_id, ledger_index)
+        .get_partial_output_with_metadata(&output_id, ledger_index, &omitted)
         .await?
-        .ok_or(MissingError::NoResults)?;
+    {
+        Some(res) => res,
+        None => return Err(missing_transaction(&database, &output_id.transaction_id).await),
+    };
 
-    if matches!(headers.get(axum::http::header::ACCEPT), Some(header) if header == BYTE_CONTENT_HEADER) {
+    if raw {
         let ctx = database
             .collection::<ProtocolUpdateCollection>()
             .get_protocol_parameters_for_ledger_index(metadata.booked.milestone_index)
@@ -298,29 +408,248 @@ async fn output(
 
     let metadata = create_output_metadata_response(metadata, ledger_index);
 
-    Ok(IotaRawResponse::Json(OutputWithMetadataResponse {
-        metadata,
-        output: output.into(),
-    }))
+    if fields.is_empty() {
+        return Ok(IotaRawResponse::Json(serde_json::to_value(
+            OutputWithMetadataResponse {
+                metadata,
+                output: output.into(),
+            },
+        )?));
+    }
+
+    // Only keep the selected fields of the output, and the metadata only if it was requested explicitly.
+    let mut response = serde_json::json!({ "output": fields.project(serde_json::to_value(OutputDto::from(output))?) });
+    if fields.contains("metadata") {
+        response["metadata"] = serde_json::to_value(metadata)?;
+    }
+    Ok(IotaRawResponse::Json(response))
+}
+
+async fn outputs_by_kind(
+    database: Extension<MongoDb>,
+    OutputsByKindPagination {
+        kind,
+        ledger_index,
+        page_size,
+        cursor,
+    }: OutputsByKindPagination,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<OutputsByKindResponse>> {
+    let ledger_index = match ledger_index {
+        Some(ledger_index) => ledger_index,
+        None => database
+            .collection::<MilestoneCollection>()
+            .get_ledger_index()
+            .await?
+            .ok_or(MissingError::NoResults)?,
+    };
+
+    let mut output_ids = database
+        .collection::<OutputCollection>()
+        // Get one extra record so that we can create the cursor.
+        .get_unspent_output_ids_by_kind(kind, ledger_index, page_size + 1, cursor)
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    // If any record is left, use it to make the cursor
+    let cursor = (output_ids.len() > page_size).then(|| {
+        OutputIdCursor {
+            output_id: output_ids.remove(page_size),
+            page_size,
+        }
+        .to_string()
+    });
+
+    Ok(Negotiated(
+        format,
+        OutputsByKindResponse {
+            ledger_index,
+            items: output_ids.iter().map(OutputId::to_hex).collect(),
+            cursor,
+        },
+    ))
+}
+
+async fn outputs_by_address(
+    database: Extension<MongoDb>,
+    Path(address): Path<String>,
+    OutputsByAddressPagination {
+        include_spent,
+        ledger_index,
+        page_size,
+        cursor,
+    }: OutputsByAddressPagination,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<OutputsByAddressResponse>> {
+    let address_dto = Address::from_str(&address).map_err(RequestError::from)?;
+    let ledger_index = match ledger_index {
+        Some(ledger_index) => ledger_index,
+        None => database
+            .collection::<MilestoneCollection>()
+            .get_ledger_index()
+            .await?
+            .ok_or(MissingError::NoResults)?,
+    };
+
+    let mut outputs = database
+        .collection::<OutputCollection>()
+        // Get one extra record so that we can create the cursor.
+        .get_outputs_for_address(&address_dto, include_spent, ledger_index, page_size + 1, cursor)
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    // If any record is left, use it to make the cursor
+    let cursor = (outputs.len() > page_size).then(|| {
+        OutputIdCursor {
+            output_id: outputs.remove(page_size).metadata.output_id,
+            page_size,
+        }
+        .to_string()
+    });
+
+    let items = outputs
+        .into_iter()
+        .map(
+            |OutputWithMetadataResult { output, metadata }| OutputWithMetadataResponse {
+                metadata: create_output_metadata_response(metadata, ledger_index),
+                output: output.into(),
+            },
+        )
+        .collect();
+
+    Ok(Negotiated(
+        format,
+        OutputsByAddressResponse {
+            address,
+            ledger_index,
+            items,
+            cursor,
+        },
+    ))
+}
+
+async fn genesis_outputs(
+    database: Extension<MongoDb>,
+    GenesisOutputsPagination { page_size, cursor }: GenesisOutputsPagination,
+) -> ApiResult<GenesisOutputsResponse> {
+    let mut output_ids = database
+        .collection::<OutputCollection>()
+        // Get one extra record so that we can create the cursor.
+        .get_genesis_output_ids(page_size + 1, cursor)
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    // If any record is left, use it to make the cursor
+    let cursor = (output_ids.len() > page_size).then(|| {
+        OutputIdCursor {
+            output_id: output_ids.remove(page_size),
+            page_size,
+        }
+        .to_string()
+    });
+
+    Ok(GenesisOutputsResponse {
+        items: output_ids.iter().map(OutputId::to_hex).collect(),
+        cursor,
+    })
 }
 
 async fn output_metadata(
     database: Extension<MongoDb>,
     Path(output_id): Path<String>,
-) -> ApiResult<IotaResponse<OutputMetadataDto>> {
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<OutputMetadataDto>> {
     let ledger_index = database
         .collection::<MilestoneCollection>()
         .get_ledger_index()
         .await?
         .ok_or(MissingError::NoResults)?;
-    let output_id = OutputId::from_str(&output_id).map_err(RequestError::from)?;
-    let metadata = database
+    let output_id = parse_output_id(&output_id)?;
+    let metadata = match database
         .collection::<OutputCollection>()
         .get_output_metadata(&output_id, ledger_index)
         .await?
+    {
+        Some(metadata) => metadata,
+        None => return Err(missing_transaction(&database, &output_id.transaction_id).await),
+    };
+
+    Ok(Negotiated(
+        format,
+        create_output_metadata_response(metadata, ledger_index),
+    ))
+}
+
+async fn output_history(
+    database: Extension<MongoDb>,
+    Path(output_id): Path<String>,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<OutputHistoryResponse>> {
+    let ledger_index = database
+        .collection::<MilestoneCollection>()
+        .get_ledger_index()
+        .await?
         .ok_or(MissingError::NoResults)?;
+    let output_id = parse_output_id(&output_id)?;
+    let history = match database
+        .collection::<OutputCollection>()
+        .get_output_metadata(&output_id, ledger_index)
+        .await?
+    {
+        Some(history) => history,
+        None => return Err(missing_transaction(&database, &output_id.transaction_id).await),
+    };
 
-    Ok(create_output_metadata_response(metadata, ledger_index).into())
+    Ok(Negotiated(
+        format,
+        OutputHistoryResponse {
+            output_id: history.output_id.to_hex(),
+            booked: OutputBookedDto {
+                block_id: history.block_id.to_hex(),
+                milestone_index: history.booked.milestone_index.0,
+                milestone_timestamp: history.booked.milestone_timestamp.0,
+            },
+            spent: history.spent_metadata.map(|spent_metadata| OutputSpentDto {
+                transaction_id: spent_metadata.transaction_id.to_hex(),
+                milestone_index: spent_metadata.spent.milestone_index.0,
+                milestone_timestamp: spent_metadata.spent.milestone_timestamp.0,
+            }),
+            ledger_index,
+        },
+    ))
+}
+
+async fn outputs_by_ids(
+    database: Extension<MongoDb>,
+    format: ResponseFormat,
+    OutputIdsBatch(output_ids): OutputIdsBatch,
+) -> ApiResult<Negotiated<OutputsByIdsResponse>> {
+    let ledger_index = database
+        .collection::<MilestoneCollection>()
+        .get_ledger_index()
+        .await?
+        .ok_or(MissingError::NoResults)?;
+
+    let mut items = output_ids
+        .iter()
+        .map(|output_id| (output_id.to_hex(), None))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut metadata = database
+        .collection::<OutputCollection>()
+        .get_output_metadata_by_ids(&output_ids, ledger_index)
+        .await?;
+    while let Some(metadata) = metadata.try_next().await? {
+        items.insert(
+            metadata.output_id.to_hex(),
+            Some(create_output_metadata_response(metadata, ledger_index)),
+        );
+    }
+
+    Ok(Negotiated(format, OutputsByIdsResponse { ledger_index, items }))
 }
 
 async fn included_block(
@@ -332,20 +661,25 @@ async fn included_block(
 
     if matches!(headers.get(axum::http::header::ACCEPT), Some(header) if header == BYTE_CONTENT_HEADER) {
         return Ok(IotaRawResponse::Raw(
-            database
+            match database
                 .collection::<BlockCollection>()
                 .get_block_raw_for_transaction(&transaction_id)
                 .await?
-                .ok_or(MissingError::NoResults)?,
+            {
+                Some(raw) => raw,
+                None => return Err(missing_transaction(&database, &transaction_id).await),
+            },
         ));
     }
 
-    let block = database
+    let block = match database
         .collection::<BlockCollection>()
         .get_block_for_transaction(&transaction_id)
         .await?
-        .ok_or(MissingError::NoResults)?
-        .block;
+    {
+        Some(res) => res.block,
+        None => return Err(missing_transaction(&database, &transaction_id).await),
+    };
 
     Ok(IotaRawResponse::Json(block.into()))
 }
@@ -356,17 +690,55 @@ async fn included_block_metadata(
 ) -> ApiResult<IotaResponse<BlockMetadataResponse>> {
     let transaction_id = TransactionId::from_str(&transaction_id).map_err(RequestError::from)?;
 
-    let res = database
+    let res = match database
         .collection::<BlockCollection>()
         .get_block_metadata_for_transaction(&transaction_id)
         .await?
-        .ok_or(MissingError::NoResults)?;
+    {
+        Some(res) => res,
+        None => return Err(missing_transaction(&database, &transaction_id).await),
+    };
     let block_id = res.block_id;
     let metadata = res.metadata;
 
     Ok(create_block_metadata_response(block_id, metadata).into())
 }
 
+async fn transaction_milestone(
+    database: Extension<MongoDb>,
+    Extension(config): Extension<ApiConfigData>,
+    Path(transaction_id): Path<String>,
+) -> ApiResult<TransactionMilestoneResponse> {
+    let transaction_id = TransactionId::from_str(&transaction_id).map_err(RequestError::from)?;
+
+    let booked = database
+        .collection::<OutputCollection>()
+        .get_transaction_booked_milestones(&transaction_id)
+        .await?;
+
+    if booked.len() > 1 {
+        tracing::warn!(
+            "Outputs of transaction {} were booked at {} different milestones.",
+            transaction_id.to_hex(),
+            booked.len()
+        );
+    }
+
+    let booked = booked.into_iter().next().ok_or(MissingError::NoResults)?;
+
+    let latest = database
+        .collection::<MilestoneCollection>()
+        .get_ledger_index()
+        .await?
+        .ok_or(CorruptStateError::Milestone)?;
+
+    Ok(TransactionMilestoneResponse {
+        milestone_index: booked.milestone_index.0,
+        milestone_timestamp: booked.milestone_timestamp.0,
+        status: FinalityStatus::new(booked.milestone_index, latest, config.confirmation_depth),
+    })
+}
+
 async fn receipts(database: Extension<MongoDb>) -> ApiResult<IotaResponse<ReceiptsResponse>> {
     let mut receipts_at = database.collection::<MilestoneCollection>().get_all_receipts().await?;
     let mut receipts = Vec::new();
@@ -423,16 +795,25 @@ async fn treasury(database: Extension<MongoDb>) -> ApiResult<IotaResponse<Treasu
 async fn milestone(
     database: Extension<MongoDb>,
     Path(milestone_id): Path<String>,
+    fields: FieldSelection,
     headers: HeaderMap,
-) -> ApiResult<IotaRawResponse<MilestonePayloadDto>> {
+) -> ApiResult<IotaRawResponse<serde_json::Value>> {
+    fields.validate(MILESTONE_FIELDS)?;
     let milestone_id = MilestoneId::from_str(&milestone_id).map_err(RequestError::from)?;
+    let raw = matches!(headers.get(axum::http::header::ACCEPT), Some(header) if header == BYTE_CONTENT_HEADER);
+    // The raw milestone payload is always packed in full.
+    let omitted = if raw {
+        Vec::new()
+    } else {
+        fields.omitted(MILESTONE_PARTS)
+    };
     let milestone_payload = database
         .collection::<MilestoneCollection>()
-        .get_milestone_payload_by_id(&milestone_id)
+        .get_partial_milestone_payload_by_id(&milestone_id, &omitted)
         .await?
         .ok_or(MissingError::NoResults)?;
 
-    if matches!(headers.get(axum::http::header::ACCEPT), Some(header) if header == BYTE_CONTENT_HEADER) {
+    if raw {
         let protocol_params = database
             .collection::<ProtocolUpdateCollection>()
             .get_protocol_parameters_for_ledger_index(milestone_payload.essence.index)
@@ -447,21 +828,35 @@ async fn milestone(
         return Ok(IotaRawResponse::Raw(milestone_payload.pack_to_vec()));
     }
 
-    Ok(IotaRawResponse::Json(milestone_payload.into()))
+    Ok(IotaRawResponse::Json(fields.project(serde_json::to_value(
+        MilestonePayloadDto::from(milestone_payload),
+    )?)))
 }
 
 async fn milestone_by_index(
     database: Extension<MongoDb>,
     Path(index): Path<MilestoneIndex>,
+    fields: FieldSelection,
     headers: HeaderMap,
-) -> ApiResult<IotaRawResponse<MilestonePayloadDto>> {
-    let milestone_payload = database
+) -> ApiResult<IotaRawResponse<serde_json::Value>> {
+    fields.validate(MILESTONE_FIELDS)?;
+    let raw = matches!(headers.get(axum::http::header::ACCEPT), Some(header) if header == BYTE_CONTENT_HEADER);
+    // The raw milestone payload is always packed in full.
+    let omitted = if raw {
+        Vec::new()
+    } else {
+        fields.omitted(MILESTONE_PARTS)
+    };
+    let milestone_payload = match database
         .collection::<MilestoneCollection>()
-        .get_milestone_payload(index)
+        .get_partial_milestone_payload(index, &omitted)
         .await?
-        .ok_or(MissingError::NoResults)?;
+    {
+        Some(milestone_payload) => milestone_payload,
+        None => return Err(missing_milestone(&database, index).await),
+    };
 
-    if matches!(headers.get(axum::http::header::ACCEPT), Some(header) if header == BYTE_CONTENT_HEADER) {
+    if raw {
         let protocol_params = database
             .collection::<ProtocolUpdateCollection>()
             .get_protocol_parameters_for_ledger_index(milestone_payload.essence.index)
@@ -476,7 +871,9 @@ async fn milestone_by_index(
         return Ok(IotaRawResponse::Raw(milestone_payload.pack_to_vec()));
     }
 
-    Ok(IotaRawResponse::Json(milestone_payload.into()))
+    Ok(IotaRawResponse::Json(fields.project(serde_json::to_value(
+        MilestonePayloadDto::from(milestone_payload),
+    )?)))
 }
 
 async fn utxo_changes(
@@ -498,9 +895,95 @@ async fn utxo_changes_by_index(
     database: Extension<MongoDb>,
     Path(milestone_index): Path<MilestoneIndex>,
 ) -> ApiResult<IotaResponse<UtxoChangesResponse>> {
+    // Outputs of pruned milestones are not stored, so the changes would wrongly appear to be empty.
+    let starting_index = get_starting_index(&database).await?;
+    if let Some(err) = pruned(milestone_index, starting_index) {
+        return Err(err.into());
+    }
     collect_utxo_changes(&database, milestone_index).await.map(Into::into)
 }
 
+async fn get_starting_index(database: &MongoDb) -> ApiResult<Option<MilestoneIndex>> {
+    Ok(database
+        .collection::<ApplicationStateCollection>()
+        .get_starting_index()
+        .await?
+        .map(|starting_index| starting_index.milestone_index))
+}
+
+/// Returns the error for a milestone that could not be found, which is [`PrunedError`] if the milestone is older than
+/// the first synchronized one.
+async fn missing_milestone(database: &MongoDb, index: MilestoneIndex) -> ApiError {
+    match get_starting_index(database).await {
+        Ok(starting_index) => pruned(index, starting_index).map_or_else(|| MissingError::NoResults.into(), Into::into),
+        Err(err) => err,
+    }
+}
+
+/// Returns the error for data of a transaction that could not be found, which is [`PrunedError`] if the transaction was
+/// booked before the first synchronized milestone. Outputs of such transactions that were still unspent are part of the
+/// ledger state that Chronicle started from, so its booking milestone is known even if the block is not.
+async fn missing_transaction(database: &MongoDb, transaction_id: &TransactionId) -> ApiError {
+    match database
+        .collection::<OutputCollection>()
+        .get_transaction_booked_milestones(transaction_id)
+        .await
+    {
+        Ok(booked) => match booked.first() {
+            Some(booked) => missing_milestone(database, booked.milestone_index).await,
+            None => MissingError::NoResults.into(),
+        },
+        Err(err) => err.into(),
+    }
+}
+
+/// Returns the error for a block that could not be found, which is [`PrunedError`] if one of the first synchronized
+/// blocks approves it, as it was then referenced before the first synchronized milestone.
+async fn missing_block(database: &MongoDb, block_id: &BlockId) -> ApiError {
+    match pruned_block(database, block_id).await {
+        Ok(pruned) => pruned.map_or_else(|| MissingError::NoResults.into(), Into::into),
+        Err(err) => err,
+    }
+}
+
+async fn pruned_block(database: &MongoDb, block_id: &BlockId) -> ApiResult<Option<PrunedError>> {
+    let starting_index = match get_starting_index(database).await? {
+        Some(starting_index) => starting_index,
+        None => return Ok(None),
+    };
+    let below_max_depth = match database
+        .collection::<ProtocolUpdateCollection>()
+        .get_protocol_parameters_for_ledger_index(starting_index)
+        .await?
+    {
+        Some(protocol) => protocol.parameters.below_max_depth,
+        None => return Ok(None),
+    };
+    // Blocks can only approve blocks that were referenced at most `below_max_depth` milestones earlier.
+    let approved = database
+        .collection::<BlockCollection>()
+        .get_block_children(block_id, starting_index, below_max_depth, 1, 0)
+        .await?
+        .try_next()
+        .await?
+        .is_some();
+    Ok(if approved {
+        pruned(starting_index.0.saturating_sub(1).into(), Some(starting_index))
+    } else {
+        None
+    })
+}
+
+/// Everything before the first milestone that Chronicle synchronized was pruned by the node beforehand.
+fn pruned(index: MilestoneIndex, starting_index: Option<MilestoneIndex>) -> Option<PrunedError> {
+    match starting_index {
+        Some(starting_index) if index < starting_index => Some(PrunedError {
+            pruning_index: starting_index.0.saturating_sub(1).into(),
+        }),
+        _ => None,
+    }
+}
+
 async fn collect_utxo_changes(database: &MongoDb, milestone_index: MilestoneIndex) -> ApiResult<UtxoChangesResponse> {
     let ledger_index = database
         .collection::<MilestoneCollection>()
@@ -525,3 +1008,151 @@ async fn collect_utxo_changes(database: &MongoDb, milestone_index: MilestoneInde
         consumed_outputs,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lookup_below_starting_index_is_pruned() {
+        assert!(matches!(
+            pruned(5.into(), Some(10.into())),
+            Some(PrunedError { pruning_index }) if pruning_index == 9
+        ));
+        assert!(pruned(10.into(), Some(10.into())).is_none());
+        assert!(pruned(5.into(), None).is_none());
+    }
+
+    #[test]
+    fn protocol_parameters_are_reported_with_their_milestone() {
+        let parameters = ProtocolParameters::from(iota_types::block::protocol::protocol_parameters());
+        let res = protocol_parameters_response(ProtocolUpdateDocument {
+            tangle_index: 42.into(),
+            parameters: parameters.clone(),
+        });
+        assert_eq!(res.milestone_index, 42);
+        assert_eq!(res.parameters.protocol_version, parameters.version);
+        assert_eq!(res.parameters.network_name, parameters.network_name);
+        assert_eq!(res.parameters.token_supply, parameters.token_supply.to_string());
+        assert_eq!(
+            res.parameters.rent_structure.v_byte_cost,
+            parameters.rent_structure.v_byte_cost
+        );
+
+        let json = serde_json::to_value(&res).unwrap();
+        assert_eq!(json["milestoneIndex"], 42);
+        assert_eq!(json["parameters"]["networkName"], parameters.network_name.as_str());
+    }
+
+    #[tokio::test]
+    async fn outputs_by_address_rejects_malformed_address() {
+        use chronicle::db::MongoDbConfig;
+
+        // Creating the client does not contact the server, and the address is rejected before any query is made.
+        let db = MongoDb::connect(&MongoDbConfig::default()).await.unwrap();
+        let err = outputs_by_address(
+            Extension(db),
+            // The checksum does not match the rest of the address.
+            Path("iota1qp6gwwy7rruqpvnh0pzpq7npeylafp3rre3fjxd5ehtyl3ks3ftz2jhwgsx".to_string()),
+            OutputsByAddressPagination {
+                include_spent: true,
+                ledger_index: Some(1.into()),
+                page_size: 10,
+                cursor: None,
+            },
+            ResponseFormat::Json,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<RequestError>(),
+            Some(RequestError::IotaStardust(_))
+        ));
+    }
+
+    /// Requires a MongoDB instance, like the database tests.
+    #[cfg(feature = "rand")]
+    #[tokio::test]
+    async fn output_spent_before_starting_index_is_pruned() {
+        use chronicle::{
+            db::MongoDbConfig,
+            model::{
+                ledger::{LedgerOutput, RentStructureBytes},
+                payload::MilestonePayload,
+                tangle::MilestoneIndexTimestamp,
+                utxo::Output,
+            },
+        };
+
+        let mut config = MongoDbConfig {
+            database_name: "test-core-pruned-outputs".to_string(),
+            ..Default::default()
+        };
+        if let Ok(conn_str) = std::env::var("MONGODB_CONN_STR") {
+            config.conn_str = conn_str;
+        }
+        let db = MongoDb::connect(&config).await.unwrap();
+        db.clear().await.unwrap();
+
+        let ctx = iota_types::block::protocol::protocol_parameters();
+        db.collection::<ApplicationStateCollection>()
+            .set_starting_index(MilestoneIndexTimestamp {
+                milestone_index: 10.into(),
+                milestone_timestamp: 10000.into(),
+            })
+            .await
+            .unwrap();
+        db.collection::<MilestoneCollection>()
+            .insert_milestone(
+                MilestoneId::rand(),
+                10.into(),
+                10000.into(),
+                MilestonePayload::rand(&ctx),
+            )
+            .await
+            .unwrap();
+        // The ledger state that Chronicle started from only holds the unspent output of the transaction.
+        let unspent = LedgerOutput {
+            output_id: OutputId::rand(),
+            rent_structure: RentStructureBytes {
+                num_key_bytes: 0,
+                num_data_bytes: 100,
+            },
+            output: Output::rand_basic(&ctx),
+            block_id: BlockId::rand(),
+            booked: MilestoneIndexTimestamp {
+                milestone_index: 5.into(),
+                milestone_timestamp: 5000.into(),
+            },
+        };
+        db.collection::<OutputCollection>()
+            .insert_unspent_outputs([&unspent])
+            .await
+            .unwrap();
+
+        let missing = |output_id: OutputId| {
+            let db = db.clone();
+            async move {
+                output_metadata(Extension(db), Path(output_id.to_hex()), ResponseFormat::Json)
+                    .await
+                    .map(|_| ())
+                    .unwrap_err()
+            }
+        };
+
+        let spent = OutputId {
+            index: unspent.output_id.index + 1,
+            ..unspent.output_id
+        };
+        assert!(matches!(
+            missing(spent).await.error.downcast_ref::<PrunedError>(),
+            Some(PrunedError { pruning_index }) if *pruning_index == 9
+        ));
+        assert!(matches!(
+            missing(OutputId::rand()).await.error.downcast_ref::<MissingError>(),
+            Some(MissingError::NoResults)
+        ));
+
+        db.drop().await.unwrap();
+    }
+}
@@ -1,14 +1,17 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{num::ParseIntError, str::ParseBoolError};
+use std::{num::ParseIntError, str::ParseBoolError, time::Duration};
 
 use axum::{
-    extract::rejection::{QueryRejection, TypedHeaderRejection},
+    extract::rejection::{JsonRejection, QueryRejection, TypedHeaderRejection},
     response::IntoResponse,
 };
-use chronicle::db::mongodb::collections::ParseSortError;
-use hyper::{header::InvalidHeaderValue, StatusCode};
+use chronicle::{db::mongodb::collections::ParseSortError, model::tangle::MilestoneIndex};
+use hyper::{
+    header::{InvalidHeaderValue, RETRY_AFTER},
+    StatusCode,
+};
 use serde::Serialize;
 use thiserror::Error;
 use tracing::error;
@@ -61,15 +64,34 @@ macro_rules! impl_internal_error {
 }
 
 impl_internal_error!(
-    mongodb::error::Error,
     axum::extract::rejection::ExtensionRejection,
     auth_helper::jwt::Error,
     argon2::Error,
-    iota_types::block::Error
+    iota_types::block::Error,
+    serde_json::Error
 );
 
+impl From<mongodb::error::Error> for ApiError {
+    fn from(error: mongodb::error::Error) -> Self {
+        if crate::startup::is_transient_mongodb_error(&error) {
+            UnavailableError(error).into()
+        } else {
+            Self {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                error: Box::new(error) as _,
+            }
+        }
+    }
+}
+
+/// Marks responses that failed because the database is unreachable.
+#[derive(Copy, Clone, Debug)]
+pub struct DatabaseOutage;
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
+        let outage = self.error.is::<UnavailableError>();
+        let retry_after = self.error.downcast_ref::<BusyError>().map(|err| err.retry_after);
         // Hide internal errors from the client, but print them to the server.
         let message = if self.code == StatusCode::INTERNAL_SERVER_ERROR {
             tracing::error!("Internal API error: {}", self.error);
@@ -77,12 +99,21 @@ impl IntoResponse for ApiError {
         } else {
             self.error.to_string()
         };
-        ErrorBody {
+        let mut response = ErrorBody {
             status: self.code,
             code: self.code.as_u16(),
             message,
         }
-        .into_response()
+        .into_response();
+        if outage {
+            response.extensions_mut().insert(DatabaseOutage);
+        }
+        if let Some(retry_after) = retry_after {
+            // The header is in whole seconds, so round up to avoid telling clients to retry right away.
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response.headers_mut().insert(RETRY_AFTER, seconds.max(1).into());
+        }
+        response
     }
 }
 
@@ -138,6 +169,70 @@ impl IntoResponse for UnimplementedError {
     }
 }
 
+#[derive(Error, Debug)]
+#[allow(missing_docs)]
+#[error("too many concurrent aggregations, try again later")]
+pub struct BusyError {
+    /// How long the client should wait before it tries again, which is sent as the `Retry-After` header.
+    pub retry_after: Duration,
+}
+
+impl ErrorStatus for BusyError {
+    fn status(&self) -> StatusCode {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+#[derive(Error, Debug)]
+#[allow(missing_docs)]
+#[error("the request did not complete within {0:?}")]
+pub struct TimeoutError(pub Duration);
+
+impl ErrorStatus for TimeoutError {
+    fn status(&self) -> StatusCode {
+        StatusCode::REQUEST_TIMEOUT
+    }
+}
+
+#[derive(Error, Debug)]
+#[allow(missing_docs)]
+#[error("the request body exceeds the maximum size of {max_size} bytes")]
+pub struct PayloadTooLargeError {
+    pub max_size: usize,
+}
+
+impl ErrorStatus for PayloadTooLargeError {
+    fn status(&self) -> StatusCode {
+        StatusCode::PAYLOAD_TOO_LARGE
+    }
+}
+
+#[derive(Error, Debug)]
+#[allow(missing_docs)]
+#[error("the database is unavailable, try again later")]
+pub struct UnavailableError(#[source] mongodb::error::Error);
+
+impl ErrorStatus for UnavailableError {
+    fn status(&self) -> StatusCode {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// The requested data is older than the first milestone that Chronicle synchronized, so it was pruned by the node
+/// before it could be stored.
+#[derive(Error, Debug)]
+#[allow(missing_docs)]
+#[error("the requested data was pruned at milestone {pruning_index}, query a permanode or archive instead")]
+pub struct PrunedError {
+    pub pruning_index: MilestoneIndex,
+}
+
+impl ErrorStatus for PrunedError {
+    fn status(&self) -> StatusCode {
+        StatusCode::GONE
+    }
+}
+
 #[derive(Error, Debug)]
 #[allow(missing_docs)]
 pub enum MissingError {
@@ -165,6 +260,8 @@ pub enum RequestError {
     BadPagingState,
     #[error("invalid time range")]
     BadTimeRange,
+    #[error("the time range must not span more than {}", humantime::format_duration(*.max))]
+    TimeRangeTooLarge { max: std::time::Duration },
 
     #[error("invalid IOTA Stardust data: {0}")]
     IotaStardust(#[from] iota_types::block::Error),
@@ -180,6 +277,26 @@ pub enum RequestError {
     InvalidAuthHeader(#[from] TypedHeaderRejection),
     #[error("invalid query parameters provided: {0}")]
     InvalidQueryParams(#[from] QueryRejection),
+    #[error("invalid request body provided: {0}")]
+    InvalidJsonBody(#[from] JsonRejection),
+    #[error("failed to read request body: {0}")]
+    Body(#[from] hyper::Error),
+    #[error("invalid output id `{id}`: {reason}")]
+    InvalidOutputId { id: String, reason: String },
+    #[error("invalid output ids provided: {}", .0.join(", "))]
+    InvalidOutputIds(Vec<String>),
+    #[error("too many ids provided: {found}, the maximum is {max}")]
+    BatchTooLarge { found: usize, max: usize },
+    #[error("too many intervals requested: {found}, the maximum is {max}")]
+    TooManyIntervals { found: usize, max: usize },
+    #[error("the interval must be at least one milestone")]
+    ZeroInterval,
+    #[error("the amount boundaries must be strictly ascending and between 1 and {}", i64::MAX)]
+    InvalidAmountBoundaries,
+    #[error("invalid output type provided: {0}")]
+    InvalidOutputKind(String),
+    #[error("unknown field requested: {0}")]
+    UnknownField(String),
     #[cfg(feature = "poi")]
     #[error(transparent)]
     PoI(#[from] crate::api::poi::RequestError),
@@ -195,6 +312,8 @@ impl ErrorStatus for RequestError {
 
 #[derive(Error, Debug)]
 pub enum ConfigError {
+    #[error("invalid base path `{0}` in config: must start with a `/` and must not end with one")]
+    InvalidBasePath(String),
     #[error("invalid allow-origin header in config: {0}")]
     InvalidHeader(#[from] InvalidHeaderValue),
     #[error("invalid hex value in config: {0}")]
@@ -9,15 +9,20 @@ use axum::{
     Extension,
 };
 use chronicle::{
-    db::mongodb::collections::SortOrder,
+    db::mongodb::collections::{default_amount_boundaries, SortOrder},
     model::{
         tangle::{MilestoneIndex, MilestoneTimestamp},
-        utxo::OutputId,
+        utxo::{Address, OutputId},
     },
 };
 use serde::Deserialize;
 
-use crate::api::{config::ApiConfigData, error::RequestError, ApiError, DEFAULT_PAGE_SIZE};
+use crate::api::{
+    config::ApiConfigData,
+    error::RequestError,
+    extractors::{parse_output_kind, TimeRange},
+    ApiError, DEFAULT_PAGE_SIZE,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LedgerUpdatesByAddressPagination {
@@ -258,6 +263,108 @@ impl<B: Send> FromRequest<B> for MilestonesPagination {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressActivityPagination {
+    pub time_range: TimeRange,
+    pub page_size: usize,
+    pub cursor: Option<Address>,
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct AddressActivityPaginationQuery {
+    pub start_timestamp: Option<u32>,
+    pub end_timestamp: Option<u32>,
+    pub page_size: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+/// The cursor of the address activity, which also fixes the time range, so that later pages are not affected by newly
+/// applied milestones. It is URL-safe base64, so that clients treat it as opaque.
+#[derive(Clone)]
+pub struct AddressActivityCursor {
+    pub start_timestamp: MilestoneTimestamp,
+    pub end_timestamp: MilestoneTimestamp,
+    /// The bech32 representation of the first address of the page.
+    pub address: String,
+    pub page_size: usize,
+}
+
+impl FromStr for AddressActivityCursor {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use base64::Engine;
+        let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(s)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or(RequestError::BadPagingState)?;
+        let parts: Vec<_> = decoded.split('.').collect();
+        Ok(match parts[..] {
+            [st, et, a, ps] => AddressActivityCursor {
+                start_timestamp: st.parse::<u32>().map_err(RequestError::from)?.into(),
+                end_timestamp: et.parse::<u32>().map_err(RequestError::from)?.into(),
+                address: a.to_string(),
+                page_size: ps.parse().map_err(RequestError::from)?,
+            },
+            _ => return Err(ApiError::from(RequestError::BadPagingState)),
+        })
+    }
+}
+
+impl Display for AddressActivityCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use base64::Engine;
+        let cursor = format!(
+            "{}.{}.{}.{}",
+            self.start_timestamp.0, self.end_timestamp.0, self.address, self.page_size
+        );
+        write!(f, "{}", base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(cursor))
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for AddressActivityPagination {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<AddressActivityPaginationQuery>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        let Extension(config) = Extension::<ApiConfigData>::from_request(req).await?;
+
+        let (time_range, page_size, cursor) = if let Some(cursor) = query.cursor {
+            let cursor: AddressActivityCursor = cursor.parse()?;
+            (
+                TimeRange::new(
+                    Some(cursor.start_timestamp),
+                    Some(cursor.end_timestamp),
+                    config.max_time_range,
+                )?,
+                cursor.page_size,
+                Some(cursor.address.parse().map_err(RequestError::from)?),
+            )
+        } else {
+            (
+                TimeRange::new(
+                    query.start_timestamp.map(Into::into),
+                    query.end_timestamp.map(Into::into),
+                    config.max_time_range,
+                )?,
+                query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+                None,
+            )
+        };
+
+        Ok(AddressActivityPagination {
+            time_range,
+            page_size: page_size.min(config.max_page_size),
+            cursor,
+        })
+    }
+}
+
 const DEFAULT_TOP_RICHLIST: usize = 100;
 
 #[derive(Clone, Deserialize)]
@@ -308,6 +415,153 @@ impl<B: Send> FromRequest<B> for LedgerIndex {
     }
 }
 
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct TransactionCountQuery {
+    pub start_index: Option<MilestoneIndex>,
+    pub end_index: Option<MilestoneIndex>,
+    /// The number of milestones per interval.
+    pub interval: u32,
+}
+
+impl Default for TransactionCountQuery {
+    fn default() -> Self {
+        Self {
+            start_index: None,
+            end_index: None,
+            interval: 1,
+        }
+    }
+}
+
+impl TransactionCountQuery {
+    /// Returns the first milestone of the range that ends at `end_index`. Without an explicit start, the range covers
+    /// the latest [`DEFAULT_PAGE_SIZE`] intervals.
+    pub fn start_index(&self, end_index: MilestoneIndex, max_intervals: usize) -> Result<MilestoneIndex, RequestError> {
+        let start_index = self.start_index.unwrap_or_else(|| {
+            let intervals = DEFAULT_PAGE_SIZE.min(max_intervals).max(1) as u32;
+            end_index
+                .0
+                .saturating_sub(self.interval.saturating_mul(intervals - 1))
+                .into()
+        });
+        if end_index < start_index {
+            return Err(RequestError::BadTimeRange);
+        }
+        let intervals = ((end_index.0 - start_index.0) / self.interval) as usize + 1;
+        if intervals > max_intervals {
+            return Err(RequestError::TooManyIntervals {
+                found: intervals,
+                max: max_intervals,
+            });
+        }
+        Ok(start_index)
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for TransactionCountQuery {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<TransactionCountQuery>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        if query.interval == 0 {
+            return Err(ApiError::from(RequestError::ZeroInterval));
+        }
+        Ok(query)
+    }
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct ClaimedTokensQuery {
+    pub address: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClaimedTokensFilter {
+    pub address: Option<Address>,
+    pub kind: Option<&'static str>,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for ClaimedTokensFilter {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(ClaimedTokensQuery { address, kind }) = Query::<ClaimedTokensQuery>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        Ok(ClaimedTokensFilter {
+            address: address
+                .as_deref()
+                .map(Address::from_str)
+                .transpose()
+                .map_err(RequestError::from)?,
+            kind: kind.as_deref().map(parse_output_kind).transpose()?,
+        })
+    }
+}
+
+/// The largest number of boundaries that can be given for the amount distribution.
+const MAX_AMOUNT_BOUNDARIES: usize = 64;
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct AmountDistributionQuery {
+    pub ledger_index: Option<MilestoneIndex>,
+    /// A comma-separated list of the amounts at which the buckets start.
+    pub boundaries: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AmountDistributionParams {
+    pub ledger_index: Option<MilestoneIndex>,
+    pub boundaries: Vec<u64>,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for AmountDistributionParams {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(AmountDistributionQuery {
+            ledger_index,
+            boundaries,
+        }) = Query::<AmountDistributionQuery>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        let boundaries = match boundaries {
+            Some(boundaries) => boundaries
+                .split(',')
+                .map(|boundary| boundary.trim().parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(RequestError::from)?,
+            None => default_amount_boundaries(),
+        };
+        if boundaries.len() > MAX_AMOUNT_BOUNDARIES {
+            return Err(ApiError::from(RequestError::BatchTooLarge {
+                found: boundaries.len(),
+                max: MAX_AMOUNT_BOUNDARIES,
+            }));
+        }
+        if boundaries.first() == Some(&0)
+            || boundaries.iter().any(|&boundary| boundary > i64::MAX as u64)
+            || boundaries.windows(2).any(|pair| pair[0] >= pair[1])
+        {
+            return Err(ApiError::from(RequestError::InvalidAmountBoundaries));
+        }
+        Ok(AmountDistributionParams {
+            ledger_index,
+            boundaries,
+        })
+    }
+}
+
 #[derive(Copy, Clone, Deserialize, Default)]
 #[serde(default, deny_unknown_fields, rename_all = "camelCase")]
 pub struct MilestoneRange {
@@ -466,6 +720,65 @@ mod test {
         assert_eq!(parsed.to_string(), cursor);
     }
 
+    #[test]
+    fn address_activity_cursor_from_to_str() {
+        let address = "rms1qqqqzqsrqszsvpcgpy9qkrqdpc83qygjzv2p29shrqv35xcur50p73t73md";
+        let cursor = AddressActivityCursor {
+            start_timestamp: 1000.into(),
+            end_timestamp: 2000.into(),
+            address: address.to_string(),
+            page_size: 50,
+        }
+        .to_string();
+        assert!(!cursor.contains(address));
+        assert!(!cursor.contains('.'));
+
+        let parsed: AddressActivityCursor = cursor.parse().unwrap();
+        assert_eq!(parsed.start_timestamp.0, 1000);
+        assert_eq!(parsed.end_timestamp.0, 2000);
+        assert_eq!(parsed.address, address);
+        assert_eq!(parsed.page_size, 50);
+        assert_eq!(parsed.to_string(), cursor);
+
+        assert!(format!("1000.2000.{address}.50")
+            .parse::<AddressActivityCursor>()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn address_activity_cursor_fixes_time_range() {
+        let address = "rms1qqqqzqsrqszsvpcgpy9qkrqdpc83qygjzv2p29shrqv35xcur50p73t73md";
+        let cursor = AddressActivityCursor {
+            start_timestamp: 1000.into(),
+            end_timestamp: 2000.into(),
+            address: address.to_string(),
+            page_size: 50,
+        };
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri(format!(
+                    "/ledger/active-addresses/by-address?startTimestamp=1&pageSize=10&cursor={cursor}"
+                ))
+                .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                .body(())
+                .unwrap(),
+        );
+        assert_eq!(
+            AddressActivityPagination::from_request(&mut req).await.unwrap(),
+            AddressActivityPagination {
+                time_range: TimeRange::new(
+                    Some(1000.into()),
+                    Some(2000.into()),
+                    ApiConfig::default().max_time_range
+                )
+                .unwrap(),
+                page_size: 50,
+                cursor: Some(address.parse().unwrap()),
+            }
+        );
+    }
+
     #[test]
     fn ledger_updates_by_milestone_cursor_from_to_str() {
         let output_id_str = "0xfa0de75d225cca2799395e5fc340702fc7eac821d2bdd79911126f131ae097a20100";
@@ -477,6 +790,82 @@ mod test {
         assert_eq!(parsed.to_string(), cursor);
     }
 
+    #[tokio::test]
+    async fn amount_boundaries_are_validated() {
+        let params = |uri: &str| {
+            let mut req = RequestParts::new(Request::builder().method("GET").uri(uri).body(()).unwrap());
+            async move { AmountDistributionParams::from_request(&mut req).await }
+        };
+
+        assert_eq!(
+            params("/").await.unwrap(),
+            AmountDistributionParams {
+                ledger_index: None,
+                boundaries: default_amount_boundaries(),
+            }
+        );
+        assert_eq!(
+            params("/?ledgerIndex=5&boundaries=10,1000,1000000").await.unwrap(),
+            AmountDistributionParams {
+                ledger_index: Some(5.into()),
+                boundaries: vec![10, 1000, 1000000],
+            }
+        );
+
+        for uri in [
+            "/?boundaries=0,10",
+            "/?boundaries=10,10",
+            "/?boundaries=100,10",
+            "/?boundaries=18446744073709551615",
+        ] {
+            let err = params(uri).await.unwrap_err();
+            assert!(
+                matches!(
+                    err.error.downcast_ref::<RequestError>(),
+                    Some(RequestError::InvalidAmountBoundaries)
+                ),
+                "{uri}"
+            );
+        }
+        let err = params("/?boundaries=ten").await.unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<RequestError>(),
+            Some(RequestError::Int(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn transaction_count_range() {
+        let mut req = RequestParts::new(Request::builder().method("GET").uri("/").body(()).unwrap());
+        let query = TransactionCountQuery::from_request(&mut req).await.unwrap();
+        assert_eq!(query, TransactionCountQuery::default());
+        // Without a start, the latest intervals are returned.
+        assert_eq!(query.start_index(500.into(), 1000).unwrap(), 401);
+        assert_eq!(query.start_index(50.into(), 1000).unwrap(), 0);
+
+        let query = TransactionCountQuery {
+            start_index: Some(10.into()),
+            end_index: None,
+            interval: 10,
+        };
+        assert_eq!(query.start_index(109.into(), 10).unwrap(), 10);
+        assert!(matches!(
+            query.start_index(110.into(), 10),
+            Err(RequestError::TooManyIntervals { found: 11, max: 10 })
+        ));
+        assert!(matches!(
+            query.start_index(9.into(), 10),
+            Err(RequestError::BadTimeRange)
+        ));
+
+        let mut req = RequestParts::new(Request::builder().method("GET").uri("/?interval=0").body(()).unwrap());
+        let err = TransactionCountQuery::from_request(&mut req).await.unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<RequestError>(),
+            Some(RequestError::ZeroInterval)
+        ));
+    }
+
     #[tokio::test]
     async fn page_size_clamped() {
         let mut req = RequestParts::new(
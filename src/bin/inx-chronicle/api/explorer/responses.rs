@@ -1,20 +1,25 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::ops::Range;
+use std::{collections::BTreeMap, ops::Range};
 
 use chronicle::{
     db::mongodb::collections::{
-        DistributionStat, LedgerUpdateByAddressRecord, LedgerUpdateByMilestoneRecord, MilestoneResult,
+        AliasHistoryRecord, AliasTransition, AmountBucket, DistributionStat, FoundrySupplyResult,
+        LedgerUpdateByAddressRecord, LedgerUpdateByMilestoneRecord, MilestoneResult, NftHistoryRecord,
+        SkippedMilestones,
     },
     model::{
         tangle::{MilestoneIndex, MilestoneTimestamp},
-        utxo::Address,
+        utxo::{Address, TokenScheme},
+        BlockId,
     },
 };
+use iota_types::{api::core::response::BlockMetadataResponse, block::BlockDto};
+use primitive_types::U256;
 use serde::{Deserialize, Serialize};
 
-use crate::api::responses::impl_success_response;
+use crate::api::{buckets::BucketInterval, responses::impl_success_response};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -84,6 +89,15 @@ pub struct BalanceResponse {
 
 impl_success_response!(BalanceResponse);
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockWithMetadataResponse {
+    pub block: BlockDto,
+    pub metadata: BlockMetadataResponse,
+}
+
+impl_success_response!(BlockWithMetadataResponse);
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BlockChildrenResponse {
@@ -126,6 +140,9 @@ impl_success_response!(BlocksByMilestoneResponse);
 pub struct MilestoneDto {
     milestone_id: String,
     index: MilestoneIndex,
+    /// The block that carries the milestone, once it was referenced by a later milestone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_id: Option<String>,
 }
 
 impl From<MilestoneResult> for MilestoneDto {
@@ -133,6 +150,7 @@ impl From<MilestoneResult> for MilestoneDto {
         Self {
             milestone_id: res.milestone_id.to_hex(),
             index: res.index,
+            block_id: res.block_id.as_ref().map(BlockId::to_hex),
         }
     }
 }
@@ -152,6 +170,260 @@ pub struct AddressStatDto {
     pub balance: String,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDepositResponse {
+    pub ledger_index: MilestoneIndex,
+    /// The number of unspent outputs.
+    pub output_count: usize,
+    pub key_bytes: u64,
+    pub data_bytes: u64,
+    /// The storage deposit required by the unspent outputs under the rent structure in effect at the ledger index.
+    pub storage_deposit: String,
+}
+
+impl_success_response!(StorageDepositResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnspentCountResponse {
+    pub ledger_index: MilestoneIndex,
+    /// The number of unspent outputs, which is the size of the UTXO set.
+    pub unspent_count: u64,
+}
+
+impl_success_response!(UnspentCountResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDepositByTypeResponse {
+    pub items: Vec<StorageDepositByTypeDto>,
+    pub ledger_index: MilestoneIndex,
+}
+
+impl_success_response!(StorageDepositByTypeResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDepositByTypeDto {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub count: usize,
+    pub key_bytes: u64,
+    pub data_bytes: u64,
+    pub storage_deposit: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputHealthResponse {
+    pub total: usize,
+    pub spent: usize,
+    pub unspent: usize,
+    /// The share of spent outputs, between `0` and `1`.
+    pub spent_ratio: f64,
+    /// The number of outputs that were spent before they were booked. Any value other than `0` indicates corrupted
+    /// data.
+    pub spent_before_booked: usize,
+    /// The number of milestones that were applied with a timestamp earlier than the one of the previous milestone.
+    pub milestone_timestamp_violations: u64,
+}
+
+impl_success_response!(OutputHealthResponse);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncState {
+    /// Chronicle is still applying milestones the node had already confirmed when the sync started.
+    CatchingUp,
+    /// Chronicle follows newly confirmed milestones.
+    Live,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatusResponse {
+    pub state: SyncState,
+    pub ledger_index: MilestoneIndex,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_index: Option<MilestoneIndex>,
+    /// The latest milestone the node is known to have confirmed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_known_index: Option<MilestoneIndex>,
+    /// The number of confirmed milestones that are not stored yet.
+    pub gap: u32,
+    /// Whether the database is at most one milestone behind the node.
+    pub is_synced: bool,
+    /// The milestones that were skipped because the node had pruned them, so that the ledger is incomplete.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_milestones: Vec<SkippedMilestones>,
+}
+
+impl_success_response!(SyncStatusResponse);
+
+impl SyncStatusResponse {
+    pub fn new(
+        ledger_index: MilestoneIndex,
+        target_index: Option<MilestoneIndex>,
+        latest_known_index: Option<MilestoneIndex>,
+    ) -> Self {
+        let state = match target_index {
+            Some(target_index) if ledger_index < target_index => SyncState::CatchingUp,
+            _ => SyncState::Live,
+        };
+        // Databases written by older versions only know the target of the initial catch-up.
+        let gap = latest_known_index
+            .or(target_index)
+            .map_or(0, |known| known.0.saturating_sub(ledger_index.0));
+        Self {
+            state,
+            ledger_index,
+            target_index,
+            latest_known_index,
+            gap,
+            is_synced: gap <= 1,
+            skipped_milestones: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimedTokensResponse {
+    pub milestone_index: MilestoneIndex,
+    pub count: usize,
+    pub amount: String,
+}
+
+impl_success_response!(ClaimedTokensResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoundrySupplyResponse {
+    pub ledger_index: MilestoneIndex,
+    /// The foundry output that was unspent at the ledger index.
+    pub output_id: String,
+    pub minted_tokens: String,
+    pub melted_tokens: String,
+    pub maximum_supply: String,
+    /// The minted tokens minus the melted tokens.
+    pub circulating_supply: String,
+}
+
+impl_success_response!(FoundrySupplyResponse);
+
+impl FoundrySupplyResponse {
+    pub fn new(res: FoundrySupplyResult, ledger_index: MilestoneIndex) -> Self {
+        let circulating_supply = res.circulating_supply().to_string();
+        let TokenScheme::Simple {
+            minted_tokens,
+            melted_tokens,
+            maximum_supply,
+        } = res.token_scheme;
+        Self {
+            ledger_index,
+            output_id: res.output_id.to_hex(),
+            minted_tokens: U256::from(minted_tokens).to_string(),
+            melted_tokens: U256::from(melted_tokens).to_string(),
+            maximum_supply: U256::from(maximum_supply).to_string(),
+            circulating_supply,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveAddressesResponse {
+    pub start_timestamp: MilestoneTimestamp,
+    pub end_timestamp: MilestoneTimestamp,
+    /// The number of addresses that owned an output which was created or consumed within the time range.
+    pub count: usize,
+    /// The number of those addresses that owned an output of each kind. An address that owned outputs of several kinds
+    /// is counted for each of them.
+    pub by_output_type: BTreeMap<String, usize>,
+}
+
+impl_success_response!(ActiveAddressesResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressActivityResponse {
+    pub start_timestamp: MilestoneTimestamp,
+    pub end_timestamp: MilestoneTimestamp,
+    pub items: Vec<AddressActivityDto>,
+    pub cursor: Option<String>,
+}
+
+impl_success_response!(AddressActivityResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressActivityDto {
+    pub address: String,
+    /// The number of outputs owned by the address that were created within the time range.
+    pub created_count: usize,
+    /// The number of outputs owned by the address that were consumed within the time range.
+    pub consumed_count: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotalSupplyResponse {
+    pub ledger_index: MilestoneIndex,
+    /// The number of unspent outputs.
+    pub output_count: usize,
+    /// The sum of the amounts of all unspent outputs.
+    pub total_supply: String,
+    /// The sum of the amounts of the unspent outputs whose timelock has not expired at the ledger index.
+    pub locked_supply: String,
+    /// The sum of the amounts of the unspent outputs that are not timelocked.
+    pub unlocked_supply: String,
+    /// The token supply defined by the protocol parameters.
+    pub expected_supply: String,
+    /// Whether the sum of the unspent outputs equals the expected supply. Any other result indicates missing or
+    /// duplicated ledger updates.
+    pub reconciled: bool,
+}
+
+impl_success_response!(TotalSupplyResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionCountResponse {
+    /// The number of milestones per interval.
+    pub interval: u32,
+    pub items: Vec<TransactionCountDto>,
+}
+
+impl_success_response!(TransactionCountResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionCountDto {
+    pub start_index: MilestoneIndex,
+    pub end_index: MilestoneIndex,
+    /// The number of transactions that were booked by the milestones of the interval.
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionCountByTimeResponse {
+    pub interval: BucketInterval,
+    pub items: Vec<TransactionCountByTimeDto>,
+}
+
+impl_success_response!(TransactionCountByTimeResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionCountByTimeDto {
+    pub start_timestamp: MilestoneTimestamp,
+    /// The inclusive end of the bucket.
+    pub end_timestamp: MilestoneTimestamp,
+    pub count: usize,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TokenDistributionResponse {
@@ -178,3 +450,183 @@ impl From<DistributionStat> for DistributionStatDto {
         }
     }
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmountDistributionResponse {
+    pub buckets: Vec<AmountBucketDto>,
+    pub ledger_index: MilestoneIndex,
+}
+
+impl_success_response!(AmountDistributionResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmountBucketDto {
+    /// The smallest amount in the bucket.
+    pub start: String,
+    /// The amount at which the next bucket starts. The last bucket has no upper bound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    pub output_count: u64,
+    pub total_amount: String,
+}
+
+impl From<AmountBucket> for AmountBucketDto {
+    fn from(b: AmountBucket) -> Self {
+        Self {
+            start: b.start.to_string(),
+            end: b.end.map(|end| end.to_string()),
+            output_count: b.output_count,
+            total_amount: b.total_amount,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NftHistoryResponse {
+    /// The owners of the NFT, from the one it was minted for to the current one.
+    pub items: Vec<NftOwnerDto>,
+    pub ledger_index: MilestoneIndex,
+}
+
+impl_success_response!(NftHistoryResponse);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NftOwnerDto {
+    pub address: Address,
+    /// The output that held the NFT while the address owned it.
+    pub output_id: String,
+    pub milestone_index_booked: MilestoneIndex,
+    pub milestone_timestamp_booked: MilestoneTimestamp,
+    /// `null` if the address still owns the NFT.
+    pub milestone_index_spent: Option<MilestoneIndex>,
+    pub milestone_timestamp_spent: Option<MilestoneTimestamp>,
+}
+
+impl From<NftHistoryRecord> for NftOwnerDto {
+    fn from(value: NftHistoryRecord) -> Self {
+        Self {
+            address: value.address,
+            output_id: value.output_id.to_hex(),
+            milestone_index_booked: value.booked.milestone_index,
+            milestone_timestamp_booked: value.booked.milestone_timestamp,
+            milestone_index_spent: value.spent_metadata.map(|spent| spent.spent.milestone_index),
+            milestone_timestamp_spent: value.spent_metadata.map(|spent| spent.spent.milestone_timestamp),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasHistoryResponse {
+    /// The transitions of the alias, from its creation to its current state.
+    pub items: Vec<AliasTransitionDto>,
+    pub ledger_index: MilestoneIndex,
+}
+
+impl_success_response!(AliasHistoryResponse);
+
+impl AliasHistoryResponse {
+    pub fn new(history: Vec<AliasHistoryRecord>, ledger_index: MilestoneIndex) -> Self {
+        let items = history
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                let transition = record.transition(i.checked_sub(1).map(|previous| &history[previous]));
+                AliasTransitionDto {
+                    transition: match transition {
+                        AliasTransition::Creation => AliasTransitionKindDto::Creation,
+                        AliasTransition::State => AliasTransitionKindDto::State,
+                        AliasTransition::Governance => AliasTransitionKindDto::Governance,
+                    },
+                    output_id: record.output_id.to_hex(),
+                    state_index: record.state_index,
+                    state_controller: record.state_controller,
+                    governor: record.governor,
+                    milestone_index_booked: record.booked.milestone_index,
+                    milestone_timestamp_booked: record.booked.milestone_timestamp,
+                    milestone_index_spent: record.spent_metadata.map(|spent| spent.spent.milestone_index),
+                    milestone_timestamp_spent: record.spent_metadata.map(|spent| spent.spent.milestone_timestamp),
+                }
+            })
+            .collect();
+        Self { items, ledger_index }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AliasTransitionKindDto {
+    Creation,
+    State,
+    Governance,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasTransitionDto {
+    pub transition: AliasTransitionKindDto,
+    /// The output that the transition created.
+    pub output_id: String,
+    pub state_index: u32,
+    pub state_controller: Address,
+    pub governor: Address,
+    pub milestone_index_booked: MilestoneIndex,
+    pub milestone_timestamp_booked: MilestoneTimestamp,
+    /// `null` if the output is the current state of the alias.
+    pub milestone_index_spent: Option<MilestoneIndex>,
+    pub milestone_timestamp_spent: Option<MilestoneTimestamp>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sync_status_reports_gap_to_node() {
+        let status = SyncStatusResponse::new(90.into(), Some(100.into()), Some(120.into()));
+        assert_eq!(status.state, SyncState::CatchingUp);
+        assert_eq!(status.gap, 30);
+        assert!(!status.is_synced);
+
+        // Being one milestone behind is expected while the latest one is applied.
+        let status = SyncStatusResponse::new(119.into(), Some(100.into()), Some(120.into()));
+        assert_eq!(status.state, SyncState::Live);
+        assert_eq!(status.gap, 1);
+        assert!(status.is_synced);
+
+        // The node status may be older than the stored ledger.
+        let status = SyncStatusResponse::new(125.into(), Some(100.into()), Some(120.into()));
+        assert_eq!(status.gap, 0);
+        assert!(status.is_synced);
+
+        // Without a known node milestone, the catch-up target is used.
+        let status = SyncStatusResponse::new(90.into(), Some(100.into()), None);
+        assert_eq!(status.gap, 10);
+        assert!(!status.is_synced);
+    }
+
+    #[test]
+    fn sync_status_lists_skipped_milestones() {
+        let status = SyncStatusResponse::new(90.into(), Some(100.into()), None);
+        assert!(serde_json::to_value(&status)
+            .unwrap()
+            .get("skippedMilestones")
+            .is_none());
+
+        let status = SyncStatusResponse {
+            skipped_milestones: vec![SkippedMilestones {
+                start: 6.into(),
+                end: 10.into(),
+            }],
+            ..status
+        };
+        assert_eq!(
+            serde_json::to_value(&status).unwrap()["skippedMilestones"],
+            serde_json::json!([{ "start": 6, "end": 10 }])
+        );
+    }
+}
@@ -3,58 +3,112 @@
 
 use std::str::FromStr;
 
-use axum::{extract::Path, routing::get, Extension};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path,
+    },
+    middleware::from_fn,
+    response::Response,
+    routing::get,
+    Extension,
+};
 use chronicle::{
     db::{
         mongodb::collections::{
-            BlockCollection, LedgerUpdateCollection, MilestoneCollection, OutputCollection, ProtocolUpdateCollection,
+            ApplicationStateCollection, BlockCollection, LedgerUpdateCollection, MilestoneCollection, OutputCollection,
+            ProtocolUpdateCollection,
         },
         MongoDb,
     },
     model::{
         payload::{MilestoneId, MilestonePayload, TaggedDataPayload, TransactionPayload, TreasuryTransactionPayload},
-        tangle::MilestoneIndex,
-        utxo::Address,
+        tangle::{MilestoneIndex, MilestoneTimestamp},
+        utxo::{Address, AliasId, FoundryId, NftId},
         BlockId,
     },
 };
 use futures::{StreamExt, TryStreamExt};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::debug;
 
 use super::{
     extractors::{
-        BlocksByMilestoneCursor, BlocksByMilestoneIdPagination, BlocksByMilestoneIndexPagination, LedgerIndex,
+        AddressActivityCursor, AddressActivityPagination, AmountDistributionParams, BlocksByMilestoneCursor,
+        BlocksByMilestoneIdPagination, BlocksByMilestoneIndexPagination, ClaimedTokensFilter, LedgerIndex,
         LedgerUpdatesByAddressCursor, LedgerUpdatesByAddressPagination, LedgerUpdatesByMilestoneCursor,
         LedgerUpdatesByMilestonePagination, MilestonesCursor, MilestonesPagination, RichestAddressesQuery,
+        TransactionCountQuery,
     },
     responses::{
-        AddressStatDto, BalanceResponse, BlockChildrenResponse, BlockPayloadTypeDto, BlocksByMilestoneResponse,
-        LedgerUpdatesByAddressResponse, LedgerUpdatesByMilestoneResponse, MilestonesResponse, RichestAddressesResponse,
-        TokenDistributionResponse,
+        ActiveAddressesResponse, AddressActivityDto, AddressActivityResponse, AddressStatDto, AliasHistoryResponse,
+        AmountDistributionResponse, BalanceResponse, BlockChildrenResponse, BlockPayloadTypeDto,
+        BlockWithMetadataResponse, BlocksByMilestoneResponse, ClaimedTokensResponse, FoundrySupplyResponse,
+        LedgerUpdatesByAddressResponse, LedgerUpdatesByMilestoneResponse, MilestonesResponse, NftHistoryResponse,
+        OutputHealthResponse, RichestAddressesResponse, StorageDepositByTypeDto, StorageDepositByTypeResponse,
+        StorageDepositResponse, SyncStatusResponse, TokenDistributionResponse, TotalSupplyResponse,
+        TransactionCountByTimeDto, TransactionCountByTimeResponse, TransactionCountDto, TransactionCountResponse,
+        UnspentCountResponse,
     },
 };
-use crate::api::{
-    error::{CorruptStateError, MissingError, RequestError},
-    extractors::Pagination,
-    router::Router,
-    ApiResult,
+use crate::{
+    api::{
+        buckets::MilestoneBucketer,
+        config::ApiConfigData,
+        core::create_block_metadata_response,
+        error::{CorruptStateError, MissingError, RequestError},
+        extractors::{
+            AggregationPermit, BucketedTimeRange, Pagination, ResponseFormat, TimeRange, QUERYABLE_OUTPUT_KINDS,
+        },
+        responses::Negotiated,
+        router::Router,
+        stale::serve_stale,
+        ApiResult,
+    },
+    events::{MilestoneEvent, MilestoneEvents},
 };
 
 pub fn routes() -> Router {
     Router::new()
         .route("/balance/:address", get(balance))
+        .route("/blocks/:block_id", get(block_with_metadata))
         .route("/blocks/:block_id/children", get(block_children))
+        .route("/sync-status", get(sync_status))
+        .route("/output-health", get(output_health))
+        .route("/nft/:nft_id/history", get(nft_history))
+        .route("/alias/:alias_id/history", get(alias_history))
         .nest(
             "/milestones",
             Router::new()
                 .route("/", get(milestones))
+                .route("/stream", get(milestone_stream))
                 .route("/:milestone_id/blocks", get(blocks_by_milestone_id))
                 .route("/by-index/:milestone_index/blocks", get(blocks_by_milestone_index)),
         )
         .nest(
             "/ledger",
             Router::new()
+                .route("/active-addresses", get(active_addresses_ledger_analytics))
+                .route("/active-addresses/by-address", get(address_activity_ledger_analytics))
+                .route("/amount-distribution", get(amount_distribution_ledger_analytics))
+                .route("/claimed-tokens/:milestone_index", get(claimed_tokens_ledger_analytics))
+                .route("/foundry/:foundry_id/supply", get(foundry_supply_ledger_analytics))
                 .route("/richest-addresses", get(richest_addresses_ledger_analytics))
+                .route("/storage-deposit", get(storage_deposit_ledger_analytics))
+                .route(
+                    "/storage-deposit/by-type",
+                    get(storage_deposit_by_type_ledger_analytics),
+                )
+                .route("/supply", get(total_supply_ledger_analytics))
                 .route("/token-distribution", get(token_distribution_ledger_analytics))
+                .route("/transactions", get(transaction_counts_ledger_analytics))
+                .route("/unspent-count", get(unspent_count_ledger_analytics))
+                .route(
+                    "/transactions/by-time",
+                    get(transaction_counts_by_time_ledger_analytics),
+                )
+                // Only applies to the analytics routes above.
+                .route_layer(from_fn(serve_stale))
                 .nest(
                     "/updates",
                     Router::new()
@@ -173,6 +227,73 @@ async fn balance(database: Extension<MongoDb>, Path(address): Path<String>) -> A
     })
 }
 
+async fn nft_history(
+    database: Extension<MongoDb>,
+    Path(nft_id): Path<String>,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<NftHistoryResponse>> {
+    let ledger_index = database
+        .collection::<MilestoneCollection>()
+        .get_ledger_index()
+        .await?
+        .ok_or(MissingError::NoResults)?;
+    let nft_id = NftId::from_str(&nft_id).map_err(RequestError::from)?;
+    let history = database
+        .collection::<OutputCollection>()
+        .get_nft_history(nft_id, ledger_index)
+        .await?;
+    if history.is_empty() {
+        return Err(MissingError::NoResults.into());
+    }
+
+    Ok(Negotiated(
+        format,
+        NftHistoryResponse {
+            items: history.into_iter().map(Into::into).collect(),
+            ledger_index,
+        },
+    ))
+}
+
+async fn alias_history(
+    database: Extension<MongoDb>,
+    Path(alias_id): Path<String>,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<AliasHistoryResponse>> {
+    let ledger_index = database
+        .collection::<MilestoneCollection>()
+        .get_ledger_index()
+        .await?
+        .ok_or(MissingError::NoResults)?;
+    let alias_id = AliasId::from_str(&alias_id).map_err(RequestError::from)?;
+    let history = database
+        .collection::<OutputCollection>()
+        .get_alias_history(alias_id, ledger_index)
+        .await?;
+    if history.is_empty() {
+        return Err(MissingError::NoResults.into());
+    }
+
+    Ok(Negotiated(format, AliasHistoryResponse::new(history, ledger_index)))
+}
+
+async fn block_with_metadata(
+    database: Extension<MongoDb>,
+    Path(block_id): Path<String>,
+) -> ApiResult<BlockWithMetadataResponse> {
+    let block_id = BlockId::from_str(&block_id).map_err(RequestError::from)?;
+    let res = database
+        .collection::<BlockCollection>()
+        .get_block_with_metadata(&block_id)
+        .await?
+        .ok_or(MissingError::NoResults)?;
+
+    Ok(BlockWithMetadataResponse {
+        block: res.block.into(),
+        metadata: create_block_metadata_response(block_id, res.metadata),
+    })
+}
+
 async fn block_children(
     database: Extension<MongoDb>,
     Path(block_id): Path<String>,
@@ -246,6 +367,41 @@ async fn milestones(
     Ok(MilestonesResponse { items, cursor })
 }
 
+async fn milestone_stream(ws: WebSocketUpgrade, Extension(milestone_events): Extension<MilestoneEvents>) -> Response {
+    // Subscribing before the upgrade makes sure that no milestone is missed once the client is connected.
+    let events = milestone_events.subscribe();
+    ws.on_upgrade(|socket| stream_milestones(socket, events))
+}
+
+/// Forwards the milestone events to the client until either side closes the connection. A client that falls behind by
+/// more than [`MILESTONE_EVENTS_CAPACITY`](crate::events::MILESTONE_EVENTS_CAPACITY) events is disconnected, so that
+/// it never receives a stream with gaps.
+async fn stream_milestones(mut socket: WebSocket, mut events: broadcast::Receiver<MilestoneEvent>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let message = serde_json::to_string(&event).expect("milestone event is serializable");
+                    if socket.send(Message::Text(message)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("Disconnecting milestone stream client that lagged behind by {skipped} milestones.");
+                    socket.close().await.ok();
+                    break;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            // Messages from the client are ignored, but reading them is how a closed connection is noticed.
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => (),
+            },
+        }
+    }
+}
+
 async fn blocks_by_milestone_index(
     database: Extension<MongoDb>,
     Path(milestone_index): Path<MilestoneIndex>,
@@ -321,7 +477,9 @@ async fn blocks_by_milestone_id(
 async fn richest_addresses_ledger_analytics(
     database: Extension<MongoDb>,
     RichestAddressesQuery { top, ledger_index }: RichestAddressesQuery,
-) -> ApiResult<RichestAddressesResponse> {
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<RichestAddressesResponse>> {
     let ledger_index = resolve_ledger_index(&database, ledger_index).await?;
     let res = database
         .collection::<OutputCollection>()
@@ -336,35 +494,481 @@ async fn richest_addresses_ledger_analytics(
         .parameters
         .bech32_hrp;
 
-    Ok(RichestAddressesResponse {
-        top: res
-            .top
-            .into_iter()
-            .map(|stat| AddressStatDto {
-                address: iota_types::block::address::Address::from(stat.address).to_bech32(hrp.clone()),
-                balance: stat.balance,
-            })
-            .collect(),
-        ledger_index,
-    })
+    Ok(Negotiated(
+        format,
+        RichestAddressesResponse {
+            top: res
+                .top
+                .into_iter()
+                .map(|stat| AddressStatDto {
+                    address: iota_types::block::address::Address::from(stat.address).to_bech32(hrp.clone()),
+                    balance: stat.balance,
+                })
+                .collect(),
+            ledger_index,
+        },
+    ))
 }
 
 async fn token_distribution_ledger_analytics(
     database: Extension<MongoDb>,
     LedgerIndex { ledger_index }: LedgerIndex,
-) -> ApiResult<TokenDistributionResponse> {
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<TokenDistributionResponse>> {
     let ledger_index = resolve_ledger_index(&database, ledger_index).await?;
     let res = database
         .collection::<OutputCollection>()
         .get_token_distribution(ledger_index)
         .await?;
 
-    Ok(TokenDistributionResponse {
-        distribution: res.distribution.into_iter().map(Into::into).collect(),
+    Ok(Negotiated(
+        format,
+        TokenDistributionResponse {
+            distribution: res.distribution.into_iter().map(Into::into).collect(),
+            ledger_index,
+        },
+    ))
+}
+
+async fn amount_distribution_ledger_analytics(
+    database: Extension<MongoDb>,
+    AmountDistributionParams {
         ledger_index,
+        boundaries,
+    }: AmountDistributionParams,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<AmountDistributionResponse>> {
+    let ledger_index = resolve_ledger_index(&database, ledger_index).await?;
+    let res = database
+        .collection::<OutputCollection>()
+        .get_amount_distribution(ledger_index, &boundaries)
+        .await?;
+
+    Ok(Negotiated(
+        format,
+        AmountDistributionResponse {
+            buckets: res.buckets.into_iter().map(Into::into).collect(),
+            ledger_index,
+        },
+    ))
+}
+
+async fn active_addresses_ledger_analytics(
+    database: Extension<MongoDb>,
+    time_range: TimeRange,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<ActiveAddressesResponse>> {
+    let (start_timestamp, end_timestamp) = resolve_time_range(&database, time_range).await?;
+    let res = database
+        .collection::<OutputCollection>()
+        // The end of the range is inclusive, so that the latest milestone is taken into account.
+        .get_address_activity_count_by_kind(start_timestamp, MilestoneTimestamp(end_timestamp.0.saturating_add(1)))
+        .await?;
+
+    // Report every output type that can be owned by an address, even if no active address owned one.
+    let by_output_type = QUERYABLE_OUTPUT_KINDS
+        .into_iter()
+        .map(|kind| {
+            let count = res
+                .by_kind
+                .iter()
+                .find(|stat| stat.kind == kind)
+                .map_or(0, |stat| stat.count);
+            (kind.to_string(), count)
+        })
+        .collect();
+
+    Ok(Negotiated(
+        format,
+        ActiveAddressesResponse {
+            start_timestamp,
+            end_timestamp,
+            count: res.count,
+            by_output_type,
+        },
+    ))
+}
+
+async fn address_activity_ledger_analytics(
+    database: Extension<MongoDb>,
+    AddressActivityPagination {
+        time_range,
+        page_size,
+        cursor,
+    }: AddressActivityPagination,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<AddressActivityResponse>> {
+    let (start_timestamp, end_timestamp) = resolve_time_range(&database, time_range).await?;
+    let hrp = database
+        .collection::<ProtocolUpdateCollection>()
+        .get_latest_protocol_parameters()
+        .await?
+        .ok_or(CorruptStateError::ProtocolParams)?
+        .parameters
+        .bech32_hrp;
+    let to_bech32 = |address: Address| iota_types::block::address::Address::from(address).to_bech32(hrp.clone());
+
+    let mut record_stream = database
+        .collection::<OutputCollection>()
+        // The end of the range is inclusive, so that the latest milestone is taken into account.
+        .get_address_activity(
+            start_timestamp,
+            MilestoneTimestamp(end_timestamp.0.saturating_add(1)),
+            page_size + 1,
+            cursor,
+        )
+        .await?;
+
+    // Take all of the requested records first
+    let items = record_stream
+        .by_ref()
+        .take(page_size)
+        .map_ok(|rec| AddressActivityDto {
+            address: to_bech32(rec.address),
+            created_count: rec.created_count,
+            consumed_count: rec.consumed_count,
+        })
+        .try_collect()
+        .await?;
+
+    // If any record is left, use it to make the paging state
+    let cursor = record_stream.try_next().await?.map(|rec| {
+        AddressActivityCursor {
+            start_timestamp,
+            end_timestamp,
+            address: to_bech32(rec.address),
+            page_size,
+        }
+        .to_string()
+    });
+
+    Ok(Negotiated(
+        format,
+        AddressActivityResponse {
+            start_timestamp,
+            end_timestamp,
+            items,
+            cursor,
+        },
+    ))
+}
+
+async fn transaction_counts_ledger_analytics(
+    database: Extension<MongoDb>,
+    Extension(config): Extension<ApiConfigData>,
+    query: TransactionCountQuery,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<TransactionCountResponse>> {
+    let end_index = resolve_ledger_index(&database, query.end_index).await?;
+    let start_index = query.start_index(end_index, config.max_page_size)?;
+    let interval = query.interval;
+
+    let mut counts = database
+        .collection::<OutputCollection>()
+        .get_transaction_counts(start_index, end_index, interval)
+        .await?
+        .into_iter()
+        .peekable();
+
+    // Intervals without any transactions are not returned by the database.
+    let items = (start_index.0..=end_index.0)
+        .step_by(interval as usize)
+        .map(|start| TransactionCountDto {
+            start_index: start.into(),
+            end_index: start.saturating_add(interval - 1).min(end_index.0).into(),
+            count: counts
+                .next_if(|res| res.start_index == start)
+                .map_or(0, |res| res.count),
+        })
+        .collect();
+
+    Ok(Negotiated(format, TransactionCountResponse { interval, items }))
+}
+
+async fn transaction_counts_by_time_ledger_analytics(
+    database: Extension<MongoDb>,
+    Extension(config): Extension<ApiConfigData>,
+    BucketedTimeRange { time_range, interval }: BucketedTimeRange,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<TransactionCountByTimeResponse>> {
+    let bucketer = MilestoneBucketer::new(resolve_time_range(&database, time_range).await?, interval);
+    if bucketer.len() > config.max_page_size {
+        return Err(RequestError::TooManyIntervals {
+            found: bucketer.len(),
+            max: config.max_page_size,
+        }
+        .into());
+    }
+
+    let collection = database.collection::<OutputCollection>();
+    let mut items = Vec::with_capacity(bucketer.len());
+    for bucket in bucketer.buckets(&database.collection()).await? {
+        let count = match bucket.milestones {
+            // The whole bucket is counted as a single interval.
+            Some((start_index, end_index)) => collection
+                .get_transaction_counts(start_index, end_index, end_index.0 - start_index.0 + 1)
+                .await?
+                .first()
+                .map_or(0, |res| res.count),
+            None => 0,
+        };
+        items.push(TransactionCountByTimeDto {
+            start_timestamp: bucket.start_timestamp,
+            end_timestamp: bucket.end_timestamp,
+            count,
+        });
+    }
+
+    Ok(Negotiated(format, TransactionCountByTimeResponse { interval, items }))
+}
+
+async fn foundry_supply_ledger_analytics(
+    database: Extension<MongoDb>,
+    Path(foundry_id): Path<String>,
+    LedgerIndex { ledger_index }: LedgerIndex,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<FoundrySupplyResponse>> {
+    let foundry_id = FoundryId::from_str(&foundry_id).map_err(RequestError::from)?;
+    let ledger_index = resolve_ledger_index(&database, ledger_index).await?;
+    let res = database
+        .collection::<OutputCollection>()
+        .get_foundry_supply(foundry_id, ledger_index)
+        .await?
+        .ok_or(MissingError::NoResults)?;
+
+    Ok(Negotiated(format, FoundrySupplyResponse::new(res, ledger_index)))
+}
+
+async fn total_supply_ledger_analytics(
+    database: Extension<MongoDb>,
+    LedgerIndex { ledger_index }: LedgerIndex,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<TotalSupplyResponse>> {
+    let ledger_index = resolve_ledger_index(&database, ledger_index).await?;
+    let ledger_timestamp = database
+        .collection::<MilestoneCollection>()
+        .get_milestone_timestamp(ledger_index)
+        .await?
+        .ok_or(MissingError::NoResults)?;
+    let res = database
+        .collection::<OutputCollection>()
+        .get_total_supply(ledger_index)
+        .await?;
+    let locked = database
+        .collection::<OutputCollection>()
+        .get_locked_supply(ledger_index, ledger_timestamp)
+        .await?;
+
+    let expected_supply = database
+        .collection::<ProtocolUpdateCollection>()
+        .get_protocol_parameters_for_ledger_index(ledger_index)
+        .await?
+        .ok_or(CorruptStateError::ProtocolParams)?
+        .parameters
+        .token_supply;
+
+    // The sum is not necessarily a valid `u64` if the ledger is inconsistent.
+    let reconciled = res.amount.parse::<u128>().ok() == Some(expected_supply as u128);
+    if !reconciled {
+        tracing::warn!(
+            "Unspent outputs at milestone {ledger_index} sum up to {}, but the token supply is {expected_supply}.",
+            res.amount
+        );
+    }
+
+    Ok(Negotiated(
+        format,
+        TotalSupplyResponse {
+            ledger_index,
+            output_count: res.count,
+            total_supply: res.amount,
+            locked_supply: locked.locked_amount,
+            unlocked_supply: locked.unlocked_amount,
+            expected_supply: expected_supply.to_string(),
+            reconciled,
+        },
+    ))
+}
+
+async fn storage_deposit_ledger_analytics(
+    database: Extension<MongoDb>,
+    LedgerIndex { ledger_index }: LedgerIndex,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<StorageDepositResponse>> {
+    let ledger_index = resolve_ledger_index(&database, ledger_index).await?;
+    let rent_structure = database
+        .collection::<ProtocolUpdateCollection>()
+        .get_protocol_parameters_for_ledger_index(ledger_index)
+        .await?
+        .ok_or(CorruptStateError::ProtocolParams)?
+        .parameters
+        .rent_structure;
+
+    let res = database
+        .collection::<OutputCollection>()
+        .get_storage_deposit_analytics(ledger_index, &rent_structure)
+        .await?;
+
+    Ok(Negotiated(
+        format,
+        StorageDepositResponse {
+            ledger_index,
+            output_count: res.output_count,
+            key_bytes: res.key_bytes,
+            data_bytes: res.data_bytes,
+            storage_deposit: res.storage_deposit.to_string(),
+        },
+    ))
+}
+
+async fn storage_deposit_by_type_ledger_analytics(
+    database: Extension<MongoDb>,
+    LedgerIndex { ledger_index }: LedgerIndex,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<StorageDepositByTypeResponse>> {
+    let ledger_index = resolve_ledger_index(&database, ledger_index).await?;
+    let stats = database
+        .collection::<OutputCollection>()
+        .get_storage_deposit_by_kind(ledger_index)
+        .await?;
+
+    let rent_structure = database
+        .collection::<ProtocolUpdateCollection>()
+        .get_protocol_parameters_for_ledger_index(ledger_index)
+        .await?
+        .ok_or(CorruptStateError::ProtocolParams)?
+        .parameters
+        .rent_structure;
+
+    // Report every output type, even if there are no unspent outputs of that type.
+    let items = QUERYABLE_OUTPUT_KINDS
+        .into_iter()
+        .map(|kind| {
+            let stat = stats.iter().find(|stat| stat.kind == kind).cloned().unwrap_or_default();
+            StorageDepositByTypeDto {
+                kind: kind.to_string(),
+                count: stat.count,
+                key_bytes: stat.key_bytes,
+                data_bytes: stat.data_bytes,
+                storage_deposit: stat.storage_deposit(&rent_structure).to_string(),
+            }
+        })
+        .collect();
+
+    Ok(Negotiated(format, StorageDepositByTypeResponse { items, ledger_index }))
+}
+
+async fn unspent_count_ledger_analytics(
+    database: Extension<MongoDb>,
+    LedgerIndex { ledger_index }: LedgerIndex,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<UnspentCountResponse>> {
+    let ledger_index = resolve_ledger_index(&database, ledger_index).await?;
+    let unspent_count = database
+        .collection::<OutputCollection>()
+        .count_unspent_outputs(ledger_index)
+        .await?;
+
+    Ok(Negotiated(
+        format,
+        UnspentCountResponse {
+            ledger_index,
+            unspent_count,
+        },
+    ))
+}
+
+async fn output_health(
+    database: Extension<MongoDb>,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<OutputHealthResponse>> {
+    let health = database.collection::<OutputCollection>().get_output_health().await?;
+    let milestone_timestamp_violations = database
+        .collection::<ApplicationStateCollection>()
+        .get_milestone_timestamp_violations()
+        .await?;
+
+    if health.spent_before_booked > 0 {
+        tracing::warn!(
+            "Found {} outputs that were spent before they were booked.",
+            health.spent_before_booked
+        );
+    }
+    if milestone_timestamp_violations > 0 {
+        tracing::warn!(
+            "Found {} milestones with a timestamp earlier than their predecessor's.",
+            milestone_timestamp_violations
+        );
+    }
+
+    Ok(Negotiated(
+        format,
+        OutputHealthResponse {
+            total: health.total,
+            spent: health.spent,
+            unspent: health.total - health.spent,
+            spent_ratio: if health.total > 0 {
+                health.spent as f64 / health.total as f64
+            } else {
+                0.0
+            },
+            spent_before_booked: health.spent_before_booked,
+            milestone_timestamp_violations,
+        },
+    ))
+}
+
+async fn sync_status(database: Extension<MongoDb>) -> ApiResult<SyncStatusResponse> {
+    let ledger_index = database
+        .collection::<MilestoneCollection>()
+        .get_ledger_index()
+        .await?
+        .ok_or(MissingError::NoResults)?;
+    let application_state = database.collection::<ApplicationStateCollection>();
+    let target_index = application_state.get_sync_target().await?;
+    let latest_known_index = application_state.get_latest_known_index().await?;
+    let skipped_milestones = application_state.get_skipped_milestones().await?;
+
+    Ok(SyncStatusResponse {
+        skipped_milestones,
+        ..SyncStatusResponse::new(ledger_index, target_index, latest_known_index)
     })
 }
 
+async fn claimed_tokens_ledger_analytics(
+    database: Extension<MongoDb>,
+    Path(milestone_index): Path<MilestoneIndex>,
+    ClaimedTokensFilter { address, kind }: ClaimedTokensFilter,
+    _permit: AggregationPermit,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<ClaimedTokensResponse>> {
+    let res = database
+        .collection::<OutputCollection>()
+        .get_claimed_tokens(milestone_index, address, kind)
+        .await?;
+
+    Ok(Negotiated(
+        format,
+        ClaimedTokensResponse {
+            milestone_index,
+            count: res.count,
+            amount: res.amount,
+        },
+    ))
+}
+
 /// This is just a helper fn to either unwrap an optional ledger index param or fetch the latest
 /// index from the database.
 async fn resolve_ledger_index(database: &MongoDb, ledger_index: Option<MilestoneIndex>) -> ApiResult<MilestoneIndex> {
@@ -378,3 +982,92 @@ async fn resolve_ledger_index(database: &MongoDb, ledger_index: Option<Milestone
             .ok_or(MissingError::NoResults)?
     })
 }
+
+/// Resolves the omitted bounds of a time range to the timestamps of the oldest and newest stored milestones.
+async fn resolve_time_range(
+    database: &MongoDb,
+    time_range: TimeRange,
+) -> ApiResult<(MilestoneTimestamp, MilestoneTimestamp)> {
+    let milestones = database.collection::<MilestoneCollection>();
+    let genesis = milestones
+        .get_oldest_milestone()
+        .await?
+        .ok_or(MissingError::NoResults)?;
+    let latest = milestones
+        .get_newest_milestone()
+        .await?
+        .ok_or(MissingError::NoResults)?;
+    Ok(time_range.resolve(genesis.milestone_timestamp, latest.milestone_timestamp))
+}
+
+#[cfg(test)]
+mod test {
+    use chronicle::db::MongoDbConfig;
+    use hyper::{server::conn::AddrIncoming, Server, StatusCode};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    use super::*;
+    use crate::{api::error::ErrorStatus, events::milestone_events};
+
+    #[tokio::test]
+    async fn malformed_block_id_is_rejected() {
+        // Creating the client does not contact the server, and the id is rejected before any query is made.
+        let db = MongoDb::connect(&MongoDbConfig::default()).await.unwrap();
+        for block_id in ["0x1234", "not-a-block-id"] {
+            let err = block_with_metadata(Extension(db.clone()), Path(block_id.to_string()))
+                .await
+                .unwrap_err();
+            let err = err.error.downcast_ref::<RequestError>().unwrap();
+            assert_eq!(err.status(), StatusCode::BAD_REQUEST, "{block_id}");
+        }
+    }
+
+    #[tokio::test]
+    async fn milestones_are_streamed() {
+        let events = milestone_events();
+        let routes = Router::new()
+            .route("/stream", get(milestone_stream))
+            .layer(Extension(events.clone()));
+        let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
+        let addr = incoming.local_addr();
+        tokio::spawn(Server::builder(incoming).serve(routes.into_make_service()));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(
+                b"GET /stream HTTP/1.1\r\nhost: localhost\r\nconnection: upgrade\r\nupgrade: websocket\r\n\
+                sec-websocket-version: 13\r\nsec-websocket-key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            response.push(stream.read_u8().await.unwrap());
+        }
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101"), "{response}");
+
+        // The client is subscribed by the time the upgrade is answered.
+        let event = MilestoneEvent {
+            milestone_index: 42,
+            milestone_timestamp: 1_700_000_000,
+            block_count: 7,
+        };
+        events.send(event).unwrap();
+
+        // An unmasked, unfragmented text frame that is shorter than 126 bytes.
+        assert_eq!(stream.read_u8().await.unwrap(), 0x81);
+        let len = stream.read_u8().await.unwrap();
+        assert!(len < 126);
+        let mut payload = vec![0; len as usize];
+        stream.read_exact(&mut payload).await.unwrap();
+        let json = serde_json::from_slice::<serde_json::Value>(&payload).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "milestoneIndex": 42, "milestoneTimestamp": 1_700_000_000, "blockCount": 7 })
+        );
+    }
+}
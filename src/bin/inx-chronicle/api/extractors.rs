@@ -1,20 +1,65 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::time::Duration;
+
 use async_trait::async_trait;
 use axum::{
     extract::{FromRequest, Query},
     Extension,
 };
-use chronicle::model::tangle::MilestoneTimestamp;
+use chronicle::model::{
+    tangle::MilestoneTimestamp,
+    utxo::{AliasOutput, BasicOutput, FoundryOutput, NftOutput, OutputId},
+};
 use serde::Deserialize;
+use tokio::sync::OwnedSemaphorePermit;
 
 use super::{
+    buckets::BucketInterval,
     config::ApiConfigData,
-    error::{ApiError, RequestError},
+    error::{ApiError, BusyError, RequestError},
+    routes::CBOR_CONTENT_HEADER,
     DEFAULT_PAGE_SIZE,
 };
 
+/// The output kinds that can be used to filter queries.
+pub const QUERYABLE_OUTPUT_KINDS: [&str; 4] = [
+    BasicOutput::KIND,
+    AliasOutput::KIND,
+    FoundryOutput::KIND,
+    NftOutput::KIND,
+];
+
+/// Parses an output kind query parameter, such as `basic` or `nft`.
+pub fn parse_output_kind(kind: &str) -> Result<&'static str, RequestError> {
+    QUERYABLE_OUTPUT_KINDS
+        .into_iter()
+        .find(|k| *k == kind)
+        .ok_or_else(|| RequestError::InvalidOutputKind(kind.to_string()))
+}
+
+/// Parses an output id, given either as `0x`-prefixed hex or as the hex encoding of its raw bytes without the prefix.
+pub fn parse_output_id(id: &str) -> Result<OutputId, RequestError> {
+    let invalid = |reason: String| RequestError::InvalidOutputId {
+        id: id.to_string(),
+        reason,
+    };
+    let hex = id.strip_prefix("0x").unwrap_or(id);
+    let bytes = hex::decode(hex).map_err(|e| invalid(e.to_string()))?;
+    if bytes.len() != iota_types::block::output::OutputId::LENGTH {
+        return Err(invalid(format!(
+            "expected {} bytes, found {}",
+            iota_types::block::output::OutputId::LENGTH,
+            bytes.len()
+        )));
+    }
+    // The transaction id and output index are validated by the conversion.
+    format!("0x{hex}")
+        .parse()
+        .map_err(|e: iota_types::block::Error| invalid(e.to_string()))
+}
+
 #[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq)]
 #[serde(default, deny_unknown_fields, rename_all = "camelCase")]
 pub struct Pagination {
@@ -70,10 +115,64 @@ pub struct TimeRangeQuery {
     end_timestamp: Option<u32>,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct TimeRange {
     pub start_timestamp: Option<MilestoneTimestamp>,
     pub end_timestamp: Option<MilestoneTimestamp>,
+    /// The longest span in seconds that the resolved range may cover, or `None` if it is unbounded.
+    pub max_span: Option<u32>,
+}
+
+impl TimeRange {
+    /// Creates a time range, and checks that its bounds are ordered and do not span more than `max_time_range`. A
+    /// maximum of zero allows any range.
+    pub fn new(
+        start_timestamp: Option<MilestoneTimestamp>,
+        end_timestamp: Option<MilestoneTimestamp>,
+        max_time_range: Duration,
+    ) -> Result<Self, RequestError> {
+        let max_span = (!max_time_range.is_zero()).then(|| max_time_range.as_secs().min(u32::MAX as u64) as u32);
+        if let (Some(start), Some(end)) = (start_timestamp, end_timestamp) {
+            if end < start {
+                return Err(RequestError::BadTimeRange);
+            }
+            if matches!(max_span, Some(max_span) if end.0 - start.0 > max_span) {
+                return Err(RequestError::TimeRangeTooLarge { max: max_time_range });
+            }
+        }
+        Ok(Self {
+            start_timestamp,
+            end_timestamp,
+            max_span,
+        })
+    }
+
+    /// Fills in the omitted bounds, so that the range starts at the `genesis` milestone and ends at the `latest` one
+    /// unless requested otherwise. If the span is limited, an omitted bound is moved closer to the other one instead,
+    /// so that the range does not exceed the limit.
+    pub fn resolve(
+        self,
+        genesis: MilestoneTimestamp,
+        latest: MilestoneTimestamp,
+    ) -> (MilestoneTimestamp, MilestoneTimestamp) {
+        let max_span = match self.max_span {
+            Some(max_span) => max_span,
+            None => {
+                return (
+                    self.start_timestamp.unwrap_or(genesis),
+                    self.end_timestamp.unwrap_or(latest),
+                );
+            }
+        };
+        match (self.start_timestamp, self.end_timestamp) {
+            (Some(start), Some(end)) => (start, end),
+            (Some(start), None) => (start, latest.min(start.0.saturating_add(max_span).into())),
+            (None, end) => {
+                let end = end.unwrap_or(latest);
+                (genesis.max(end.0.saturating_sub(max_span).into()), end)
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -87,14 +186,193 @@ impl<B: Send> FromRequest<B> for TimeRange {
         }) = Query::<TimeRangeQuery>::from_request(req)
             .await
             .map_err(RequestError::from)?;
-        if matches!((start_timestamp, end_timestamp), (Some(start), Some(end)) if end < start) {
-            return Err(ApiError::from(RequestError::BadTimeRange));
+        let Extension(config) = Extension::<ApiConfigData>::from_request(req).await?;
+        Ok(TimeRange::new(
+            start_timestamp.map(Into::into),
+            end_timestamp.map(Into::into),
+            config.max_time_range,
+        )?)
+    }
+}
+
+#[derive(Copy, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct BucketedTimeRangeQuery {
+    start_timestamp: Option<u32>,
+    end_timestamp: Option<u32>,
+    interval: BucketInterval,
+}
+
+impl Default for BucketedTimeRangeQuery {
+    fn default() -> Self {
+        Self {
+            start_timestamp: None,
+            end_timestamp: None,
+            interval: BucketInterval::Day,
+        }
+    }
+}
+
+/// A [`TimeRange`] that is split into buckets of the given interval.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BucketedTimeRange {
+    pub time_range: TimeRange,
+    pub interval: BucketInterval,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for BucketedTimeRange {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(BucketedTimeRangeQuery {
+            start_timestamp,
+            end_timestamp,
+            interval,
+        }) = Query::<BucketedTimeRangeQuery>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        let Extension(config) = Extension::<ApiConfigData>::from_request(req).await?;
+        Ok(BucketedTimeRange {
+            time_range: TimeRange::new(
+                start_timestamp.map(Into::into),
+                end_timestamp.map(Into::into),
+                config.max_time_range,
+            )?,
+            interval,
+        })
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct FieldSelectionQuery {
+    fields: Option<String>,
+}
+
+/// A selection of response fields, given as a comma-separated list via `?fields=`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldSelection(Option<Vec<String>>);
+
+impl FieldSelection {
+    /// Returns `true` if no selection was requested and the full response should be returned.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns `true` if the given field was selected.
+    pub fn contains(&self, field: &str) -> bool {
+        matches!(&self.0, Some(fields) if fields.iter().any(|f| f == field))
+    }
+
+    /// Checks that all selected fields are part of the `known` fields.
+    pub fn validate(&self, known: &[&str]) -> Result<(), RequestError> {
+        if let Some(field) = self.0.iter().flatten().find(|f| !known.contains(&f.as_str())) {
+            return Err(RequestError::UnknownField(field.clone()));
+        }
+        Ok(())
+    }
+
+    /// Returns the parts whose field was not selected, so that the database can leave them out. Nothing is left out if
+    /// no selection was requested.
+    pub fn omitted<T: Copy>(&self, parts: &[(&str, T)]) -> Vec<T> {
+        match &self.0 {
+            Some(fields) => parts
+                .iter()
+                .filter(|(field, _)| !fields.iter().any(|f| f == field))
+                .map(|(_, part)| *part)
+                .collect(),
+            None => Vec::new(),
         }
-        let time_range = TimeRange {
-            start_timestamp: start_timestamp.map(Into::into),
-            end_timestamp: end_timestamp.map(Into::into),
+    }
+
+    /// Removes all keys that were not selected from the given JSON object. Values that are not objects are returned
+    /// unchanged. This trims the fields that the database could not leave out.
+    pub fn project(&self, mut value: serde_json::Value) -> serde_json::Value {
+        if let (Some(fields), Some(object)) = (&self.0, value.as_object_mut()) {
+            object.retain(|key, _| fields.contains(key));
+        }
+        value
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for FieldSelection {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(FieldSelectionQuery { fields }) = Query::<FieldSelectionQuery>::from_request(req)
+            .await
+            .map_err(RequestError::from)?;
+        Ok(FieldSelection(fields.map(|fields| {
+            fields
+                .split(',')
+                .map(str::trim)
+                .filter(|f| !f.is_empty())
+                .map(ToOwned::to_owned)
+                .collect()
+        })))
+    }
+}
+
+/// The serialization format of a response, negotiated via the `Accept` header. Defaults to JSON.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    Cbor,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for ResponseFormat {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let accepts_cbor = req
+            .headers()
+            .get_all(axum::http::header::ACCEPT)
+            .iter()
+            .filter_map(|header| header.to_str().ok())
+            .flat_map(|header| header.split(','))
+            .any(|media_type| media_type.split(';').next().unwrap_or_default().trim() == CBOR_CONTENT_HEADER);
+        Ok(if accepts_cbor { Self::Cbor } else { Self::Json })
+    }
+}
+
+/// A slot for running an expensive aggregation. Handlers that take this extractor are rejected with
+/// [`BusyError`] if no slot becomes available within the configured timeout, or right away if too many requests are
+/// already waiting for one. The slot is released when the handler returns, whether it succeeded or not.
+#[derive(Debug)]
+pub struct AggregationPermit {
+    _admission: OwnedSemaphorePermit,
+    _permit: OwnedSemaphorePermit,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for AggregationPermit {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Extension(config) = Extension::<ApiConfigData>::from_request(req).await?;
+        let busy = BusyError {
+            retry_after: config.aggregation_timeout,
         };
-        Ok(time_range)
+        let admission = match config.aggregation_admissions.clone().try_acquire_owned() {
+            Ok(admission) => admission,
+            Err(_) => return Err(busy.into()),
+        };
+        match tokio::time::timeout(
+            config.aggregation_timeout,
+            config.aggregation_permits.clone().acquire_owned(),
+        )
+        .await
+        {
+            // Unwrap: The semaphore is never closed.
+            Ok(permit) => Ok(AggregationPermit {
+                _admission: admission,
+                _permit: permit.unwrap(),
+            }),
+            Err(_) => Err(busy.into()),
+        }
     }
 }
 
@@ -106,7 +384,40 @@ mod test {
     };
 
     use super::*;
-    use crate::api::ApiConfig;
+    use crate::api::{error::ErrorStatus, ApiConfig};
+
+    const OUTPUT_ID: &str = "0x52fdfc072182654f163f5f0f9a621d729566c74d10037c4d7bbb0407d1e2c6490000";
+
+    #[test]
+    fn output_id_is_parsed_from_prefix_hex() {
+        let output_id = parse_output_id(OUTPUT_ID).unwrap();
+        assert_eq!(output_id.to_hex(), OUTPUT_ID);
+    }
+
+    #[test]
+    fn output_id_without_prefix_is_parsed_from_raw_bytes() {
+        assert_eq!(
+            parse_output_id(OUTPUT_ID.trim_start_matches("0x")).unwrap(),
+            parse_output_id(OUTPUT_ID).unwrap()
+        );
+        assert!(matches!(
+            parse_output_id("0xnot-hex"),
+            Err(RequestError::InvalidOutputId { .. })
+        ));
+    }
+
+    #[test]
+    fn output_id_with_wrong_length_is_rejected() {
+        let err = parse_output_id(&OUTPUT_ID[..OUTPUT_ID.len() - 2]).unwrap_err();
+        assert_eq!(err.status(), hyper::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "invalid output id `{}`: expected 34 bytes, found 33",
+                &OUTPUT_ID[..OUTPUT_ID.len() - 2]
+            )
+        );
+    }
 
     #[tokio::test]
     async fn page_size_clamped() {
@@ -126,4 +437,278 @@ mod test {
             }
         );
     }
+
+    fn time_range_request(uri: &str, max_time_range: Duration) -> RequestParts<()> {
+        RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .extension(
+                    ApiConfigData::try_from(ApiConfig {
+                        max_time_range,
+                        ..Default::default()
+                    })
+                    .unwrap(),
+                )
+                .body(())
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn time_range_defaults_to_genesis_and_latest() {
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/")
+                .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                .body(())
+                .unwrap(),
+        );
+        let time_range = TimeRange::from_request(&mut req).await.unwrap();
+        assert_eq!(
+            time_range,
+            TimeRange {
+                start_timestamp: None,
+                end_timestamp: None,
+                max_span: None,
+            }
+        );
+        assert_eq!(
+            time_range.resolve(100.into(), 500.into()),
+            (MilestoneTimestamp(100), MilestoneTimestamp(500))
+        );
+
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/?startTimestamp=200&endTimestamp=300")
+                .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                .body(())
+                .unwrap(),
+        );
+        let time_range = TimeRange::from_request(&mut req).await.unwrap();
+        assert_eq!(
+            time_range.resolve(100.into(), 500.into()),
+            (MilestoneTimestamp(200), MilestoneTimestamp(300))
+        );
+    }
+
+    #[tokio::test]
+    async fn time_range_is_validated() {
+        let max_time_range = Duration::from_secs(100);
+
+        let mut req = time_range_request("/?startTimestamp=300&endTimestamp=200", max_time_range);
+        let err = TimeRange::from_request(&mut req).await.unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<RequestError>(),
+            Some(RequestError::BadTimeRange)
+        ));
+
+        let mut req = time_range_request("/?startTimestamp=200&endTimestamp=301", max_time_range);
+        let err = TimeRange::from_request(&mut req).await.unwrap_err();
+        let err = err.error.downcast_ref::<RequestError>().unwrap();
+        assert_eq!(err.status(), hyper::StatusCode::BAD_REQUEST);
+        assert!(matches!(err, RequestError::TimeRangeTooLarge { max } if *max == max_time_range));
+        assert_eq!(err.to_string(), "the time range must not span more than 1m 40s");
+
+        let mut req = time_range_request("/?startTimestamp=200&endTimestamp=300", max_time_range);
+        assert!(TimeRange::from_request(&mut req).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn open_time_range_is_limited() {
+        let max_time_range = Duration::from_secs(100);
+        let resolve = |uri: &str| {
+            let mut req = time_range_request(uri, max_time_range);
+            async move {
+                TimeRange::from_request(&mut req)
+                    .await
+                    .unwrap()
+                    .resolve(1000.into(), 5000.into())
+            }
+        };
+
+        // Without bounds, the range ends at the latest milestone.
+        assert_eq!(resolve("/").await, (MilestoneTimestamp(4900), MilestoneTimestamp(5000)));
+        assert_eq!(
+            resolve("/?startTimestamp=2000").await,
+            (MilestoneTimestamp(2000), MilestoneTimestamp(2100))
+        );
+        assert_eq!(
+            resolve("/?endTimestamp=2000").await,
+            (MilestoneTimestamp(1900), MilestoneTimestamp(2000))
+        );
+        // The filled in bounds do not go beyond the stored milestones.
+        assert_eq!(
+            resolve("/?startTimestamp=4950").await,
+            (MilestoneTimestamp(4950), MilestoneTimestamp(5000))
+        );
+        assert_eq!(
+            resolve("/?endTimestamp=1050").await,
+            (MilestoneTimestamp(1000), MilestoneTimestamp(1050))
+        );
+    }
+
+    #[tokio::test]
+    async fn bucketed_time_range_defaults_to_days() {
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/")
+                .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                .body(())
+                .unwrap(),
+        );
+        assert_eq!(
+            BucketedTimeRange::from_request(&mut req).await.unwrap().interval,
+            BucketInterval::Day
+        );
+
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/?startTimestamp=200&interval=hour")
+                .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                .body(())
+                .unwrap(),
+        );
+        assert_eq!(
+            BucketedTimeRange::from_request(&mut req).await.unwrap(),
+            BucketedTimeRange {
+                time_range: TimeRange {
+                    start_timestamp: Some(200.into()),
+                    end_timestamp: None,
+                    max_span: None,
+                },
+                interval: BucketInterval::Hour,
+            }
+        );
+
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/?interval=month")
+                .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                .body(())
+                .unwrap(),
+        );
+        assert!(BucketedTimeRange::from_request(&mut req).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn field_selection_projects_known_fields() {
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/?fields=amount")
+                .body(())
+                .unwrap(),
+        );
+        let fields = FieldSelection::from_request(&mut req).await.unwrap();
+        fields.validate(&["type", "amount"]).unwrap();
+        assert_eq!(
+            fields.project(serde_json::json!({ "type": 3, "amount": "1000" })),
+            serde_json::json!({ "amount": "1000" })
+        );
+
+        let mut req = RequestParts::new(Request::builder().method("GET").uri("/?fields=amout").body(()).unwrap());
+        let fields = FieldSelection::from_request(&mut req).await.unwrap();
+        assert!(matches!(
+            fields.validate(&["type", "amount"]),
+            Err(RequestError::UnknownField(f)) if f == "amout"
+        ));
+    }
+
+    #[tokio::test]
+    async fn field_selection_omits_unselected_parts() {
+        const PARTS: &[(&str, u8)] = &[("features", 1), ("nativeTokens", 2)];
+
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/?fields=amount,features")
+                .body(())
+                .unwrap(),
+        );
+        let fields = FieldSelection::from_request(&mut req).await.unwrap();
+        assert_eq!(fields.omitted(PARTS), vec![2]);
+
+        let mut req = RequestParts::new(Request::builder().method("GET").uri("/").body(()).unwrap());
+        let fields = FieldSelection::from_request(&mut req).await.unwrap();
+        assert!(fields.omitted(PARTS).is_empty());
+    }
+
+    #[tokio::test]
+    async fn aggregations_are_limited() {
+        let config = ApiConfigData::try_from(ApiConfig {
+            max_concurrent_aggregations: 1,
+            aggregation_timeout: std::time::Duration::from_millis(10),
+            ..Default::default()
+        })
+        .unwrap();
+        let request = || RequestParts::new(Request::builder().extension(config.clone()).body(()).unwrap());
+
+        let permit = AggregationPermit::from_request(&mut request()).await.unwrap();
+        let err = AggregationPermit::from_request(&mut request()).await.unwrap_err();
+        assert!(err.error.is::<BusyError>());
+
+        drop(permit);
+        assert!(AggregationPermit::from_request(&mut request()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn aggregation_queue_is_bounded() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        use axum::response::IntoResponse;
+
+        let config = ApiConfigData::try_from(ApiConfig {
+            max_concurrent_aggregations: 2,
+            max_queued_aggregations: 3,
+            aggregation_timeout: std::time::Duration::from_millis(1500),
+            ..Default::default()
+        })
+        .unwrap();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let requests = (0..10)
+            .map(|_| {
+                let config = config.clone();
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                tokio::spawn(async move {
+                    let mut req = RequestParts::new(Request::builder().extension(config).body(()).unwrap());
+                    let _permit = AggregationPermit::from_request(&mut req).await?;
+                    let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(running, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok::<_, ApiError>(())
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut rejected = Vec::new();
+        for request in requests {
+            if let Err(err) = request.await.unwrap() {
+                rejected.push(err);
+            }
+        }
+
+        // Two requests run at once and three wait for them, the others are turned away.
+        assert_eq!(rejected.len(), 5);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+
+        let response = rejected.into_iter().next().unwrap().into_response();
+        assert_eq!(response.status(), hyper::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[hyper::header::RETRY_AFTER], "2");
+
+        // All slots were released again.
+        assert_eq!(config.aggregation_permits.available_permits(), 2);
+        assert_eq!(config.aggregation_admissions.available_permits(), 5);
+    }
 }
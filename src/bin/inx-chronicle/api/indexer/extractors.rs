@@ -31,6 +31,8 @@ where
     pub cursor: Option<(MilestoneIndex, OutputId)>,
     pub sort: SortOrder,
     pub include_spent: bool,
+    /// Whether the total number of matching outputs should be counted.
+    pub count: bool,
 }
 
 #[derive(Clone)]
@@ -92,6 +94,7 @@ pub struct BasicOutputsPaginationQuery {
     pub cursor: Option<String>,
     pub sort: Option<String>,
     pub include_spent: Option<bool>,
+    pub count: Option<bool>,
 }
 
 #[async_trait]
@@ -169,6 +172,7 @@ impl<B: Send> FromRequest<B> for IndexedOutputsPagination<BasicOutputsQuery> {
             cursor,
             sort,
             include_spent: query.include_spent.unwrap_or_default(),
+            count: query.count.unwrap_or_default(),
         })
     }
 }
@@ -189,6 +193,7 @@ pub struct AliasOutputsPaginationQuery {
     pub cursor: Option<String>,
     pub sort: Option<String>,
     pub include_spent: Option<bool>,
+    pub count: Option<bool>,
 }
 
 #[async_trait]
@@ -254,6 +259,7 @@ impl<B: Send> FromRequest<B> for IndexedOutputsPagination<AliasOutputsQuery> {
             cursor,
             sort,
             include_spent: query.include_spent.unwrap_or_default(),
+            count: query.count.unwrap_or_default(),
         })
     }
 }
@@ -271,6 +277,7 @@ pub struct FoundryOutputsPaginationQuery {
     pub cursor: Option<String>,
     pub sort: Option<String>,
     pub include_spent: Option<bool>,
+    pub count: Option<bool>,
 }
 
 #[async_trait]
@@ -321,6 +328,7 @@ impl<B: Send> FromRequest<B> for IndexedOutputsPagination<FoundryOutputsQuery> {
             cursor,
             sort,
             include_spent: query.include_spent.unwrap_or_default(),
+            count: query.count.unwrap_or_default(),
         })
     }
 }
@@ -350,6 +358,7 @@ pub struct NftOutputsPaginationQuery {
     pub cursor: Option<String>,
     pub sort: Option<String>,
     pub include_spent: Option<bool>,
+    pub count: Option<bool>,
 }
 
 #[async_trait]
@@ -432,6 +441,7 @@ impl<B: Send> FromRequest<B> for IndexedOutputsPagination<NftOutputsQuery> {
             cursor,
             sort,
             include_spent: query.include_spent.unwrap_or_default(),
+            count: query.count.unwrap_or_default(),
         })
     }
 }
@@ -473,8 +483,27 @@ mod test {
                 query: Default::default(),
                 cursor: Default::default(),
                 sort: Default::default(),
-                include_spent: Default::default()
+                include_spent: Default::default(),
+                count: Default::default(),
             }
         );
     }
+
+    #[tokio::test]
+    async fn count_is_opt_in() {
+        let mut req = RequestParts::new(
+            Request::builder()
+                .method("GET")
+                .uri("/outputs/nft?count=true")
+                .extension(ApiConfigData::try_from(ApiConfig::default()).unwrap())
+                .body(())
+                .unwrap(),
+        );
+        assert!(
+            IndexedOutputsPagination::<NftOutputsQuery>::from_request(&mut req)
+                .await
+                .unwrap()
+                .count
+        );
+    }
 }
@@ -13,6 +13,12 @@ pub struct IndexerOutputsResponse {
     pub items: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cursor: Option<String>,
+    /// The total number of matching outputs, only present if requested with `count=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    /// Whether counting stopped at the configured maximum, in which case `total` is a lower bound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_capped: Option<bool>,
 }
 
 impl_success_response!(IndexerOutputsResponse);
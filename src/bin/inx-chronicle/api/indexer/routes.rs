@@ -18,8 +18,11 @@ use mongodb::bson;
 
 use super::{extractors::IndexedOutputsPagination, responses::IndexerOutputsResponse};
 use crate::api::{
+    config::ApiConfigData,
     error::{MissingError, RequestError},
+    extractors::ResponseFormat,
     indexer::extractors::IndexedOutputsCursor,
+    responses::Negotiated,
     router::Router,
     ApiResult,
 };
@@ -53,7 +56,8 @@ pub fn routes() -> Router {
 async fn indexed_output_by_id<ID>(
     database: Extension<MongoDb>,
     Path(id): Path<String>,
-) -> ApiResult<IndexerOutputsResponse>
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<IndexerOutputsResponse>>
 where
     ID: Into<IndexedId> + FromStr,
     RequestError: From<ID::Err>,
@@ -69,11 +73,16 @@ where
         .get_indexed_output_by_id(id, ledger_index)
         .await?
         .ok_or(MissingError::NoResults)?;
-    Ok(IndexerOutputsResponse {
-        ledger_index,
-        items: vec![res.output_id.to_hex()],
-        cursor: None,
-    })
+    Ok(Negotiated(
+        format,
+        IndexerOutputsResponse {
+            ledger_index,
+            items: vec![res.output_id.to_hex()],
+            cursor: None,
+            total: None,
+            total_capped: None,
+        },
+    ))
 }
 
 async fn indexed_outputs<Q>(
@@ -84,9 +93,13 @@ async fn indexed_outputs<Q>(
         cursor,
         sort,
         include_spent,
+        count,
     }: IndexedOutputsPagination<Q>,
-) -> ApiResult<IndexerOutputsResponse>
+    Extension(config): Extension<ApiConfigData>,
+    format: ResponseFormat,
+) -> ApiResult<Negotiated<IndexerOutputsResponse>>
 where
+    Q: Clone,
     bson::Document: From<Q>,
 {
     let ledger_index = database
@@ -94,6 +107,20 @@ where
         .get_ledger_index()
         .await?
         .ok_or(MissingError::NoResults)?;
+
+    // Counting is opt-in since it has to visit every matching output.
+    let total = if count {
+        let limit = (config.max_total_count > 0).then_some(config.max_total_count);
+        Some(
+            database
+                .collection::<OutputCollection>()
+                .count_indexed_outputs(query.clone(), include_spent, ledger_index, limit)
+                .await?,
+        )
+    } else {
+        None
+    };
+
     let res = database
         .collection::<OutputCollection>()
         .get_indexed_outputs(
@@ -122,9 +149,14 @@ where
         .to_string()
     });
 
-    Ok(IndexerOutputsResponse {
-        ledger_index,
-        items,
-        cursor,
-    })
+    Ok(Negotiated(
+        format,
+        IndexerOutputsResponse {
+            ledger_index,
+            items,
+            cursor,
+            total,
+            total_capped: total.map(|total| config.max_total_count > 0 && total >= config.max_total_count),
+        },
+    ))
 }
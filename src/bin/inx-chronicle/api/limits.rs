@@ -0,0 +1,133 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use axum::{
+    body::Body,
+    http::{header::CONTENT_LENGTH, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use hyper::body::HttpBody;
+
+use super::{
+    config::ApiConfigData,
+    error::{PayloadTooLargeError, RequestError, TimeoutError},
+    ApiError,
+};
+
+/// Rejects request bodies that exceed the configured size, and cancels requests that do not complete within the
+/// configured timeout. Reading the body counts towards the timeout, so slow uploads are cut off as well.
+pub async fn limit_requests(req: Request<Body>, next: Next<Body>) -> Response {
+    let (timeout, max_body_size) = match req.extensions().get::<ApiConfigData>() {
+        Some(config) => (config.request_timeout, config.max_request_body_size),
+        None => return next.run(req).await,
+    };
+    // Dropping the handler future on timeout cancels any database query that it is waiting on.
+    let res = tokio::time::timeout(timeout, async move {
+        let req = limit_body(req, max_body_size).await?;
+        Ok::<_, ApiError>(next.run(req).await)
+    })
+    .await;
+    match res {
+        Ok(Ok(response)) => response,
+        Ok(Err(err)) => err.into_response(),
+        Err(_) => ApiError::from(TimeoutError(timeout)).into_response(),
+    }
+}
+
+/// Buffers the body of the request, unless it is larger than `max_size`. The declared content length is checked first,
+/// so that oversized requests are rejected without reading them.
+async fn limit_body(req: Request<Body>, max_size: usize) -> Result<Request<Body>, ApiError> {
+    let content_length = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if matches!(content_length, Some(len) if len > max_size as u64) {
+        return Err(PayloadTooLargeError { max_size }.into());
+    }
+
+    let (parts, mut body) = req.into_parts();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(RequestError::from)?;
+        if bytes.len() + chunk.len() > max_size {
+            return Err(PayloadTooLargeError { max_size }.into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Request::from_parts(parts, Body::from(bytes)))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::{middleware::from_fn, routing::get, Extension, Router};
+    use hyper::StatusCode;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::api::ApiConfig;
+
+    fn router(handler_state: Arc<()>) -> Router {
+        let config = ApiConfigData::try_from(ApiConfig {
+            request_timeout: Duration::from_millis(50),
+            max_request_body_size: 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        Router::new()
+            .route(
+                "/slow",
+                get(move || {
+                    let state = handler_state.clone();
+                    async move {
+                        let _state = state;
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                    }
+                }),
+            )
+            .route("/echo", axum::routing::post(|body: String| async move { body }))
+            .layer(from_fn(limit_requests))
+            .layer(Extension(config))
+    }
+
+    #[tokio::test]
+    async fn slow_handler_is_cancelled() {
+        let handler_state = Arc::new(());
+        let response = router(handler_state.clone())
+            .oneshot(Request::get("/slow").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+        // The handler future was dropped, together with everything it held.
+        assert_eq!(Arc::strong_count(&handler_state), 1);
+    }
+
+    #[tokio::test]
+    async fn oversized_body_is_rejected() {
+        let post = |body: Body| Request::post("/echo").body(body).unwrap();
+
+        let response = router(Default::default())
+            .oneshot(post(Body::from(vec![b'a'; 1024])))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap().len(), 1024);
+
+        // The declared length is rejected before the body is read.
+        let mut request = post(Body::from(vec![b'a'; 1025]));
+        request.headers_mut().insert(CONTENT_LENGTH, 1025.into());
+        let response = router(Default::default()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // A streamed body does not declare its length up front.
+        let chunks = vec![Ok::<_, std::io::Error>(vec![b'a'; 600]), Ok(vec![b'a'; 600])];
+        let response = router(Default::default())
+            .oneshot(post(Body::wrap_stream(futures::stream::iter(chunks))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
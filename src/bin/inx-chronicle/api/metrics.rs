@@ -0,0 +1,120 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chronicle::db::{
+    mongodb::collections::{
+        ApplicationStateCollection, BlockCollection, ConfigurationUpdateCollection, DeadLetterCollection,
+        LedgerUpdateCollection, MilestoneCollection, OutputCollection, ProtocolUpdateCollection, TreasuryCollection,
+    },
+    MongoDb, MongoDbCollection,
+};
+use futures::Future;
+use tokio::sync::Mutex;
+
+use super::{responses::CollectionStatsResponse, ApiResult};
+
+/// The collection sizes that were last gathered, together with the time at which they were gathered.
+#[derive(Clone, Debug)]
+pub struct CollectionStatsCache {
+    interval: Duration,
+    cached: Arc<Mutex<Option<(Instant, CollectionStatsResponse)>>>,
+}
+
+impl CollectionStatsCache {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            cached: Default::default(),
+        }
+    }
+
+    /// Returns the cached stats while they are younger than the interval, and gathers them with `fetch` otherwise.
+    /// Concurrent requests wait for the same refresh instead of each querying the database.
+    pub async fn get_or_refresh<F>(&self, fetch: impl FnOnce() -> F) -> ApiResult<CollectionStatsResponse>
+    where
+        F: Future<Output = ApiResult<CollectionStatsResponse>>,
+    {
+        let mut cached = self.cached.lock().await;
+        if let Some((gathered_at, stats)) = cached.as_ref() {
+            if gathered_at.elapsed() < self.interval {
+                return Ok(stats.clone());
+            }
+        }
+        let stats = fetch().await?;
+        *cached = Some((Instant::now(), stats.clone()));
+        Ok(stats)
+    }
+}
+
+/// Gathers the sizes of all collections that Chronicle writes to.
+pub async fn collection_stats(db: &MongoDb) -> ApiResult<CollectionStatsResponse> {
+    let stats = tokio::try_join!(
+        db.collection_stats::<OutputCollection>(),
+        db.collection_stats::<BlockCollection>(),
+        db.collection_stats::<LedgerUpdateCollection>(),
+        db.collection_stats::<MilestoneCollection>(),
+        db.collection_stats::<TreasuryCollection>(),
+        db.collection_stats::<ProtocolUpdateCollection>(),
+        db.collection_stats::<ConfigurationUpdateCollection>(),
+        db.collection_stats::<ApplicationStateCollection>(),
+        db.collection_stats::<DeadLetterCollection>(),
+    )?;
+    Ok(CollectionStatsResponse::new([
+        (OutputCollection::NAME, stats.0),
+        (BlockCollection::NAME, stats.1),
+        (LedgerUpdateCollection::NAME, stats.2),
+        (MilestoneCollection::NAME, stats.3),
+        (TreasuryCollection::NAME, stats.4),
+        (ProtocolUpdateCollection::NAME, stats.5),
+        (ConfigurationUpdateCollection::NAME, stats.6),
+        (ApplicationStateCollection::NAME, stats.7),
+        (DeadLetterCollection::NAME, stats.8),
+    ]))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use chronicle::db::CollectionStats;
+
+    use super::*;
+
+    async fn fetch_counted(cache: &CollectionStatsCache, fetches: &AtomicU64) -> u64 {
+        cache
+            .get_or_refresh(|| async {
+                let count = fetches.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(CollectionStatsResponse::new([(
+                    "stardust_outputs",
+                    CollectionStats {
+                        count,
+                        ..Default::default()
+                    },
+                )]))
+            })
+            .await
+            .unwrap()
+            .total
+            .count
+    }
+
+    #[tokio::test]
+    async fn stats_are_cached_for_the_interval() {
+        let fetches = AtomicU64::new(0);
+        let cache = CollectionStatsCache::new(Duration::from_secs(3600));
+        assert_eq!(fetch_counted(&cache, &fetches).await, 1);
+        assert_eq!(fetch_counted(&cache, &fetches).await, 1);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // Without an interval, every request gathers the stats again.
+        let fetches = AtomicU64::new(0);
+        let cache = CollectionStatsCache::new(Duration::ZERO);
+        assert_eq!(fetch_counted(&cache, &fetches).await, 1);
+        assert_eq!(fetch_counted(&cache, &fetches).await, 2);
+    }
+}
@@ -10,31 +10,51 @@ mod secret_key;
 #[macro_use]
 mod responses;
 mod auth;
+mod buckets;
 pub mod config;
 mod core;
 mod explorer;
 mod indexer;
+mod limits;
+mod metrics;
 #[cfg(feature = "poi")]
 mod poi;
+mod request_id;
 mod router;
 mod routes;
+mod stale;
 
-use axum::{Extension, Server};
-use chronicle::db::MongoDb;
-use futures::Future;
-use hyper::Method;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{middleware::from_fn, Extension, Server};
+use chronicle::db::{
+    mongodb::collections::{MilestoneCollection, MilestoneResult, SortOrder},
+    MongoDb,
+};
+use futures::{Future, TryStreamExt};
+use hyper::{
+    rt::Executor,
+    server::{conn::AddrIncoming, Builder},
+    Method,
+};
+use tokio::{sync::oneshot, task::JoinHandle};
 use tower_http::{
     catch_panic::CatchPanicLayer,
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
-use tracing::info;
+use tracing::{info, warn};
 
+use self::router::Router;
 pub use self::{
     config::{ApiConfig, ApiConfigData},
     error::{ApiError, ApiResult, AuthError, ConfigError},
     secret_key::SecretKey,
 };
+use crate::events::MilestoneEvents;
 
 pub const DEFAULT_PAGE_SIZE: usize = 100;
 
@@ -43,26 +63,40 @@ pub const DEFAULT_PAGE_SIZE: usize = 100;
 pub struct ApiWorker {
     db: MongoDb,
     api_data: ApiConfigData,
+    milestone_events: MilestoneEvents,
 }
 
 impl ApiWorker {
-    /// Create a new Chronicle API actor from a mongo connection.
-    pub fn new(db: MongoDb, config: ApiConfig) -> Result<Self, ConfigError> {
+    /// Create a new Chronicle API actor from a mongo connection. The `milestone_events` are streamed to the clients
+    /// that subscribe to them.
+    pub fn new(db: MongoDb, config: ApiConfig, milestone_events: MilestoneEvents) -> Result<Self, ConfigError> {
         Ok(Self {
             db,
             api_data: config.try_into()?,
+            milestone_events,
         })
     }
 
     pub async fn run(&self, shutdown_handle: impl Future<Output = ()>) -> eyre::Result<()> {
+        if self.api_data.warmup_milestones > 0 {
+            if let Err(e) = self.warmup().await {
+                warn!("API warmup failed: {e}");
+            }
+        }
+
         info!("Starting API server on port `{}`", self.api_data.port);
 
         let port = self.api_data.port;
-        let routes = routes::routes()
+        let routes = routes::routes(&self.api_data.base_path)
+            // Added before the extensions, so that it can read the configuration from them.
+            .layer(from_fn(limits::limit_requests))
             .layer(Extension(self.db.clone()))
             .layer(Extension(self.api_data.clone()))
+            .layer(Extension(self.milestone_events.clone()))
             .layer(CatchPanicLayer::new())
             .layer(TraceLayer::new_for_http())
+            // Added after the trace layer, so that the request span is its parent.
+            .layer(from_fn(request_id::propagate_request_id))
             .layer(
                 CorsLayer::new()
                     .allow_origin(self.api_data.allow_origins.clone())
@@ -71,11 +105,174 @@ impl ApiWorker {
                     .allow_credentials(false),
             );
 
-        Server::bind(&([0, 0, 0, 0], port).into())
-            .serve(routes.into_make_service())
-            .with_graceful_shutdown(shutdown_handle)
-            .await?;
+        serve(
+            Server::try_bind(&([0, 0, 0, 0], port).into())?,
+            routes,
+            shutdown_handle,
+            self.api_data.shutdown_grace_period,
+        )
+        .await?;
+
+        Ok(())
+    }
 
+    /// Prefetches the latest milestones, which primes the connection pool and the database cache before the first
+    /// request is served.
+    async fn warmup(&self) -> eyre::Result<()> {
+        let start_time = std::time::Instant::now();
+        let collection = self.db.collection::<MilestoneCollection>();
+        let mut milestones = collection
+            .get_milestones(None, None, SortOrder::Newest, self.api_data.warmup_milestones, None)
+            .await?;
+        let mut count = 0;
+        while let Some(MilestoneResult { index, .. }) = milestones.try_next().await? {
+            collection.get_milestone_payload(index).await?;
+            count += 1;
+        }
+        info!("Prefetched {count} milestones in {:?}.", start_time.elapsed());
         Ok(())
     }
 }
+
+/// Serves the routes until `shutdown_handle` completes. From then on, no new connections are accepted, and the requests
+/// in flight are given `grace_period` to complete before their connections are closed.
+async fn serve(
+    builder: Builder<AddrIncoming>,
+    routes: Router,
+    shutdown_handle: impl Future<Output = ()>,
+    grace_period: Duration,
+) -> hyper::Result<()> {
+    let connections = ConnectionExecutor::default();
+    let (shutdown_started, on_shutdown_started) = oneshot::channel();
+    let server = builder
+        .executor(connections.clone())
+        .serve(routes.into_make_service())
+        .with_graceful_shutdown(async move {
+            shutdown_handle.await;
+            shutdown_started.send(()).ok();
+        });
+    tokio::pin!(server);
+
+    tokio::select! {
+        res = &mut server => return res,
+        _ = on_shutdown_started => (),
+    }
+    match tokio::time::timeout(grace_period, server).await {
+        Ok(res) => res,
+        Err(_) => {
+            warn!(
+                "Requests still in flight after {}, closing their connections.",
+                humantime::format_duration(grace_period)
+            );
+            connections.abort_all();
+            Ok(())
+        }
+    }
+}
+
+/// Spawns the connections of the server, and keeps track of them, so that those that outlive the shutdown grace
+/// period can be aborted. Otherwise, they would keep running after the server was dropped.
+#[derive(Clone, Debug, Default)]
+struct ConnectionExecutor(Arc<Mutex<Vec<JoinHandle<()>>>>);
+
+impl ConnectionExecutor {
+    fn abort_all(&self) {
+        for connection in self.0.lock().unwrap().drain(..) {
+            connection.abort();
+        }
+    }
+}
+
+impl<F> Executor<F> for ConnectionExecutor
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    fn execute(&self, fut: F) {
+        let mut connections = self.0.lock().unwrap();
+        connections.retain(|connection| !connection.is_finished());
+        connections.push(tokio::spawn(fut));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::routing::get;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::Notify,
+    };
+
+    use super::*;
+
+    /// Starts a server with a handler that takes `duration` to respond, and a request to it. Returns once the request
+    /// has reached the handler.
+    async fn start_slow_request(
+        duration: Duration,
+        grace_period: Duration,
+    ) -> (
+        oneshot::Sender<()>,
+        tokio::task::JoinHandle<hyper::Result<()>>,
+        TcpStream,
+    ) {
+        let started = Arc::new(Notify::new());
+        let routes = Router::new().route(
+            "/slow",
+            get({
+                let started = started.clone();
+                move || async move {
+                    started.notify_one();
+                    tokio::time::sleep(duration).await;
+                    "done"
+                }
+            }),
+        );
+        let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
+        let addr = incoming.local_addr();
+        let (shutdown, on_shutdown) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve(
+            Server::builder(incoming),
+            routes,
+            async move {
+                on_shutdown.await.ok();
+            },
+            grace_period,
+        ));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /slow HTTP/1.1\r\nhost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        started.notified().await;
+        (shutdown, server, stream)
+    }
+
+    #[tokio::test]
+    async fn requests_in_flight_are_drained() {
+        let (shutdown, server, mut stream) =
+            start_slow_request(Duration::from_millis(200), Duration::from_secs(10)).await;
+        shutdown.send(()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("done"), "{response}");
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn requests_are_dropped_after_grace_period() {
+        let (shutdown, server, mut stream) =
+            start_slow_request(Duration::from_secs(60), Duration::from_millis(100)).await;
+        let start = std::time::Instant::now();
+        shutdown.send(()).unwrap();
+
+        server.await.unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        // The connection was closed without a response.
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.ok();
+        assert!(response.is_empty(), "{response}");
+    }
+}
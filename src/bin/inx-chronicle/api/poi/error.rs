@@ -1,9 +1,12 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use hyper::StatusCode;
 use iota_types::block::payload::milestone::MilestoneValidationError;
 use thiserror::Error;
 
+use crate::api::error::ErrorStatus;
+
 #[derive(Error, Debug)]
 #[allow(missing_docs)]
 pub enum RequestError {
@@ -19,6 +22,8 @@ pub enum RequestError {
     BlockNotApplied(String),
     #[error("Invalid milestone: {0:?}")]
     InvalidMilestone(MilestoneValidationError),
+    #[error("Proof index {index} is out of bounds for {len} blocks")]
+    ProofIndexOutOfBounds { index: usize, len: usize },
 }
 
 #[derive(Error, Debug)]
@@ -34,6 +39,17 @@ pub enum CorruptStateError {
     DecodePublicKey,
 }
 
+/// Proofs of inclusion need the complete milestone cones, which are not stored if blocks are filtered.
+#[derive(Error, Debug)]
+#[error("Proofs of inclusion are unavailable, because not all blocks are stored")]
+pub struct PartialBlocksError;
+
+impl ErrorStatus for PartialBlocksError {
+    fn status(&self) -> StatusCode {
+        StatusCode::NOT_IMPLEMENTED
+    }
+}
+
 #[derive(Error, Debug)]
 #[allow(missing_docs)]
 pub enum CreateProofError {
@@ -47,3 +63,24 @@ pub enum CreateProofError {
         expected_merkle_root: String,
     },
 }
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum DecodeProofError {
+    #[error("Encoded proof is empty")]
+    Empty,
+    #[error("Encoded proof with {siblings} siblings is truncated: expected {expected} bytes, found {found}")]
+    Truncated {
+        siblings: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Encoded proof with {siblings} siblings is too long: expected {expected} bytes, found {found}")]
+    TrailingBytes {
+        siblings: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Encoded proof sets unused bits of its side bitmap")]
+    NonCanonicalBitmap,
+}
@@ -0,0 +1,29 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+use axum::{extract::FromRequest, Extension};
+use chronicle::db::{mongodb::collections::ApplicationStateCollection, MongoDb};
+
+use super::error::PartialBlocksError;
+use crate::api::ApiError;
+
+/// Rejects the request if blocks were filtered when they were stored, since the proofs need complete milestone cones.
+pub struct CompleteCones;
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for CompleteCones {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut axum::extract::RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Extension(database) = Extension::<MongoDb>::from_request(req).await?;
+        if database
+            .collection::<ApplicationStateCollection>()
+            .get_partial_blocks()
+            .await?
+        {
+            return Err(PartialBlocksError.into());
+        }
+        Ok(CompleteCones)
+    }
+}
@@ -1,13 +1,117 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use chronicle::model::BlockId;
 use crypto::hashes::{blake2b::Blake2b256, Digest, Output};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::error::DecodeProofError;
 
 const LEAF_HASH_PREFIX: u8 = 0;
 const NODE_HASH_PREFIX: u8 = 1;
 
 pub type MerkleHash = Output<Blake2b256>;
 
+/// The side of a sibling, relative to the hash it is combined with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MerkleSide {
+    Left,
+    Right,
+}
+
+/// The sibling hashes that are needed to recompute the Merkle root from a single leaf, ordered from the leaf up to
+/// the root. The proof of a tree that consists of a single leaf has no siblings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleInclusionProof {
+    pub siblings: Vec<(MerkleSide, MerkleHash)>,
+}
+
+impl MerkleInclusionProof {
+    /// Encodes the proof as the number of siblings, followed by a bitmap of their sides and the sibling hashes in the
+    /// same order. A set bit marks a sibling on the left. Bits are counted from the least significant bit of the first
+    /// byte, and unused bits are zero, so that every proof has exactly one encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = self.siblings.len();
+        // A tree of `usize::MAX` leaves is less than 64 levels deep.
+        debug_assert!(len <= u8::MAX as usize);
+        let mut bytes = Vec::with_capacity(Self::encoded_len(len));
+        bytes.push(len as u8);
+        let mut bitmap = vec![0u8; Self::bitmap_len(len)];
+        for (i, (side, _)) in self.siblings.iter().enumerate() {
+            if *side == MerkleSide::Left {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bytes.extend(bitmap);
+        for (_, hash) in &self.siblings {
+            bytes.extend_from_slice(hash);
+        }
+        bytes
+    }
+
+    /// Decodes a proof that was encoded by [`to_bytes`](Self::to_bytes). Buffers that are shorter or longer than the
+    /// number of siblings requires are rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeProofError> {
+        let (&len, rest) = bytes.split_first().ok_or(DecodeProofError::Empty)?;
+        let len = len as usize;
+        let expected = Self::encoded_len(len);
+        if bytes.len() < expected {
+            return Err(DecodeProofError::Truncated {
+                siblings: len,
+                expected,
+                found: bytes.len(),
+            });
+        }
+        if bytes.len() > expected {
+            return Err(DecodeProofError::TrailingBytes {
+                siblings: len,
+                expected,
+                found: bytes.len(),
+            });
+        }
+        let (bitmap, hashes) = rest.split_at(Self::bitmap_len(len));
+        if len % 8 != 0 && bitmap[len / 8] >> (len % 8) != 0 {
+            return Err(DecodeProofError::NonCanonicalBitmap);
+        }
+        let siblings = hashes
+            .chunks_exact(MerkleHash::default().len())
+            .enumerate()
+            .map(|(i, hash)| {
+                let side = if bitmap[i / 8] & (1 << (i % 8)) != 0 {
+                    MerkleSide::Left
+                } else {
+                    MerkleSide::Right
+                };
+                (side, MerkleHash::clone_from_slice(hash))
+            })
+            .collect();
+        Ok(Self { siblings })
+    }
+
+    fn bitmap_len(siblings: usize) -> usize {
+        (siblings + 7) / 8
+    }
+
+    fn encoded_len(siblings: usize) -> usize {
+        1 + Self::bitmap_len(siblings) + siblings * MerkleHash::default().len()
+    }
+}
+
+/// In JSON, a proof is represented by its binary encoding as a hex string.
+impl Serialize for MerkleInclusionProof {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&prefix_hex::encode(self.to_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for MerkleInclusionProof {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = prefix_hex::decode::<Vec<u8>>(&String::deserialize(deserializer)?).map_err(de::Error::custom)?;
+        Self::from_bytes(&bytes).map_err(de::Error::custom)
+    }
+}
+
 /// A Merkle tree hasher that uses the `Blake2b256` hash function.
 pub struct MerkleHasher;
 
@@ -43,6 +147,46 @@ impl MerkleHasher {
         hasher.update(r);
         hasher.finalize()
     }
+
+    /// Creates the proof for the leaf at `index`, or returns `None` if there is no such leaf.
+    pub fn inclusion_proof(data: &[impl AsRef<[u8]>], index: usize) -> Option<MerkleInclusionProof> {
+        if index >= data.len() {
+            return None;
+        }
+        let (mut data, mut index) = (data, index);
+        let mut siblings = Vec::new();
+        // Descend from the root to the leaf, which collects the siblings in reverse order.
+        while data.len() > 1 {
+            let k = largest_power_of_two(data.len());
+            if index < k {
+                siblings.push((MerkleSide::Right, Self::hash(&data[k..])));
+                data = &data[..k];
+            } else {
+                siblings.push((MerkleSide::Left, Self::hash(&data[..k])));
+                data = &data[k..];
+                index -= k;
+            }
+        }
+        siblings.reverse();
+        Some(MerkleInclusionProof { siblings })
+    }
+
+    /// Checks whether the proof recomputes `expected_root` from the given leaf.
+    pub fn verify_proof(leaf: impl AsRef<[u8]>, proof: &MerkleInclusionProof, expected_root: &MerkleHash) -> bool {
+        let root = proof
+            .siblings
+            .iter()
+            .fold(Self::hash_leaf(leaf), |hash, (side, sibling)| match side {
+                MerkleSide::Left => Self::hash_node(sibling, hash),
+                MerkleSide::Right => Self::hash_node(hash, sibling),
+            });
+        &root == expected_root
+    }
+
+    pub fn hash_block_ids(data: &[BlockId]) -> MerkleHash {
+        let data = data.iter().map(|id| &id.0[..]).collect::<Vec<_>>();
+        Self::hash(&data[..])
+    }
 }
 
 /// Returns the largest power of 2 less than a given number `n`.
@@ -59,17 +203,8 @@ const fn bit_length(n: u32) -> u32 {
 mod tests {
     use std::str::FromStr;
 
-    use chronicle::model::BlockId;
-
     use super::*;
 
-    impl MerkleHasher {
-        pub fn hash_block_ids(data: &[BlockId]) -> MerkleHash {
-            let data = data.iter().map(|id| &id.0[..]).collect::<Vec<_>>();
-            Self::hash(&data[..])
-        }
-    }
-
     #[test]
     fn test_largest_power_of_two_lte_number() {
         assert_eq!(2u32.pow(0) as usize, largest_power_of_two(2));
@@ -122,4 +257,101 @@ mod tests {
             "0xbf67ce7ba23e8c0951b5abaec4f5524360d2c26d971ff226d3359fa70cdb0beb"
         )
     }
+
+    #[test]
+    fn test_inclusion_proof() {
+        for n in 1..=9u8 {
+            let data = (0..n).map(|i| [i; 32]).collect::<Vec<_>>();
+            let root = MerkleHasher::hash(&data);
+            for (index, leaf) in data.iter().enumerate() {
+                let proof = MerkleHasher::inclusion_proof(&data, index).unwrap();
+                assert!(MerkleHasher::verify_proof(leaf, &proof, &root), "n={n}, index={index}");
+                assert!(
+                    !MerkleHasher::verify_proof([n; 32], &proof, &root),
+                    "n={n}, index={index}"
+                );
+            }
+            assert_eq!(MerkleHasher::inclusion_proof(&data, n as usize), None);
+        }
+    }
+
+    #[test]
+    fn test_inclusion_proof_encoding_roundtrip() {
+        for n in [1usize, 2, 7, 1024] {
+            let data = (0..n).map(|i| (i as u32).to_le_bytes()).collect::<Vec<_>>();
+            let root = MerkleHasher::hash(&data);
+            let indices = if n <= 8 {
+                (0..n).collect::<Vec<_>>()
+            } else {
+                vec![0, 1, n / 2 - 1, n / 2, n - 1]
+            };
+            for index in indices {
+                let proof = MerkleHasher::inclusion_proof(&data, index).unwrap();
+                let bytes = proof.to_bytes();
+                assert_eq!(
+                    bytes.len(),
+                    1 + (proof.siblings.len() + 7) / 8 + 32 * proof.siblings.len()
+                );
+
+                let decoded = MerkleInclusionProof::from_bytes(&bytes).unwrap();
+                assert_eq!(decoded, proof, "n={n}, index={index}");
+                assert_eq!(decoded.to_bytes(), bytes, "n={n}, index={index}");
+                assert!(MerkleHasher::verify_proof(data[index], &decoded, &root));
+
+                let json = serde_json::to_string(&proof).unwrap();
+                assert_eq!(serde_json::from_str::<MerkleInclusionProof>(&json).unwrap(), proof);
+            }
+        }
+    }
+
+    #[test]
+    fn test_inclusion_proof_decoding_rejects_invalid_length() {
+        let data = (0..7u8).map(|i| [i; 32]).collect::<Vec<_>>();
+        let bytes = MerkleHasher::inclusion_proof(&data, 3).unwrap().to_bytes();
+        assert_eq!(bytes.len(), 98);
+
+        assert_eq!(MerkleInclusionProof::from_bytes(&[]), Err(DecodeProofError::Empty));
+        assert_eq!(
+            MerkleInclusionProof::from_bytes(&bytes[..97]),
+            Err(DecodeProofError::Truncated {
+                siblings: 3,
+                expected: 98,
+                found: 97
+            })
+        );
+        let mut too_long = bytes.clone();
+        too_long.push(0);
+        assert_eq!(
+            MerkleInclusionProof::from_bytes(&too_long),
+            Err(DecodeProofError::TrailingBytes {
+                siblings: 3,
+                expected: 98,
+                found: 99
+            })
+        );
+
+        // The bitmap has room for eight siblings, but only the first three bits are used.
+        let mut non_canonical = bytes;
+        non_canonical[1] |= 1 << 3;
+        assert_eq!(
+            MerkleInclusionProof::from_bytes(&non_canonical),
+            Err(DecodeProofError::NonCanonicalBitmap)
+        );
+    }
+
+    #[test]
+    fn test_inclusion_proof_edge_cases() {
+        let empty: [[u8; 32]; 0] = [];
+        assert_eq!(MerkleHasher::inclusion_proof(&empty, 0), None);
+
+        // A single leaf is its own root.
+        let single = [[1; 32]];
+        let proof = MerkleHasher::inclusion_proof(&single, 0).unwrap();
+        assert!(proof.siblings.is_empty());
+        assert!(MerkleHasher::verify_proof(
+            single[0],
+            &proof,
+            &MerkleHasher::hash(&single)
+        ));
+    }
 }
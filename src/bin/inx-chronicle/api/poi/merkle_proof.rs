@@ -83,7 +83,7 @@ impl MerkleProof {
     //
     // For further details on the usage of Merkle trees and Proof of Inclusion in IOTA, have a look at:
     // [TIP-0004](https://github.com/iotaledger/tips/blob/main/tips/TIP-0004/tip-0004.md).
-    fn create_audit_path_from_index(block_ids: &[BlockId], index: usize) -> MerkleAuditPath {
+    pub fn create_audit_path_from_index(block_ids: &[BlockId], index: usize) -> MerkleAuditPath {
         let n = block_ids.len();
         debug_assert!(n > 0 && index < n, "n={n}, index={index}");
 
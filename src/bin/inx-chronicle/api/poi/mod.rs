@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod error;
+mod extractors;
 mod merkle_hasher;
 mod merkle_proof;
 mod responses;
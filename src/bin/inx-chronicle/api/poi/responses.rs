@@ -1,10 +1,14 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use chronicle::model::tangle::MilestoneIndex;
 use iota_types::block::{payload::dto::MilestonePayloadDto, BlockDto};
 use serde::{Deserialize, Serialize};
 
-use super::merkle_proof::MerkleAuditPathDto;
+use super::{
+    merkle_hasher::{MerkleInclusionProof, MerkleSide},
+    merkle_proof::MerkleAuditPathDto,
+};
 use crate::api::responses::impl_success_response;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -25,3 +29,87 @@ pub struct ValidateProofResponse {
 }
 
 impl_success_response!(ValidateProofResponse);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateProofsRequest {
+    pub milestone_index: MilestoneIndex,
+    pub block_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateProofsResponse {
+    pub milestone_index: MilestoneIndex,
+    pub applied_merkle_root: String,
+    /// The proofs in the order of the requested block ids.
+    pub proofs: Vec<BlockProofDto>,
+}
+
+impl_success_response!(CreateProofsResponse);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockProofDto {
+    pub block_id: String,
+    /// Whether the block was applied by the milestone. Only applied blocks have a proof.
+    pub included: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<MerkleInclusionProof>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MilestoneRootResponse {
+    pub applied_merkle_root: String,
+    pub referenced_blocks_count: usize,
+}
+
+impl_success_response!(MilestoneRootResponse);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputeRootRequest {
+    /// The block ids in White-Flag order.
+    pub block_ids: Vec<String>,
+    /// The position of the block to create a proof for, if any.
+    #[serde(default)]
+    pub proof_index: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputeRootResponse {
+    pub merkle_root: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<MerkleAuditPathDto>,
+    /// The same proof as the ordered list of sibling hashes from the leaf up to the root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inclusion_path: Option<Vec<MerkleSiblingDto>>,
+    /// The same proof in its compact binary encoding, as a hex string, for clients that store proofs to verify them
+    /// offline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoded_inclusion_path: Option<MerkleInclusionProof>,
+}
+
+impl_success_response!(ComputeRootResponse);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleSiblingDto {
+    pub side: MerkleSide,
+    pub hash: String,
+}
+
+impl From<MerkleInclusionProof> for Vec<MerkleSiblingDto> {
+    fn from(value: MerkleInclusionProof) -> Self {
+        value
+            .siblings
+            .into_iter()
+            .map(|(side, hash)| MerkleSiblingDto {
+                side,
+                hash: prefix_hex::encode(hash.as_slice()),
+            })
+            .collect()
+    }
+}
@@ -1,10 +1,14 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::HashSet, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
 use axum::{
     extract::{Json, Path},
+    middleware::from_extractor,
     routing::{get, post},
     Extension,
 };
@@ -18,10 +22,16 @@ use chronicle::{
 
 use super::{
     error as poi,
+    extractors::CompleteCones,
+    merkle_hasher::MerkleHasher,
     merkle_proof::{MerkleAuditPath, MerkleProof},
-    responses::{CreateProofResponse, ValidateProofResponse},
+    responses::{
+        BlockProofDto, ComputeRootRequest, ComputeRootResponse, CreateProofResponse, CreateProofsRequest,
+        CreateProofsResponse, MilestoneRootResponse, ValidateProofResponse,
+    },
 };
 use crate::api::{
+    config::ApiConfigData,
     error::{CorruptStateError, MissingError, RequestError},
     router::Router,
     ApiResult,
@@ -36,6 +46,11 @@ pub fn routes() -> Router {
         .route("/referenced-block/validate", post(validate_proof_for_referenced_blocks))
         .route("/applied-block/create/:block_id", get(create_proof_for_applied_blocks))
         .route("/applied-block/validate", post(validate_proof_for_applied_blocks))
+        .route("/applied-block/create-batch", post(create_proofs_for_applied_blocks))
+        .route("/:milestone_index/root", get(milestone_root))
+        .route_layer(from_extractor::<CompleteCones>())
+        // Added after the layer, since computing a root from the given block ids does not read the database.
+        .route("/compute-root", post(compute_root))
 }
 
 async fn create_proof_for_referenced_blocks(
@@ -249,6 +264,146 @@ async fn validate_proof_for_applied_blocks(
     }
 }
 
+/// Creates the proofs for several blocks that were applied by the same milestone, from a single query of the applied
+/// blocks.
+async fn create_proofs_for_applied_blocks(
+    database: Extension<MongoDb>,
+    Extension(config): Extension<ApiConfigData>,
+    Json(CreateProofsRequest {
+        milestone_index,
+        block_ids,
+    }): Json<CreateProofsRequest>,
+) -> ApiResult<CreateProofsResponse> {
+    if block_ids.len() > config.max_batch_size {
+        return Err(RequestError::BatchTooLarge {
+            found: block_ids.len(),
+            max: config.max_batch_size,
+        }
+        .into());
+    }
+    let block_ids = block_ids
+        .iter()
+        .map(|block_id| BlockId::from_str(block_id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(RequestError::from)?;
+
+    let milestone = database
+        .collection::<MilestoneCollection>()
+        .get_milestone_payload(milestone_index)
+        .await?
+        .ok_or(MissingError::NoResults)?;
+    let applied_block_ids = database
+        .collection::<BlockCollection>()
+        .get_applied_blocks_in_white_flag_order(milestone_index)
+        .await?;
+
+    // Ensure that the proofs are created against the tree that the milestone committed to.
+    let calculated_merkle_root = MerkleHasher::hash_block_ids(&applied_block_ids);
+    let expected_merkle_root = milestone.essence.applied_merkle_root;
+    if calculated_merkle_root.as_slice() != expected_merkle_root {
+        return Err(CorruptStateError::PoI(poi::CorruptStateError::CreateProof(
+            poi::CreateProofError::MerkleRootMismatch {
+                calculated_merkle_root: prefix_hex::encode(calculated_merkle_root.as_slice()),
+                expected_merkle_root: prefix_hex::encode(expected_merkle_root),
+            },
+        ))
+        .into());
+    }
+
+    Ok(CreateProofsResponse {
+        milestone_index,
+        applied_merkle_root: prefix_hex::encode(expected_merkle_root),
+        proofs: block_proofs(&applied_block_ids, &block_ids),
+    })
+}
+
+/// Creates the proof of every requested block against the Merkle tree of the given blocks, or marks it as not
+/// included if it is not part of the tree.
+fn block_proofs(tree_block_ids: &[BlockId], block_ids: &[BlockId]) -> Vec<BlockProofDto> {
+    let leaves = tree_block_ids.iter().map(|id| &id.0[..]).collect::<Vec<_>>();
+    let positions = tree_block_ids
+        .iter()
+        .enumerate()
+        .map(|(index, block_id)| (block_id, index))
+        .collect::<HashMap<_, _>>();
+    block_ids
+        .iter()
+        .map(|block_id| {
+            let proof = positions
+                .get(block_id)
+                .and_then(|&index| MerkleHasher::inclusion_proof(&leaves, index));
+            BlockProofDto {
+                block_id: block_id.to_hex(),
+                included: proof.is_some(),
+                proof,
+            }
+        })
+        .collect()
+}
+
+async fn milestone_root(
+    database: Extension<MongoDb>,
+    Path(milestone_index): Path<MilestoneIndex>,
+) -> ApiResult<MilestoneRootResponse> {
+    let milestone = database
+        .collection::<MilestoneCollection>()
+        .get_milestone_payload(milestone_index)
+        .await?
+        .ok_or(MissingError::NoResults)?;
+
+    let referenced_blocks_count = database
+        .collection::<BlockCollection>()
+        .get_referenced_block_count(milestone_index)
+        .await?;
+
+    Ok(MilestoneRootResponse {
+        applied_merkle_root: prefix_hex::encode(milestone.essence.applied_merkle_root),
+        referenced_blocks_count,
+    })
+}
+
+/// Computes the Merkle root of an arbitrary list of block ids, without consulting the database.
+async fn compute_root(
+    Json(ComputeRootRequest { block_ids, proof_index }): Json<ComputeRootRequest>,
+) -> ApiResult<ComputeRootResponse> {
+    let block_ids = block_ids
+        .iter()
+        .map(|block_id| BlockId::from_str(block_id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(RequestError::from)?;
+
+    let merkle_root = MerkleHasher::hash_block_ids(&block_ids);
+    let (proof, inclusion_path, encoded_inclusion_path) = match proof_index {
+        Some(index) => {
+            let leaves = block_ids.iter().map(|id| &id.0[..]).collect::<Vec<_>>();
+            let inclusion_proof = MerkleHasher::inclusion_proof(&leaves, index).ok_or_else(|| {
+                RequestError::PoI(poi::RequestError::ProofIndexOutOfBounds {
+                    index,
+                    len: block_ids.len(),
+                })
+            })?;
+            debug_assert!(MerkleHasher::verify_proof(
+                leaves[index],
+                &inclusion_proof,
+                &merkle_root
+            ));
+            (
+                Some(MerkleProof::create_audit_path_from_index(&block_ids, index).into()),
+                Some(inclusion_proof.clone().into()),
+                Some(inclusion_proof),
+            )
+        }
+        None => (None, None, None),
+    };
+
+    Ok(ComputeRootResponse {
+        merkle_root: prefix_hex::encode(merkle_root.as_slice()),
+        proof,
+        inclusion_path,
+        encoded_inclusion_path,
+    })
+}
+
 // The returned public keys must be hex strings without the `0x` prefix for the milestone validation to work.
 #[allow(clippy::boxed_local)]
 fn get_valid_public_keys_for_index(
@@ -271,3 +426,184 @@ fn get_valid_public_keys_for_index(
     }
     Ok(public_keys.into_iter().collect::<Vec<_>>())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::poi::merkle_hasher::MerkleSide;
+
+    const BLOCK_IDS: [&str; 7] = [
+        "0x52fdfc072182654f163f5f0f9a621d729566c74d10037c4d7bbb0407d1e2c649",
+        "0x81855ad8681d0d86d1e91e00167939cb6694d2c422acd208a0072939487f6999",
+        "0xeb9d18a44784045d87f3c67cf22746e995af5a25367951baa2ff6cd471c483f1",
+        "0x5fb90badb37c5821b6d95526a41a9504680b4e7c8b763a1b1d49d4955c848621",
+        "0x6325253fec738dd7a9e28bf921119c160f0702448615bbda08313f6a8eb668d2",
+        "0x0bf5059875921e668a5bdf2c7fc4844592d2572bcd0668d2d6c52f5054e2d083",
+        "0x6bf84c7174cb7476364cc3dbd968b0f7172ed85794bb358b0c3b525da1786f9f",
+    ];
+
+    fn request(block_ids: &[&str], proof_index: Option<usize>) -> Json<ComputeRootRequest> {
+        Json(ComputeRootRequest {
+            block_ids: block_ids.iter().map(ToString::to_string).collect(),
+            proof_index,
+        })
+    }
+
+    #[tokio::test]
+    async fn computes_known_root() {
+        let res = compute_root(request(&BLOCK_IDS, Some(3))).await.unwrap();
+        assert_eq!(
+            res.merkle_root,
+            "0xbf67ce7ba23e8c0951b5abaec4f5524360d2c26d971ff226d3359fa70cdb0beb"
+        );
+
+        let proof = MerkleAuditPath::try_from(res.proof.unwrap()).unwrap();
+        assert!(proof.contains_block_id(&BlockId::from_str(BLOCK_IDS[3]).unwrap()));
+        assert_eq!(prefix_hex::encode(proof.hash().as_slice()), res.merkle_root);
+
+        let inclusion_path = res.inclusion_path.unwrap();
+        assert_eq!(inclusion_path.len(), 3);
+        assert_eq!(inclusion_path[0].side, MerkleSide::Left);
+
+        let encoded = res.encoded_inclusion_path.unwrap();
+        assert_eq!(encoded.siblings.len(), 3);
+        assert_eq!(encoded.siblings[0].0, MerkleSide::Left);
+        assert_eq!(
+            prefix_hex::encode(encoded.siblings[0].1.as_slice()),
+            inclusion_path[0].hash
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_input() {
+        let err = compute_root(request(&["0x1234"], None)).await.unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<RequestError>(),
+            Some(RequestError::IotaStardust(_))
+        ));
+
+        let err = compute_root(request(&BLOCK_IDS, Some(7))).await.unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<RequestError>(),
+            Some(RequestError::PoI(poi::RequestError::ProofIndexOutOfBounds {
+                index: 7,
+                len: 7
+            }))
+        ));
+    }
+
+    #[test]
+    fn block_proofs_mark_excluded_blocks() {
+        let block_id = |index: usize| BlockId::from_str(BLOCK_IDS[index]).unwrap();
+        let applied_block_ids = (0..5).map(block_id).collect::<Vec<_>>();
+        let root = MerkleHasher::hash_block_ids(&applied_block_ids);
+
+        let proofs = block_proofs(
+            &applied_block_ids,
+            &[block_id(1), block_id(6), block_id(4), block_id(1)],
+        );
+        assert_eq!(
+            proofs.iter().map(|proof| proof.included).collect::<Vec<_>>(),
+            [true, false, true, true]
+        );
+        assert_eq!(proofs[1].block_id, BLOCK_IDS[6]);
+        assert!(proofs[1].proof.is_none());
+        for (proof, index) in proofs.iter().zip([1, 6, 4, 1]).filter(|(proof, _)| proof.included) {
+            assert!(MerkleHasher::verify_proof(
+                block_id(index).0,
+                proof.proof.as_ref().unwrap(),
+                &root
+            ));
+        }
+
+        // The marker is serialized without a proof.
+        assert_eq!(
+            serde_json::to_value(&proofs[1]).unwrap(),
+            serde_json::json!({ "blockId": BLOCK_IDS[6], "included": false })
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_proof_batch_is_rejected() {
+        use chronicle::db::MongoDbConfig;
+
+        use crate::api::ApiConfig;
+
+        // Creating the client does not contact the server, and the batch is rejected before any query is made.
+        let db = MongoDb::connect(&MongoDbConfig::default()).await.unwrap();
+        let config = ApiConfigData::try_from(ApiConfig {
+            max_batch_size: 2,
+            ..Default::default()
+        })
+        .unwrap();
+        let err = create_proofs_for_applied_blocks(
+            Extension(db),
+            Extension(config),
+            Json(CreateProofsRequest {
+                milestone_index: 1.into(),
+                block_ids: BLOCK_IDS[..3].iter().map(ToString::to_string).collect(),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<RequestError>(),
+            Some(RequestError::BatchTooLarge { found: 3, max: 2 })
+        ));
+    }
+
+    /// Requires a MongoDB instance, like the database tests.
+    #[cfg(feature = "rand")]
+    #[tokio::test]
+    async fn proofs_are_unavailable_for_filtered_blocks() {
+        use axum::{body::Body, http::Request};
+        use chronicle::db::{mongodb::collections::ApplicationStateCollection, MongoDbConfig};
+        use hyper::StatusCode;
+        use tower::ServiceExt;
+
+        let mut config = MongoDbConfig {
+            database_name: "test-poi-partial-blocks".to_string(),
+            ..Default::default()
+        };
+        if let Ok(conn_str) = std::env::var("MONGODB_CONN_STR") {
+            config.conn_str = conn_str;
+        }
+        let db = MongoDb::connect(&config).await.unwrap();
+        db.clear().await.unwrap();
+        let status = |db: MongoDb| async move {
+            routes()
+                .layer(Extension(db))
+                .oneshot(
+                    Request::get(format!("/referenced-block/create/{}", BLOCK_IDS[0]))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+        };
+
+        // With complete milestone cones, the unknown block is simply not found.
+        assert_eq!(status(db.clone()).await, StatusCode::NOT_FOUND);
+
+        db.collection::<ApplicationStateCollection>()
+            .set_partial_blocks()
+            .await
+            .unwrap();
+        assert_eq!(status(db.clone()).await, StatusCode::NOT_IMPLEMENTED);
+
+        let compute_root = routes()
+            .layer(Extension(db.clone()))
+            .oneshot(
+                Request::post("/compute-root")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&request(&BLOCK_IDS, None).0).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(compute_root.status(), StatusCode::OK);
+
+        db.drop().await.unwrap();
+    }
+}
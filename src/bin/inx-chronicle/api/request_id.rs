@@ -0,0 +1,137 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use axum::{
+    body::Body,
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The longest request id that is accepted from a client. Longer ids are replaced, so that they do not bloat the logs.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Runs the request within a span that carries its id, so that every log line emitted while handling it, including
+/// those of the database queries it makes, can be correlated. The id is taken from the `X-Request-Id` header if the
+/// client sent a valid one, and generated otherwise. It is echoed in the same header of the response.
+pub async fn propagate_request_id(req: Request<Body>, next: Next<Body>) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .filter(|value| is_valid_request_id(value))
+        .cloned()
+        .unwrap_or_else(|| {
+            // Panic: A hyphenated uuid is a valid header value.
+            HeaderValue::from_str(&uuid::Uuid::new_v4().to_string()).unwrap()
+        });
+    // Panic: Only visible ASCII characters are accepted above.
+    let span = tracing::info_span!("request", request_id = %request_id.to_str().unwrap());
+    let mut res = next.run(req).instrument(span).await;
+    res.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    res
+}
+
+fn is_valid_request_id(value: &HeaderValue) -> bool {
+    !value.is_empty() && value.len() <= MAX_REQUEST_ID_LEN && value.as_bytes().iter().all(|b| b.is_ascii_graphic())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{middleware::from_fn, routing::get, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    // Stands in for a database query that is awaited by the handler.
+                    async { tracing::info!("Running aggregation.") }.await;
+                }),
+            )
+            .layer(from_fn(propagate_request_id))
+    }
+
+    fn request_id(res: &Response) -> &str {
+        res.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn request_id_is_logged_and_returned() {
+        let buffer = Buffer::default();
+        let make_writer = {
+            let buffer = buffer.clone();
+            move || buffer.clone()
+        };
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::fmt()
+                .with_writer(make_writer)
+                .with_ansi(false)
+                .finish(),
+        );
+
+        let res = router()
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let generated = request_id(&res).to_string();
+        assert!(uuid::Uuid::parse_str(&generated).is_ok());
+
+        let res = router()
+            .oneshot(
+                Request::get("/")
+                    .header(REQUEST_ID_HEADER, "client-id-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(request_id(&res), "client-id-1");
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(&format!("request_id={generated}")));
+        assert!(lines[0].contains("Running aggregation."));
+        assert!(lines[1].contains("request_id=client-id-1"));
+    }
+
+    #[tokio::test]
+    async fn invalid_request_id_is_replaced() {
+        for invalid in ["", "has space", &"a".repeat(MAX_REQUEST_ID_LEN + 1)] {
+            let res = router()
+                .oneshot(
+                    Request::get("/")
+                        .header(REQUEST_ID_HEADER, invalid)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert!(uuid::Uuid::parse_str(request_id(&res)).is_ok());
+        }
+    }
+}
@@ -1,8 +1,17 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeMap;
+
+use axum::{
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::IntoResponse,
+};
+use chronicle::db::{mongodb::collections::SkippedMilestones, CollectionStats};
 use serde::{Deserialize, Serialize};
 
+use super::{extractors::ResponseFormat, routes::CBOR_CONTENT_HEADER};
+
 macro_rules! impl_success_response {
     ($($type:ty),*) => {
         $(
@@ -17,6 +26,25 @@ macro_rules! impl_success_response {
 
 pub(crate) use impl_success_response;
 
+/// A response that is serialized in the [`ResponseFormat`] requested by the client.
+#[derive(Clone, Debug)]
+pub struct Negotiated<T>(pub ResponseFormat, pub T);
+
+impl<T: Serialize> IntoResponse for Negotiated<T> {
+    fn into_response(self) -> axum::response::Response {
+        match self.0 {
+            ResponseFormat::Json => axum::Json(self.1).into_response(),
+            ResponseFormat::Cbor => {
+                let mut bytes = Vec::new();
+                match ciborium::ser::into_writer(&self.1, &mut bytes) {
+                    Ok(()) => ([(CONTENT_TYPE, CBOR_CONTENT_HEADER.clone())], bytes).into_response(),
+                    Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RoutesResponse {
@@ -24,3 +52,121 @@ pub struct RoutesResponse {
 }
 
 impl_success_response!(RoutesResponse);
+
+/// The health of a single part of Chronicle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentHealth {
+    pub is_healthy: bool,
+    /// The unix timestamp at which the component was last known to make progress.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+    pub is_healthy: bool,
+    pub database: ComponentHealth,
+    /// Whether the ledger is kept up to date with the node.
+    pub sync: ComponentHealth,
+    /// The milestones that were skipped because the node had pruned them, so that the ledger is incomplete. This does
+    /// not affect the health, since the sync can still keep up with the node.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_milestones: Vec<SkippedMilestones>,
+}
+
+/// The sizes of the collections that Chronicle writes to.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionStatsResponse {
+    /// The stats of each collection by its name. Collections that do not exist yet are reported as empty.
+    pub collections: BTreeMap<String, CollectionStatsDto>,
+    /// The sum of the stats of all collections.
+    pub total: CollectionStatsDto,
+}
+
+impl_success_response!(CollectionStatsResponse);
+
+impl CollectionStatsResponse {
+    pub fn new<'a>(stats: impl IntoIterator<Item = (&'a str, CollectionStats)>) -> Self {
+        let collections = stats
+            .into_iter()
+            .map(|(name, stats)| (name.to_string(), CollectionStatsDto::from(stats)))
+            .collect::<BTreeMap<_, _>>();
+        let total = collections
+            .values()
+            .fold(CollectionStatsDto::default(), |total, stats| CollectionStatsDto {
+                count: total.count + stats.count,
+                size: total.size + stats.size,
+                storage_size: total.storage_size + stats.storage_size,
+                index_size: total.index_size + stats.index_size,
+            });
+        Self { collections, total }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionStatsDto {
+    pub count: u64,
+    /// The uncompressed size of the documents in bytes.
+    pub size: u64,
+    /// The storage allocated for the documents in bytes.
+    pub storage_size: u64,
+    /// The storage allocated for the indexes in bytes.
+    pub index_size: u64,
+}
+
+impl From<CollectionStats> for CollectionStatsDto {
+    fn from(value: CollectionStats) -> Self {
+        Self {
+            count: value.count,
+            size: value.size,
+            storage_size: value.storage_size,
+            index_size: value.index_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{
+        extract::{FromRequest, RequestParts},
+        http::Request,
+    };
+
+    use super::*;
+
+    async fn negotiated_response(accept: &str) -> axum::response::Response {
+        let mut req = RequestParts::new(
+            Request::builder()
+                .uri("/routes")
+                .header(axum::http::header::ACCEPT, accept)
+                .body(())
+                .unwrap(),
+        );
+        let format = ResponseFormat::from_request(&mut req).await.unwrap();
+        Negotiated(
+            format,
+            RoutesResponse {
+                routes: vec!["/api/core/v2/outputs".to_string()],
+            },
+        )
+        .into_response()
+    }
+
+    #[tokio::test]
+    async fn cbor_is_negotiated() {
+        let res = negotiated_response("application/json;q=0.5, application/cbor").await;
+        assert_eq!(res.headers()[CONTENT_TYPE], CBOR_CONTENT_HEADER);
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let decoded: RoutesResponse = ciborium::de::from_reader(bytes.as_ref()).unwrap();
+        assert_eq!(decoded.routes, vec!["/api/core/v2/outputs".to_string()]);
+
+        let res = negotiated_response("*/*").await;
+        assert_eq!(res.headers()[CONTENT_TYPE], "application/json");
+    }
+}
@@ -11,50 +11,64 @@ use axum::{
     Extension, Json, TypedHeader,
 };
 use chronicle::{
-    db::{mongodb::collections::MilestoneCollection, MongoDb},
-    model::tangle::MilestoneTimestamp,
+    db::{
+        mongodb::collections::{ApplicationStateCollection, MilestoneCollection},
+        MongoDb,
+    },
+    model::tangle::{MilestoneIndex, MilestoneIndexTimestamp, MilestoneTimestamp},
 };
 use hyper::StatusCode;
 use regex::RegexSet;
 use serde::Deserialize;
-use time::{Duration, OffsetDateTime};
+use time::OffsetDateTime;
 
 use super::{
     auth::Auth,
     config::ApiConfigData,
     error::{ApiError, MissingError, UnimplementedError},
     extractors::ListRoutesQuery,
-    responses::RoutesResponse,
+    metrics::collection_stats,
+    responses::{CollectionStatsResponse, ComponentHealth, HealthResponse, RoutesResponse},
     router::{RouteNode, Router},
     ApiResult, AuthError,
 };
 
 pub(crate) static BYTE_CONTENT_HEADER: HeaderValue = HeaderValue::from_static("application/vnd.iota.serializer-v1");
+pub(crate) static CBOR_CONTENT_HEADER: HeaderValue = HeaderValue::from_static("application/cbor");
 
 const ALWAYS_AVAILABLE_ROUTES: &[&str] = &["/health", "/login", "/routes"];
 
-// Similar to Hornet, we enforce that the latest known milestone is newer than 5 minutes. This should give Chronicle
-// sufficient time to catch up with the node that it is connected too. The current milestone interval is 5 seconds.
-const STALE_MILESTONE_DURATION: Duration = Duration::minutes(5);
+/// The error that `/health` reports when the database could not be queried.
+const DATABASE_UNAVAILABLE: &str = "database unavailable";
 
-pub fn routes() -> Router {
+/// Creates the routes of the API, nested under the `base_path` unless it is empty.
+pub fn routes(base_path: &str) -> Router {
     #[allow(unused_mut)]
     let mut router = Router::new()
         .nest("/core/v2", super::core::routes())
         .nest("/explorer/v2", super::explorer::routes())
-        .nest("/indexer/v1", super::indexer::routes());
+        .nest("/indexer/v1", super::indexer::routes())
+        .route("/metrics", get(metrics));
 
     #[cfg(feature = "poi")]
     {
         router = router.nest("/poi/v1", super::poi::routes());
     }
 
-    Router::new()
+    let router = Router::new()
         .route("/health", get(health))
         .route("/login", post(login))
         .route("/routes", get(list_routes))
-        .nest("/api", router.route_layer(from_extractor::<Auth>()))
-        .fallback(not_found.into_service())
+        .nest("/api", router.route_layer(from_extractor::<Auth>()));
+
+    let router = if base_path.is_empty() {
+        router
+    } else {
+        Router::new().nest(base_path, router)
+    };
+
+    // Nested routers cannot have a fallback, so it is only added at the top level.
+    router.fallback(not_found.into_service())
 }
 
 #[derive(Deserialize)]
@@ -98,10 +112,18 @@ pub fn password_verify(
     Ok(hash == argon2::hash_raw(password, salt, &config)?)
 }
 
-fn is_new_enough(timestamp: MilestoneTimestamp) -> bool {
+/// Whether the milestone with the given timestamp is recent enough at `now`. The age of the newest milestone is the
+/// time for which Chronicle has been lagging behind, so lags within the `grace_period` are tolerated.
+fn is_new_enough(timestamp: MilestoneTimestamp, now: OffsetDateTime, grace_period: std::time::Duration) -> bool {
     // Panic: The milestone_timestamp is guaranteeed to be valid.
     let timestamp = OffsetDateTime::from_unix_timestamp(timestamp.0 as i64).unwrap();
-    OffsetDateTime::now_utc() <= timestamp + STALE_MILESTONE_DURATION
+    now <= timestamp + grace_period
+}
+
+/// Whether the newest stored milestone is at most `max_lag` milestones behind the latest one confirmed by the node. A
+/// `max_lag` of zero disables the check.
+fn is_close_enough(newest: MilestoneIndex, latest_known: Option<MilestoneIndex>, max_lag: u32) -> bool {
+    max_lag == 0 || latest_known.map_or(true, |latest_known| latest_known.0.saturating_sub(newest.0) <= max_lag)
 }
 
 async fn list_routes(
@@ -137,36 +159,117 @@ async fn list_routes(
     Ok(RoutesResponse { routes })
 }
 
-pub async fn is_healthy(database: &MongoDb) -> ApiResult<bool> {
-    {
-        let newest = match database
-            .collection::<MilestoneCollection>()
-            .get_newest_milestone()
-            .await?
-        {
-            Some(last) => last,
-            None => return Ok(false),
-        };
+pub async fn is_healthy(
+    database: &MongoDb,
+    grace_period: std::time::Duration,
+    max_milestone_lag: u32,
+) -> ApiResult<bool> {
+    let (newest, latest_known) = tokio::try_join!(
+        database.collection::<MilestoneCollection>().get_newest_milestone(),
+        database
+            .collection::<ApplicationStateCollection>()
+            .get_latest_known_index(),
+    )?;
+    Ok(sync_health(
+        newest,
+        latest_known,
+        OffsetDateTime::now_utc(),
+        grace_period,
+        max_milestone_lag,
+    )
+    .is_healthy)
+}
 
-        if !is_new_enough(newest.milestone_timestamp) {
-            return Ok(false);
-        }
+/// The health of the sync, given the newest stored milestone and the latest milestone confirmed by the node.
+fn sync_health(
+    newest: Option<MilestoneIndexTimestamp>,
+    latest_known: Option<MilestoneIndex>,
+    now: OffsetDateTime,
+    grace_period: std::time::Duration,
+    max_milestone_lag: u32,
+) -> ComponentHealth {
+    ComponentHealth {
+        is_healthy: newest.map_or(false, |newest| {
+            is_new_enough(newest.milestone_timestamp, now, grace_period)
+                && is_close_enough(newest.milestone_index, latest_known, max_milestone_lag)
+        }),
+        last_seen: newest.map(|newest| newest.milestone_timestamp.0),
+        error: None,
     }
+}
 
-    Ok(true)
+/// Reports the number of documents and the storage size of each collection, for capacity planning.
+async fn metrics(
+    database: Extension<MongoDb>,
+    Extension(config): Extension<ApiConfigData>,
+) -> ApiResult<CollectionStatsResponse> {
+    config
+        .collection_stats
+        .get_or_refresh(|| collection_stats(&database))
+        .await
 }
 
-pub async fn health(database: Extension<MongoDb>) -> StatusCode {
-    let handle_error = |ApiError { error, .. }| {
-        tracing::error!("An error occured during health check: {error}");
-        false
+pub async fn health(
+    database: Extension<MongoDb>,
+    Extension(config): Extension<ApiConfigData>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let (database, sync, skipped_milestones) = match tokio::try_join!(
+        database.collection::<MilestoneCollection>().get_newest_milestone(),
+        database
+            .collection::<ApplicationStateCollection>()
+            .get_latest_known_index(),
+        database
+            .collection::<ApplicationStateCollection>()
+            .get_skipped_milestones(),
+    ) {
+        Ok((newest, latest_known, skipped_milestones)) => (
+            ComponentHealth {
+                is_healthy: true,
+                last_seen: None,
+                error: None,
+            },
+            sync_health(
+                newest,
+                latest_known,
+                OffsetDateTime::now_utc(),
+                config.health_grace_period,
+                config.max_milestone_lag,
+            ),
+            skipped_milestones,
+        ),
+        Err(error) => {
+            tracing::error!("An error occured during health check: {error}");
+            // The driver error can reveal details of the deployment, so it is only logged.
+            let unhealthy = ComponentHealth {
+                is_healthy: false,
+                last_seen: None,
+                error: Some(DATABASE_UNAVAILABLE.to_string()),
+            };
+            (
+                unhealthy.clone(),
+                ComponentHealth {
+                    error: None,
+                    ..unhealthy
+                },
+                Vec::new(),
+            )
+        }
     };
-
-    if is_healthy(&database).await.unwrap_or_else(handle_error) {
+    let is_healthy = database.is_healthy && sync.is_healthy;
+    let status = if is_healthy {
         StatusCode::OK
     } else {
         StatusCode::SERVICE_UNAVAILABLE
-    }
+    };
+    (
+        status,
+        Json(HealthResponse {
+            is_healthy,
+            database,
+            sync,
+            skipped_milestones,
+        }),
+    )
 }
 
 pub async fn not_found() -> MissingError {
@@ -176,3 +279,105 @@ pub async fn not_found() -> MissingError {
 pub async fn not_implemented() -> UnimplementedError {
     UnimplementedError
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use hyper::{Body, Request};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::api::{ApiConfig, ConfigError};
+
+    #[test]
+    fn lag_within_grace_period_is_healthy() {
+        let timestamp = MilestoneTimestamp(1_700_000_000);
+        let at = |seconds: u64| {
+            OffsetDateTime::from_unix_timestamp(timestamp.0 as i64).unwrap() + Duration::from_secs(seconds)
+        };
+        let grace_period = Duration::from_secs(30);
+
+        // A single late milestone (the interval is 5 seconds) does not flip the health check.
+        assert!(is_new_enough(timestamp, at(10), grace_period));
+        assert!(is_new_enough(timestamp, at(30), grace_period));
+        // Only a lag that persists beyond the grace period does.
+        assert!(!is_new_enough(timestamp, at(31), grace_period));
+    }
+
+    #[test]
+    fn sync_is_stale_after_grace_period() {
+        let timestamp = MilestoneTimestamp(1_700_000_000);
+        let newest = MilestoneIndexTimestamp {
+            milestone_index: MilestoneIndex(100),
+            milestone_timestamp: timestamp,
+        };
+        let now = OffsetDateTime::from_unix_timestamp(timestamp.0 as i64).unwrap() + Duration::from_secs(31);
+
+        let health = sync_health(Some(newest), None, now, Duration::from_secs(60), 0);
+        assert!(health.is_healthy);
+        assert_eq!(health.last_seen, Some(timestamp.0));
+
+        assert!(!sync_health(Some(newest), None, now, Duration::from_secs(30), 0).is_healthy);
+        // Nothing was synced yet.
+        assert!(!sync_health(None, None, now, Duration::from_secs(30), 0).is_healthy);
+    }
+
+    #[test]
+    fn sync_is_unhealthy_when_lagging_behind_node() {
+        let timestamp = MilestoneTimestamp(1_700_000_000);
+        let newest = MilestoneIndexTimestamp {
+            milestone_index: MilestoneIndex(100),
+            milestone_timestamp: timestamp,
+        };
+        // The newest milestone is recent, so only the lag can fail the check.
+        let now = OffsetDateTime::from_unix_timestamp(timestamp.0 as i64).unwrap();
+        let grace_period = Duration::from_secs(60);
+        let health = |latest_known: u32, max_lag: u32| {
+            sync_health(Some(newest), Some(latest_known.into()), now, grace_period, max_lag)
+        };
+
+        assert!(health(100, 3).is_healthy);
+        assert!(health(103, 3).is_healthy);
+        assert!(!health(104, 3).is_healthy);
+        // A lag of zero disables the check.
+        assert!(health(1_000, 0).is_healthy);
+        // The node has not reported its latest milestone yet.
+        assert!(sync_health(Some(newest), None, now, grace_period, 3).is_healthy);
+    }
+
+    #[tokio::test]
+    async fn routes_are_nested_under_base_path() {
+        let config = ApiConfigData::try_from(ApiConfig {
+            base_path: "/chronicle".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let router = routes(&config.base_path).layer(Extension(config));
+        let status = |uri: &'static str| {
+            let router = router.clone();
+            async move {
+                router
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        // The public route reaches its handler, which is not implemented.
+        assert_eq!(status("/chronicle/api/core/v2/tips").await, StatusCode::NOT_IMPLEMENTED);
+        assert_eq!(status("/api/core/v2/tips").await, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn invalid_base_path_is_rejected() {
+        for base_path in ["chronicle", "/chronicle/", "/"] {
+            let res = ApiConfigData::try_from(ApiConfig {
+                base_path: base_path.to_string(),
+                ..Default::default()
+            });
+            assert!(matches!(res, Err(ConfigError::InvalidBasePath(_))), "{base_path}");
+        }
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::{boxed, Bytes, Full},
+    http::{header::ACCEPT, HeaderMap, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use hyper::StatusCode;
+
+use super::{config::ApiConfigData, error::DatabaseOutage};
+
+/// The header that marks a response as served from the fallback cache.
+pub const STALE_HEADER: &str = "x-cache-stale";
+
+/// The maximum number of responses that are retained. Once it is reached, only existing entries are refreshed.
+const MAX_STALE_RESPONSES: usize = 1000;
+
+#[derive(Clone, Debug)]
+struct StaleResponse {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// The last successful response of each request, which is served instead of an error while the database is
+/// unreachable. Entries never expire, since they are only used as a fallback.
+#[derive(Clone, Debug, Default)]
+pub struct StaleResponses(Arc<Mutex<HashMap<String, StaleResponse>>>);
+
+impl StaleResponses {
+    /// Records successful responses for the given key, and replaces responses that failed because of a database
+    /// outage with the last recorded one.
+    pub async fn handle(&self, key: String, response: Response) -> Response {
+        if response.status() == StatusCode::OK {
+            let (parts, body) = response.into_parts();
+            let body = match hyper::body::to_bytes(body).await {
+                Ok(body) => body,
+                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            };
+            let mut responses = self.0.lock().unwrap();
+            if responses.len() < MAX_STALE_RESPONSES || responses.contains_key(&key) {
+                responses.insert(
+                    key,
+                    StaleResponse {
+                        headers: parts.headers.clone(),
+                        body: body.clone(),
+                    },
+                );
+            }
+            Response::from_parts(parts, boxed(Full::from(body)))
+        } else if response.extensions().get::<DatabaseOutage>().is_some() {
+            match self.0.lock().unwrap().get(&key).cloned() {
+                Some(StaleResponse { headers, body }) => {
+                    let mut stale = Response::new(boxed(Full::from(body)));
+                    *stale.headers_mut() = headers;
+                    stale
+                        .headers_mut()
+                        .insert(STALE_HEADER, HeaderValue::from_static("true"));
+                    stale
+                }
+                None => response,
+            }
+        } else {
+            response
+        }
+    }
+}
+
+/// Serves the last successful response while the database is unreachable, if enabled by the configuration.
+pub async fn serve_stale<B>(req: Request<B>, next: Next<B>) -> Response {
+    let stale_responses = req
+        .extensions()
+        .get::<ApiConfigData>()
+        .and_then(|config| config.stale_responses.clone());
+    match stale_responses {
+        Some(stale_responses) => {
+            // Responses are negotiated, so the same uri can produce different bodies.
+            let key = format!(
+                "{} {}",
+                req.uri(),
+                req.headers()
+                    .get(ACCEPT)
+                    .and_then(|accept| accept.to_str().ok())
+                    .unwrap_or_default()
+            );
+            let response = next.run(req).await;
+            stale_responses.handle(key, response).await
+        }
+        None => next.run(req).await,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::ApiError;
+
+    fn outage() -> Response {
+        let err: mongodb::error::Error = std::io::Error::from(std::io::ErrorKind::ConnectionRefused).into();
+        ApiError::from(err).into_response()
+    }
+
+    #[tokio::test]
+    async fn stale_response_is_served_during_outage() {
+        let stale_responses = StaleResponses::default();
+        let key = "/api/explorer/v2/ledger/supply ".to_string();
+
+        // Without a cached entry, the outage is reported as is.
+        let response = stale_responses.handle(key.clone(), outage()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let response = stale_responses
+            .handle(key.clone(), (StatusCode::OK, "cached").into_response())
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(STALE_HEADER).is_none());
+
+        let response = stale_responses.handle(key.clone(), outage()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(STALE_HEADER).unwrap(), "true");
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "cached");
+
+        // Other errors are not hidden.
+        let response = stale_responses
+            .handle(key, (StatusCode::NOT_FOUND, "missing").into_response())
+            .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -157,8 +157,11 @@ impl FillAnalyticsCommand {
                 match input_source {
                     #[cfg(feature = "inx")]
                     InputSourceChoice::Inx => {
-                        tracing::info!("Connecting to INX at url `{}`.", config.inx.url);
-                        let inx = chronicle::inx::Inx::connect(config.inx.url.clone()).await?;
+                        tracing::info!("Connecting to INX at url `{}`.", config.inx.urls.join("`, `"));
+                        let (_, inx) = crate::inx::connect_first(&config.inx.urls, 0, |url| {
+                            chronicle::inx::Inx::connect(url.to_string())
+                        })
+                        .await?;
                         fill_analytics(
                             &db,
                             &influx_db,
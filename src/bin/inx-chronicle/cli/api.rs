@@ -4,6 +4,7 @@
 use api::ApiConfig;
 use clap::{Args, Parser};
 
+use super::parse_duration;
 use crate::api::config as api;
 
 #[derive(Args, Debug)]
@@ -20,6 +21,61 @@ pub struct ApiArgs {
     /// Maximum number of results returned by a single API call.
     #[arg(long, value_name = "SIZE", default_value_t = api::DEFAULT_MAX_PAGE_SIZE)]
     pub max_page_size: usize,
+    /// Maximum number of ids that can be looked up in a single batch request.
+    #[arg(long, value_name = "SIZE", default_value_t = api::DEFAULT_MAX_BATCH_SIZE)]
+    pub max_batch_size: usize,
+    /// The number of milestones that must follow the booking milestone before a transaction is reported as confirmed.
+    #[arg(long, value_name = "DEPTH", default_value_t = api::DEFAULT_CONFIRMATION_DEPTH)]
+    pub confirmation_depth: u32,
+    /// The number of latest milestones to prefetch when the API starts. A value of `0` disables the warmup.
+    #[arg(long, value_name = "COUNT", default_value_t = api::DEFAULT_WARMUP_MILESTONES)]
+    pub api_warmup_milestones: usize,
+    /// The maximum number of analytics aggregations that are run concurrently.
+    #[arg(long, value_name = "COUNT", default_value_t = api::DEFAULT_MAX_CONCURRENT_AGGREGATIONS)]
+    pub max_concurrent_aggregations: usize,
+    /// The maximum number of analytics requests that wait for a free aggregation slot. Any further requests are
+    /// rejected as busy right away.
+    #[arg(long, value_name = "COUNT", default_value_t = api::DEFAULT_MAX_QUEUED_AGGREGATIONS)]
+    pub max_queued_aggregations: usize,
+    /// How long an analytics request waits for a free aggregation slot before it is rejected as busy.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = api::DEFAULT_AGGREGATION_TIMEOUT)]
+    pub aggregation_timeout: std::time::Duration,
+    /// The longest time range that an analytics request may cover. Omitted bounds are filled in so that the range does
+    /// not exceed it. A value of `0s` allows any range.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = api::DEFAULT_MAX_TIME_RANGE)]
+    pub max_time_range: std::time::Duration,
+    /// The number of matches at which counting the total of a list response stops. A value of `0` counts exactly.
+    #[arg(long, value_name = "COUNT", default_value_t = api::DEFAULT_MAX_TOTAL_COUNT)]
+    pub max_total_count: u64,
+    /// Serve the last successful analytics response, marked with an `X-Cache-Stale` header, while the database is
+    /// unreachable.
+    #[arg(long, default_value_t = api::DEFAULT_SERVE_STALE_ON_OUTAGE)]
+    pub serve_stale_on_outage: bool,
+    /// How long no new milestone may be applied before Chronicle reports itself as unhealthy. Short lags, such as a
+    /// single late milestone, within this period do not fail the health check.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = api::DEFAULT_HEALTH_GRACE_PERIOD)]
+    pub health_grace_period: std::time::Duration,
+    /// How many milestones the database may fall behind the latest milestone confirmed by the node before Chronicle
+    /// reports itself as unhealthy. A value of `0` disables the check.
+    #[arg(long, value_name = "COUNT", default_value_t = api::DEFAULT_MAX_MILESTONE_LAG)]
+    pub max_milestone_lag: u32,
+    /// The path prefix under which the API is served, e.g. `/chronicle` when running behind a shared reverse proxy.
+    /// Must start with a `/` and must not end with one.
+    #[arg(long, value_name = "PATH", default_value = api::DEFAULT_BASE_PATH)]
+    pub api_base_path: String,
+    /// How long a request may take before it is cancelled with `408 Request Timeout`.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = api::DEFAULT_REQUEST_TIMEOUT)]
+    pub api_request_timeout: std::time::Duration,
+    /// The maximum size of a request body in bytes. Larger requests are rejected with `413 Payload Too Large`.
+    #[arg(long, value_name = "BYTES", default_value_t = api::DEFAULT_MAX_REQUEST_BODY_SIZE)]
+    pub api_max_request_body_size: usize,
+    /// How long the requests in flight are given to complete on shutdown, before their connections are closed.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = api::DEFAULT_SHUTDOWN_GRACE_PERIOD)]
+    pub api_shutdown_grace_period: std::time::Duration,
+    /// How long the collection sizes reported by `/api/metrics` are cached before MongoDB is queried again. A value of
+    /// `0s` queries MongoDB on every request.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = api::DEFAULT_METRICS_CACHE_INTERVAL)]
+    pub api_metrics_cache_interval: std::time::Duration,
     /// JWT arguments.
     #[command(flatten)]
     pub jwt: JwtArgs,
@@ -39,7 +95,23 @@ impl From<&ApiArgs> for api::ApiConfig {
             jwt_identity_file: value.jwt.jwt_identity.clone(),
             jwt_expiration: value.jwt.jwt_expiration,
             max_page_size: value.max_page_size,
+            max_batch_size: value.max_batch_size,
             public_routes: value.public_routes.clone(),
+            confirmation_depth: value.confirmation_depth,
+            warmup_milestones: value.api_warmup_milestones,
+            max_concurrent_aggregations: value.max_concurrent_aggregations,
+            max_queued_aggregations: value.max_queued_aggregations,
+            aggregation_timeout: value.aggregation_timeout,
+            max_time_range: value.max_time_range,
+            max_total_count: value.max_total_count,
+            serve_stale_on_outage: value.serve_stale_on_outage,
+            health_grace_period: value.health_grace_period,
+            max_milestone_lag: value.max_milestone_lag,
+            base_path: value.api_base_path.clone(),
+            request_timeout: value.api_request_timeout,
+            max_request_body_size: value.api_max_request_body_size,
+            shutdown_grace_period: value.api_shutdown_grace_period,
+            metrics_cache_interval: value.api_metrics_cache_interval,
         }
     }
 }
@@ -60,10 +132,6 @@ pub struct JwtArgs {
     pub jwt_expiration: std::time::Duration,
 }
 
-fn parse_duration(arg: &str) -> Result<std::time::Duration, humantime::DurationError> {
-    arg.parse::<humantime::Duration>().map(Into::into)
-}
-
 /// Generate a JWT token using the available config.
 #[derive(Clone, Debug, PartialEq, Eq, Parser)]
 pub struct GenerateJWTCommand;
@@ -1,19 +1,107 @@
 // Copyright 2023 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use clap::Args;
+use chronicle::model::tangle::MilestoneIndex;
+use clap::{Args, Parser};
 
-use crate::inx::config as inx;
+use super::parse_duration;
+use crate::{
+    config::ChronicleConfig,
+    inx::{
+        config::{self as inx, BlockPayloadChoice, OutputKindChoice},
+        InxWorker,
+    },
+};
 
 #[derive(Args, Debug)]
 pub struct InxArgs {
-    /// The address of the node INX interface Chronicle tries to connect to - if enabled.
-    #[arg(long, value_name = "URL", env = "INX_URL", default_value = inx::DEFAULT_URL)]
-    pub inx_url: String,
+    /// The address of the node INX interface Chronicle tries to connect to - if enabled. Several nodes can be given
+    /// as a comma-separated list, in which case Chronicle fails over to the next one when a connection fails.
+    #[arg(
+        long,
+        value_name = "URL",
+        env = "INX_URL",
+        default_value = inx::DEFAULT_URL,
+        value_delimiter = ',',
+        num_args = 1..
+    )]
+    pub inx_url: Vec<String>,
     /// Milestone at which synchronization should begin. If set to `1` Chronicle will try to sync back until the
     /// genesis block. If set to `0` Chronicle will start syncing from the most recent milestone it received.
     #[arg(long, value_name = "START", default_value_t = inx::DEFAULT_SYNC_START)]
     pub inx_sync_start: u32,
+    /// Continue from the node's earliest milestone if it pruned milestones that are missing from the database, instead
+    /// of failing. The ledger state is incomplete afterwards.
+    #[arg(long, default_value_t = inx::DEFAULT_SKIP_PRUNED_MILESTONES)]
+    pub inx_skip_pruned_milestones: bool,
+    /// Log only one in every `N` blocks received via INX at `trace` level.
+    #[arg(long, value_name = "N", default_value_t = inx::DEFAULT_TRACE_SAMPLE_RATE)]
+    pub inx_trace_sample_rate: u64,
+    /// The maximum time a single database operation may take during synchronization before it is aborted.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = inx::DEFAULT_DB_OPERATION_TIMEOUT)]
+    pub inx_db_operation_timeout: std::time::Duration,
+    /// How long to keep retrying a milestone while the database is unreachable before giving up. A value of `0s`
+    /// fails on the first connection error.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = inx::DEFAULT_DB_RECONNECT_TIMEOUT)]
+    pub inx_db_reconnect_timeout: std::time::Duration,
+    /// The number of restarts after database failures within `--inx-db-restart-window` after which restarts are
+    /// paused for `--inx-db-restart-cooldown`. A value of `0` stops Chronicle once the database fails.
+    #[arg(long, value_name = "COUNT", default_value_t = inx::DEFAULT_DB_RESTART_THRESHOLD)]
+    pub inx_db_restart_threshold: usize,
+    /// The period in which restarts after database failures are counted.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = inx::DEFAULT_DB_RESTART_WINDOW)]
+    pub inx_db_restart_window: std::time::Duration,
+    /// How long restarts are paused once too many of them happened, before a single restart is attempted.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = inx::DEFAULT_DB_RESTART_COOLDOWN)]
+    pub inx_db_restart_cooldown: std::time::Duration,
+    /// The maximum number of documents written to the database in a single command during synchronization.
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        default_value_t = inx::DEFAULT_INSERT_BATCH_SIZE
+    )]
+    pub inx_insert_batch_size: usize,
+    /// The number of milestones that are fetched from the node concurrently while catching up. Milestones are still
+    /// applied in order.
+    #[arg(
+        long,
+        value_name = "COUNT",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        default_value_t = inx::DEFAULT_FETCH_CONCURRENCY
+    )]
+    pub inx_fetch_concurrency: usize,
+    /// The delay before reconnecting after the connection to the node was lost. It doubles with every failed attempt.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = inx::DEFAULT_CONNECTION_RETRY_INITIAL_INTERVAL)]
+    pub inx_connection_retry_initial_interval: std::time::Duration,
+    /// The longest delay between attempts to reconnect to the node. A value of `0s` stops Chronicle once the
+    /// connection is lost.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = inx::DEFAULT_CONNECTION_RETRY_MAX_INTERVAL)]
+    pub inx_connection_retry_max_interval: std::time::Duration,
+    /// Record data that the node sent but that could not be decoded in a capped collection, so that it can be
+    /// inspected after Chronicle stopped.
+    #[arg(long, default_value_t = inx::DEFAULT_DEAD_LETTERS)]
+    pub inx_dead_letters: bool,
+    /// Update the metadata of stored blocks when the node reports that they became solid or were referenced by a
+    /// milestone.
+    #[arg(long, default_value_t = inx::DEFAULT_BLOCK_STATUS_UPDATES)]
+    pub inx_block_status_updates: bool,
+    /// How often the number of prefetched milestones that wait to be applied is written to the metrics database.
+    #[cfg(feature = "metrics")]
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = inx::DEFAULT_QUEUE_METRICS_INTERVAL)]
+    pub inx_queue_metrics_interval: std::time::Duration,
+    /// Do not request the blocks referenced by each milestone from the node. Milestones and ledger updates are always
+    /// stored, but treasury payloads are not recorded and no proofs of inclusion can be created.
+    #[arg(long, default_value_t = !inx::StreamFilter::default().blocks)]
+    pub inx_disable_blocks: bool,
+    /// Only store the blocks with the selected payloads. All blocks are stored by default. Once any block was left
+    /// out, the proof of inclusion routes of the API are disabled for the database.
+    #[arg(long, value_enum, default_values_t = inx::all_block_payloads())]
+    pub inx_block_payloads: Vec<BlockPayloadChoice>,
+    /// Only store the outputs of the selected kinds, together with their ledger updates. All outputs are stored by
+    /// default. Requires analytics to be disabled, and cannot be changed for an existing database.
+    #[arg(long, value_enum, default_values_t = inx::all_output_kinds())]
+    pub inx_output_kinds: Vec<OutputKindChoice>,
     /// Disable the INX synchronization workflow.
     #[arg(long, default_value_t = !inx::DEFAULT_ENABLED)]
     pub disable_inx: bool,
@@ -23,8 +111,52 @@ impl From<&InxArgs> for inx::InxConfig {
     fn from(value: &InxArgs) -> Self {
         Self {
             enabled: !value.disable_inx,
-            url: value.inx_url.clone(),
+            urls: value.inx_url.clone(),
             sync_start_milestone: value.inx_sync_start.into(),
+            skip_pruned_milestones: value.inx_skip_pruned_milestones,
+            trace_sample_rate: value.inx_trace_sample_rate,
+            db_operation_timeout: value.inx_db_operation_timeout,
+            db_reconnect_timeout: value.inx_db_reconnect_timeout,
+            db_restart_threshold: value.inx_db_restart_threshold,
+            db_restart_window: value.inx_db_restart_window,
+            db_restart_cooldown: value.inx_db_restart_cooldown,
+            insert_batch_size: value.inx_insert_batch_size,
+            fetch_concurrency: value.inx_fetch_concurrency,
+            connection_retry_initial_interval: value.inx_connection_retry_initial_interval,
+            connection_retry_max_interval: value.inx_connection_retry_max_interval,
+            dead_letters: value.inx_dead_letters,
+            block_status_updates: value.inx_block_status_updates,
+            #[cfg(feature = "metrics")]
+            queue_metrics_interval: value.inx_queue_metrics_interval,
+            stream_filter: inx::StreamFilter {
+                blocks: !value.inx_disable_blocks,
+                block_payloads: value.inx_block_payloads.iter().copied().collect(),
+                output_kinds: value.inx_output_kinds.iter().copied().collect(),
+            },
         }
     }
 }
+
+/// Revert the data of a range of milestones and apply them again from the node, then exit. The range must already be
+/// synced, and no other instance may be syncing the same database while the replay runs.
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct ReplayCommand {
+    /// The first milestone to replay.
+    #[arg(long, value_name = "MILESTONE")]
+    from: MilestoneIndex,
+    /// The last milestone to replay, inclusive.
+    #[arg(long, value_name = "MILESTONE")]
+    to: MilestoneIndex,
+}
+
+impl ReplayCommand {
+    pub async fn handle(&self, config: &ChronicleConfig) -> eyre::Result<()> {
+        tracing::info!("Connecting to database using hosts: `{}`.", config.mongodb.hosts_str()?);
+        let db = crate::startup::connect_mongodb(&config.mongodb, config.startup.wait_for_deps).await?;
+        crate::migrations::check_migration_version(&db).await?;
+
+        let mut worker = InxWorker::new(db, config.inx.clone());
+        worker.set_wait_for_deps(config.startup.wait_for_deps);
+        worker.replay(self.from..=self.to).await
+    }
+}
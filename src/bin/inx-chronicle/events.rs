@@ -0,0 +1,28 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Events that are published by the ingestion and forwarded to the clients of the API.
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// The number of milestone events that are buffered for each subscriber before it is considered to be lagging.
+pub const MILESTONE_EVENTS_CAPACITY: usize = 64;
+
+/// Published after a milestone was applied to the database.
+#[cfg_attr(not(feature = "inx"), allow(dead_code))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MilestoneEvent {
+    pub milestone_index: u32,
+    pub milestone_timestamp: u32,
+    pub block_count: usize,
+}
+
+/// The sending half of the channel that milestone events are published on.
+pub type MilestoneEvents = broadcast::Sender<MilestoneEvent>;
+
+/// Creates the channel that milestone events are published on.
+pub fn milestone_events() -> MilestoneEvents {
+    broadcast::channel(MILESTONE_EVENTS_CAPACITY).0
+}
@@ -0,0 +1,154 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use tracing::warn;
+
+/// The state of a [`CircuitBreaker`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Restarts happen right away.
+    Closed,
+    /// Too many restarts happened recently, so the next one waits for the cooldown to pass.
+    Open,
+    /// The cooldown has passed and a single restart is attempted. If it fails again, the breaker opens again.
+    HalfOpen,
+}
+
+/// Limits how often the worker is restarted after the database failed. Restarts are immediate until `threshold` of
+/// them happened within `window`, at which point the breaker trips and pauses restarts for `cooldown`.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: usize,
+    window: Duration,
+    cooldown: Duration,
+    /// When the restarts within the current window happened.
+    restarts: VecDeque<Instant>,
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed breaker. A `threshold` of zero disables restarting.
+    pub fn new(threshold: usize, window: Duration, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            window,
+            cooldown,
+            restarts: VecDeque::new(),
+            opened_at: None,
+        }
+    }
+
+    /// Whether the worker should be restarted at all.
+    pub fn is_enabled(&self) -> bool {
+        self.threshold > 0
+    }
+
+    /// Returns the state of the breaker at `now`.
+    pub fn state(&self, now: Instant) -> BreakerState {
+        match self.opened_at {
+            None => BreakerState::Closed,
+            Some(opened_at) if now.saturating_duration_since(opened_at) < self.cooldown => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Records a failure at `now`, and returns how long to wait before the next restart.
+    pub fn on_failure(&mut self, now: Instant) -> Duration {
+        match self.state(now) {
+            BreakerState::Closed => {
+                let window = self.window;
+                self.restarts
+                    .retain(|&restart| now.saturating_duration_since(restart) < window);
+                self.restarts.push_back(now);
+                if self.restarts.len() < self.threshold {
+                    return Duration::ZERO;
+                }
+                warn!(
+                    "The database failed {} times within {}. Pausing restarts for {}.",
+                    self.restarts.len(),
+                    humantime::format_duration(self.window),
+                    humantime::format_duration(self.cooldown)
+                );
+                self.restarts.clear();
+            }
+            BreakerState::Open | BreakerState::HalfOpen => warn!(
+                "The database is still failing. Pausing restarts for {}.",
+                humantime::format_duration(self.cooldown)
+            ),
+        }
+        self.opened_at = Some(now);
+        self.cooldown
+    }
+
+    /// Closes the breaker, once the worker ran long enough to be considered healthy again.
+    pub fn reset(&mut self) {
+        self.restarts.clear();
+        self.opened_at = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+    const COOLDOWN: Duration = Duration::from_secs(300);
+
+    #[test]
+    fn restarts_stop_after_threshold() {
+        let mut breaker = CircuitBreaker::new(3, WINDOW, COOLDOWN);
+        let start = Instant::now();
+
+        assert_eq!(breaker.on_failure(start), Duration::ZERO);
+        assert_eq!(breaker.on_failure(start + Duration::from_secs(1)), Duration::ZERO);
+        assert_eq!(breaker.state(start + Duration::from_secs(1)), BreakerState::Closed);
+        // The third failure within the window trips the breaker.
+        let tripped = start + Duration::from_secs(2);
+        assert_eq!(breaker.on_failure(tripped), COOLDOWN);
+        assert_eq!(breaker.state(tripped + COOLDOWN / 2), BreakerState::Open);
+
+        // After the cooldown, a single attempt is made, and its failure opens the breaker again.
+        let retried = tripped + COOLDOWN;
+        assert_eq!(breaker.state(retried), BreakerState::HalfOpen);
+        assert_eq!(breaker.on_failure(retried), COOLDOWN);
+        assert_eq!(breaker.state(retried), BreakerState::Open);
+
+        breaker.reset();
+        assert_eq!(breaker.state(retried), BreakerState::Closed);
+        assert_eq!(breaker.on_failure(retried), Duration::ZERO);
+    }
+
+    #[test]
+    fn repeated_io_errors_trip_the_breaker() {
+        let mut breaker = CircuitBreaker::new(3, WINDOW, COOLDOWN);
+        let err = eyre::Report::new(mongodb::error::Error::from(std::io::Error::from(
+            std::io::ErrorKind::ConnectionRefused,
+        )));
+        let now = Instant::now();
+        let mut restarts = 0;
+        while super::super::is_transient_db_error(&err) && breaker.on_failure(now).is_zero() {
+            restarts += 1;
+        }
+        assert_eq!(restarts, 2);
+        assert_eq!(breaker.state(now), BreakerState::Open);
+    }
+
+    #[test]
+    fn failures_outside_window_are_forgotten() {
+        let mut breaker = CircuitBreaker::new(2, WINDOW, COOLDOWN);
+        let start = Instant::now();
+        assert_eq!(breaker.on_failure(start), Duration::ZERO);
+        assert_eq!(breaker.on_failure(start + WINDOW), Duration::ZERO);
+        assert_eq!(breaker.on_failure(start + WINDOW + Duration::from_secs(1)), COOLDOWN);
+    }
+
+    #[test]
+    fn zero_threshold_disables_restarting() {
+        assert!(!CircuitBreaker::new(0, WINDOW, COOLDOWN).is_enabled());
+    }
+}
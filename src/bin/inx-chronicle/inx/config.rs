@@ -1,28 +1,336 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use chronicle::model::tangle::MilestoneIndex;
+use std::{collections::HashSet, time::Duration};
+
+use chronicle::model::{payload::Payload, tangle::MilestoneIndex, utxo::Output, Block};
 
 pub const DEFAULT_ENABLED: bool = true;
 pub const DEFAULT_URL: &str = "http://localhost:9029";
 pub const DEFAULT_SYNC_START: u32 = 0;
+pub const DEFAULT_SKIP_PRUNED_MILESTONES: bool = false;
+pub const DEFAULT_TRACE_SAMPLE_RATE: u64 = 1;
+pub const DEFAULT_DB_OPERATION_TIMEOUT: &str = "60s";
+pub const DEFAULT_DB_RECONNECT_TIMEOUT: &str = "30s";
+pub const DEFAULT_DB_RESTART_THRESHOLD: usize = 5;
+pub const DEFAULT_DB_RESTART_WINDOW: &str = "5m";
+pub const DEFAULT_DB_RESTART_COOLDOWN: &str = "5m";
+pub const DEFAULT_INSERT_BATCH_SIZE: usize = 1000;
+pub const DEFAULT_FETCH_CONCURRENCY: usize = 1;
+pub const DEFAULT_CONNECTION_RETRY_INITIAL_INTERVAL: &str = "1s";
+pub const DEFAULT_CONNECTION_RETRY_MAX_INTERVAL: &str = "1m";
+pub const DEFAULT_DEAD_LETTERS: bool = false;
+pub const DEFAULT_BLOCK_STATUS_UPDATES: bool = false;
+#[cfg(feature = "metrics")]
+pub const DEFAULT_QUEUE_METRICS_INTERVAL: &str = "10s";
 
 /// Configuration for an INX connection.
 #[derive(Clone, Debug)]
 pub struct InxConfig {
     pub enabled: bool,
-    /// The bind address of node's INX interface.
-    pub url: String,
+    /// The bind addresses of the INX interfaces of the nodes. They are tried in order, and the next one is used once
+    /// the connection to the current one is lost.
+    pub urls: Vec<String>,
     /// The milestone at which synchronization should begin.
     pub sync_start_milestone: MilestoneIndex,
+    /// Whether the sync continues from the node's earliest milestone if the node pruned the milestones after the newest
+    /// one in the database. The skipped milestones are recorded, since the ledger is incomplete afterwards.
+    pub skip_pruned_milestones: bool,
+    /// Only one in every `trace_sample_rate` high-frequency events is logged at `trace` level.
+    pub trace_sample_rate: u64,
+    /// The maximum time a single database operation may take before it is aborted.
+    pub db_operation_timeout: Duration,
+    /// How long applying a milestone is retried after the database became unreachable before the worker fails.
+    pub db_reconnect_timeout: Duration,
+    /// The number of restarts after database failures within `db_restart_window` after which restarts are paused for
+    /// `db_restart_cooldown`. Zero disables restarting, so that the worker fails with the database.
+    pub db_restart_threshold: usize,
+    /// The period in which restarts after database failures are counted.
+    pub db_restart_window: Duration,
+    /// How long restarts are paused once too many of them happened. Afterwards, a single restart is attempted.
+    pub db_restart_cooldown: Duration,
+    /// The maximum number of documents written to the database in a single command. Large milestones are split into
+    /// several commands, so that none of them exceeds MongoDb's size limit.
+    pub insert_batch_size: usize,
+    /// The number of milestones whose ledger updates are fetched from the node ahead of time. Milestones are still
+    /// applied one after the other, in order.
+    pub fetch_concurrency: usize,
+    /// The delay before reconnecting after the connection to the node was lost. It doubles with every failed attempt.
+    pub connection_retry_initial_interval: Duration,
+    /// The longest delay between attempts to reconnect to the node. Zero disables reconnecting, so that the worker
+    /// fails once the connection is lost.
+    pub connection_retry_max_interval: Duration,
+    /// Whether data that the node sent but that could not be decoded is recorded in the database before the worker
+    /// fails.
+    pub dead_letters: bool,
+    /// Whether the metadata of stored blocks is updated when the node reports that they became solid or were
+    /// referenced. Has no effect if blocks are not stored.
+    pub block_status_updates: bool,
+    /// How often the depth of the milestone prefetch queue is written to the metrics database.
+    #[cfg(feature = "metrics")]
+    pub queue_metrics_interval: Duration,
+    /// Selects which of the data that is streamed by the node is stored.
+    pub stream_filter: StreamFilter,
 }
 
 impl Default for InxConfig {
     fn default() -> Self {
         Self {
             enabled: DEFAULT_ENABLED,
-            url: DEFAULT_URL.to_string(),
+            urls: vec![DEFAULT_URL.to_string()],
             sync_start_milestone: DEFAULT_SYNC_START.into(),
+            skip_pruned_milestones: DEFAULT_SKIP_PRUNED_MILESTONES,
+            trace_sample_rate: DEFAULT_TRACE_SAMPLE_RATE,
+            db_operation_timeout: DEFAULT_DB_OPERATION_TIMEOUT
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            db_reconnect_timeout: DEFAULT_DB_RECONNECT_TIMEOUT
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            db_restart_threshold: DEFAULT_DB_RESTART_THRESHOLD,
+            db_restart_window: DEFAULT_DB_RESTART_WINDOW.parse::<humantime::Duration>().unwrap().into(),
+            db_restart_cooldown: DEFAULT_DB_RESTART_COOLDOWN
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            insert_batch_size: DEFAULT_INSERT_BATCH_SIZE,
+            fetch_concurrency: DEFAULT_FETCH_CONCURRENCY,
+            connection_retry_initial_interval: DEFAULT_CONNECTION_RETRY_INITIAL_INTERVAL
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            connection_retry_max_interval: DEFAULT_CONNECTION_RETRY_MAX_INTERVAL
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            dead_letters: DEFAULT_DEAD_LETTERS,
+            block_status_updates: DEFAULT_BLOCK_STATUS_UPDATES,
+            #[cfg(feature = "metrics")]
+            queue_metrics_interval: DEFAULT_QUEUE_METRICS_INTERVAL
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            stream_filter: Default::default(),
+        }
+    }
+}
+
+/// Selects which of the data that is streamed by the node is stored. Milestones are required to maintain the ledger
+/// state, so only blocks and outputs can be filtered. By default, everything is stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamFilter {
+    /// Whether the blocks referenced by each milestone are requested from the node at all. Without them, treasury
+    /// payloads and the blocks that carried milestones are not recorded, and no proofs of inclusion can be created.
+    pub blocks: bool,
+    /// The payloads of the blocks that are stored.
+    pub block_payloads: HashSet<BlockPayloadChoice>,
+    /// The kinds of outputs that are stored, together with their ledger updates. Outputs of other kinds are skipped
+    /// both when they are created and when they are spent, so that the routes and analytics that read the ledger
+    /// from the database only see the selected kinds.
+    pub output_kinds: HashSet<OutputKindChoice>,
+}
+
+impl Default for StreamFilter {
+    fn default() -> Self {
+        Self {
+            blocks: true,
+            block_payloads: all_block_payloads().into_iter().collect(),
+            output_kinds: all_output_kinds().into_iter().collect(),
+        }
+    }
+}
+
+impl StreamFilter {
+    /// Whether the block should be stored.
+    pub fn allows_block(&self, block: &Block) -> bool {
+        self.blocks
+            && self
+                .block_payloads
+                .contains(&BlockPayloadChoice::of(block.payload.as_ref()))
+    }
+
+    /// Whether every block is stored, so that the milestone cones are complete.
+    pub fn stores_all_blocks(&self) -> bool {
+        self.blocks
+            && all_block_payloads()
+                .iter()
+                .all(|payload| self.block_payloads.contains(payload))
+    }
+
+    /// Whether the output should be stored.
+    pub fn allows_output(&self, output: &Output) -> bool {
+        self.output_kinds.contains(&OutputKindChoice::of(output))
+    }
+
+    /// The sorted names of the stored output kinds, or `None` if all outputs are stored. This is recorded in the
+    /// database, since the ledger only stays consistent as long as the same kinds are stored.
+    pub fn restricted_output_kinds(&self) -> Option<Vec<String>> {
+        if all_output_kinds().iter().all(|kind| self.output_kinds.contains(kind)) {
+            return None;
+        }
+        let mut names = self
+            .output_kinds
+            .iter()
+            .filter_map(|kind| clap::ValueEnum::to_possible_value(kind))
+            .map(|value| value.get_name().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        Some(names)
+    }
+}
+
+/// The kinds of blocks that can be selected, by their payload.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum BlockPayloadChoice {
+    NoPayload,
+    Transaction,
+    Milestone,
+    TreasuryTransaction,
+    TaggedData,
+}
+
+impl BlockPayloadChoice {
+    fn of(payload: Option<&Payload>) -> Self {
+        match payload {
+            None => Self::NoPayload,
+            Some(Payload::Transaction(_)) => Self::Transaction,
+            Some(Payload::Milestone(_)) => Self::Milestone,
+            Some(Payload::TreasuryTransaction(_)) => Self::TreasuryTransaction,
+            Some(Payload::TaggedData(_)) => Self::TaggedData,
+        }
+    }
+}
+
+pub fn all_block_payloads() -> Vec<BlockPayloadChoice> {
+    vec![
+        BlockPayloadChoice::NoPayload,
+        BlockPayloadChoice::Transaction,
+        BlockPayloadChoice::Milestone,
+        BlockPayloadChoice::TreasuryTransaction,
+        BlockPayloadChoice::TaggedData,
+    ]
+}
+
+/// The kinds of outputs that can be selected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum OutputKindChoice {
+    Treasury,
+    Basic,
+    Alias,
+    Foundry,
+    Nft,
+}
+
+impl OutputKindChoice {
+    fn of(output: &Output) -> Self {
+        match output {
+            Output::Treasury(_) => Self::Treasury,
+            Output::Basic(_) => Self::Basic,
+            Output::Alias(_) => Self::Alias,
+            Output::Foundry(_) => Self::Foundry,
+            Output::Nft(_) => Self::Nft,
+        }
+    }
+}
+
+pub fn all_output_kinds() -> Vec<OutputKindChoice> {
+    vec![
+        OutputKindChoice::Treasury,
+        OutputKindChoice::Basic,
+        OutputKindChoice::Alias,
+        OutputKindChoice::Foundry,
+        OutputKindChoice::Nft,
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use iota_types::block::payload::TaggedDataPayload;
+
+    use super::*;
+
+    fn block(payload: Option<Payload>) -> Block {
+        Block {
+            protocol_version: 2,
+            parents: Box::new([]),
+            payload,
+            nonce: 0,
         }
     }
+
+    #[test]
+    fn blocks_are_filtered_by_payload() {
+        let tagged_data = block(Some(
+            iota_types::block::payload::Payload::from(TaggedDataPayload::new(vec![1], vec![2]).unwrap()).into(),
+        ));
+        let no_payload = block(None);
+
+        // Everything is stored by default.
+        let filter = StreamFilter::default();
+        assert!(filter.allows_block(&tagged_data));
+        assert!(filter.allows_block(&no_payload));
+
+        let filter = StreamFilter {
+            blocks: true,
+            block_payloads: [BlockPayloadChoice::TaggedData].into_iter().collect(),
+            ..Default::default()
+        };
+        assert!(filter.allows_block(&tagged_data));
+        assert!(!filter.allows_block(&no_payload));
+
+        assert!(!filter.stores_all_blocks());
+
+        let filter = StreamFilter {
+            blocks: false,
+            ..Default::default()
+        };
+        assert!(!filter.allows_block(&tagged_data));
+        assert!(!filter.stores_all_blocks());
+        assert!(StreamFilter::default().stores_all_blocks());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn outputs_are_filtered_by_kind() {
+        let ctx = iota_types::block::protocol::protocol_parameters();
+        let batch = vec![
+            Output::rand_basic(&ctx),
+            Output::rand_nft(&ctx),
+            Output::rand_alias(&ctx),
+            Output::rand_foundry(&ctx),
+            Output::rand_nft(&ctx),
+            Output::rand_treasury(&ctx),
+        ];
+
+        // Everything is stored by default.
+        let filter = StreamFilter::default();
+        assert!(batch.iter().all(|output| filter.allows_output(output)));
+
+        let filter = StreamFilter {
+            output_kinds: [OutputKindChoice::Nft].into_iter().collect(),
+            ..Default::default()
+        };
+        let stored = batch
+            .iter()
+            .filter(|output| filter.allows_output(output))
+            .collect::<Vec<_>>();
+        assert_eq!(stored.len(), 2);
+        assert!(stored.iter().all(|output| matches!(output, Output::Nft(_))));
+    }
+
+    #[test]
+    fn restricted_output_kinds_are_named() {
+        assert_eq!(StreamFilter::default().restricted_output_kinds(), None);
+
+        let filter = StreamFilter {
+            output_kinds: [OutputKindChoice::Nft, OutputKindChoice::Alias].into_iter().collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.restricted_output_kinds(),
+            Some(vec!["alias".to_string(), "nft".to_string()])
+        );
+    }
 }
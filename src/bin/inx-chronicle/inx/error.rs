@@ -1,11 +1,15 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::time::Duration;
+
 use chronicle::model::tangle::MilestoneIndex;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum InxWorkerError {
+    #[error("database operation did not complete within {0:?}")]
+    DbOperationTimeout(Duration),
     #[error("expected INX address with format `http://<address>:<port>`, but found `{0}`")]
     InvalidAddress(String),
     #[error("invalid unspent output stream: found ledger index {found}, expected {expected}")]
@@ -18,7 +22,23 @@ pub enum InxWorkerError {
     MissingAppState,
     #[error("network changed from previous run. old network name: `{old}`, new network name: `{new}`")]
     NetworkChanged { old: String, new: String },
-    #[error("node pruned required milestones between `{start}` and `{end}`")]
+    #[error(
+        "the stored output kinds changed from `{old}` to `{new}`, which would leave outputs of the removed kinds unspent; \
+        sync into an empty database instead"
+    )]
+    OutputKindsChanged { old: String, new: String },
+    #[error("milestone {0} of the replayed range was not received from the node")]
+    ReplayMissingMilestone(MilestoneIndex),
+    #[error("cannot replay milestones {start} to {end}: {reason}")]
+    ReplayRangeUnavailable {
+        start: MilestoneIndex,
+        end: MilestoneIndex,
+        reason: String,
+    },
+    #[error(
+        "node pruned required milestones between `{start}` and `{end}`, set `--inx-skip-pruned-milestones` to continue \
+         with an incomplete ledger"
+    )]
     SyncMilestoneGap { start: MilestoneIndex, end: MilestoneIndex },
     #[error("node confirmed milestone index `{node}` is less than index in database `{db}`")]
     SyncMilestoneIndexMismatch { node: MilestoneIndex, db: MilestoneIndex },
@@ -46,6 +46,11 @@ impl AnalyticsInfo {
             None
         })
     }
+
+    /// Discards the analytics state, so that it is recomputed from the database for the next milestone.
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
 }
 
 impl InxWorker {
@@ -14,6 +14,7 @@ impl InxWorker {
         milestone: &Milestone<'a, Inx>,
         #[cfg(feature = "analytics")] analytics_info: Option<&mut analytics::AnalyticsInfo>,
         #[cfg(feature = "metrics")] milestone_start_time: std::time::Instant,
+        #[cfg(feature = "metrics")] block_count: usize,
     ) -> eyre::Result<()> {
         #[cfg(all(feature = "analytics", feature = "metrics"))]
         let analytics_start_time = std::time::Instant::now();
@@ -51,6 +52,8 @@ impl InxWorker {
                         time: chrono::Utc::now(),
                         milestone_index: milestone.at.milestone_index,
                         milestone_time: elapsed.as_millis() as u64,
+                        block_count: block_count as u64,
+                        milestone_lag: milestone_lag(self.latest_known_index, milestone.at.milestone_index),
                         chronicle_version: std::env!("CARGO_PKG_VERSION").to_string(),
                     })
                     .await?;
@@ -59,4 +62,47 @@ impl InxWorker {
 
         Ok(())
     }
+
+    /// Writes the number of prefetched milestones that are waiting to be applied.
+    #[cfg(feature = "metrics")]
+    pub async fn update_queue_metrics(&self, depth: usize) -> eyre::Result<()> {
+        if let Some(influx_db) = &self.influx_db {
+            if influx_db.config().metrics_enabled {
+                influx_db
+                    .metrics()
+                    .insert(chronicle::metrics::QueueMetrics {
+                        time: chrono::Utc::now(),
+                        depth: depth as u64,
+                        queue: "milestone_prefetch".to_string(),
+                        chronicle_version: std::env!("CARGO_PKG_VERSION").to_string(),
+                    })
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The number of milestones that the node confirmed beyond the applied one. The node status may be older than the
+/// applied milestone, in which case there is no lag.
+#[cfg(feature = "metrics")]
+fn milestone_lag(
+    latest_known_index: chronicle::model::tangle::MilestoneIndex,
+    milestone_index: chronicle::model::tangle::MilestoneIndex,
+) -> u64 {
+    latest_known_index.0.saturating_sub(milestone_index.0) as u64
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod test {
+    use chronicle::model::tangle::MilestoneIndex;
+
+    use super::milestone_lag;
+
+    #[test]
+    fn milestone_lag_counts_confirmed_milestones() {
+        assert_eq!(milestone_lag(MilestoneIndex(110), MilestoneIndex(100)), 10);
+        assert_eq!(milestone_lag(MilestoneIndex(100), MilestoneIndex(100)), 0);
+        assert_eq!(milestone_lag(MilestoneIndex(90), MilestoneIndex(100)), 0);
+    }
 }
@@ -0,0 +1,99 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::BTreeMap,
+    ops::{Bound, RangeBounds},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use chronicle::{
+    model::{
+        node::{BaseToken, NodeConfiguration},
+        payload::{MilestoneId, MilestonePayload},
+        tangle::{MilestoneIndex, MilestoneIndexTimestamp},
+    },
+    tangle::{BlockData, InputSource, LedgerUpdateStore, MilestoneData},
+};
+use futures::stream::BoxStream;
+
+/// Stands in for the node, and records which milestones it was asked for.
+pub struct MockInx {
+    milestones: BTreeMap<MilestoneIndex, MilestoneData>,
+    cones: BTreeMap<MilestoneIndex, Vec<BlockData>>,
+    pub requested: Arc<Mutex<Option<(Bound<MilestoneIndex>, Bound<MilestoneIndex>)>>>,
+}
+
+impl MockInx {
+    /// Creates a node that confirmed the milestones with the given indexes, each with an empty cone.
+    pub fn new(indexes: impl IntoIterator<Item = u32>) -> Self {
+        let ctx = iota_types::block::protocol::protocol_parameters();
+        let milestones = indexes
+            .into_iter()
+            .map(|index| {
+                let data = MilestoneData {
+                    milestone_id: MilestoneId::rand(),
+                    at: MilestoneIndexTimestamp {
+                        milestone_index: index.into(),
+                        milestone_timestamp: (1000 + index).into(),
+                    },
+                    payload: MilestonePayload::rand(&ctx),
+                    protocol_params: ctx.clone().into(),
+                    node_config: NodeConfiguration {
+                        milestone_public_key_count: 0,
+                        milestone_key_ranges: Box::new([]),
+                        base_token: BaseToken {
+                            name: "Shimmer".to_string(),
+                            ticker_symbol: "SMR".to_string(),
+                            unit: "SMR".to_string(),
+                            subunit: "glow".to_string(),
+                            decimals: 6,
+                            use_metric_prefix: false,
+                        },
+                    },
+                };
+                (index.into(), data)
+            })
+            .collect();
+        Self {
+            milestones,
+            cones: Default::default(),
+            requested: Default::default(),
+        }
+    }
+
+    /// Sets the blocks that the milestone with the given index references.
+    pub fn with_cone(mut self, index: u32, blocks: Vec<BlockData>) -> Self {
+        self.cones.insert(index.into(), blocks);
+        self
+    }
+}
+
+#[async_trait]
+impl InputSource for MockInx {
+    type Error = std::convert::Infallible;
+
+    async fn milestone_stream(
+        &self,
+        range: impl RangeBounds<MilestoneIndex> + Send,
+    ) -> Result<BoxStream<Result<MilestoneData, Self::Error>>, Self::Error> {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        self.requested.lock().unwrap().replace(bounds);
+        Ok(Box::pin(futures::stream::iter(
+            self.milestones.range(bounds).map(|(_, data)| Ok(data.clone())),
+        )))
+    }
+
+    async fn cone_stream(
+        &self,
+        index: MilestoneIndex,
+    ) -> Result<BoxStream<Result<BlockData, Self::Error>>, Self::Error> {
+        let blocks = self.cones.get(&index).cloned().unwrap_or_default();
+        Ok(Box::pin(futures::stream::iter(blocks.into_iter().map(Ok))))
+    }
+
+    async fn ledger_updates(&self, _index: MilestoneIndex) -> Result<LedgerUpdateStore, Self::Error> {
+        Ok(LedgerUpdateStore::default())
+    }
+}
@@ -1,46 +1,76 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+mod breaker;
 pub mod config;
 mod error;
 #[cfg(feature = "influx")]
 mod influx;
+#[cfg(all(test, feature = "rand"))]
+mod mock;
+mod progress;
+mod reconnect;
+mod replay;
+mod sampler;
 
-use std::time::Duration;
+use std::{cmp::Ordering, future::Future, ops::RangeInclusive, time::Duration};
 
 use chronicle::{
     db::{
         mongodb::collections::{
-            ApplicationStateCollection, BlockCollection, ConfigurationUpdateCollection, LedgerUpdateCollection,
-            MilestoneCollection, OutputCollection, ProtocolUpdateCollection, TreasuryCollection,
+            ApplicationStateCollection, BlockCollection, ConfigurationUpdateCollection, DeadLetterCollection,
+            DeadLetterDocument, LedgerUpdateCollection, MilestoneCollection, OutputCollection,
+            ProtocolUpdateCollection, SkippedMilestones, TreasuryCollection,
         },
         MongoDb,
     },
-    inx::{Inx, InxError},
+    inx::{DecodeStage, Inx, InxError},
     model::{
         ledger::{LedgerOutput, LedgerSpent},
         metadata::LedgerInclusionState,
         payload::Payload,
         tangle::{MilestoneIndex, MilestoneIndexTimestamp},
     },
-    tangle::{Milestone, Tangle},
+    tangle::{InputSource, Milestone, Tangle},
 };
-use eyre::{bail, Result};
+use eyre::{bail, Result, WrapErr};
 use futures::{StreamExt, TryStreamExt};
 use tokio::{task::JoinSet, try_join};
-use tracing::{debug, info, instrument, trace_span, Instrument};
+use tracing::{debug, info, instrument, trace, trace_span, warn, Instrument};
 
-pub use self::{config::InxConfig, error::InxWorkerError};
-use crate::migrations::{LatestMigration, Migration};
-
-/// Batch size for insert operations.
-pub const INSERT_BATCH_SIZE: usize = 1000;
+use self::{
+    breaker::CircuitBreaker,
+    progress::{SyncProgress, NODE_STATUS_INTERVAL, SYNC_PROGRESS_INTERVAL},
+    reconnect::{is_connection_error, ReconnectBackoff, RECONNECT_GRACE_PERIOD},
+    replay::{check_replay_range, revert_milestones, ReplayCursor},
+    sampler::LogSampler,
+};
+pub use self::{config::InxConfig, error::InxWorkerError, reconnect::connect_first};
+use crate::{
+    events::{MilestoneEvent, MilestoneEvents},
+    migrations::{LatestMigration, Migration},
+    startup::{is_transient_mongodb_error, Backoff},
+};
 
 pub struct InxWorker {
     db: MongoDb,
     config: InxConfig,
+    block_log_sampler: LogSampler,
+    wait_for_deps: Duration,
+    /// The delay before reconnecting to the node, which keeps growing until a connection stays up.
+    reconnect_backoff: ReconnectBackoff,
+    /// Limits how often synchronization is restarted after the database failed.
+    restart_breaker: CircuitBreaker,
+    /// The index of the configured INX endpoint that is tried first.
+    endpoint: usize,
+    /// The latest milestone the node is known to have confirmed.
+    latest_known_index: MilestoneIndex,
+    /// The most recently applied milestone, used to verify that milestone timestamps do not go backwards.
+    last_milestone: Option<MilestoneIndexTimestamp>,
     #[cfg(feature = "influx")]
     influx_db: Option<chronicle::db::influxdb::InfluxDb>,
+    /// Where the applied milestones are published, if anyone is interested in them.
+    milestone_events: Option<MilestoneEvents>,
 }
 
 impl InxWorker {
@@ -48,46 +78,219 @@ impl InxWorker {
     pub fn new(db: MongoDb, inx_config: InxConfig) -> Self {
         Self {
             db,
+            block_log_sampler: LogSampler::new(inx_config.trace_sample_rate),
+            reconnect_backoff: ReconnectBackoff::new(
+                inx_config.connection_retry_initial_interval,
+                inx_config.connection_retry_max_interval,
+            ),
+            restart_breaker: CircuitBreaker::new(
+                inx_config.db_restart_threshold,
+                inx_config.db_restart_window,
+                inx_config.db_restart_cooldown,
+            ),
+            endpoint: 0,
             config: inx_config,
+            wait_for_deps: Duration::ZERO,
+            latest_known_index: 0.into(),
+            last_milestone: None,
             #[cfg(feature = "influx")]
             influx_db: None,
+            milestone_events: None,
         }
     }
 
+    /// Sets how long to wait for the node's INX interface to become available.
+    pub fn set_wait_for_deps(&mut self, wait_for_deps: Duration) {
+        self.wait_for_deps = wait_for_deps;
+    }
+
+    /// Sets the channel that an event is published on after each applied milestone.
+    pub fn set_milestone_events(&mut self, milestone_events: &MilestoneEvents) {
+        self.milestone_events.replace(milestone_events.clone());
+    }
+
     #[cfg(feature = "influx")]
     pub fn set_influx_db(&mut self, influx_db: &chronicle::db::influxdb::InfluxDb) {
         self.influx_db.replace(influx_db.clone());
     }
 
-    async fn connect(&self) -> Result<Inx> {
-        let url = url::Url::parse(&self.config.url)?;
+    async fn connect(&mut self) -> Result<Inx> {
+        for url in &self.config.urls {
+            if url::Url::parse(url)?.scheme() != "http" {
+                bail!(InxWorkerError::InvalidAddress(url.clone()));
+            }
+        }
 
-        if url.scheme() != "http" {
-            bail!(InxWorkerError::InvalidAddress(self.config.url.clone()));
+        // The node may still be starting up, but a valid address will not become invalid by waiting.
+        let (urls, start) = (&self.config.urls, self.endpoint);
+        let (endpoint, inx) = crate::startup::wait_for(
+            "INX",
+            self.wait_for_deps,
+            |err| matches!(err, InxError::TonicError(_)),
+            move || connect_first(urls, start, |url| Inx::connect(url.to_string())),
+        )
+        .await?;
+        self.endpoint = endpoint;
+        Ok(inx)
+    }
+
+    /// Synchronizes with the node, and reconnects whenever the connection to it is lost. Synchronization is also
+    /// restarted when the database fails, unless that happens too often. Returns once `shutdown_handle` completes,
+    /// which also cuts short the wait before reconnecting.
+    pub async fn run(&mut self, shutdown_handle: impl Future<Output = ()>) -> Result<()> {
+        tokio::pin!(shutdown_handle);
+        loop {
+            let connected = std::time::Instant::now();
+            let res = tokio::select! {
+                res = self.sync() => res,
+                _ = &mut shutdown_handle => return Ok(()),
+            };
+            if connected.elapsed() >= RECONNECT_GRACE_PERIOD {
+                self.reconnect_backoff.reset();
+                self.restart_breaker.reset();
+            }
+            let delay = match res {
+                Err(err) if self.reconnect_backoff.is_enabled() && is_connection_error(&err) => {
+                    // Start with the next node, in case the current one is down.
+                    self.endpoint = (self.endpoint + 1) % self.config.urls.len();
+                    let delay = self.reconnect_backoff.next_delay();
+                    warn!(
+                        "Lost the connection to INX: {err}. Reconnecting in {}.",
+                        humantime::format_duration(Duration::from_millis(delay.as_millis() as u64))
+                    );
+                    delay
+                }
+                Err(err) if self.restart_breaker.is_enabled() && is_transient_db_error(&err) => {
+                    let delay = self.restart_breaker.on_failure(std::time::Instant::now());
+                    warn!(
+                        "Synchronization failed because of the database: {err}. Restarting in {}.",
+                        humantime::format_duration(delay)
+                    );
+                    delay
+                }
+                Err(err) => {
+                    if self.config.dead_letters {
+                        self.record_dead_letter(&err).await;
+                    }
+                    return Err(err);
+                }
+                res => return res,
+            };
+            // Only try again if no shutdown was requested in the meantime.
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => (),
+                _ = &mut shutdown_handle => {
+                    debug!("Shutdown requested while waiting to reconnect.");
+                    return Ok(());
+                }
+            }
         }
+    }
 
-        Ok(Inx::connect(self.config.url.clone()).await?)
+    /// Records the data that caused the worker to fail in the dead-letter collection, if it could not be decoded.
+    /// Decoding failures still stop the worker, since skipping the data would leave the ledger state incomplete.
+    async fn record_dead_letter(&self, err: &eyre::Report) {
+        let dead_letter = match err
+            .chain()
+            .find_map(|e| e.downcast_ref::<InxError>())
+            .and_then(DeadLetterDocument::from_error)
+        {
+            Some(dead_letter) => dead_letter,
+            None => return,
+        };
+        let res = async {
+            self.db.create_indexes::<DeadLetterCollection>().await?;
+            self.db
+                .collection::<DeadLetterCollection>()
+                .insert_dead_letter(&dead_letter)
+                .await
+        }
+        .await;
+        match res {
+            Ok(()) => warn!(
+                "Recorded the {} of {} that could not be decoded.",
+                dead_letter.stage, dead_letter.subject
+            ),
+            Err(e) => warn!(
+                "Failed to record the {} of {} that could not be decoded: {e}.",
+                dead_letter.stage, dead_letter.subject
+            ),
+        }
     }
 
-    pub async fn run(&mut self) -> Result<()> {
-        let (start_index, inx) = self.init().await?;
+    async fn sync(&mut self) -> Result<()> {
+        let (start_index, target_index, inx) = self.init().await?;
 
+        // The stream consumes the connection, so a separate handle is kept to poll the node status.
+        let mut node = inx.clone();
         let tangle = Tangle::from(inx);
 
-        let mut stream = tangle.milestone_stream(start_index..).await?;
+        // Dropping the set when the sync ends stops following the status of the stored blocks.
+        let mut block_status = JoinSet::new();
+        if self.config.block_status_updates && self.config.stream_filter.blocks {
+            let (db, inx) = (self.db.clone(), node.clone());
+            block_status.spawn(async move {
+                // The sync only depends on the blocks that milestones referenced, so a failure must not stop it.
+                if let Err(e) = follow_block_status(&db, inx).await {
+                    warn!("Stopped updating the status of stored blocks: {e}");
+                }
+            });
+        }
+
+        let mut stream = tangle
+            .buffered_milestone_stream(start_index.., self.config.fetch_concurrency)
+            .await?;
 
         #[cfg(feature = "analytics")]
         let mut analytics_info = influx::analytics::AnalyticsInfo::init(&self.db, self.influx_db.as_ref()).await?;
 
+        #[cfg(feature = "metrics")]
+        let queue_depth = stream.queue_depth();
+        #[cfg(feature = "metrics")]
+        let mut last_queue_sample = std::time::Instant::now();
+
         debug!("Started listening to ledger updates via INX.");
 
+        let mut progress = SyncProgress::new(start_index, target_index, SYNC_PROGRESS_INTERVAL);
+        let mut last_node_status = std::time::Instant::now();
+        if progress.is_catching_up(start_index) {
+            info!("Catching up from milestone {start_index} to {target_index}.");
+        }
+
         while let Some(milestone) = stream.try_next().await? {
-            self.handle_ledger_update(
-                milestone,
-                #[cfg(feature = "analytics")]
-                analytics_info.as_mut(),
-            )
-            .await?;
+            let index = milestone.at.milestone_index;
+            if self.is_milestone_applied(index).await? {
+                debug!("Milestone {index} was already applied, skipping it.");
+            } else {
+                self.apply_milestone(
+                    &milestone,
+                    #[cfg(feature = "analytics")]
+                    analytics_info.as_mut(),
+                )
+                .await?;
+            }
+
+            if let Some(report) = progress.record(index, std::time::Instant::now()) {
+                info!("{report}");
+            }
+            if index == progress.target_index() {
+                info!("Caught up with the node at milestone {index}.");
+            }
+
+            if last_node_status.elapsed() >= NODE_STATUS_INTERVAL {
+                last_node_status = std::time::Instant::now();
+                // Only the reported sync status depends on this, so a failure must not stop the sync.
+                if let Err(e) = self.update_latest_known_index(&mut node).await {
+                    warn!("Failed to update the node's latest confirmed milestone: {e}");
+                }
+            }
+
+            // Sampled instead of written per milestone, so that the metric does not slow down the sync.
+            #[cfg(feature = "metrics")]
+            if last_queue_sample.elapsed() >= self.config.queue_metrics_interval {
+                last_queue_sample = std::time::Instant::now();
+                self.update_queue_metrics(queue_depth.get()).await?;
+            }
         }
 
         tracing::debug!("INX stream closed unexpectedly.");
@@ -95,11 +298,85 @@ impl InxWorker {
         Ok(())
     }
 
+    /// Reverts the milestones in `range` and applies them again from the node, one milestone at a time. Returns once
+    /// the last milestone of the range was applied, instead of following the node.
+    pub async fn replay(&mut self, range: RangeInclusive<MilestoneIndex>) -> Result<()> {
+        info!("Connecting to INX at bind address `{}`.", self.config.urls.join("`, `"));
+        let mut inx = self.connect().await?;
+        info!("Connected to INX at `{}`.", self.config.urls[self.endpoint]);
+
+        let node_status = inx.read_node_status().await?;
+        let newest = self
+            .db
+            .collection::<MilestoneCollection>()
+            .get_newest_milestone()
+            .await?;
+        check_replay_range(
+            &range,
+            node_status.tangle_pruning_index,
+            node_status.confirmed_milestone.milestone_info.milestone_index,
+            newest.map(|MilestoneIndexTimestamp { milestone_index, .. }| milestone_index),
+        )?;
+
+        let protocol_parameters: chronicle::model::ProtocolParameters = inx
+            .read_protocol_parameters(range.start().0.into())
+            .await?
+            .params
+            .decode_unverified(format!("milestone {}", range.start()), DecodeStage::ProtocolParameters)?
+            .into();
+        if let Some(latest) = self
+            .db
+            .collection::<ProtocolUpdateCollection>()
+            .get_latest_protocol_parameters()
+            .await?
+        {
+            if latest.parameters.network_name != protocol_parameters.network_name {
+                bail!(InxWorkerError::NetworkChanged {
+                    old: latest.parameters.network_name,
+                    new: protocol_parameters.network_name,
+                });
+            }
+        }
+
+        info!("Replaying milestones {} to {}.", range.start(), range.end());
+        let tangle = Tangle::from(inx);
+        let mut stream = tangle
+            .buffered_milestone_stream(range.clone(), self.config.fetch_concurrency)
+            .await?;
+        let mut cursor = ReplayCursor::new(&range);
+        let mut progress = SyncProgress::new(*range.start(), *range.end(), SYNC_PROGRESS_INTERVAL);
+        self.last_milestone = None;
+        while let Some(milestone) = cursor.next(&mut stream).await? {
+            let index = milestone.at.milestone_index;
+            // Every milestone is only reverted right before it is applied again, so that an aborted replay leaves the
+            // rest of the range intact and can be resumed from the milestone it stopped at.
+            async {
+                revert_milestones(&self.db, index..=index).await?;
+                self.apply_milestone(
+                    &milestone,
+                    #[cfg(feature = "analytics")]
+                    None,
+                )
+                .await
+            }
+            .await
+            .wrap_err_with(|| format!("replay stopped at milestone {index}, replay it again from there"))?;
+            if let Some(report) = progress.record(milestone.at.milestone_index, std::time::Instant::now()) {
+                info!("{report}");
+            }
+        }
+
+        info!("Replayed milestones {} to {}.", range.start(), range.end());
+        Ok(())
+    }
+
+    /// Connects to INX and prepares the database. Returns the index to start syncing from, the node's confirmed
+    /// milestone index (the target of the initial catch-up) and the INX connection.
     #[instrument(skip_all, err, level = "trace")]
-    async fn init(&mut self) -> Result<(MilestoneIndex, Inx)> {
-        info!("Connecting to INX at bind address `{}`.", &self.config.url);
+    async fn init(&mut self) -> Result<(MilestoneIndex, MilestoneIndex, Inx)> {
+        info!("Connecting to INX at bind address `{}`.", self.config.urls.join("`, `"));
         let mut inx = self.connect().await?;
-        info!("Connected to INX.");
+        info!("Connected to INX at `{}`.", self.config.urls[self.endpoint]);
 
         // Request the node status so we can get the pruning index and latest confirmed milestone
         let node_status = loop {
@@ -117,40 +394,39 @@ impl InxWorker {
             node_status.tangle_pruning_index, node_status.confirmed_milestone.milestone_info.milestone_index,
         );
 
-        // Check if there is an unfixable gap in our node data.
-        let start_index = if let Some(MilestoneIndexTimestamp {
-            milestone_index: latest_milestone,
-            ..
-        }) = self
+        let target_index = node_status.confirmed_milestone.milestone_info.milestone_index;
+
+        self.last_milestone = self
             .db
             .collection::<MilestoneCollection>()
             .get_newest_milestone()
-            .await?
-        {
-            if node_status.tangle_pruning_index.0 > latest_milestone.0 {
-                bail!(InxWorkerError::SyncMilestoneGap {
-                    start: latest_milestone + 1,
-                    end: node_status.tangle_pruning_index,
-                });
-            } else if node_status.confirmed_milestone.milestone_info.milestone_index.0 < latest_milestone.0 {
-                bail!(InxWorkerError::SyncMilestoneIndexMismatch {
-                    node: node_status.confirmed_milestone.milestone_info.milestone_index,
-                    db: latest_milestone,
-                });
-            } else {
-                latest_milestone + 1
-            }
-        } else {
-            self.config
-                .sync_start_milestone
-                .max(node_status.tangle_pruning_index + 1)
-        };
+            .await?;
+
+        let (start_index, skipped) = resume_index(
+            self.last_milestone
+                .map(|MilestoneIndexTimestamp { milestone_index, .. }| milestone_index),
+            node_status.tangle_pruning_index,
+            target_index,
+            self.config.sync_start_milestone,
+            self.config.skip_pruned_milestones,
+        )?;
+        if let Some(skipped) = skipped {
+            warn!(
+                "The node pruned milestones `{}` to `{}`, which are missing from the database. Continuing from \
+                 milestone `{start_index}`, the ledger state will be incomplete.",
+                skipped.start, skipped.end,
+            );
+            self.db
+                .collection::<ApplicationStateCollection>()
+                .add_skipped_milestones(skipped)
+                .await?;
+        }
 
         let protocol_parameters = inx
             .read_protocol_parameters(start_index.0.into())
             .await?
             .params
-            .inner_unverified()?;
+            .decode_unverified(format!("milestone {start_index}"), DecodeStage::ProtocolParameters)?;
 
         let node_configuration = inx.read_node_configuration().await?;
 
@@ -175,6 +451,19 @@ impl InxWorker {
                 });
             }
             debug!("Found matching network in the database.");
+            let output_kinds = self.config.stream_filter.restricted_output_kinds();
+            let stored_output_kinds = self
+                .db
+                .collection::<ApplicationStateCollection>()
+                .get_output_kinds()
+                .await?;
+            if stored_output_kinds != output_kinds {
+                let describe = |kinds: Option<Vec<String>>| kinds.map_or("all".to_string(), |kinds| kinds.join(", "));
+                bail!(InxWorkerError::OutputKindsChanged {
+                    old: describe(stored_output_kinds),
+                    new: describe(output_kinds),
+                });
+            }
             if latest.parameters != protocol_parameters {
                 debug!("Updating protocol parameters.");
                 self.db
@@ -191,6 +480,10 @@ impl InxWorker {
                 .collection::<ApplicationStateCollection>()
                 .set_last_migration(latest_version)
                 .await?;
+            self.db
+                .collection::<ApplicationStateCollection>()
+                .set_output_kinds(self.config.stream_filter.restricted_output_kinds())
+                .await?;
             info!("Reading unspent outputs.");
             let unspent_output_stream = inx
                 .read_unspent_outputs()
@@ -199,9 +492,10 @@ impl InxWorker {
 
             let mut starting_index = None;
 
+            let batch_size = self.config.insert_batch_size;
+            let stream_filter = &self.config.stream_filter;
             let mut count = 0;
             let mut tasks = unspent_output_stream
-                .inspect_ok(|_| count += 1)
                 .map(|msg| {
                     let msg = msg?;
                     let ledger_index = &msg.ledger_index;
@@ -218,7 +512,9 @@ impl InxWorker {
                     Ok(msg)
                 })
                 .map(|res| Ok(res?.output))
-                .try_chunks(INSERT_BATCH_SIZE)
+                .try_filter(|output| futures::future::ready(stream_filter.allows_output(&output.output)))
+                .inspect_ok(|_| count += 1)
+                .try_chunks(batch_size)
                 // We only care if we had an error, so discard the other data
                 .map_err(|e| e.1)
                 // Convert batches to tasks
@@ -271,85 +567,245 @@ impl InxWorker {
                 .await?;
         }
 
-        Ok((start_index, inx))
+        if !self.config.stream_filter.stores_all_blocks() {
+            // Lets the API know that proofs of inclusion cannot be created from the stored milestone cones.
+            self.db
+                .collection::<ApplicationStateCollection>()
+                .set_partial_blocks()
+                .await?;
+        }
+
+        self.db
+            .collection::<ApplicationStateCollection>()
+            .set_sync_target(target_index)
+            .await?;
+        self.raise_latest_known_index(target_index).await?;
+
+        Ok((start_index, target_index, inx))
+    }
+
+    /// Records the node's latest confirmed milestone, which the sync status compares against the database.
+    async fn update_latest_known_index(&mut self, inx: &mut Inx) -> Result<()> {
+        let node_status = inx.read_node_status().await?;
+        self.raise_latest_known_index(node_status.confirmed_milestone.milestone_info.milestone_index)
+            .await
+    }
+
+    /// Records that the node confirmed the milestone with the given index. The known index never decreases.
+    async fn raise_latest_known_index(&mut self, index: MilestoneIndex) -> Result<()> {
+        self.db
+            .collection::<ApplicationStateCollection>()
+            .raise_latest_known_index(index)
+            .await?;
+        self.latest_known_index = self.latest_known_index.max(index);
+        Ok(())
+    }
+
+    /// Whether the milestone was already applied, for example because the node replayed it after a reconnect. Only a
+    /// milestone that does not follow the last applied one can have been applied before, so no other is looked up.
+    async fn is_milestone_applied(&self, index: MilestoneIndex) -> Result<bool> {
+        if !matches!(self.last_milestone, Some(last) if index <= last.milestone_index) {
+            return Ok(false);
+        }
+        with_db_timeout(self.config.db_operation_timeout, async {
+            Ok(self
+                .db
+                .collection::<MilestoneCollection>()
+                .is_milestone_applied(index)
+                .await?)
+        })
+        .await
+    }
+
+    /// Applies a milestone, retrying while the database is unreachable. All writes of a milestone are idempotent and
+    /// the milestone itself is recorded last, so a partially applied milestone can safely be applied again.
+    async fn apply_milestone<'a>(
+        &mut self,
+        milestone: &Milestone<'a, Inx>,
+        #[cfg(feature = "analytics")] mut analytics_info: Option<&mut influx::analytics::AnalyticsInfo>,
+    ) -> Result<()> {
+        let mut backoff = Backoff::new(self.config.db_reconnect_timeout);
+        loop {
+            let res = match self.check_milestone_timestamp(milestone.at).await {
+                Ok(()) => {
+                    self.handle_ledger_update(
+                        milestone,
+                        #[cfg(feature = "analytics")]
+                        analytics_info.as_deref_mut(),
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            };
+            let err = match res {
+                Ok(()) => return Ok(()),
+                Err(err) if is_transient_db_error(&err) => err,
+                Err(err) => return Err(err),
+            };
+            let delay = match backoff.next_delay() {
+                Some(delay) => delay,
+                None => return Err(err),
+            };
+            warn!(
+                "Applying milestone {} failed: {err}. Retrying in {delay:?}.",
+                milestone.at.milestone_index
+            );
+            // The analytics may already have been updated with this milestone, so they are recomputed on retry.
+            #[cfg(feature = "analytics")]
+            if let Some(analytics_info) = analytics_info.as_deref_mut() {
+                analytics_info.reset();
+            }
+            tokio::time::sleep(delay).await;
+        }
     }
 
     #[instrument(skip_all, fields(milestone_index, created, consumed), err, level = "debug")]
     async fn handle_ledger_update<'a>(
         &mut self,
-        milestone: Milestone<'a, Inx>,
+        milestone: &Milestone<'a, Inx>,
         #[cfg(feature = "analytics")] analytics_info: Option<&mut influx::analytics::AnalyticsInfo>,
     ) -> Result<()> {
         #[cfg(feature = "metrics")]
         let start_time = std::time::Instant::now();
 
-        let mut tasks = JoinSet::new();
+        let timeout = self.config.db_operation_timeout;
 
-        for batch in milestone.ledger_updates().created_outputs().chunks(INSERT_BATCH_SIZE) {
-            let db = self.db.clone();
-            let batch = batch.to_vec();
-            tasks.spawn(async move { insert_unspent_outputs(&db, &batch).await });
-        }
+        let stream_filter = &self.config.stream_filter;
+        let created = milestone
+            .ledger_updates()
+            .created_outputs()
+            .iter()
+            .filter(|output| stream_filter.allows_output(&output.output))
+            .collect::<Vec<_>>();
+        let consumed = milestone
+            .ledger_updates()
+            .consumed_outputs()
+            .iter()
+            .filter(|spent| stream_filter.allows_output(&spent.output.output))
+            .collect::<Vec<_>>();
 
-        for batch in milestone.ledger_updates().consumed_outputs().chunks(INSERT_BATCH_SIZE) {
-            let db = self.db.clone();
-            let batch = batch.to_vec();
-            tasks.spawn(async move { update_spent_outputs(&db, &batch).await });
-        }
-
-        while let Some(res) = tasks.join_next().await {
-            res??;
-        }
+        write_ledger_updates(&self.db, &created, &consumed, self.config.insert_batch_size, timeout).await?;
 
         // Record the result as part of the current span.
         tracing::Span::current().record("milestone_index", milestone.at.milestone_index.0);
         tracing::Span::current().record("created", milestone.ledger_updates().created_outputs().len());
         tracing::Span::current().record("consumed", milestone.ledger_updates().consumed_outputs().len());
 
-        self.handle_cone_stream(&milestone).await?;
-        self.db
-            .collection::<ProtocolUpdateCollection>()
-            .upsert_protocol_parameters(milestone.at.milestone_index, milestone.protocol_params.clone())
-            .await?;
-        self.db
-            .collection::<ConfigurationUpdateCollection>()
-            .upsert_node_configuration(milestone.at.milestone_index, milestone.node_config.clone())
-            .await?;
+        let block_count = self.handle_cone_stream(milestone).await?;
+        with_db_timeout(timeout, async {
+            self.db
+                .collection::<ProtocolUpdateCollection>()
+                .upsert_protocol_parameters(milestone.at.milestone_index, milestone.protocol_params.clone())
+                .await?;
+            self.db
+                .collection::<ConfigurationUpdateCollection>()
+                .upsert_node_configuration(milestone.at.milestone_index, milestone.node_config.clone())
+                .await?;
+            Ok(())
+        })
+        .await?;
 
         #[cfg(feature = "influx")]
         self.update_influx(
-            &milestone,
+            milestone,
             #[cfg(feature = "analytics")]
             analytics_info,
             #[cfg(feature = "metrics")]
             start_time,
+            #[cfg(feature = "metrics")]
+            block_count,
         )
         .await?;
 
         // This acts as a checkpoint for the syncing and has to be done last, after everything else completed.
-        self.db
-            .collection::<MilestoneCollection>()
-            .insert_milestone(
-                milestone.milestone_id,
-                milestone.at.milestone_index,
-                milestone.at.milestone_timestamp,
-                milestone.payload.clone(),
-            )
-            .await?;
+        with_db_timeout(timeout, async {
+            self.db
+                .collection::<MilestoneCollection>()
+                .insert_milestone(
+                    milestone.milestone_id,
+                    milestone.at.milestone_index,
+                    milestone.at.milestone_timestamp,
+                    milestone.payload.clone(),
+                )
+                .await?;
+            Ok(())
+        })
+        .await?;
 
+        if let Some(milestone_events) = &self.milestone_events {
+            // Sending only fails if there are no subscribers at the moment, which is fine.
+            milestone_events
+                .send(MilestoneEvent {
+                    milestone_index: milestone.at.milestone_index.0,
+                    milestone_timestamp: milestone.at.milestone_timestamp.0,
+                    block_count,
+                })
+                .ok();
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the timestamp of the milestone does not precede the one of the previously applied milestone. A
+    /// violation is logged and counted, but the milestone is still applied.
+    async fn check_milestone_timestamp(&mut self, at: MilestoneIndexTimestamp) -> Result<()> {
+        let previous = match self.last_milestone.replace(at) {
+            Some(previous) => previous,
+            None => return Ok(()),
+        };
+        match timestamp_order(previous, at) {
+            Ordering::Less => {
+                warn!(
+                    "Milestone {} has timestamp {}, which is earlier than timestamp {} of milestone {}.",
+                    at.milestone_index,
+                    at.milestone_timestamp.0,
+                    previous.milestone_timestamp.0,
+                    previous.milestone_index
+                );
+                with_db_timeout(self.config.db_operation_timeout, async {
+                    self.db
+                        .collection::<ApplicationStateCollection>()
+                        .increment_milestone_timestamp_violations()
+                        .await?;
+                    Ok(())
+                })
+                .await?;
+            }
+            Ordering::Equal => debug!(
+                "Milestone {} has the same timestamp as milestone {}.",
+                at.milestone_index, previous.milestone_index
+            ),
+            Ordering::Greater => (),
+        }
         Ok(())
     }
 
+    /// Stores the blocks referenced by the milestone, and returns how many were received.
     #[instrument(skip_all, err, level = "trace")]
-    async fn handle_cone_stream<'a>(&mut self, milestone: &Milestone<'a, Inx>) -> Result<()> {
+    async fn handle_cone_stream<'a, I: InputSource>(&mut self, milestone: &Milestone<'a, I>) -> Result<usize> {
+        if !self.config.stream_filter.blocks {
+            return Ok(0);
+        }
         let cone_stream = milestone.cone_stream().await?;
 
+        let timeout = self.config.db_operation_timeout;
+        let batch_size = self.config.insert_batch_size;
+        let stream_filter = &self.config.stream_filter;
+        let block_log_sampler = &mut self.block_log_sampler;
+        let mut block_count = 0;
         let mut tasks = cone_stream
-            .try_chunks(INSERT_BATCH_SIZE)
+            .inspect_ok(|data| {
+                block_count += 1;
+                if block_log_sampler.sample() {
+                    trace!("Received block `{}`.", data.block_id.to_hex());
+                }
+            })
+            .try_chunks(batch_size)
             .map_err(|e| e.1)
             .try_fold(JoinSet::new(), |mut tasks, batch| async {
                 let db = self.db.clone();
-                tasks.spawn(async move {
+                let stream_filter = stream_filter.clone();
+                tasks.spawn(with_db_timeout(timeout, async move {
                     let payloads = batch
                         .iter()
                         .filter_map(|data| {
@@ -370,11 +826,31 @@ impl InxWorker {
                             .insert_treasury_payloads(payloads)
                             .await?;
                     }
-                    db.collection::<BlockCollection>()
-                        .insert_blocks_with_metadata(batch)
-                        .await?;
+                    // A milestone block is referenced by a later milestone, so the milestone it carries is already
+                    // stored.
+                    let milestone_blocks = batch
+                        .iter()
+                        .filter_map(|data| match &data.block.payload {
+                            Some(Payload::Milestone(payload)) => Some((payload.essence.index, data.block_id)),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    if !milestone_blocks.is_empty() {
+                        db.collection::<MilestoneCollection>()
+                            .insert_milestone_block_ids(milestone_blocks)
+                            .await?;
+                    }
+                    let blocks = batch
+                        .into_iter()
+                        .filter(|data| stream_filter.allows_block(&data.block))
+                        .collect::<Vec<_>>();
+                    if !blocks.is_empty() {
+                        db.collection::<BlockCollection>()
+                            .insert_blocks_with_metadata(blocks)
+                            .await?;
+                    }
                     Result::<_>::Ok(())
-                });
+                }));
                 Ok(tasks)
             })
             .await?;
@@ -383,10 +859,117 @@ impl InxWorker {
             res??;
         }
 
-        Ok(())
+        Ok(block_count)
     }
 }
 
+/// Determines the milestone index to continue syncing from. Every applied milestone is checkpointed in the database, so
+/// after a restart the sync resumes right after the `newest` stored milestone. If the node already pruned the
+/// milestones after it, the sync fails, unless `skip_pruned` is set. Then it continues from the earliest milestone that
+/// is still available, and the skipped milestones are returned as well.
+fn resume_index(
+    newest: Option<MilestoneIndex>,
+    pruning_index: MilestoneIndex,
+    confirmed_index: MilestoneIndex,
+    sync_start_milestone: MilestoneIndex,
+    skip_pruned: bool,
+) -> Result<(MilestoneIndex, Option<SkippedMilestones>)> {
+    Ok(if let Some(latest_milestone) = newest {
+        if confirmed_index.0 < latest_milestone.0 {
+            bail!(InxWorkerError::SyncMilestoneIndexMismatch {
+                node: confirmed_index,
+                db: latest_milestone,
+            });
+        } else if pruning_index.0 > latest_milestone.0 {
+            let skipped = SkippedMilestones {
+                start: latest_milestone + 1,
+                end: pruning_index,
+            };
+            if !skip_pruned {
+                bail!(InxWorkerError::SyncMilestoneGap {
+                    start: skipped.start,
+                    end: skipped.end,
+                });
+            }
+            (pruning_index + 1, Some(skipped))
+        } else {
+            (latest_milestone + 1, None)
+        }
+    } else {
+        (sync_start_milestone.max(pruning_index + 1), None)
+    })
+}
+
+/// Compares the timestamp of the `current` milestone with the one of the `previous` milestone. Timestamps are expected
+/// to increase with every milestone.
+fn timestamp_order(previous: MilestoneIndexTimestamp, current: MilestoneIndexTimestamp) -> Ordering {
+    current.milestone_timestamp.cmp(&previous.milestone_timestamp)
+}
+
+/// Whether the error is caused by the database being unreachable or slow, rather than by the data.
+fn is_transient_db_error(err: &eyre::Report) -> bool {
+    err.chain().any(|e| {
+        matches!(
+            e.downcast_ref::<InxWorkerError>(),
+            Some(InxWorkerError::DbOperationTimeout(_))
+        ) || matches!(e.downcast_ref::<mongodb::error::Error>(), Some(e) if is_transient_mongodb_error(e))
+    })
+}
+
+/// Runs a database operation, failing with [`InxWorkerError::DbOperationTimeout`] if it does not complete in time.
+async fn with_db_timeout<T>(timeout: Duration, op: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(timeout, op)
+        .await
+        .map_err(|_| InxWorkerError::DbOperationTimeout(timeout))?
+}
+
+/// Writes the created and consumed outputs of a milestone in concurrent batches of at most `batch_size` documents, so
+/// that no single command exceeds MongoDb's size limit.
+async fn write_ledger_updates(
+    db: &MongoDb,
+    created: &[&LedgerOutput],
+    consumed: &[&LedgerSpent],
+    batch_size: usize,
+    timeout: Duration,
+) -> Result<()> {
+    let mut tasks = JoinSet::new();
+
+    for batch in created.chunks(batch_size) {
+        let db = db.clone();
+        let batch = batch.iter().copied().cloned().collect::<Vec<_>>();
+        tasks.spawn(async move { with_db_timeout(timeout, insert_unspent_outputs(&db, &batch)).await });
+    }
+
+    for batch in consumed.chunks(batch_size) {
+        let db = db.clone();
+        let batch = batch.iter().copied().cloned().collect::<Vec<_>>();
+        tasks.spawn(async move { with_db_timeout(timeout, update_spent_outputs(&db, &batch)).await });
+    }
+
+    while let Some(res) = tasks.join_next().await {
+        res??;
+    }
+    Ok(())
+}
+
+/// Updates the metadata of the stored blocks that the node reports as solid or referenced, until the connection ends.
+async fn follow_block_status(db: &MongoDb, mut inx: Inx) -> Result<()> {
+    let solid = inx.listen_to_solid_blocks().await?.boxed();
+    let referenced = inx.listen_to_referenced_blocks().await?.boxed();
+    let mut updates = futures::stream::select(solid, referenced);
+    let block_collection = db.collection::<BlockCollection>();
+    while let Some(metadata) = updates.try_next().await? {
+        let block_id = metadata.block_id;
+        if block_collection
+            .update_block_metadata(&block_id, metadata.into())
+            .await?
+        {
+            trace!("Updated the metadata of block {}.", block_id.to_hex());
+        }
+    }
+    Ok(())
+}
+
 #[instrument(skip_all, err, fields(num = outputs.len()), level = "trace")]
 async fn insert_unspent_outputs(db: &MongoDb, outputs: &[LedgerOutput]) -> Result<()> {
     let output_collection = db.collection::<OutputCollection>();
@@ -420,3 +1003,343 @@ async fn update_spent_outputs(db: &MongoDb, outputs: &[LedgerSpent]) -> Result<(
     }
     .and(Ok(()))
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cmp::Ordering,
+        sync::{
+            atomic::{AtomicUsize, Ordering as AtomicOrdering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use chronicle::{
+        db::{mongodb::collections::SkippedMilestones, MongoDb, MongoDbConfig},
+        model::tangle::{MilestoneIndex, MilestoneIndexTimestamp},
+    };
+    use tokio::{net::TcpListener, sync::oneshot};
+
+    use super::{
+        is_transient_db_error, resume_index, timestamp_order, with_db_timeout, InxConfig, InxWorker, InxWorkerError,
+    };
+
+    #[tokio::test]
+    async fn shutdown_interrupts_reconnect_backoff() {
+        // The listener drops every connection, so that each attempt to connect to it fails.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let attempts = Arc::new(AtomicUsize::new(0));
+        tokio::spawn({
+            let attempts = attempts.clone();
+            async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    attempts.fetch_add(1, AtomicOrdering::SeqCst);
+                    drop(socket);
+                }
+            }
+        });
+
+        // Creating the client does not contact the server, and the worker fails before it queries the database.
+        let db = MongoDb::connect(&MongoDbConfig::default()).await.unwrap();
+        let mut worker = InxWorker::new(
+            db,
+            InxConfig {
+                urls: vec![format!("http://{addr}")],
+                connection_retry_initial_interval: Duration::from_secs(60),
+                connection_retry_max_interval: Duration::from_secs(60),
+                ..Default::default()
+            },
+        );
+        let (shutdown, on_shutdown) = oneshot::channel::<()>();
+        let worker = tokio::spawn(async move {
+            worker
+                .run(async {
+                    on_shutdown.await.ok();
+                })
+                .await
+        });
+
+        // Give the first attempt time to fail, so that the worker waits to reconnect.
+        while attempts.load(AtomicOrdering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let attempts_before_shutdown = attempts.load(AtomicOrdering::SeqCst);
+
+        shutdown.send(()).unwrap();
+        let res = tokio::time::timeout(Duration::from_secs(5), worker)
+            .await
+            .expect("the worker kept waiting to reconnect");
+        assert!(res.unwrap().is_ok());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(attempts.load(AtomicOrdering::SeqCst), attempts_before_shutdown);
+    }
+
+    #[tokio::test]
+    async fn db_operation_times_out() {
+        let res = with_db_timeout(Duration::from_millis(10), async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<InxWorkerError>(),
+            Some(InxWorkerError::DbOperationTimeout(_))
+        ));
+
+        let res = with_db_timeout(Duration::from_secs(5), async { Ok(42) }).await;
+        assert_eq!(res.unwrap(), 42);
+    }
+
+    #[test]
+    fn sync_resumes_after_last_checkpoint() {
+        // A fresh database starts at the configured milestone, unless the node already pruned it.
+        assert_eq!(
+            resume_index(None, 10.into(), 500.into(), 1.into(), false).unwrap().0,
+            11
+        );
+        assert_eq!(
+            resume_index(None, 10.into(), 500.into(), 100.into(), false).unwrap().0,
+            100
+        );
+
+        // After an interrupted catch-up the sync continues after the newest applied milestone.
+        assert_eq!(
+            resume_index(Some(250.into()), 10.into(), 500.into(), 1.into(), false).unwrap(),
+            (251.into(), None)
+        );
+        assert_eq!(
+            resume_index(Some(10.into()), 10.into(), 500.into(), 1.into(), false).unwrap(),
+            (11.into(), None)
+        );
+
+        assert!(matches!(
+            resume_index(Some(5.into()), 10.into(), 500.into(), 1.into(), false)
+                .unwrap_err()
+                .downcast_ref::<InxWorkerError>(),
+            Some(InxWorkerError::SyncMilestoneGap { .. })
+        ));
+        // Only when skipping is enabled, the sync continues from the node's earliest milestone.
+        assert_eq!(
+            resume_index(Some(5.into()), 10.into(), 500.into(), 1.into(), true).unwrap(),
+            (
+                11.into(),
+                Some(SkippedMilestones {
+                    start: 6.into(),
+                    end: 10.into()
+                })
+            )
+        );
+
+        assert!(matches!(
+            resume_index(Some(600.into()), 10.into(), 500.into(), 1.into(), false)
+                .unwrap_err()
+                .downcast_ref::<InxWorkerError>(),
+            Some(InxWorkerError::SyncMilestoneIndexMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn backwards_milestone_timestamp_is_detected() {
+        let at = |milestone_index: u32, milestone_timestamp: u32| MilestoneIndexTimestamp {
+            milestone_index: milestone_index.into(),
+            milestone_timestamp: milestone_timestamp.into(),
+        };
+        assert_eq!(timestamp_order(at(1, 100), at(2, 110)), Ordering::Greater);
+        assert_eq!(timestamp_order(at(2, 110), at(3, 110)), Ordering::Equal);
+        assert_eq!(timestamp_order(at(3, 110), at(4, 90)), Ordering::Less);
+    }
+
+    #[tokio::test]
+    async fn unreachable_database_is_retried() {
+        let err = with_db_timeout(Duration::from_millis(10), async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await
+        .unwrap_err();
+        assert!(is_transient_db_error(&err));
+
+        let err = eyre::Report::new(mongodb::error::Error::from(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset,
+        )))
+        .wrap_err("failed to insert outputs");
+        assert!(is_transient_db_error(&err));
+
+        let err = eyre::Report::new(InxWorkerError::NetworkChanged {
+            old: "shimmer".to_string(),
+            new: "testnet".to_string(),
+        });
+        assert!(!is_transient_db_error(&err));
+    }
+
+    /// Connects to an empty database of a MongoDB instance, like the database tests.
+    #[cfg(feature = "rand")]
+    async fn connect_test_db(database_name: &str) -> MongoDb {
+        let mut config = MongoDbConfig {
+            database_name: database_name.to_string(),
+            ..Default::default()
+        };
+        if let Ok(conn_str) = std::env::var("MONGODB_CONN_STR") {
+            config.conn_str = conn_str;
+        }
+        let db = MongoDb::connect(&config).await.unwrap();
+        db.clear().await.unwrap();
+        db
+    }
+
+    #[cfg(feature = "rand")]
+    #[tokio::test]
+    async fn ledger_updates_are_written_in_batches() {
+        use chronicle::{
+            db::mongodb::collections::OutputCollection,
+            model::{
+                ledger::{LedgerOutput, LedgerSpent, RentStructureBytes},
+                metadata::SpentMetadata,
+                payload::TransactionId,
+                utxo::{Output, OutputId},
+                BlockId,
+            },
+        };
+
+        use super::write_ledger_updates;
+
+        let db = connect_test_db("test-ledger-updates-in-batches").await;
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+        let outputs = std::iter::repeat_with(|| LedgerOutput {
+            output_id: OutputId::rand(),
+            rent_structure: RentStructureBytes {
+                num_key_bytes: 0,
+                num_data_bytes: 100,
+            },
+            output: Output::rand(&protocol_params),
+            block_id: BlockId::rand(),
+            booked: MilestoneIndexTimestamp {
+                milestone_index: 1.into(),
+                milestone_timestamp: 12345.into(),
+            },
+        })
+        .take(10)
+        .collect::<Vec<_>>();
+        // The milestone spends some of its own outputs, so both lists are split into several batches.
+        let spent = outputs[..7]
+            .iter()
+            .map(|output| LedgerSpent {
+                output: output.clone(),
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: output.booked,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        write_ledger_updates(
+            &db,
+            &outputs.iter().collect::<Vec<_>>(),
+            &spent.iter().collect::<Vec<_>>(),
+            3,
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        let output_collection = db.collection::<OutputCollection>();
+        for (i, output) in outputs.iter().enumerate() {
+            let metadata = output_collection
+                .get_output_metadata(&output.output_id, 1.into())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(metadata.spent_metadata, spent.get(i).map(|spent| spent.spent_metadata));
+        }
+
+        db.drop().await.unwrap();
+    }
+
+    #[cfg(feature = "rand")]
+    #[tokio::test]
+    async fn received_blocks_are_counted() {
+        use chronicle::{
+            db::{mongodb::collections::BlockCollection, MongoDbCollectionExt},
+            model::{
+                metadata::{BlockMetadata, ConflictReason, LedgerInclusionState},
+                Block, BlockId,
+            },
+            tangle::{BlockData, Tangle},
+        };
+        use futures::TryStreamExt;
+
+        use super::mock::MockInx;
+
+        let db = connect_test_db("test-received-blocks-are-counted").await;
+
+        let blocks = std::iter::repeat_with(Block::rand_no_payload)
+            .take(5)
+            .enumerate()
+            .map(|(i, block)| BlockData {
+                block_id: BlockId::rand(),
+                metadata: BlockMetadata {
+                    parents: block.parents.clone(),
+                    is_solid: true,
+                    should_promote: false,
+                    should_reattach: false,
+                    referenced_by_milestone_index: 1.into(),
+                    milestone_index: 0.into(),
+                    inclusion_state: LedgerInclusionState::NoTransaction,
+                    conflict_reason: ConflictReason::None,
+                    white_flag_index: i as u32,
+                },
+                block,
+                raw: iota_types::block::rand::bytes::rand_bytes(100),
+            })
+            .collect::<Vec<_>>();
+        let tangle = Tangle::from(MockInx::new([1]).with_cone(1, blocks));
+        let milestone = tangle
+            .milestone_stream(MilestoneIndex(1)..=MilestoneIndex(1))
+            .await
+            .unwrap()
+            .try_next()
+            .await
+            .unwrap()
+            .unwrap();
+
+        // The blocks are split into several batches, which are all counted.
+        let mut worker = InxWorker::new(
+            db.clone(),
+            InxConfig {
+                insert_batch_size: 2,
+                ..Default::default()
+            },
+        );
+        assert_eq!(worker.handle_cone_stream(&milestone).await.unwrap(), 5);
+        assert_eq!(db.collection::<BlockCollection>().count().await.unwrap(), 5);
+
+        db.drop().await.unwrap();
+    }
+
+    #[cfg(feature = "rand")]
+    #[tokio::test]
+    async fn latest_known_index_never_decreases() {
+        use chronicle::db::mongodb::collections::ApplicationStateCollection;
+
+        let db = connect_test_db("test-latest-known-index-never-decreases").await;
+        let mut worker = InxWorker::new(db.clone(), InxConfig::default());
+
+        for (index, expected) in [(10, 10), (12, 12), (11, 12)] {
+            worker.raise_latest_known_index(MilestoneIndex(index)).await.unwrap();
+            assert_eq!(worker.latest_known_index, MilestoneIndex(expected));
+            assert_eq!(
+                db.collection::<ApplicationStateCollection>()
+                    .get_latest_known_index()
+                    .await
+                    .unwrap(),
+                Some(MilestoneIndex(expected))
+            );
+        }
+
+        db.drop().await.unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{Duration, Instant};
+
+use chronicle::model::tangle::MilestoneIndex;
+
+/// How often the progress of a historical sync is logged.
+pub const SYNC_PROGRESS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the node's latest confirmed milestone is read, so that the sync status can report how far behind the
+/// database is.
+pub const NODE_STATUS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Tracks the progress of catching up with the node, from the first synced milestone to the node's confirmed milestone
+/// at startup.
+#[derive(Debug)]
+pub struct SyncProgress {
+    start_index: MilestoneIndex,
+    target_index: MilestoneIndex,
+    started: Instant,
+    last_report: Instant,
+    report_interval: Duration,
+}
+
+impl SyncProgress {
+    pub fn new(start_index: MilestoneIndex, target_index: MilestoneIndex, report_interval: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            start_index,
+            target_index,
+            started: now,
+            last_report: now,
+            report_interval,
+        }
+    }
+
+    pub fn target_index(&self) -> MilestoneIndex {
+        self.target_index
+    }
+
+    /// Whether the given (just applied) milestone is still behind the target.
+    pub fn is_catching_up(&self, index: MilestoneIndex) -> bool {
+        index < self.target_index
+    }
+
+    /// Records that the milestone with the given index was applied, and returns a report if one is due.
+    pub fn record(&mut self, index: MilestoneIndex, now: Instant) -> Option<SyncProgressReport> {
+        if !self.is_catching_up(index) || now.duration_since(self.last_report) < self.report_interval {
+            return None;
+        }
+        self.last_report = now;
+        Some(self.report(index, now))
+    }
+
+    fn report(&self, index: MilestoneIndex, now: Instant) -> SyncProgressReport {
+        let elapsed = now.duration_since(self.started).as_secs_f64();
+        let done = (index.0 + 1).saturating_sub(self.start_index.0);
+        let remaining = self.target_index.0.saturating_sub(index.0);
+        let rate = if elapsed > 0.0 { done as f64 / elapsed } else { 0.0 };
+        SyncProgressReport {
+            index,
+            target_index: self.target_index,
+            rate,
+            eta: (rate > 0.0).then(|| Duration::from_secs_f64(remaining as f64 / rate)),
+        }
+    }
+}
+
+/// A snapshot of the sync progress.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncProgressReport {
+    pub index: MilestoneIndex,
+    pub target_index: MilestoneIndex,
+    /// Applied milestones per second.
+    pub rate: f64,
+    pub eta: Option<Duration>,
+}
+
+impl std::fmt::Display for SyncProgressReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Syncing milestone {}/{} ({:.2} milestones/s",
+            self.index, self.target_index, self.rate
+        )?;
+        if let Some(eta) = self.eta {
+            write!(
+                f,
+                ", ETA {}",
+                humantime::format_duration(Duration::from_secs(eta.as_secs()))
+            )?;
+        }
+        write!(f, ").")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reports_rate_and_eta_while_catching_up() {
+        let mut progress = SyncProgress::new(2.into(), 101.into(), Duration::from_secs(10));
+        let start = progress.started;
+
+        // Reports are throttled by the interval.
+        assert_eq!(progress.record(5.into(), start + Duration::from_secs(1)), None);
+
+        let report = progress.record(51.into(), start + Duration::from_secs(10)).unwrap();
+        assert_eq!(report.index, 51);
+        assert_eq!(report.target_index, 101);
+        assert_eq!(report.rate, 5.0);
+        assert_eq!(report.eta, Some(Duration::from_secs(10)));
+
+        assert_eq!(progress.record(60.into(), start + Duration::from_secs(15)), None);
+    }
+
+    #[test]
+    fn stops_reporting_once_live() {
+        let mut progress = SyncProgress::new(1.into(), 10.into(), Duration::ZERO);
+        assert!(!progress.is_catching_up(10.into()));
+        assert_eq!(progress.record(10.into(), Instant::now()), None);
+        assert_eq!(progress.record(11.into(), Instant::now()), None);
+    }
+}
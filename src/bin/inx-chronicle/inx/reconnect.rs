@@ -0,0 +1,159 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt::Display, future::Future, time::Duration};
+
+use chronicle::inx::InxError;
+use rand::Rng;
+use tracing::warn;
+
+/// How long a connection has to stay up before the delay between reconnects starts over from the initial interval.
+pub const RECONNECT_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+/// The largest fraction by which a delay is shortened at random, so that several instances do not reconnect in
+/// lockstep.
+const MAX_JITTER: f64 = 0.25;
+
+/// Exponentially growing delays between attempts to reconnect to the node. Unlike the backoff used at startup, it
+/// never gives up, and is kept across connections so that a flapping node is not hammered.
+#[derive(Debug)]
+pub struct ReconnectBackoff {
+    initial_interval: Duration,
+    max_interval: Duration,
+    interval: Duration,
+}
+
+impl ReconnectBackoff {
+    /// Creates a backoff that starts at `initial_interval` and doubles up to `max_interval`. A maximum of zero disables
+    /// reconnecting.
+    pub fn new(initial_interval: Duration, max_interval: Duration) -> Self {
+        let initial_interval = initial_interval.min(max_interval);
+        Self {
+            initial_interval,
+            max_interval,
+            interval: initial_interval,
+        }
+    }
+
+    /// Whether the worker should reconnect at all.
+    pub fn is_enabled(&self) -> bool {
+        !self.max_interval.is_zero()
+    }
+
+    /// Returns how long to wait before the next attempt.
+    pub fn next_delay(&mut self) -> Duration {
+        self.next_delay_with_jitter(rand::thread_rng().gen_range(0.0..=MAX_JITTER))
+    }
+
+    fn next_delay_with_jitter(&mut self, jitter: f64) -> Duration {
+        let delay = self.interval.mul_f64(1.0 - jitter);
+        self.interval = (self.interval * 2).min(self.max_interval);
+        delay
+    }
+
+    /// Starts over from the initial interval, once a connection was established successfully.
+    pub fn reset(&mut self) {
+        self.interval = self.initial_interval;
+    }
+}
+
+/// Whether the worker failed because the connection to the node was lost, as opposed to the node sending invalid data
+/// or the database failing.
+pub fn is_connection_error(err: &eyre::Report) -> bool {
+    match err.chain().find_map(|e| e.downcast_ref::<InxError>()) {
+        Some(InxError::TonicError(_)) => true,
+        Some(InxError::StatusCode(status)) => matches!(
+            status.code(),
+            tonic::Code::Unavailable | tonic::Code::Unknown | tonic::Code::Cancelled
+        ),
+        _ => false,
+    }
+}
+
+/// Tries the endpoints in turn, starting at `start` and wrapping around, and returns the index of the first one that
+/// accepts the connection. If none of them does, the error of the last attempt is returned.
+pub async fn connect_first<T, E, F, Fut>(endpoints: &[String], start: usize, mut connect: F) -> Result<(usize, T), E>
+where
+    E: Display,
+    F: FnMut(&str) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut last_err = None;
+    for offset in 0..endpoints.len() {
+        let index = (start + offset) % endpoints.len();
+        match connect(&endpoints[index]).await {
+            Ok(res) => return Ok((index, res)),
+            Err(err) => {
+                if endpoints.len() > 1 {
+                    warn!("Failed to connect to INX at `{}`: {err}.", endpoints[index]);
+                }
+                last_err = Some(err);
+            }
+        }
+    }
+    // Panic: The configuration always contains at least one endpoint.
+    Err(last_err.expect("no INX endpoint configured"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interval_grows_on_successive_failures() {
+        let mut backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        let delays = (0..4).map(|_| backoff.next_delay_with_jitter(0.0)).collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            [1, 2, 4, 5].map(Duration::from_secs),
+            "the interval doubles up to the maximum"
+        );
+
+        // Jitter only ever shortens the delay.
+        let delay = backoff.next_delay();
+        assert!(delay <= Duration::from_secs(5) && delay >= Duration::from_secs(5).mul_f64(1.0 - MAX_JITTER));
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay_with_jitter(0.0), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_next_endpoint() {
+        let endpoints = ["http://first:9029".to_string(), "http://second:9029".to_string()];
+        let connect = |url: &str| {
+            let res = if url.contains("first") {
+                Err("connection refused")
+            } else {
+                Ok(url.to_string())
+            };
+            async move { res }
+        };
+
+        assert_eq!(
+            connect_first(&endpoints, 0, connect).await,
+            Ok((1, "http://second:9029".to_string()))
+        );
+        // The search wraps around to the start of the list.
+        let reversed = [endpoints[1].clone(), endpoints[0].clone()];
+        assert_eq!(connect_first(&reversed, 1, connect).await.unwrap().0, 0);
+        assert_eq!(
+            connect_first(&endpoints[..1], 0, connect).await,
+            Err("connection refused")
+        );
+    }
+
+    #[test]
+    fn zero_maximum_disables_reconnecting() {
+        assert!(!ReconnectBackoff::new(Duration::from_secs(1), Duration::ZERO).is_enabled());
+    }
+
+    #[test]
+    fn only_connection_errors_are_retried() {
+        let unavailable = eyre::Report::new(InxError::from(tonic::Status::unavailable("node is down")));
+        assert!(is_connection_error(&unavailable.wrap_err("failed to read milestone")));
+
+        let invalid = eyre::Report::new(InxError::MissingField("milestone_info"));
+        assert!(!is_connection_error(&invalid));
+        assert!(!is_connection_error(&eyre::eyre!("database failure")));
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ops::RangeInclusive;
+
+use chronicle::{
+    db::{
+        mongodb::collections::{BlockCollection, LedgerUpdateCollection, OutputCollection, TreasuryCollection},
+        MongoDb,
+    },
+    model::tangle::MilestoneIndex,
+    tangle::{InputSource, Milestone, MilestoneStream},
+};
+use eyre::Result;
+use futures::TryStreamExt;
+use tokio::try_join;
+use tracing::debug;
+
+use super::InxWorkerError;
+
+/// Verifies that every milestone of `range` was already synced and is still available from the node, so that
+/// replaying it neither leaves a gap in the database nor asks the node for milestones it does not have.
+pub fn check_replay_range(
+    range: &RangeInclusive<MilestoneIndex>,
+    pruning_index: MilestoneIndex,
+    confirmed_index: MilestoneIndex,
+    newest: Option<MilestoneIndex>,
+) -> Result<(), InxWorkerError> {
+    let (start, end) = (*range.start(), *range.end());
+    let unavailable = |reason| InxWorkerError::ReplayRangeUnavailable { start, end, reason };
+    if start > end {
+        return Err(unavailable("the range is empty".to_string()));
+    }
+    if start <= pruning_index {
+        return Err(unavailable(format!("the node pruned milestones up to {pruning_index}")));
+    }
+    if end > confirmed_index {
+        return Err(unavailable(format!(
+            "the node only confirmed milestones up to {confirmed_index}"
+        )));
+    }
+    match newest {
+        Some(newest) if end <= newest => Ok(()),
+        Some(newest) => Err(unavailable(format!(
+            "the database only contains milestones up to {newest}"
+        ))),
+        None => Err(unavailable("the database does not contain any milestones".to_string())),
+    }
+}
+
+/// Removes the data of the milestones in `range` from the database. The milestone documents themselves are kept,
+/// because applying a milestone again overwrites them without losing the link to the block that carries them.
+pub async fn revert_milestones(db: &MongoDb, range: RangeInclusive<MilestoneIndex>) -> Result<()> {
+    let (outputs, ledger_updates, blocks, treasury) = try_join!(
+        db.collection::<OutputCollection>().revert_milestones(range.clone()),
+        db.collection::<LedgerUpdateCollection>()
+            .revert_milestones(range.clone()),
+        db.collection::<BlockCollection>().revert_milestones(range.clone()),
+        db.collection::<TreasuryCollection>().revert_milestones(range),
+    )?;
+    debug!(
+        "Reverted {outputs} outputs, {ledger_updates} ledger updates, {blocks} blocks and {treasury} treasury updates."
+    );
+    Ok(())
+}
+
+/// Tracks which milestone of a replayed range is expected next.
+#[derive(Debug)]
+pub struct ReplayCursor {
+    next: MilestoneIndex,
+    end: MilestoneIndex,
+}
+
+impl ReplayCursor {
+    pub fn new(range: &RangeInclusive<MilestoneIndex>) -> Self {
+        Self {
+            next: *range.start(),
+            end: *range.end(),
+        }
+    }
+
+    /// Returns the next milestone of the range from `stream`, or `None` once the whole range was replayed. Fails if
+    /// the stream skips a milestone or ends early.
+    pub async fn next<'a, I: InputSource>(
+        &mut self,
+        stream: &mut MilestoneStream<'a, I>,
+    ) -> Result<Option<Milestone<'a, I>>> {
+        if self.next > self.end {
+            return Ok(None);
+        }
+        match stream.try_next().await? {
+            Some(milestone) if milestone.at.milestone_index == self.next => {
+                self.next = self.next + 1;
+                Ok(Some(milestone))
+            }
+            _ => Err(InxWorkerError::ReplayMissingMilestone(self.next).into()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use std::ops::Bound;
+
+    use chronicle::tangle::Tangle;
+
+    use super::*;
+    use crate::inx::mock::MockInx;
+
+    async fn replay(source: MockInx, range: RangeInclusive<MilestoneIndex>) -> Result<Vec<u32>> {
+        let tangle = Tangle::from(source);
+        let mut stream = tangle.buffered_milestone_stream(range.clone(), 2).await?;
+        let mut cursor = ReplayCursor::new(&range);
+        let mut replayed = Vec::new();
+        while let Some(milestone) = cursor.next(&mut stream).await? {
+            replayed.push(milestone.at.milestone_index.0);
+        }
+        Ok(replayed)
+    }
+
+    #[tokio::test]
+    async fn range_is_replayed_in_order() {
+        let range = MilestoneIndex(3)..=MilestoneIndex(5);
+        let source = MockInx::new(1..=8);
+        let requested = source.requested.clone();
+        assert_eq!(replay(source, range).await.unwrap(), vec![3, 4, 5]);
+        assert_eq!(
+            *requested.lock().unwrap(),
+            Some((Bound::Included(3.into()), Bound::Included(5.into())))
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_milestones_fail_the_replay() {
+        let range = MilestoneIndex(3)..=MilestoneIndex(5);
+        let res = replay(MockInx::new([3, 5]), range.clone()).await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref(),
+            Some(InxWorkerError::ReplayMissingMilestone(index)) if *index == 4
+        ));
+
+        // The node stops before the end of the range.
+        let res = replay(MockInx::new(1..=4), range).await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref(),
+            Some(InxWorkerError::ReplayMissingMilestone(index)) if *index == 5
+        ));
+    }
+
+    #[test]
+    fn replay_range_must_be_synced_and_available() {
+        let check = |start: u32, end: u32, newest: Option<u32>| {
+            check_replay_range(
+                &(start.into()..=end.into()),
+                10.into(),
+                100.into(),
+                newest.map(Into::into),
+            )
+        };
+        assert!(check(11, 20, Some(50)).is_ok());
+        assert!(check(11, 50, Some(50)).is_ok());
+        // Empty range.
+        assert!(check(20, 11, Some(50)).is_err());
+        // Pruned by the node.
+        assert!(check(10, 20, Some(50)).is_err());
+        // Not confirmed by the node yet.
+        assert!(check(90, 101, Some(150)).is_err());
+        // Not synced yet.
+        assert!(check(40, 60, Some(50)).is_err());
+        assert!(check(11, 20, None).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+/// Samples high-frequency log events so that only one in every `rate` events is emitted.
+#[derive(Debug)]
+pub struct LogSampler {
+    rate: u64,
+    count: u64,
+}
+
+impl LogSampler {
+    /// Creates a sampler that lets through one in every `rate` events. A rate of `0` is treated as `1`.
+    pub fn new(rate: u64) -> Self {
+        Self {
+            rate: rate.max(1),
+            count: 0,
+        }
+    }
+
+    /// Records an event and returns whether it should be logged.
+    pub fn sample(&mut self) -> bool {
+        let emit = self.count % self.rate == 0;
+        self.count = self.count.wrapping_add(1);
+        emit
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LogSampler;
+
+    #[test]
+    fn samples_one_in_n() {
+        let mut sampler = LogSampler::new(3);
+        let emitted = (0..9).map(|_| sampler.sample()).collect::<Vec<_>>();
+        assert_eq!(
+            emitted,
+            vec![true, false, false, true, false, false, true, false, false]
+        );
+    }
+
+    #[test]
+    fn zero_rate_logs_everything() {
+        let mut sampler = LogSampler::new(0);
+        assert!((0..5).all(|_| sampler.sample()));
+    }
+}
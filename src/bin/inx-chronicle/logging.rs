@@ -0,0 +1,177 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use tracing::{error, Subscriber};
+use tracing_subscriber::{
+    filter::{Directive, LevelFilter},
+    fmt::{format::FmtSpan, MakeWriter},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+/// The format of the log output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    Text,
+    /// One JSON object per line, for log aggregation pipelines.
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
+/// Configuration of the log output.
+#[derive(Clone, Debug, Default)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// The levels of the modules whose path starts with a given prefix, such as `chronicle::inx=debug`. A level without
+    /// a prefix applies to all other modules. The `RUST_LOG` environment variable takes precedence when it is set.
+    pub levels: Vec<Directive>,
+}
+
+/// Installs the global subscriber and a panic hook that reports panics through it.
+pub fn set_up_logging(config: &LoggingConfig) -> eyre::Result<()> {
+    std::panic::set_hook(Box::new(|info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Box<dyn Any>");
+        match info.location() {
+            Some(location) => error!(target: "panic", location = %location, "{message}"),
+            None => error!(target: "panic", "{message}"),
+        }
+    }));
+
+    let (text, json) = match config.format {
+        LogFormat::Text => (
+            Some(tracing_subscriber::fmt::layer().with_span_events(FmtSpan::CLOSE)),
+            None,
+        ),
+        LogFormat::Json => (None, Some(json_layer(std::io::stdout))),
+    };
+
+    tracing_subscriber::registry()
+        .with(env_filter(
+            &config.levels,
+            std::env::var(EnvFilter::DEFAULT_ENV)
+                .ok()
+                .filter(|directives| !directives.is_empty())
+                .as_deref(),
+        ))
+        .with(text)
+        .with(json)
+        .try_init()?;
+    Ok(())
+}
+
+/// Builds the filter of the log output from the `RUST_LOG` directives if they are given, and from the configured levels
+/// otherwise. Without either, only errors are logged.
+fn env_filter(levels: &[Directive], rust_log: Option<&str>) -> EnvFilter {
+    let builder = EnvFilter::builder().with_default_directive(LevelFilter::ERROR.into());
+    match rust_log {
+        Some(directives) => builder.parse_lossy(directives),
+        None => builder.parse_lossy(levels.iter().map(ToString::to_string).collect::<Vec<_>>().join(",")),
+    }
+}
+
+/// A layer that writes every event as a JSON object with the `timestamp`, `level`, `target` and `message` at the top
+/// level, followed by the remaining fields of the event.
+fn json_layer<S, W>(make_writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .json()
+        .flatten_event(true)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(make_writer)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::info;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_lines_contain_expected_keys() {
+        let buffer = Buffer::default();
+        let make_writer = {
+            let buffer = buffer.clone();
+            move || buffer.clone()
+        };
+        let subscriber = tracing_subscriber::registry().with(json_layer(make_writer));
+        tracing::subscriber::with_default(subscriber, || {
+            info!(milestone_index = 42, "Milestone synced.");
+            error!(target: "panic", location = "src/main.rs:1:1", "worker failed");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        for line in &lines {
+            for key in ["timestamp", "level", "target", "message"] {
+                assert!(line.get(key).is_some(), "missing `{key}` in {line}");
+            }
+        }
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["message"], "Milestone synced.");
+        assert_eq!(lines[0]["milestone_index"], 42);
+        assert_eq!(lines[1]["target"], "panic");
+        assert_eq!(lines[1]["location"], "src/main.rs:1:1");
+    }
+
+    #[test]
+    fn filter_is_built_from_levels() {
+        let directives = |filter: EnvFilter| {
+            let mut directives = filter.to_string().split(',').map(str::to_owned).collect::<Vec<_>>();
+            directives.sort();
+            directives
+        };
+        let levels = ["chronicle::inx=debug", "hyper=warn", "info"]
+            .into_iter()
+            .map(|level| level.parse().unwrap())
+            .collect::<Vec<Directive>>();
+
+        assert_eq!(
+            directives(env_filter(&levels, None)),
+            ["chronicle::inx=debug", "hyper=warn", "info"]
+        );
+        // `RUST_LOG` replaces the configured levels entirely.
+        assert_eq!(
+            directives(env_filter(&levels, Some("chronicle=trace"))),
+            ["chronicle=trace"]
+        );
+        assert_eq!(directives(env_filter(&[], None)), ["error"]);
+    }
+}
@@ -8,38 +8,47 @@
 mod api;
 mod cli;
 mod config;
+#[cfg(any(feature = "inx", feature = "api"))]
+mod events;
 #[cfg(feature = "inx")]
 mod inx;
+mod logging;
 mod migrations;
 mod process;
+mod startup;
+
+use std::process::ExitCode;
 
 use bytesize::ByteSize;
-use chronicle::db::MongoDb;
-use clap::Parser;
-use tokio::task::JoinSet;
-use tracing::{debug, error, info};
-use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use chronicle::db::{MongoDb, MongoDbCollection};
+use tokio::{sync::broadcast, task::JoinSet};
+use tracing::{debug, info, warn};
 
 use self::{
-    cli::{ClArgs, PostCommand},
+    cli::{ClArgs, PostCommand, Subcommands},
     migrations::check_migration_version,
+    process::ShutdownReason,
 };
 
 #[tokio::main]
-async fn main() -> eyre::Result<()> {
+async fn main() -> eyre::Result<ExitCode> {
     dotenvy::dotenv().ok();
 
-    let cl_args = ClArgs::parse();
+    let cl_args = ClArgs::parse_with_env();
     let config = cl_args.get_config();
+    // An invalid configuration can still be printed, to find out where the offending value comes from.
+    if !matches!(cl_args.subcommand, Some(Subcommands::PrintConfig { .. })) {
+        config.validate()?;
+    }
 
-    set_up_logging()?;
+    logging::set_up_logging(&config.logging)?;
 
     if cl_args.process_subcommands(&config).await? == PostCommand::Exit {
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     }
 
     info!("Connecting to database using hosts: `{}`.", config.mongodb.hosts_str()?);
-    let db = MongoDb::connect(&config.mongodb).await?;
+    let db = startup::connect_mongodb(&config.mongodb, config.startup.wait_for_deps).await?;
     debug!("Available databases: `{:?}`", db.get_databases().await?);
     info!(
         "Connected to database `{}` ({})",
@@ -49,15 +58,30 @@ async fn main() -> eyre::Result<()> {
 
     check_migration_version(&db).await?;
 
+    #[allow(unused_mut)]
+    let mut ingestion_enabled = false;
     #[cfg(feature = "inx")]
-    build_indexes(&db).await?;
+    {
+        ingestion_enabled |= config.inx.enabled;
+    }
+    if ingestion_enabled {
+        build_indexes(&db).await?;
+    } else if let Err(e) = build_indexes(&db).await {
+        // An API-only deployment may connect with a user that cannot create indexes, and relies on the indexes that
+        // the ingesting instance created.
+        warn!("Failed to create indexes, queries may be slow until the ingesting instance creates them: {e}");
+    }
 
     let mut tasks: JoinSet<eyre::Result<()>> = JoinSet::new();
+    // Shared between the ingestion, which publishes the applied milestones, and the API, which streams them.
+    #[cfg(any(feature = "inx", feature = "api"))]
+    let milestone_events = events::milestone_events();
 
-    let (shutdown_signal, _) = tokio::sync::broadcast::channel::<()>(1);
+    let (shutdown_signal, _) = broadcast::channel::<()>(1);
 
     #[cfg(feature = "inx")]
     if config.inx.enabled {
+        use futures::FutureExt;
         #[cfg(feature = "influx")]
         #[allow(unused_mut)]
         let mut influx_required = false;
@@ -87,6 +111,8 @@ async fn main() -> eyre::Result<()> {
         };
 
         let mut worker = inx::InxWorker::new(db.clone(), config.inx.clone());
+        worker.set_wait_for_deps(config.startup.wait_for_deps);
+        worker.set_milestone_events(&milestone_events);
         #[cfg(feature = "influx")]
         if let Some(influx_db) = &influx_db {
             worker.set_influx_db(influx_db);
@@ -94,10 +120,7 @@ async fn main() -> eyre::Result<()> {
 
         let mut handle = shutdown_signal.subscribe();
         tasks.spawn(async move {
-            tokio::select! {
-                res = worker.run() => res?,
-                _ = handle.recv() => {},
-            }
+            worker.run(handle.recv().then(|_| async {})).await?;
             Ok(())
         });
     }
@@ -105,7 +128,7 @@ async fn main() -> eyre::Result<()> {
     #[cfg(feature = "api")]
     if config.api.enabled {
         use futures::FutureExt;
-        let worker = api::ApiWorker::new(db.clone(), config.api.clone())?;
+        let worker = api::ApiWorker::new(db.clone(), config.api.clone(), milestone_events.clone())?;
         let mut handle = shutdown_signal.subscribe();
         tasks.spawn(async move {
             worker.run(handle.recv().then(|_| async {})).await?;
@@ -113,34 +136,40 @@ async fn main() -> eyre::Result<()> {
         });
     }
 
-    let mut exit_code = Ok(());
+    let mut shutdown_reason = ShutdownReason::WorkersFinished;
 
     // We wait for either the interrupt signal to arrive or for a component of our system to signal a shutdown.
     tokio::select! {
         res = process::interrupt_or_terminate() => {
-            if let Err(err) = res {
-                tracing::error!("subscribing to OS interrupt signals failed with error: {err}; shutting down");
-                exit_code = Err(err);
+            shutdown_reason = if let Err(err) = res {
+                tracing::error!("subscribing to OS interrupt signals failed with error: {err}");
+                ShutdownReason::SignalHandler
             } else {
-                tracing::info!("received ctrl-c or terminate; shutting down");
-            }
+                ShutdownReason::Signal
+            };
         },
         res = tasks.join_next() => {
-            if let Some(Ok(Err(err))) = res {
-                tracing::error!("a worker failed with error: {err}");
-                exit_code = Err(err);
+            match res {
+                Some(Ok(Err(err))) => {
+                    tracing::error!("a worker failed with error: {err}");
+                    shutdown_reason = ShutdownReason::from_error(&err);
+                }
+                Some(Err(err)) => {
+                    tracing::error!("a worker panicked: {err}");
+                    shutdown_reason = ShutdownReason::Worker;
+                }
+                _ => (),
             }
         },
     }
 
-    shutdown_signal.send(())?;
+    shutdown_with(&shutdown_signal, shutdown_reason);
 
     // Allow the user to abort if the tasks aren't shutting down quickly.
     tokio::select! {
         res = process::interrupt_or_terminate() => {
             if let Err(err) = res {
                 tracing::error!("subscribing to OS interrupt signals failed with error: {err}; aborting");
-                exit_code = Err(err);
             } else {
                 tracing::info!("received second ctrl-c or terminate; aborting");
             }
@@ -152,47 +181,47 @@ async fn main() -> eyre::Result<()> {
         },
     }
 
-    exit_code
+    Ok(ExitCode::from(shutdown_reason.exit_code()))
 }
 
-fn set_up_logging() -> eyre::Result<()> {
-    std::panic::set_hook(Box::new(|p| {
-        error!("{}", p);
-    }));
-
-    let registry = tracing_subscriber::registry();
-
-    let registry = {
-        registry
-            .with(EnvFilter::from_default_env())
-            .with(tracing_subscriber::fmt::layer().with_span_events(FmtSpan::CLOSE))
-    };
-
-    registry.init();
-    Ok(())
+/// Records the reason for shutting down and signals all workers to stop.
+fn shutdown_with(shutdown_signal: &broadcast::Sender<()>, reason: ShutdownReason) {
+    if reason.is_error() {
+        tracing::error!("shutting down ({reason}), exit code {}", reason.exit_code());
+    } else {
+        tracing::info!("shutting down ({reason})");
+    }
+    // Sending only fails if all workers have already stopped and dropped their receivers.
+    shutdown_signal.send(()).ok();
 }
 
 async fn build_indexes(db: &MongoDb) -> eyre::Result<()> {
     use chronicle::db::mongodb::collections;
-    let start_indexes = db.get_index_names().await?;
-    db.create_indexes::<collections::OutputCollection>().await?;
-    db.create_indexes::<collections::BlockCollection>().await?;
-    db.create_indexes::<collections::LedgerUpdateCollection>().await?;
-    db.create_indexes::<collections::MilestoneCollection>().await?;
-    let end_indexes = db.get_index_names().await?;
-    for (collection, indexes) in end_indexes {
-        if let Some(old_indexes) = start_indexes.get(&collection) {
-            let num_created = indexes.difference(old_indexes).count();
-            if num_created > 0 {
-                info!("Created {} new indexes in {}", num_created, collection);
-                if tracing::enabled!(tracing::Level::DEBUG) {
-                    for index in indexes.difference(old_indexes) {
-                        debug!(" - {}", index);
-                    }
-                }
-            }
-        } else {
-            info!("Created {} new indexes in {}", indexes.len(), collection);
+    build_collection_indexes::<collections::OutputCollection>(db).await?;
+    build_collection_indexes::<collections::BlockCollection>(db).await?;
+    build_collection_indexes::<collections::LedgerUpdateCollection>(db).await?;
+    build_collection_indexes::<collections::MilestoneCollection>(db).await?;
+    Ok(())
+}
+
+/// Creates the indexes of a collection that do not exist yet, and logs which of them were created.
+async fn build_collection_indexes<T: MongoDbCollection + Send + Sync>(db: &MongoDb) -> eyre::Result<()> {
+    let report = db.create_indexes::<T>().await?;
+    if report.created.is_empty() {
+        debug!(
+            "All {} indexes in {} were already present.",
+            report.present.len(),
+            T::NAME
+        );
+    } else {
+        info!(
+            "Created {} new indexes in {}, {} were already present.",
+            report.created.len(),
+            T::NAME,
+            report.present.len()
+        );
+        for index in &report.created {
+            debug!(" - {}", index);
         }
     }
     Ok(())
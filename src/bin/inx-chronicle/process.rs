@@ -18,3 +18,152 @@ pub async fn interrupt_or_terminate() -> eyre::Result<()> {
 
     Ok(())
 }
+
+/// The reason for which Chronicle shut down. Each reason maps to a distinct process exit code so that orchestration
+/// can decide whether a restart is worthwhile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// A `SIGINT` or `SIGTERM` was received.
+    Signal,
+    /// Listening for OS signals failed.
+    SignalHandler,
+    /// All workers finished without an error.
+    WorkersFinished,
+    /// The INX configuration, or the state of the connected node, is incompatible with the database.
+    InxConfiguration,
+    /// The connection to the node via INX failed.
+    Inx,
+    /// An unrecoverable database error occurred.
+    Database,
+    /// A worker failed for any other reason, or panicked.
+    Worker,
+}
+
+impl ShutdownReason {
+    /// Classifies a worker error by looking for known error types in its chain of causes.
+    pub fn from_error(err: &eyre::Report) -> Self {
+        err.chain()
+            .find_map(|e| {
+                #[cfg(feature = "inx")]
+                {
+                    use crate::inx::InxWorkerError;
+                    if let Some(e) = e.downcast_ref::<InxWorkerError>() {
+                        return Some(match e {
+                            InxWorkerError::InvalidAddress(_)
+                            | InxWorkerError::NetworkChanged { .. }
+                            | InxWorkerError::OutputKindsChanged { .. }
+                            | InxWorkerError::ReplayRangeUnavailable { .. }
+                            | InxWorkerError::SyncMilestoneGap { .. }
+                            | InxWorkerError::SyncMilestoneIndexMismatch { .. } => Self::InxConfiguration,
+                            InxWorkerError::InvalidUnspentOutputIndex { .. }
+                            | InxWorkerError::ReplayMissingMilestone(_) => Self::Inx,
+                            InxWorkerError::DbOperationTimeout(_) => Self::Database,
+                            #[cfg(feature = "analytics")]
+                            InxWorkerError::MissingAppState => Self::Database,
+                        });
+                    }
+                    if e.is::<chronicle::inx::InxError>() {
+                        return Some(Self::Inx);
+                    }
+                }
+                e.is::<mongodb::error::Error>().then_some(Self::Database)
+            })
+            .unwrap_or(Self::Worker)
+    }
+
+    /// The process exit code associated with this reason.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Signal | Self::WorkersFinished => 0,
+            Self::SignalHandler | Self::Worker => 1,
+            Self::Inx => 69,
+            Self::Database => 75,
+            Self::InxConfiguration => 78,
+        }
+    }
+
+    /// Whether this reason indicates a failure.
+    pub fn is_error(&self) -> bool {
+        self.exit_code() != 0
+    }
+}
+
+impl std::fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Signal => "received ctrl-c or terminate",
+            Self::SignalHandler => "failed to listen to OS signals",
+            Self::WorkersFinished => "all workers finished",
+            Self::InxConfiguration => "incompatible INX configuration",
+            Self::Inx => "INX connection failed",
+            Self::Database => "unrecoverable database error",
+            Self::Worker => "a worker failed",
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ShutdownReason;
+
+    #[cfg(feature = "inx")]
+    #[test]
+    fn invalid_inx_address_is_a_configuration_error() {
+        let err = eyre::Report::new(crate::inx::InxWorkerError::InvalidAddress("localhost:9029".to_string()));
+        let reason = ShutdownReason::from_error(&err);
+        assert_eq!(reason, ShutdownReason::InxConfiguration);
+        assert_eq!(reason.exit_code(), 78);
+    }
+
+    #[cfg(feature = "inx")]
+    #[test]
+    fn inx_worker_errors_are_classified() {
+        use crate::inx::InxWorkerError;
+
+        let reason = |err: InxWorkerError| ShutdownReason::from_error(&eyre::Report::new(err));
+        assert_eq!(
+            reason(InxWorkerError::NetworkChanged {
+                old: "shimmer".to_string(),
+                new: "testnet".to_string(),
+            }),
+            ShutdownReason::InxConfiguration
+        );
+        assert_eq!(
+            reason(InxWorkerError::OutputKindsChanged {
+                old: "all".to_string(),
+                new: "basic".to_string(),
+            }),
+            ShutdownReason::InxConfiguration
+        );
+        assert_eq!(
+            reason(InxWorkerError::SyncMilestoneGap {
+                start: 1.into(),
+                end: 10.into(),
+            }),
+            ShutdownReason::InxConfiguration
+        );
+        assert_eq!(
+            reason(InxWorkerError::ReplayRangeUnavailable {
+                start: 1.into(),
+                end: 10.into(),
+                reason: "the range is empty".to_string(),
+            }),
+            ShutdownReason::InxConfiguration
+        );
+        assert_eq!(
+            reason(InxWorkerError::ReplayMissingMilestone(5.into())),
+            ShutdownReason::Inx
+        );
+        assert_eq!(
+            reason(InxWorkerError::DbOperationTimeout(std::time::Duration::from_secs(1))),
+            ShutdownReason::Database
+        );
+    }
+
+    #[test]
+    fn unknown_error_is_a_worker_error() {
+        let reason = ShutdownReason::from_error(&eyre::eyre!("boom"));
+        assert_eq!(reason, ShutdownReason::Worker);
+        assert_eq!(reason.exit_code(), 1);
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use chronicle::db::{MongoDb, MongoDbConfig};
+use mongodb::error::ErrorKind;
+use tracing::{info, warn};
+
+pub const DEFAULT_WAIT_FOR_DEPS: &str = "0s";
+
+/// The delay before the first retry. Every following retry waits twice as long, up to [`MAX_RETRY_DELAY`].
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(250);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Configuration of the startup behavior.
+#[derive(Clone, Debug)]
+pub struct StartupConfig {
+    /// How long to wait for MongoDb and the node to become available. Zero fails on the first attempt.
+    pub wait_for_deps: Duration,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            wait_for_deps: DEFAULT_WAIT_FOR_DEPS.parse::<humantime::Duration>().unwrap().into(),
+        }
+    }
+}
+
+/// Exponentially growing delays between retries that stop once a timeout has passed.
+#[derive(Debug)]
+pub struct Backoff {
+    deadline: Instant,
+    delay: Duration,
+}
+
+impl Backoff {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + timeout,
+            delay: INITIAL_RETRY_DELAY,
+        }
+    }
+
+    /// Returns how long to wait before the next retry, or `None` if the timeout has passed.
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return None;
+        }
+        let delay = self.delay.min(remaining);
+        self.delay = (self.delay * 2).min(MAX_RETRY_DELAY);
+        Some(delay)
+    }
+}
+
+/// Repeatedly runs `attempt` until it succeeds, fails with an error that is not `transient`, or `timeout` has passed.
+/// The delay between attempts grows exponentially.
+pub async fn wait_for<T, E, F, Fut>(
+    name: &str,
+    timeout: Duration,
+    transient: impl Fn(&E) -> bool,
+    mut attempt: F,
+) -> Result<T, E>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut backoff = Backoff::new(timeout);
+    loop {
+        match attempt().await {
+            Ok(res) => return Ok(res),
+            Err(err) if transient(&err) => match backoff.next_delay() {
+                Some(delay) => {
+                    info!("Waiting for {name}: {err}. Retrying in {delay:?}.");
+                    tokio::time::sleep(delay).await;
+                }
+                None => {
+                    if !timeout.is_zero() {
+                        warn!(
+                            "{name} did not become available within {}.",
+                            humantime::format_duration(timeout)
+                        );
+                    }
+                    return Err(err);
+                }
+            },
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Connects to MongoDb and makes sure the server is reachable, waiting up to `timeout` for it to come up.
+pub async fn connect_mongodb(config: &MongoDbConfig, timeout: Duration) -> Result<MongoDb, mongodb::error::Error> {
+    wait_for("MongoDb", timeout, is_transient_mongodb_error, || async {
+        let db = MongoDb::connect(config).await?;
+        // Creating the client does not contact the server, so make sure it actually responds.
+        db.get_databases().await?;
+        Ok(db)
+    })
+    .await
+}
+
+/// Whether the error is caused by the server not being reachable (yet), as opposed to a misconfiguration such as an
+/// invalid connection string or bad credentials.
+pub fn is_transient_mongodb_error(err: &mongodb::error::Error) -> bool {
+    matches!(
+        *err.kind,
+        ErrorKind::ServerSelection { .. } | ErrorKind::Io(_) | ErrorKind::DnsResolve { .. }
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn unreachable_error() -> mongodb::error::Error {
+        std::io::Error::from(std::io::ErrorKind::ConnectionRefused).into()
+    }
+
+    #[tokio::test]
+    async fn waits_until_available() {
+        let attempts = AtomicUsize::new(0);
+        // The stub only becomes available on its third attempt.
+        let res = wait_for("stub", Duration::from_secs(5), is_transient_mongodb_error, || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(unreachable_error())
+            } else {
+                Ok(())
+            }
+        })
+        .await;
+        assert!(res.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_timeout() {
+        let attempts = AtomicUsize::new(0);
+        let res = wait_for(
+            "stub",
+            Duration::from_millis(300),
+            is_transient_mongodb_error,
+            || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(unreachable_error())
+            },
+        )
+        .await;
+        assert!(res.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn invalid_connection_string_is_not_retried() {
+        let config = MongoDbConfig {
+            conn_str: "not-a-connection-string".to_string(),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let err = connect_mongodb(&config, Duration::from_secs(60)).await.unwrap_err();
+        assert!(!is_transient_mongodb_error(&err));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -9,4 +9,6 @@ pub mod influxdb;
 /// Module containing MongoDb types and traits.
 pub mod mongodb;
 
-pub use self::mongodb::{config::MongoDbConfig, MongoDb, MongoDbCollection, MongoDbCollectionExt};
+pub use self::mongodb::{
+    config::MongoDbConfig, CollectionStats, IndexReport, MongoDb, MongoDbCollection, MongoDbCollectionExt,
+};
@@ -1,7 +1,12 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::borrow::Borrow;
+use std::{
+    borrow::Borrow,
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use futures::{Stream, StreamExt};
@@ -9,10 +14,10 @@ use mongodb::{
     bson::{self, doc, Document},
     error::{Error, ErrorKind},
     options::{
-        AggregateOptions, CreateIndexOptions, DropIndexOptions, FindOneOptions, FindOptions, InsertManyOptions,
-        InsertOneOptions, ReplaceOptions, UpdateModifications, UpdateOptions,
+        AggregateOptions, CreateIndexOptions, DeleteOptions, DropIndexOptions, FindOneOptions, FindOptions,
+        InsertManyOptions, InsertOneOptions, ReplaceOptions, UpdateModifications, UpdateOptions,
     },
-    results::{CreateIndexResult, InsertManyResult, InsertOneResult, UpdateResult},
+    results::{CreateIndexResult, DeleteResult, InsertManyResult, InsertOneResult, UpdateResult},
     Cursor, IndexModel,
 };
 use serde::{de::DeserializeOwned, Serialize};
@@ -22,6 +27,60 @@ use super::MongoDb;
 const DUPLICATE_KEY_CODE: i32 = 11000;
 const INDEX_NOT_FOUND_CODE: i32 = 27;
 
+/// The slow query threshold in milliseconds, shared by all collections of the process. Zero disables the log.
+static SLOW_QUERY_THRESHOLD_MS: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn set_slow_query_threshold(threshold: Duration) {
+    SLOW_QUERY_THRESHOLD_MS.store(threshold.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// Logs the query at `warn` level if it took longer than the slow query threshold, and returns whether it did.
+fn log_slow_query(collection: &str, operation: &str, summary: impl FnOnce() -> String, elapsed: Duration) -> bool {
+    let threshold = SLOW_QUERY_THRESHOLD_MS.load(Ordering::Relaxed);
+    if threshold == 0 || elapsed.as_millis() < threshold as u128 {
+        return false;
+    }
+    tracing::warn!(
+        "Slow query: `{operation}` on `{collection}` took {}ms ({}).",
+        elapsed.as_millis(),
+        summary()
+    );
+    true
+}
+
+/// Runs the query and logs it if it was slow. Slow queries are logged within the current span, which, for API
+/// requests, contains the route.
+async fn timed<T>(
+    collection: &str,
+    operation: &str,
+    summary: impl FnOnce() -> String,
+    query: impl Future<Output = T>,
+) -> T {
+    let start = Instant::now();
+    let res = query.await;
+    log_slow_query(collection, operation, summary, start.elapsed());
+    res
+}
+
+/// Summarizes a pipeline by the names of its stages, as the stages themselves may be large.
+fn pipeline_summary(pipeline: &[Document]) -> String {
+    let stages = pipeline
+        .iter()
+        .filter_map(|stage| stage.keys().next().map(String::as_str))
+        .collect::<Vec<_>>();
+    format!("stages: {}", stages.join(", "))
+}
+
+/// Summarizes a filter by its top-level fields.
+fn filter_summary(filter: Option<&Document>) -> String {
+    let fields = filter
+        .iter()
+        .flat_map(|filter| filter.keys())
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    format!("filter: {}", fields.join(", "))
+}
+
 /// A MongoDB collection.
 #[async_trait]
 pub trait MongoDbCollection {
@@ -48,8 +107,18 @@ pub trait MongoDbCollection {
         Ok(())
     }
 
-    /// Creates the collection indexes.
+    /// The indexes of the collection, besides the one on `_id`. They are only defined here, so that creating them and
+    /// checking for them cannot diverge. Every index must be named.
+    fn indexes() -> Vec<IndexModel> {
+        Vec::new()
+    }
+
+    /// Creates the collection indexes. Indexes that already exist are left untouched.
     async fn create_indexes(&self) -> Result<(), Error> {
+        let indexes = Self::indexes();
+        if !indexes.is_empty() {
+            self.collection().create_indexes(indexes, None).await?;
+        }
         Ok(())
     }
 }
@@ -96,11 +165,17 @@ pub trait MongoDbCollectionExt: MongoDbCollection {
         pipeline: impl IntoIterator<Item = Document> + Send + Sync,
         options: impl Into<Option<AggregateOptions>> + Send + Sync,
     ) -> Result<Box<dyn Stream<Item = Result<T, Error>> + Unpin + Send>, Error> {
+        let pipeline = pipeline.into_iter().collect::<Vec<_>>();
+        let summary = pipeline_summary(&pipeline);
         Ok(Box::new(
-            self.collection()
-                .aggregate(pipeline, options)
-                .await?
-                .map(|doc| Ok(bson::from_document::<T>(doc?)?)),
+            timed(
+                Self::NAME,
+                "aggregate",
+                || summary,
+                self.collection().aggregate(pipeline, options),
+            )
+            .await?
+            .map(|doc| Ok(bson::from_document::<T>(doc?)?)),
         ))
     }
 
@@ -110,7 +185,9 @@ pub trait MongoDbCollectionExt: MongoDbCollection {
         filter: impl Into<Option<Document>> + Send + Sync,
         options: impl Into<Option<FindOptions>> + Send + Sync,
     ) -> Result<Cursor<T>, Error> {
-        self.with_type().find(filter, options).await
+        let filter: Option<Document> = filter.into();
+        let summary = filter_summary(filter.as_ref());
+        timed(Self::NAME, "find", || summary, self.with_type().find(filter, options)).await
     }
 
     /// Calls [`mongodb::Collection::find_one()`] and coerces the document type.
@@ -119,7 +196,15 @@ pub trait MongoDbCollectionExt: MongoDbCollection {
         filter: impl Into<Option<Document>> + Send + Sync,
         options: impl Into<Option<FindOneOptions>> + Send + Sync,
     ) -> Result<Option<T>, Error> {
-        self.with_type().find_one(filter, options).await
+        let filter: Option<Document> = filter.into();
+        let summary = filter_summary(filter.as_ref());
+        timed(
+            Self::NAME,
+            "find_one",
+            || summary,
+            self.with_type().find_one(filter, options),
+        )
+        .await
     }
 
     /// Calls [`mongodb::Collection::insert_many()`] and coerces the document type.
@@ -150,6 +235,17 @@ pub trait MongoDbCollectionExt: MongoDbCollection {
         self.collection().update_one(doc, update, options).await
     }
 
+    /// Calls [`mongodb::Collection::update_one()`], inserting a new document if none matches the query.
+    async fn upsert_one(
+        &self,
+        doc: Document,
+        update: impl Into<UpdateModifications> + Send + Sync,
+    ) -> Result<UpdateResult, Error> {
+        self.collection()
+            .update_one(doc, update, UpdateOptions::builder().upsert(true).build())
+            .await
+    }
+
     /// Calls [`mongodb::Collection::update_many()`].
     async fn update_many(
         &self,
@@ -160,6 +256,15 @@ pub trait MongoDbCollectionExt: MongoDbCollection {
         self.collection().update_many(doc, update, options).await
     }
 
+    /// Calls [`mongodb::Collection::delete_many()`].
+    async fn delete_many(
+        &self,
+        doc: Document,
+        options: impl Into<Option<DeleteOptions>> + Send + Sync,
+    ) -> Result<DeleteResult, Error> {
+        self.collection().delete_many(doc, options).await
+    }
+
     /// Calls [`mongodb::Collection::replace_one()`] and coerces the document type.
     async fn replace_one<T: Serialize + Send + Sync>(
         &self,
@@ -221,3 +326,44 @@ impl<T: MongoDbCollectionExt + Send + Sync, D: Serialize + Send + Sync> InsertIg
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn slow_queries_are_logged() {
+        set_slow_query_threshold(Duration::from_millis(20));
+
+        let start = Instant::now();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(log_slow_query("outputs", "aggregate", String::new, start.elapsed()));
+        assert!(!log_slow_query(
+            "outputs",
+            "aggregate",
+            String::new,
+            Duration::from_millis(5)
+        ));
+
+        set_slow_query_threshold(Duration::ZERO);
+        assert!(!log_slow_query(
+            "outputs",
+            "aggregate",
+            String::new,
+            Duration::from_secs(60)
+        ));
+    }
+
+    #[test]
+    fn pipelines_are_summarized_by_stage() {
+        let pipeline = [
+            doc! { "$match": { "output.kind": "basic" } },
+            doc! { "$group": { "_id": null, "count": { "$sum": 1 } } },
+        ];
+        assert_eq!(pipeline_summary(&pipeline), "stages: $match, $group");
+        assert_eq!(
+            filter_summary(Some(&doc! { "_id": 1, "metadata.booked.milestone_index": 2 })),
+            "filter: _id, metadata.booked.milestone_index"
+        );
+    }
+}
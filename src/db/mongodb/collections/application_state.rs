@@ -1,7 +1,7 @@
 // Copyright 2023 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use mongodb::{bson::doc, error::Error, options::UpdateOptions};
+use mongodb::{bson::doc, error::Error};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -9,7 +9,7 @@ use crate::{
         mongodb::{MongoDbCollection, MongoDbCollectionExt},
         MongoDb,
     },
-    model::tangle::MilestoneIndexTimestamp,
+    model::tangle::{MilestoneIndex, MilestoneIndexTimestamp},
 };
 
 /// The MongoDb document representation of singleton Application State.
@@ -17,6 +17,30 @@ use crate::{
 pub struct ApplicationStateDocument {
     pub starting_index: Option<MilestoneIndexTimestamp>,
     pub last_migration: Option<MigrationVersion>,
+    pub sync_target: Option<MilestoneIndex>,
+    /// The latest milestone index the node is known to have confirmed.
+    #[serde(default)]
+    pub latest_known_index: Option<MilestoneIndex>,
+    /// The number of applied milestones whose timestamp was earlier than the one of the previous milestone.
+    #[serde(default)]
+    pub milestone_timestamp_violations: u64,
+    /// The kinds of outputs that the ledger is restricted to, or `None` if it contains all outputs.
+    #[serde(default)]
+    pub output_kinds: Option<Vec<String>>,
+    /// Whether blocks were ever filtered when they were stored, so that milestone cones may be incomplete.
+    #[serde(default)]
+    pub partial_blocks: bool,
+    /// The ranges of milestones that were pruned by the node before they could be applied.
+    #[serde(default)]
+    pub skipped_milestones: Vec<SkippedMilestones>,
+}
+
+/// An inclusive range of milestones that were skipped, so that the ledger is missing their changes.
+#[allow(missing_docs)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SkippedMilestones {
+    pub start: MilestoneIndex,
+    pub end: MilestoneIndex,
 }
 
 /// The migration version and associated metadata.
@@ -63,12 +87,11 @@ impl ApplicationStateCollection {
 
     /// Set the starting milestone index in the singleton application state.
     pub async fn set_starting_index(&self, starting_index: MilestoneIndexTimestamp) -> Result<(), Error> {
-        self.update_one(
+        self.upsert_one(
             doc! {},
             doc! {
                 "$set": { "starting_index": starting_index }
             },
-            UpdateOptions::builder().upsert(true).build(),
         )
         .await?;
         Ok(())
@@ -84,12 +107,135 @@ impl ApplicationStateCollection {
 
     /// Set the current version in the singleton application state.
     pub async fn set_last_migration(&self, last_migration: MigrationVersion) -> Result<(), Error> {
-        self.update_one(
+        self.upsert_one(
             doc! {},
             doc! {
                 "$set": { "last_migration": mongodb::bson::to_bson(&last_migration)? }
             },
-            UpdateOptions::builder().upsert(true).build(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the milestone index that the initial catch-up with the node is syncing to.
+    pub async fn get_sync_target(&self) -> Result<Option<MilestoneIndex>, Error> {
+        Ok(self
+            .find_one::<ApplicationStateDocument>(doc! {}, None)
+            .await?
+            .and_then(|doc| doc.sync_target))
+    }
+
+    /// Set the milestone index that the initial catch-up with the node is syncing to.
+    pub async fn set_sync_target(&self, sync_target: MilestoneIndex) -> Result<(), Error> {
+        self.upsert_one(
+            doc! {},
+            doc! {
+                "$set": { "sync_target": sync_target }
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the latest milestone index the node is known to have confirmed.
+    pub async fn get_latest_known_index(&self) -> Result<Option<MilestoneIndex>, Error> {
+        Ok(self
+            .find_one::<ApplicationStateDocument>(doc! {}, None)
+            .await?
+            .and_then(|doc| doc.latest_known_index))
+    }
+
+    /// Records that the node has confirmed the milestone with the given index. The stored index never decreases.
+    pub async fn raise_latest_known_index(&self, index: MilestoneIndex) -> Result<(), Error> {
+        self.upsert_one(
+            doc! {},
+            doc! {
+                "$max": { "latest_known_index": index }
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the number of milestones that were applied with a timestamp earlier than their predecessor's.
+    pub async fn get_milestone_timestamp_violations(&self) -> Result<u64, Error> {
+        Ok(self
+            .find_one::<ApplicationStateDocument>(doc! {}, None)
+            .await?
+            .map(|doc| doc.milestone_timestamp_violations)
+            .unwrap_or_default())
+    }
+
+    /// Gets the kinds of outputs that the ledger is restricted to, or `None` if it contains all outputs.
+    pub async fn get_output_kinds(&self) -> Result<Option<Vec<String>>, Error> {
+        Ok(self
+            .find_one::<ApplicationStateDocument>(doc! {}, None)
+            .await?
+            .and_then(|doc| doc.output_kinds))
+    }
+
+    /// Records the kinds of outputs that the ledger is restricted to, or `None` if it contains all outputs.
+    pub async fn set_output_kinds(&self, output_kinds: Option<Vec<String>>) -> Result<(), Error> {
+        self.upsert_one(
+            doc! {},
+            doc! {
+                "$set": { "output_kinds": output_kinds }
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Gets whether blocks were ever filtered when they were stored, so that milestone cones may be incomplete.
+    pub async fn get_partial_blocks(&self) -> Result<bool, Error> {
+        Ok(self
+            .find_one::<ApplicationStateDocument>(doc! {}, None)
+            .await?
+            .map(|doc| doc.partial_blocks)
+            .unwrap_or_default())
+    }
+
+    /// Records that blocks are filtered when they are stored. This is never undone, since the milestone cones that
+    /// were stored in the meantime stay incomplete.
+    pub async fn set_partial_blocks(&self) -> Result<(), Error> {
+        self.upsert_one(
+            doc! {},
+            doc! {
+                "$set": { "partial_blocks": true }
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the ranges of milestones that were skipped because the node had already pruned them.
+    pub async fn get_skipped_milestones(&self) -> Result<Vec<SkippedMilestones>, Error> {
+        Ok(self
+            .find_one::<ApplicationStateDocument>(doc! {}, None)
+            .await?
+            .map(|doc| doc.skipped_milestones)
+            .unwrap_or_default())
+    }
+
+    /// Records a range of milestones that was skipped because the node had already pruned it.
+    pub async fn add_skipped_milestones(&self, skipped: SkippedMilestones) -> Result<(), Error> {
+        self.upsert_one(
+            doc! {},
+            doc! {
+                "$push": { "skipped_milestones": mongodb::bson::to_bson(&skipped)? }
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Records that a milestone was applied with a timestamp earlier than its predecessor's.
+    pub async fn increment_milestone_timestamp_violations(&self) -> Result<(), Error> {
+        self.upsert_one(
+            doc! {},
+            doc! {
+                "$inc": { "milestone_timestamp_violations": 1_i64 }
+            },
         )
         .await?;
         Ok(())
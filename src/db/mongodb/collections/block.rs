@@ -1,6 +1,8 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::ops::RangeInclusive;
+
 use futures::{Stream, TryStreamExt};
 use mongodb::{
     bson::doc,
@@ -38,8 +40,58 @@ pub struct BlockDocument {
     /// The raw bytes of the block.
     #[serde(with = "serde_bytes")]
     raw: Vec<u8>,
+    /// Whether `raw` is compressed with zstd. Missing in blocks that were stored before compression was supported.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    raw_compressed: bool,
     /// The block's metadata.
     metadata: BlockMetadata,
+    /// How far the block progressed according to its metadata.
+    #[serde(default)]
+    status: BlockStatus,
+}
+
+/// How far a stored block progressed, as last reported by the node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockStatus {
+    /// The node knows the block, but not yet its whole past cone.
+    Pending,
+    /// The node knows the block and its whole past cone.
+    Solid,
+    /// A milestone referenced the block, so its metadata is final.
+    Referenced,
+}
+
+/// Blocks that were stored before the status was recorded all were referenced by a milestone.
+impl Default for BlockStatus {
+    fn default() -> Self {
+        Self::Referenced
+    }
+}
+
+impl From<&BlockMetadata> for BlockStatus {
+    fn from(metadata: &BlockMetadata) -> Self {
+        if metadata.referenced_by_milestone_index.0 > 0 {
+            Self::Referenced
+        } else if metadata.is_solid {
+            Self::Solid
+        } else {
+            Self::Pending
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+impl BlockDocument {
+    /// Compresses the raw bytes with the given zstd level, unless that does not make them smaller.
+    fn compress(mut self, level: i32) -> Result<Self, Error> {
+        let compressed = zstd::encode_all(self.raw.as_slice(), level)?;
+        if compressed.len() < self.raw.len() {
+            self.raw = compressed;
+            self.raw_compressed = true;
+        }
+        Ok(self)
+    }
 }
 
 impl From<BlockData> for BlockDocument {
@@ -55,6 +107,8 @@ impl From<BlockData> for BlockDocument {
             block_id,
             block,
             raw,
+            raw_compressed: false,
+            status: BlockStatus::from(&metadata),
             metadata,
         }
     }
@@ -66,6 +120,8 @@ impl From<(BlockId, Block, Vec<u8>, BlockMetadata)> for BlockDocument {
             block_id,
             block,
             raw,
+            raw_compressed: false,
+            status: BlockStatus::from(&metadata),
             metadata,
         }
     }
@@ -74,23 +130,28 @@ impl From<(BlockId, Block, Vec<u8>, BlockMetadata)> for BlockDocument {
 /// The stardust blocks collection.
 pub struct BlockCollection {
     collection: mongodb::Collection<BlockDocument>,
+    #[cfg(feature = "compression")]
+    compression_level: Option<i32>,
 }
 
-#[async_trait::async_trait]
 impl MongoDbCollection for BlockCollection {
     const NAME: &'static str = "stardust_blocks";
     type Document = BlockDocument;
 
-    fn instantiate(_db: &MongoDb, collection: mongodb::Collection<Self::Document>) -> Self {
-        Self { collection }
+    fn instantiate(db: &MongoDb, collection: mongodb::Collection<Self::Document>) -> Self {
+        Self {
+            collection,
+            #[cfg(feature = "compression")]
+            compression_level: db.block_compression_level,
+        }
     }
 
     fn collection(&self) -> &mongodb::Collection<Self::Document> {
         &self.collection
     }
 
-    async fn create_indexes(&self) -> Result<(), Error> {
-        self.create_index(
+    fn indexes() -> Vec<IndexModel> {
+        vec![
             IndexModel::builder()
                 .keys(doc! { "block.payload.transaction_id": 1 })
                 .options(
@@ -104,11 +165,6 @@ impl MongoDbCollection for BlockCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "metadata.referenced_by_milestone_index": -1, "metadata.white_flag_index": 1, "metadata.inclusion_state": 1 })
                 .options(
@@ -117,11 +173,7 @@ impl MongoDbCollection for BlockCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        Ok(())
+        ]
     }
 }
 
@@ -139,10 +191,61 @@ pub struct IncludedBlockMetadataResult {
     pub metadata: BlockMetadata,
 }
 
+/// A block together with its metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub struct BlockWithMetadataResult {
+    pub block: Block,
+    pub metadata: BlockMetadata,
+}
+
+#[derive(Deserialize)]
+struct RawWithMetadataResult {
+    #[serde(with = "serde_bytes")]
+    raw: Vec<u8>,
+    #[serde(default)]
+    raw_compressed: bool,
+    metadata: BlockMetadata,
+}
+
 #[derive(Deserialize)]
 struct RawResult {
     #[serde(with = "serde_bytes")]
     raw: Vec<u8>,
+    #[serde(default)]
+    raw_compressed: bool,
+}
+
+impl RawResult {
+    fn into_raw(self) -> Result<Vec<u8>, Error> {
+        decompress(self.raw, self.raw_compressed)
+    }
+}
+
+/// Returns the raw bytes of a block as they were before they were stored.
+fn decompress(raw: Vec<u8>, compressed: bool) -> Result<Vec<u8>, Error> {
+    if compressed {
+        #[cfg(feature = "compression")]
+        return Ok(zstd::decode_all(raw.as_slice())?);
+        #[cfg(not(feature = "compression"))]
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "the block is compressed, but the `compression` feature is disabled",
+        )
+        .into());
+    }
+    Ok(raw)
+}
+
+/// Unpacks the raw bytes of a stored block, failing instead of panicking if they are corrupt.
+fn unpack_block(raw: &[u8]) -> Result<iota_types::block::Block, Error> {
+    iota_types::block::Block::unpack_unverified(raw).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid raw block bytes: {e:?}"),
+        )
+        .into()
+    })
 }
 
 #[derive(Deserialize)]
@@ -155,26 +258,26 @@ struct BlockIdResult {
 impl BlockCollection {
     /// Get a [`Block`] by its [`BlockId`].
     pub async fn get_block(&self, block_id: &BlockId) -> Result<Option<Block>, Error> {
-        Ok(self
-            .get_block_raw(block_id)
+        self.get_block_raw(block_id)
             .await?
-            .map(|raw| iota_types::block::Block::unpack_unverified(raw).unwrap().into()))
+            .map(|raw| Ok(unpack_block(&raw)?.into()))
+            .transpose()
     }
 
     /// Get the raw bytes of a [`Block`] by its [`BlockId`].
     pub async fn get_block_raw(&self, block_id: &BlockId) -> Result<Option<Vec<u8>>, Error> {
-        Ok(self
-            .aggregate(
-                [
-                    doc! { "$match": { "_id": block_id } },
-                    doc! { "$project": { "raw": 1 } },
-                ],
-                None,
-            )
-            .await?
-            .try_next()
-            .await?
-            .map(|RawResult { raw }| raw))
+        self.aggregate(
+            [
+                doc! { "$match": { "_id": block_id } },
+                doc! { "$project": { "raw": 1, "raw_compressed": 1 } },
+            ],
+            None,
+        )
+        .await?
+        .try_next()
+        .await?
+        .map(RawResult::into_raw)
+        .transpose()
     }
 
     /// Get the metadata of a [`Block`] by its [`BlockId`].
@@ -191,6 +294,33 @@ impl BlockCollection {
         .await
     }
 
+    /// Get a [`Block`] and its metadata by its [`BlockId`] in a single query.
+    pub async fn get_block_with_metadata(&self, block_id: &BlockId) -> Result<Option<BlockWithMetadataResult>, Error> {
+        self.aggregate(
+            [
+                doc! { "$match": { "_id": block_id } },
+                doc! { "$project": { "raw": 1, "raw_compressed": 1, "metadata": 1 } },
+            ],
+            None,
+        )
+        .await?
+        .try_next()
+        .await?
+        .map(
+            |RawWithMetadataResult {
+                 raw,
+                 raw_compressed,
+                 metadata,
+             }| {
+                Ok(BlockWithMetadataResult {
+                    block: unpack_block(&decompress(raw, raw_compressed)?)?.into(),
+                    metadata,
+                })
+            },
+        )
+        .transpose()
+    }
+
     /// Get the children of a [`Block`] as a stream of [`BlockId`]s.
     pub async fn get_block_children(
         &self,
@@ -256,6 +386,8 @@ impl BlockCollection {
             block_id: BlockId,
             #[serde(with = "serde_bytes")]
             raw: Vec<u8>,
+            #[serde(default)]
+            raw_compressed: bool,
             metadata: BlockMetadata,
         }
 
@@ -268,18 +400,20 @@ impl BlockCollection {
                 None,
             )
             .await?
-            .map_ok(|r| {
-                (
-                    r.block_id,
-                    iota_types::block::Block::unpack_unverified(r.raw.clone())
-                        .unwrap()
-                        .into(),
-                    r.raw,
-                    r.metadata,
-                )
+            .and_then(|r| async move {
+                let raw = decompress(r.raw, r.raw_compressed)?;
+                Ok((r.block_id, unpack_block(&raw)?.into(), raw, r.metadata))
             }))
     }
 
+    /// Get the number of blocks that were referenced by the specified milestone.
+    pub async fn get_referenced_block_count(&self, index: MilestoneIndex) -> Result<usize, Error> {
+        self.collection()
+            .count_documents(doc! { "metadata.referenced_by_milestone_index": index }, None)
+            .await
+            .map(|count| count as usize)
+    }
+
     /// Get the blocks that were applied by the specified milestone (in White-Flag order).
     pub async fn get_applied_blocks_in_white_flag_order(&self, index: MilestoneIndex) -> Result<Vec<BlockId>, Error> {
         let block_ids = self
@@ -311,6 +445,29 @@ impl BlockCollection {
         BlockDocument: From<B>,
     {
         let blocks_with_metadata = blocks_with_metadata.into_iter().map(BlockDocument::from);
+        #[cfg(feature = "compression")]
+        let blocks_with_metadata = match self.compression_level {
+            Some(level) => {
+                let (mut original, mut compressed) = (0, 0);
+                let blocks = blocks_with_metadata
+                    .map(|block| {
+                        original += block.raw.len();
+                        let block = block.compress(level)?;
+                        compressed += block.raw.len();
+                        Ok(block)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                if original > 0 {
+                    tracing::debug!(
+                        "Compressed the raw bytes of {} blocks from {original} to {compressed} bytes (ratio {:.2}).",
+                        blocks.len(),
+                        original as f64 / compressed as f64
+                    );
+                }
+                blocks
+            }
+            None => blocks_with_metadata.collect(),
+        };
 
         self.insert_many_ignore_duplicates(
             blocks_with_metadata,
@@ -321,18 +478,62 @@ impl BlockCollection {
         Ok(())
     }
 
+    /// Replaces the metadata of a stored block with a newer one that the node reported, and returns whether the block
+    /// was updated. Metadata that does not advance the [`BlockStatus`] is ignored, so that notifications may arrive
+    /// out of order. Blocks that are not stored are not created, since they are stored with their final metadata once
+    /// a milestone references them.
+    #[instrument(skip_all, err, level = "trace")]
+    pub async fn update_block_metadata(&self, block_id: &BlockId, metadata: BlockMetadata) -> Result<bool, Error> {
+        let status = BlockStatus::from(&metadata);
+        let earlier = [BlockStatus::Pending, BlockStatus::Solid]
+            .into_iter()
+            .filter(|&earlier| earlier < status)
+            .map(|earlier| mongodb::bson::to_bson(&earlier))
+            .collect::<Result<Vec<_>, _>>()?;
+        if earlier.is_empty() {
+            return Ok(false);
+        }
+        let res = self
+            .update_one(
+                doc! { "_id": block_id, "status": { "$in": earlier } },
+                doc! { "$set": {
+                    "metadata": mongodb::bson::to_bson(&metadata)?,
+                    "status": mongodb::bson::to_bson(&status)?,
+                } },
+                None,
+            )
+            .await?;
+        Ok(res.modified_count > 0)
+    }
+
+    /// Removes the blocks that were referenced by the milestones in `range`, and returns how many were removed.
+    #[instrument(skip_all, err, level = "trace")]
+    pub async fn revert_milestones(&self, range: RangeInclusive<MilestoneIndex>) -> Result<u64, Error> {
+        let (start, end) = range.into_inner();
+        Ok(self
+            .delete_many(
+                doc! { "metadata.referenced_by_milestone_index": { "$gte": start, "$lte": end } },
+                None,
+            )
+            .await?
+            .deleted_count)
+    }
+
     /// Finds the [`Block`] that included a transaction by [`TransactionId`].
     pub async fn get_block_for_transaction(
         &self,
         transaction_id: &TransactionId,
     ) -> Result<Option<IncludedBlockResult>, Error> {
-        Ok(self.get_block_raw_for_transaction(transaction_id).await?.map(|raw| {
-            let block = iota_types::block::Block::unpack_unverified(raw).unwrap();
-            IncludedBlockResult {
-                block_id: block.id().into(),
-                block: block.into(),
-            }
-        }))
+        self.get_block_raw_for_transaction(transaction_id)
+            .await?
+            .map(|raw| {
+                let block = unpack_block(&raw)?;
+                Ok(IncludedBlockResult {
+                    block_id: block.id().into(),
+                    block: block.into(),
+                })
+            })
+            .transpose()
     }
 
     /// Finds the raw bytes of the block that included a transaction by [`TransactionId`].
@@ -340,21 +541,21 @@ impl BlockCollection {
         &self,
         transaction_id: &TransactionId,
     ) -> Result<Option<Vec<u8>>, Error> {
-        Ok(self
-            .aggregate(
-                [
-                    doc! { "$match": {
-                        "metadata.inclusion_state": LedgerInclusionState::Included,
-                        "block.payload.transaction_id": transaction_id,
-                    } },
-                    doc! { "$project": { "raw": 1 } },
-                ],
-                None,
-            )
-            .await?
-            .try_next()
-            .await?
-            .map(|RawResult { raw }| raw))
+        self.aggregate(
+            [
+                doc! { "$match": {
+                    "metadata.inclusion_state": LedgerInclusionState::Included,
+                    "block.payload.transaction_id": transaction_id,
+                } },
+                doc! { "$project": { "raw": 1, "raw_compressed": 1 } },
+            ],
+            None,
+        )
+        .await?
+        .try_next()
+        .await?
+        .map(RawResult::into_raw)
+        .transpose()
     }
 
     /// Finds the [`BlockMetadata`] that included a transaction by [`TransactionId`].
@@ -389,12 +590,12 @@ impl BlockCollection {
                     "block.payload.essence.inputs.transaction_id": &output_id.transaction_id,
                     "block.payload.essence.inputs.index": &(output_id.index as i32)
                 } },
-                doc! { "$project": { "raw": 1 } },
+                doc! { "$project": { "raw": 1, "raw_compressed": 1 } },
             ],
             None,
         )
         .await?
-        .map_ok(|RawResult { raw }| iota_types::block::Block::unpack_unverified(raw).unwrap().into())
+        .and_then(|raw| async move { Ok(unpack_block(&raw.into_raw()?)?.into()) })
         .try_next()
         .await
     }
@@ -1,11 +1,7 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use mongodb::{
-    bson::doc,
-    error::Error,
-    options::{FindOneOptions, UpdateOptions},
-};
+use mongodb::{bson::doc, error::Error, options::FindOneOptions};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -70,10 +66,9 @@ impl ConfigurationUpdateCollection {
     ) -> Result<(), Error> {
         let node_config = self.get_node_configuration_for_ledger_index(ledger_index).await?;
         if !matches!(node_config, Some(node_config) if node_config.config == config) {
-            self.update_one(
+            self.upsert_one(
                 doc! { "_id": ledger_index },
                 doc! { "$set": mongodb::bson::to_bson(&config)? },
-                UpdateOptions::builder().upsert(true).build(),
             )
             .await?;
         }
@@ -0,0 +1,116 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, DateTime},
+    error::Error,
+    options::{CreateCollectionOptions, FindOptions},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{
+    mongodb::{MongoDbCollection, MongoDbCollectionExt},
+    MongoDb,
+};
+#[cfg(feature = "inx")]
+use crate::inx::InxError;
+
+/// The largest size of the dead-letter collection in bytes, after which the oldest documents are discarded.
+pub const DEAD_LETTER_MAX_SIZE: u64 = 16 * 1024 * 1024;
+/// The largest number of documents in the dead-letter collection, after which the oldest ones are discarded.
+pub const DEAD_LETTER_MAX_DOCUMENTS: u64 = 1000;
+
+/// Data that was received from the node but could not be decoded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetterDocument {
+    /// When the data was received.
+    pub timestamp: DateTime,
+    /// What the data describes, for example the id of a block.
+    pub subject: String,
+    /// Which part of the data could not be decoded.
+    pub stage: String,
+    /// Why the data could not be decoded.
+    pub error: String,
+    /// The bytes that could not be decoded, if they were transmitted as raw bytes.
+    #[serde(with = "serde_bytes")]
+    pub raw: Option<Vec<u8>>,
+}
+
+#[cfg(feature = "inx")]
+impl DeadLetterDocument {
+    /// Creates a document from a decoding error, or returns `None` if the error did not occur while decoding.
+    pub fn from_error(error: &InxError) -> Option<Self> {
+        match error {
+            InxError::Decode {
+                subject,
+                stage,
+                source,
+                raw,
+            } => Some(Self {
+                timestamp: DateTime::now(),
+                subject: subject.clone(),
+                stage: stage.to_string(),
+                error: source.to_string(),
+                raw: raw.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A capped collection of the data that could not be decoded, so that it can be inspected after the fact.
+pub struct DeadLetterCollection {
+    collection: mongodb::Collection<DeadLetterDocument>,
+}
+
+#[async_trait::async_trait]
+impl MongoDbCollection for DeadLetterCollection {
+    const NAME: &'static str = "stardust_dead_letters";
+    type Document = DeadLetterDocument;
+
+    fn instantiate(_db: &MongoDb, collection: mongodb::Collection<Self::Document>) -> Self {
+        Self { collection }
+    }
+
+    fn collection(&self) -> &mongodb::Collection<Self::Document> {
+        &self.collection
+    }
+
+    async fn create_collection(&self, db: &MongoDb) -> Result<(), Error> {
+        db.db()
+            .create_collection(
+                Self::NAME,
+                CreateCollectionOptions::builder()
+                    .capped(true)
+                    .size(DEAD_LETTER_MAX_SIZE)
+                    .max(DEAD_LETTER_MAX_DOCUMENTS)
+                    .build(),
+            )
+            .await
+            .ok();
+        Ok(())
+    }
+}
+
+impl DeadLetterCollection {
+    /// Records data that could not be decoded.
+    pub async fn insert_dead_letter(&self, dead_letter: &DeadLetterDocument) -> Result<(), Error> {
+        self.insert_one::<DeadLetterDocument>(dead_letter, None).await?;
+        Ok(())
+    }
+
+    /// Gets the most recently recorded data that could not be decoded, newest first.
+    pub async fn get_dead_letters(&self, limit: i64) -> Result<Vec<DeadLetterDocument>, Error> {
+        self.find::<DeadLetterDocument>(
+            doc! {},
+            FindOptions::builder()
+                .sort(doc! { "$natural": -1 })
+                .limit(limit)
+                .build(),
+        )
+        .await?
+        .try_collect()
+        .await
+    }
+}
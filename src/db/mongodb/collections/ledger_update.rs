@@ -1,6 +1,8 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::ops::RangeInclusive;
+
 use futures::{Stream, TryStreamExt};
 use mongodb::{
     bson::{doc, Document},
@@ -45,7 +47,6 @@ pub struct LedgerUpdateCollection {
     collection: mongodb::Collection<LedgerUpdateDocument>,
 }
 
-#[async_trait::async_trait]
 impl MongoDbCollection for LedgerUpdateCollection {
     const NAME: &'static str = "stardust_ledger_updates";
     type Document = LedgerUpdateDocument;
@@ -58,22 +59,16 @@ impl MongoDbCollection for LedgerUpdateCollection {
         &self.collection
     }
 
-    async fn create_indexes(&self) -> Result<(), Error> {
-        self.create_index(
-            IndexModel::builder()
-                .keys(newest())
-                .options(
-                    IndexOptions::builder()
-                        .unique(true)
-                        .name("ledger_update_index".to_string())
-                        .build(),
-                )
-                .build(),
-            None,
-        )
-        .await?;
-
-        Ok(())
+    fn indexes() -> Vec<IndexModel> {
+        vec![IndexModel::builder()
+            .keys(newest())
+            .options(
+                IndexOptions::builder()
+                    .unique(true)
+                    .name("ledger_update_index".to_string())
+                    .build(),
+            )
+            .build()]
     }
 }
 
@@ -165,6 +160,16 @@ impl LedgerUpdateCollection {
         Ok(())
     }
 
+    /// Removes the ledger updates of the milestones in `range`, and returns how many were removed.
+    #[instrument(skip_all, err, level = "trace")]
+    pub async fn revert_milestones(&self, range: RangeInclusive<MilestoneIndex>) -> Result<u64, Error> {
+        let (start, end) = range.into_inner();
+        Ok(self
+            .delete_many(doc! { "_id.milestone_index": { "$gte": start, "$lte": end } }, None)
+            .await?
+            .deleted_count)
+    }
+
     /// Streams updates to the ledger for a given address.
     pub async fn get_ledger_updates_by_address(
         &self,
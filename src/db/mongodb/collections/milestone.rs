@@ -5,7 +5,7 @@ use std::ops::RangeInclusive;
 
 use futures::{Stream, TryStreamExt};
 use mongodb::{
-    bson::doc,
+    bson::{doc, spec::BinarySubtype, Binary, Bson, Document},
     error::Error,
     options::{FindOneOptions, FindOptions, IndexOptions},
     IndexModel,
@@ -22,6 +22,7 @@ use crate::{
     model::{
         payload::{MilestoneId, MilestoneOption, MilestonePayload},
         tangle::{MilestoneIndex, MilestoneIndexTimestamp, MilestoneTimestamp},
+        BlockId,
     },
 };
 
@@ -38,6 +39,10 @@ pub struct MilestoneDocument {
     at: MilestoneIndexTimestamp,
     /// The milestone's payload.
     payload: MilestonePayload,
+    /// The block that carries the milestone payload. It is only known once the block is referenced by a later
+    /// milestone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    block_id: Option<BlockId>,
 }
 
 /// The stardust milestones collection.
@@ -45,7 +50,6 @@ pub struct MilestoneCollection {
     collection: mongodb::Collection<MilestoneDocument>,
 }
 
-#[async_trait::async_trait]
 impl MongoDbCollection for MilestoneCollection {
     const NAME: &'static str = "stardust_milestones";
     type Document = MilestoneDocument;
@@ -58,8 +62,8 @@ impl MongoDbCollection for MilestoneCollection {
         &self.collection
     }
 
-    async fn create_indexes(&self) -> Result<(), Error> {
-        self.create_index(
+    fn indexes() -> Vec<IndexModel> {
+        vec![
             IndexModel::builder()
                 .keys(doc! { "at.milestone_index": BY_OLDEST })
                 .options(
@@ -69,11 +73,6 @@ impl MongoDbCollection for MilestoneCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "at.milestone_timestamp": BY_OLDEST })
                 .options(
@@ -83,11 +82,7 @@ impl MongoDbCollection for MilestoneCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        Ok(())
+        ]
     }
 }
 
@@ -100,17 +95,66 @@ pub struct SyncData {
     pub gaps: Vec<RangeInclusive<MilestoneIndex>>,
 }
 
+/// A part of a [`MilestonePayload`] that can be left out when reading it. Parts that are left out are not sent by the
+/// database and read back empty.
+#[allow(missing_docs)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MilestonePayloadPart {
+    Parents,
+    Metadata,
+    Options,
+    Signatures,
+}
+
+impl MilestonePayloadPart {
+    fn path(self) -> &'static str {
+        match self {
+            Self::Parents => "essence.parents",
+            Self::Metadata => "essence.metadata",
+            Self::Options => "essence.options",
+            Self::Signatures => "signatures",
+        }
+    }
+
+    fn empty(self) -> Bson {
+        match self {
+            Self::Metadata => Bson::Binary(Binary {
+                subtype: BinarySubtype::Generic,
+                bytes: Vec::new(),
+            }),
+            _ => Bson::Array(Vec::new()),
+        }
+    }
+}
+
+/// Creates the stages that replace the milestone document with its payload, leaving out the `omitted` parts.
+fn payload_stages(omitted: &[MilestonePayloadPart]) -> Vec<Document> {
+    let mut stages = vec![doc! { "$replaceWith": "$payload" }];
+    if !omitted.is_empty() {
+        stages.push(
+            doc! { "$set": omitted.iter().map(|part| (part.path().to_string(), part.empty())).collect::<Document>() },
+        );
+    }
+    stages
+}
+
 impl MilestoneCollection {
     /// Gets the [`MilestonePayload`] of a milestone.
     pub async fn get_milestone_payload_by_id(
         &self,
         milestone_id: &MilestoneId,
+    ) -> Result<Option<MilestonePayload>, Error> {
+        self.get_partial_milestone_payload_by_id(milestone_id, &[]).await
+    }
+
+    /// Gets the [`MilestonePayload`] of a milestone without the `omitted` parts.
+    pub async fn get_partial_milestone_payload_by_id(
+        &self,
+        milestone_id: &MilestoneId,
+        omitted: &[MilestonePayloadPart],
     ) -> Result<Option<MilestonePayload>, Error> {
         self.aggregate(
-            [
-                doc! { "$match": { "_id": milestone_id } },
-                doc! { "$replaceWith": "$payload" },
-            ],
+            std::iter::once(doc! { "$match": { "_id": milestone_id } }).chain(payload_stages(omitted)),
             None,
         )
         .await?
@@ -120,11 +164,17 @@ impl MilestoneCollection {
 
     /// Gets [`MilestonePayload`] of a milestone by the [`MilestoneIndex`].
     pub async fn get_milestone_payload(&self, index: MilestoneIndex) -> Result<Option<MilestonePayload>, Error> {
+        self.get_partial_milestone_payload(index, &[]).await
+    }
+
+    /// Gets [`MilestonePayload`] of a milestone by the [`MilestoneIndex`] without the `omitted` parts.
+    pub async fn get_partial_milestone_payload(
+        &self,
+        index: MilestoneIndex,
+        omitted: &[MilestonePayloadPart],
+    ) -> Result<Option<MilestonePayload>, Error> {
         self.aggregate(
-            [
-                doc! { "$match": { "at.milestone_index": index } },
-                doc! { "$replaceWith": "$payload" },
-            ],
+            std::iter::once(doc! { "$match": { "at.milestone_index": index } }).chain(payload_stages(omitted)),
             None,
         )
         .await?
@@ -144,6 +194,7 @@ impl MilestoneCollection {
                      milestone_id,
                      at,
                      payload,
+                     ..
                  }| (milestone_id, at, payload),
             )
             .try_next()
@@ -192,7 +243,8 @@ impl MilestoneCollection {
             .map(|ts| ts.milestone_id))
     }
 
-    /// Inserts the information of a milestone into the database.
+    /// Inserts the information of a milestone into the database. Inserting the same milestone again leaves the
+    /// existing document unchanged, so that a milestone can be re-applied after an interrupted sync.
     #[instrument(skip(self, milestone_id, milestone_timestamp, payload), err, level = "trace")]
     pub async fn insert_milestone(
         &self,
@@ -201,20 +253,68 @@ impl MilestoneCollection {
         milestone_timestamp: MilestoneTimestamp,
         payload: MilestonePayload,
     ) -> Result<(), Error> {
-        let milestone_document = MilestoneDocument {
-            at: MilestoneIndexTimestamp {
-                milestone_index,
-                milestone_timestamp,
-            },
-            milestone_id,
-            payload,
+        let at = MilestoneIndexTimestamp {
+            milestone_index,
+            milestone_timestamp,
         };
 
-        self.insert_one(milestone_document, None).await?;
+        // The block id may already be linked, so it must not be overwritten.
+        self.upsert_one(
+            doc! { "_id": milestone_id },
+            doc! { "$set": {
+                "at": mongodb::bson::to_bson(&at)?,
+                "payload": mongodb::bson::to_bson(&payload)?,
+            } },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Whether a milestone was fully applied. The milestone is recorded after all of its other data, so its presence
+    /// marks that its ledger updates do not need to be applied again.
+    pub async fn is_milestone_applied(&self, index: MilestoneIndex) -> Result<bool, Error> {
+        Ok(self
+            .find_one::<Document>(
+                doc! { "at.milestone_index": index },
+                FindOneOptions::builder().projection(doc! { "_id": 1 }).build(),
+            )
+            .await?
+            .is_some())
+    }
 
+    /// Links milestones to the blocks that carry their payloads.
+    #[instrument(skip_all, err, level = "trace")]
+    pub async fn insert_milestone_block_ids(
+        &self,
+        block_ids: impl IntoIterator<Item = (MilestoneIndex, BlockId)>,
+    ) -> Result<(), Error> {
+        for (milestone_index, block_id) in block_ids {
+            self.update_one(
+                doc! { "at.milestone_index": milestone_index },
+                doc! { "$set": { "block_id": block_id } },
+                None,
+            )
+            .await?;
+        }
         Ok(())
     }
 
+    /// Gets the id of the block that carries the payload of a milestone by the [`MilestoneIndex`].
+    pub async fn get_milestone_block_id(&self, index: MilestoneIndex) -> Result<Option<BlockId>, Error> {
+        #[derive(Deserialize)]
+        struct BlockIdResult {
+            block_id: Option<BlockId>,
+        }
+        Ok(self
+            .find_one::<BlockIdResult>(
+                doc! { "at.milestone_index": index },
+                FindOneOptions::builder().projection(doc! { "block_id": 1 }).build(),
+            )
+            .await?
+            .and_then(|res| res.block_id))
+    }
+
     /// Find the starting milestone.
     pub async fn find_first_milestone(
         &self,
@@ -359,6 +459,8 @@ impl MilestoneCollection {
 pub struct MilestoneResult {
     pub milestone_id: MilestoneId,
     pub index: MilestoneIndex,
+    #[serde(default)]
+    pub block_id: Option<BlockId>,
 }
 
 impl MilestoneCollection {
@@ -389,7 +491,8 @@ impl MilestoneCollection {
                 doc! { "$limit": page_size as i64 },
                 doc! { "$project": {
                     "milestone_id": "$_id",
-                    "index": "$at.milestone_index"
+                    "index": "$at.milestone_index",
+                    "block_id": 1,
                 } },
             ],
             None,
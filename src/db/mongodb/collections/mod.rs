@@ -6,6 +6,8 @@ mod application_state;
 mod block;
 /// Module containing the node configuration collection.
 mod configuration_update;
+/// Module containing the collection of data that could not be decoded.
+mod dead_letter;
 /// Module containing the LedgerUpdate model.
 mod ledger_update;
 /// Module containing the Milestone document model.
@@ -22,17 +24,21 @@ use std::str::FromStr;
 use thiserror::Error;
 
 pub use self::{
-    application_state::{ApplicationStateCollection, MigrationVersion},
-    block::BlockCollection,
+    application_state::{ApplicationStateCollection, MigrationVersion, SkippedMilestones},
+    block::{BlockCollection, BlockStatus, BlockWithMetadataResult},
     configuration_update::ConfigurationUpdateCollection,
+    dead_letter::{DeadLetterCollection, DeadLetterDocument, DEAD_LETTER_MAX_DOCUMENTS, DEAD_LETTER_MAX_SIZE},
     ledger_update::{LedgerUpdateByAddressRecord, LedgerUpdateByMilestoneRecord, LedgerUpdateCollection},
-    milestone::{MilestoneCollection, MilestoneResult, SyncData},
+    milestone::{MilestoneCollection, MilestonePayloadPart, MilestoneResult, SyncData},
     outputs::{
-        AddressStat, AliasOutputsQuery, BasicOutputsQuery, DistributionStat, FoundryOutputsQuery, IndexedId,
-        NftOutputsQuery, OutputCollection, OutputMetadataResult, OutputWithMetadataResult, OutputsResult,
-        UtxoChangesResult,
+        default_amount_boundaries, AddressActivityCountResult, AddressActivityKindCount, AddressActivityResult,
+        AddressStat, AliasHistoryRecord, AliasOutputsQuery, AliasTransition, AmountBucket, AmountDistribution,
+        BasicOutputsQuery, ClaimedTokensResult, DistributionStat, FoundryOutputsQuery, FoundrySupplyResult, IndexedId,
+        LockedSupplyResult, NftHistoryRecord, NftOutputsQuery, OutputCollection, OutputHealth, OutputMetadataResult,
+        OutputPart, OutputWithMetadataResult, OutputsResult, StorageDepositAnalyticsResult, StorageDepositStat,
+        TotalSupplyResult, TransactionCountResult, UtxoChangesResult,
     },
-    protocol_update::ProtocolUpdateCollection,
+    protocol_update::{ProtocolUpdateCollection, ProtocolUpdateDocument},
     treasury::{TreasuryCollection, TreasuryResult},
 };
 use crate::model::utxo::{AliasOutput, BasicOutput, FoundryOutput, NftOutput, Output};
@@ -12,7 +12,7 @@ use futures::TryStreamExt;
 use mongodb::{
     bson::{self, doc, Bson},
     error::Error,
-    options::IndexOptions,
+    options::{CountOptions, IndexOptions},
     IndexModel,
 };
 use serde::{Deserialize, Serialize};
@@ -22,7 +22,7 @@ pub use self::{
 };
 use super::{OutputCollection, OutputDocument};
 use crate::{
-    db::mongodb::{collections::SortOrder, MongoDbCollectionExt},
+    db::mongodb::{collections::SortOrder, MongoDbCollection, MongoDbCollectionExt},
     model::{
         metadata::OutputMetadata,
         tangle::MilestoneIndex,
@@ -79,6 +79,17 @@ pub struct IndexedOutputResult {
     pub output_id: OutputId,
 }
 
+/// Restricts a query to outputs that were booked, and optionally not yet spent, at the given ledger index.
+fn ledger_queries(include_spent: bool, ledger_index: MilestoneIndex) -> Vec<bson::Document> {
+    let mut queries = vec![doc! { "metadata.booked.milestone_index": { "$lte": ledger_index } }];
+    if !include_spent {
+        queries.push(doc! {
+            "metadata.spent_metadata.spent.milestone_index": { "$not": { "$lte": ledger_index } }
+        });
+    }
+    queries
+}
+
 impl OutputCollection {
     /// Gets the current unspent indexed output id with the given indexed id.
     pub async fn get_indexed_output_by_id(
@@ -138,12 +149,7 @@ impl OutputCollection {
         };
 
         let query_doc = bson::Document::from(query);
-        let mut additional_queries = vec![doc! { "metadata.booked.milestone_index": { "$lte": ledger_index } }];
-        if !include_spent {
-            additional_queries.push(doc! {
-                "metadata.spent_metadata.spent.milestone_index": { "$not": { "$lte": ledger_index } }
-            });
-        }
+        let mut additional_queries = ledger_queries(include_spent, ledger_index);
         if let Some((start_ms, start_output_id)) = cursor {
             additional_queries.push(doc! { "$or": [
                 doc! { "metadata.booked.milestone_index": { cmp1: start_ms } },
@@ -178,18 +184,37 @@ impl OutputCollection {
         Ok(OutputsResult { outputs })
     }
 
-    /// Creates indexer output indexes.
-    pub async fn create_indexer_indexes(&self) -> Result<(), Error> {
-        self.create_index(
+    /// Counts the indexed outputs that match the provided query. Counting stops once `limit` outputs were found.
+    pub async fn count_indexed_outputs<Q>(
+        &self,
+        query: Q,
+        include_spent: bool,
+        ledger_index: MilestoneIndex,
+        limit: Option<u64>,
+    ) -> Result<u64, Error>
+    where
+        bson::Document: From<Q>,
+    {
+        self.collection()
+            .count_documents(
+                doc! {
+                    "$and": [
+                        bson::Document::from(query),
+                        { "$and": ledger_queries(include_spent, ledger_index) }
+                    ]
+                },
+                CountOptions::builder().limit(limit).build(),
+            )
+            .await
+    }
+
+    /// The indexes that support the indexer queries.
+    pub fn indexer_indexes() -> Vec<IndexModel> {
+        vec![
             IndexModel::builder()
                 .keys(doc! { "output.kind": 1 })
                 .options(IndexOptions::builder().name("output_kind_index".to_string()).build())
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "details.indexed_id": 1 })
                 .options(
@@ -201,11 +226,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "details.address": 1 })
                 .options(
@@ -217,11 +237,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "output.storage_deposit_return_unlock_condition.return_address": 1 })
                 .options(
@@ -233,11 +248,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "output.timelock_unlock_condition.timestamp": 1 })
                 .options(
@@ -249,11 +259,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "output.expiration_unlock_condition.return_address": 1 })
                 .options(
@@ -265,11 +270,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "output.expiration_unlock_condition.timestamp": 1 })
                 .options(
@@ -281,11 +281,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "output.governor_address_unlock_condition.address": 1 })
                 .options(
@@ -297,20 +292,10 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "output.features": 1 })
                 .options(IndexOptions::builder().name("output_feature_index".to_string()).build())
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "output.native_tokens": 1 })
                 .options(
@@ -319,11 +304,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "metadata.booked.milestone_index": -1 })
                 .options(
@@ -332,11 +312,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(
                     doc! { "metadata.spent_metadata.spent.milestone_index": -1, "metadata.booked.milestone_index": 1,  "details.address": 1 },
@@ -347,11 +322,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "metadata.booked.milestone_timestamp": -1 })
                 .options(
@@ -360,11 +330,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        self.create_index(
             IndexModel::builder()
                 .keys(doc! { "metadata.spent_metadata.spent.milestone_timestamp": -1 })
                 .options(
@@ -373,10 +338,6 @@ impl OutputCollection {
                         .build(),
                 )
                 .build(),
-            None,
-        )
-        .await?;
-
-        Ok(())
+        ]
     }
 }
@@ -3,15 +3,16 @@
 
 mod indexer;
 
-use std::borrow::Borrow;
+use std::{borrow::Borrow, ops::RangeInclusive};
 
 use futures::{Stream, TryStreamExt};
 use mongodb::{
-    bson::{doc, to_bson, to_document},
+    bson::{doc, spec::BinarySubtype, to_bson, to_document, Binary, Bson, Document},
     error::Error,
     options::{IndexOptions, InsertManyOptions},
     IndexModel,
 };
+use primitive_types::U256;
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
@@ -26,9 +27,12 @@ use crate::{
     model::{
         ledger::{LedgerOutput, LedgerSpent, RentStructureBytes},
         metadata::{OutputMetadata, SpentMetadata},
+        payload::TransactionId,
         tangle::{MilestoneIndex, MilestoneIndexTimestamp, MilestoneTimestamp},
-        utxo::{Address, AliasId, NftId, Output, OutputId},
-        BlockId,
+        utxo::{
+            Address, AliasId, AliasOutput, FoundryId, FoundryOutput, NftId, NftOutput, Output, OutputId, TokenScheme,
+        },
+        BlockId, RentStructure,
     },
 };
 
@@ -48,7 +52,6 @@ pub struct OutputCollection {
     collection: mongodb::Collection<OutputDocument>,
 }
 
-#[async_trait::async_trait]
 impl MongoDbCollection for OutputCollection {
     const NAME: &'static str = "stardust_outputs";
     type Document = OutputDocument;
@@ -64,24 +67,18 @@ impl MongoDbCollection for OutputCollection {
         &self.collection
     }
 
-    async fn create_indexes(&self) -> Result<(), Error> {
-        self.create_index(
-            IndexModel::builder()
-                .keys(doc! { "metadata.block_id": 1 })
-                .options(
-                    IndexOptions::builder()
-                        .unique(false)
-                        .name("metadata_block_id".to_string())
-                        .build(),
-                )
-                .build(),
-            None,
-        )
-        .await?;
-
-        self.create_indexer_indexes().await?;
-
-        Ok(())
+    fn indexes() -> Vec<IndexModel> {
+        let mut indexes = vec![IndexModel::builder()
+            .keys(doc! { "metadata.block_id": 1 })
+            .options(
+                IndexOptions::builder()
+                    .unique(false)
+                    .name("metadata_block_id".to_string())
+                    .build(),
+            )
+            .build()];
+        indexes.extend(Self::indexer_indexes());
+        indexes
     }
 }
 
@@ -155,6 +152,52 @@ pub struct OutputMetadataResult {
     pub spent_metadata: Option<SpentMetadata>,
 }
 
+/// An output that held an NFT, and the address that owned the NFT while the output was unspent.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct NftHistoryRecord {
+    pub output_id: OutputId,
+    pub address: Address,
+    pub booked: MilestoneIndexTimestamp,
+    /// `None` if the output was still unspent at the ledger index.
+    pub spent_metadata: Option<SpentMetadata>,
+}
+
+/// An output that held an alias, together with its state and the addresses that controlled it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct AliasHistoryRecord {
+    pub output_id: OutputId,
+    pub state_index: u32,
+    pub state_controller: Address,
+    pub governor: Address,
+    pub booked: MilestoneIndexTimestamp,
+    /// `None` if the output was still unspent at the ledger index.
+    pub spent_metadata: Option<SpentMetadata>,
+}
+
+/// The kind of transition that created an alias output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AliasTransition {
+    /// The output created the alias.
+    Creation,
+    /// The state controller changed the state of the alias, which increments its state index.
+    State,
+    /// The governor changed the controlling addresses or features of the alias, which keeps its state index.
+    Governance,
+}
+
+impl AliasHistoryRecord {
+    /// Determines the kind of transition that created this output from the `previous` output of the alias.
+    pub fn transition(&self, previous: Option<&Self>) -> AliasTransition {
+        match previous {
+            None => AliasTransition::Creation,
+            Some(previous) if self.state_index > previous.state_index => AliasTransition::State,
+            Some(_) => AliasTransition::Governance,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
 #[allow(missing_docs)]
 pub struct OutputWithMetadataResult {
@@ -162,6 +205,38 @@ pub struct OutputWithMetadataResult {
     pub metadata: OutputMetadataResult,
 }
 
+/// A part of an [`Output`] that can be left out when reading it. Parts that are left out are not sent by the database
+/// and read back empty.
+#[allow(missing_docs)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputPart {
+    NativeTokens,
+    StateMetadata,
+    Features,
+    ImmutableFeatures,
+}
+
+impl OutputPart {
+    fn path(self) -> &'static str {
+        match self {
+            Self::NativeTokens => "output.native_tokens",
+            Self::StateMetadata => "output.state_metadata",
+            Self::Features => "output.features",
+            Self::ImmutableFeatures => "output.immutable_features",
+        }
+    }
+
+    fn empty(self) -> Bson {
+        match self {
+            Self::StateMetadata => Bson::Binary(Binary {
+                subtype: BinarySubtype::Generic,
+                bytes: Vec::new(),
+            }),
+            _ => Bson::Array(Vec::new()),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[allow(missing_docs)]
 pub struct BalanceResult {
@@ -169,6 +244,98 @@ pub struct BalanceResult {
     pub sig_locked_balance: String,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct AddressActivityResult {
+    #[serde(rename = "_id")]
+    pub address: Address,
+    /// The number of outputs owned by the address that were created within the time range.
+    pub created_count: usize,
+    /// The number of outputs owned by the address that were consumed within the time range.
+    pub consumed_count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct ClaimedTokensResult {
+    pub count: usize,
+    pub amount: String,
+}
+
+impl Default for ClaimedTokensResult {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            amount: "0".to_string(),
+        }
+    }
+}
+
+/// The number of unspent outputs at a ledger index and the sum of their amounts.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct TotalSupplyResult {
+    pub count: usize,
+    pub amount: String,
+}
+
+impl Default for TotalSupplyResult {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            amount: "0".to_string(),
+        }
+    }
+}
+
+/// The sums of the amounts of the unspent outputs at a ledger index, split by whether a timelock still prevents them
+/// from being unlocked.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct LockedSupplyResult {
+    pub locked_amount: String,
+    pub unlocked_amount: String,
+}
+
+impl Default for LockedSupplyResult {
+    fn default() -> Self {
+        Self {
+            locked_amount: "0".to_string(),
+            unlocked_amount: "0".to_string(),
+        }
+    }
+}
+
+/// The token scheme of the foundry output that was unspent at a ledger index.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct FoundrySupplyResult {
+    pub output_id: OutputId,
+    pub token_scheme: TokenScheme,
+}
+
+impl FoundrySupplyResult {
+    /// The tokens that were minted and not melted yet.
+    pub fn circulating_supply(&self) -> U256 {
+        match self.token_scheme {
+            TokenScheme::Simple {
+                minted_tokens,
+                melted_tokens,
+                ..
+            } => U256::from(minted_tokens).saturating_sub(melted_tokens.into()),
+        }
+    }
+}
+
+/// The number of transactions that were booked within an interval of milestones.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionCountResult {
+    /// The first milestone of the interval.
+    pub start_index: MilestoneIndex,
+    /// The number of distinct transactions.
+    pub count: usize,
+}
+
 #[derive(Clone, Debug, Default, Deserialize)]
 #[allow(missing_docs)]
 pub struct UtxoChangesResult {
@@ -209,6 +376,47 @@ impl OutputCollection {
         Ok(())
     }
 
+    /// Marks all outputs that were spent at or after the given milestone as unspent again, and returns how many were
+    /// changed. Reverting the same milestone again has no further effect.
+    #[instrument(skip_all, err, level = "trace")]
+    pub async fn revert_spent_outputs(&self, index: MilestoneIndex) -> Result<u64, Error> {
+        Ok(self
+            .update_many(
+                doc! { "metadata.spent_metadata.spent.milestone_index": { "$gte": index } },
+                doc! { "$set": { "metadata.spent_metadata": null } },
+                None,
+            )
+            .await?
+            .modified_count)
+    }
+
+    /// Reverts the ledger updates of the milestones in `range`, and returns how many outputs were changed. Outputs that
+    /// were spent within the range are unspent again, and outputs that were created within the range are removed,
+    /// unless a later milestone spent them.
+    #[instrument(skip_all, err, level = "trace")]
+    pub async fn revert_milestones(&self, range: RangeInclusive<MilestoneIndex>) -> Result<u64, Error> {
+        let (start, end) = range.into_inner();
+        let reverted = self
+            .update_many(
+                doc! { "metadata.spent_metadata.spent.milestone_index": { "$gte": start, "$lte": end } },
+                doc! { "$set": { "metadata.spent_metadata": null } },
+                None,
+            )
+            .await?
+            .modified_count;
+        let removed = self
+            .delete_many(
+                doc! {
+                    "metadata.booked.milestone_index": { "$gte": start, "$lte": end },
+                    "metadata.spent_metadata": null,
+                },
+                None,
+            )
+            .await?
+            .deleted_count;
+        Ok(reverted + removed)
+    }
+
     /// Inserts [`Outputs`](crate::model::utxo::Output) with their
     /// [`OutputMetadata`](crate::model::metadata::OutputMetadata).
     #[instrument(skip_all, err, level = "trace")]
@@ -247,20 +455,58 @@ impl OutputCollection {
         output_id: &OutputId,
         ledger_index: MilestoneIndex,
     ) -> Result<Option<OutputWithMetadataResult>, Error> {
+        self.get_partial_output_with_metadata(output_id, ledger_index, &[])
+            .await
+    }
+
+    /// Get an [`Output`] without the `omitted` parts, with its [`OutputMetadata`] by [`OutputId`].
+    pub async fn get_partial_output_with_metadata(
+        &self,
+        output_id: &OutputId,
+        ledger_index: MilestoneIndex,
+        omitted: &[OutputPart],
+    ) -> Result<Option<OutputWithMetadataResult>, Error> {
+        let mut pipeline = vec![doc! { "$match": {
+            "_id": output_id,
+            "metadata.booked.milestone_index": { "$lte": ledger_index }
+        } }];
+        if !omitted.is_empty() {
+            pipeline.push(doc! { "$set": omitted.iter().map(|part| (part.path().to_string(), part.empty())).collect::<Document>() });
+        }
+        pipeline.push(doc! { "$project": {
+            "output": "$output",
+            "metadata": {
+                "output_id": "$_id",
+                "block_id": "$metadata.block_id",
+                "booked": "$metadata.booked",
+                "spent_metadata": "$metadata.spent_metadata",
+            },
+        } });
+        self.aggregate(pipeline, None).await?.try_next().await
+    }
+
+    /// Get an [`OutputMetadata`] by [`OutputId`] as of the ledger index. Outputs that were booked later are unknown, and
+    /// outputs that were spent later are still unspent.
+    pub async fn get_output_metadata(
+        &self,
+        output_id: &OutputId,
+        ledger_index: MilestoneIndex,
+    ) -> Result<Option<OutputMetadataResult>, Error> {
         self.aggregate(
             [
                 doc! { "$match": {
-                    "_id": output_id,
+                    "_id": &output_id,
                     "metadata.booked.milestone_index": { "$lte": ledger_index }
                 } },
                 doc! { "$project": {
-                    "output": "$output",
-                    "metadata": {
-                        "output_id": "$_id",
-                        "block_id": "$metadata.block_id",
-                        "booked": "$metadata.booked",
-                        "spent_metadata": "$metadata.spent_metadata",
-                    },
+                    "output_id": "$_id",
+                    "block_id": "$metadata.block_id",
+                    "booked": "$metadata.booked",
+                    "spent_metadata": { "$cond": [
+                        { "$lte": [ "$metadata.spent_metadata.spent.milestone_index", ledger_index ] },
+                        "$metadata.spent_metadata",
+                        "$$REMOVE",
+                    ] },
                 } },
             ],
             None,
@@ -270,16 +516,95 @@ impl OutputCollection {
         .await
     }
 
-    /// Get an [`OutputMetadata`] by [`OutputId`].
-    pub async fn get_output_metadata(
+    /// Gets the outputs that held an NFT as of the ledger index, from the one that minted it to the current one. Each
+    /// output is followed by the output that the transaction spending it created.
+    pub async fn get_nft_history(
         &self,
-        output_id: &OutputId,
+        nft_id: NftId,
         ledger_index: MilestoneIndex,
-    ) -> Result<Option<OutputMetadataResult>, Error> {
+    ) -> Result<Vec<NftHistoryRecord>, Error> {
+        let records = self
+            .aggregate::<NftHistoryRecord>(
+                [
+                    doc! { "$match": {
+                        "output.kind": NftOutput::KIND,
+                        "details.indexed_id": nft_id,
+                        "metadata.booked.milestone_index": { "$lte": ledger_index }
+                    } },
+                    doc! { "$sort": { "metadata.booked.milestone_index": 1 } },
+                    doc! { "$project": {
+                        "output_id": "$_id",
+                        "address": "$details.address",
+                        "booked": "$metadata.booked",
+                        "spent_metadata": { "$cond": [
+                            { "$lte": [ "$metadata.spent_metadata.spent.milestone_index", ledger_index ] },
+                            "$metadata.spent_metadata",
+                            "$$REMOVE",
+                        ] },
+                    } },
+                ],
+                None,
+            )
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(order_by_spending_transaction(records, |record| {
+            (&record.output_id, record.spent_metadata.as_ref())
+        }))
+    }
+
+    /// Gets the outputs that held an alias as of the ledger index, from the one that created it to the current one.
+    /// Each output is followed by the output that the transaction spending it created.
+    pub async fn get_alias_history(
+        &self,
+        alias_id: AliasId,
+        ledger_index: MilestoneIndex,
+    ) -> Result<Vec<AliasHistoryRecord>, Error> {
+        let records = self
+            .aggregate::<AliasHistoryRecord>(
+                [
+                    doc! { "$match": {
+                        "output.kind": AliasOutput::KIND,
+                        "details.indexed_id": alias_id,
+                        "metadata.booked.milestone_index": { "$lte": ledger_index }
+                    } },
+                    doc! { "$sort": { "metadata.booked.milestone_index": 1 } },
+                    doc! { "$project": {
+                        "output_id": "$_id",
+                        "state_index": "$output.state_index",
+                        "state_controller": "$output.state_controller_address_unlock_condition.address",
+                        "governor": "$output.governor_address_unlock_condition.address",
+                        "booked": "$metadata.booked",
+                        "spent_metadata": { "$cond": [
+                            { "$lte": [ "$metadata.spent_metadata.spent.milestone_index", ledger_index ] },
+                            "$metadata.spent_metadata",
+                            "$$REMOVE",
+                        ] },
+                    } },
+                ],
+                None,
+            )
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(order_by_spending_transaction(records, |record| {
+            (&record.output_id, record.spent_metadata.as_ref())
+        }))
+    }
+
+    /// Stream the [`OutputMetadataResult`]s of all given [`OutputId`]s that are known at the ledger index. Unknown ids
+    /// are skipped.
+    pub async fn get_output_metadata_by_ids(
+        &self,
+        output_ids: &[OutputId],
+        ledger_index: MilestoneIndex,
+    ) -> Result<impl Stream<Item = Result<OutputMetadataResult, Error>>, Error> {
         self.aggregate(
             [
                 doc! { "$match": {
-                    "_id": &output_id,
+                    "_id": { "$in": output_ids },
                     "metadata.booked.milestone_index": { "$lte": ledger_index }
                 } },
                 doc! { "$project": {
@@ -291,8 +616,6 @@ impl OutputCollection {
             ],
             None,
         )
-        .await?
-        .try_next()
         .await
     }
 
@@ -320,6 +643,107 @@ impl OutputCollection {
         .await
     }
 
+    /// Stream the [`OutputId`]s of all outputs of the given kind that were unspent at a given ledger index, ordered by
+    /// [`OutputId`] and starting at the optional `cursor`.
+    pub async fn get_unspent_output_ids_by_kind(
+        &self,
+        kind: &str,
+        ledger_index: MilestoneIndex,
+        page_size: usize,
+        cursor: Option<OutputId>,
+    ) -> Result<impl Stream<Item = Result<OutputId, Error>>, Error> {
+        let mut match_doc = doc! {
+            "output.kind": kind,
+            "metadata.booked.milestone_index": { "$lte": ledger_index },
+            "metadata.spent_metadata.spent.milestone_index": { "$not": { "$lte": ledger_index } }
+        };
+        if let Some(output_id) = cursor {
+            match_doc.insert("_id", doc! { "$gte": output_id });
+        }
+        self.aggregate(
+            [
+                doc! { "$match": match_doc },
+                doc! { "$sort": { "_id": 1 } },
+                doc! { "$limit": page_size as i64 },
+                doc! { "$replaceWith": "$_id" },
+            ],
+            None,
+        )
+        .await
+    }
+
+    /// Stream the outputs, with their metadata, whose address unlock condition is the given [`Address`] as of the
+    /// ledger index, ordered by [`OutputId`] and starting at the optional `cursor`. Outputs that were spent at the
+    /// ledger index are only included if `include_spent` is set.
+    pub async fn get_outputs_for_address(
+        &self,
+        address: &Address,
+        include_spent: bool,
+        ledger_index: MilestoneIndex,
+        page_size: usize,
+        cursor: Option<OutputId>,
+    ) -> Result<impl Stream<Item = Result<OutputWithMetadataResult, Error>>, Error> {
+        let mut match_doc = doc! {
+            "details.address": address,
+            "metadata.booked.milestone_index": { "$lte": ledger_index },
+        };
+        if !include_spent {
+            match_doc.insert(
+                "metadata.spent_metadata.spent.milestone_index",
+                doc! { "$not": { "$lte": ledger_index } },
+            );
+        }
+        if let Some(output_id) = cursor {
+            match_doc.insert("_id", doc! { "$gte": output_id });
+        }
+        self.aggregate(
+            [
+                doc! { "$match": match_doc },
+                doc! { "$sort": { "_id": 1 } },
+                doc! { "$limit": page_size as i64 },
+                doc! { "$project": {
+                    "output": "$output",
+                    "metadata": {
+                        "output_id": "$_id",
+                        "block_id": "$metadata.block_id",
+                        "booked": "$metadata.booked",
+                        // An output that was spent after the ledger index is still unspent as of it.
+                        "spent_metadata": { "$cond": [
+                            { "$lte": [ "$metadata.spent_metadata.spent.milestone_index", ledger_index ] },
+                            "$metadata.spent_metadata",
+                            null,
+                        ] },
+                    },
+                } },
+            ],
+            None,
+        )
+        .await
+    }
+
+    /// Stream the [`OutputId`]s of all genesis outputs (those booked at milestone `0`), ordered by [`OutputId`] and
+    /// starting at the optional `cursor`.
+    pub async fn get_genesis_output_ids(
+        &self,
+        page_size: usize,
+        cursor: Option<OutputId>,
+    ) -> Result<impl Stream<Item = Result<OutputId, Error>>, Error> {
+        let mut match_doc = doc! { "metadata.booked.milestone_index": 0 };
+        if let Some(output_id) = cursor {
+            match_doc.insert("_id", doc! { "$gte": output_id });
+        }
+        self.aggregate(
+            [
+                doc! { "$match": match_doc },
+                doc! { "$sort": { "_id": 1 } },
+                doc! { "$limit": page_size as i64 },
+                doc! { "$replaceWith": "$_id" },
+            ],
+            None,
+        )
+        .await
+    }
+
     /// Get all created [`LedgerOutput`]s for the given milestone.
     pub async fn get_created_outputs(
         &self,
@@ -343,6 +767,32 @@ impl OutputCollection {
         .await
     }
 
+    /// Streams all [`LedgerOutput`]s that were created from `start_index` to `end_index` (inclusive), ordered by the
+    /// milestone that booked them and then by [`OutputId`].
+    pub async fn get_outputs_in_milestone_range(
+        &self,
+        start_index: MilestoneIndex,
+        end_index: MilestoneIndex,
+    ) -> Result<impl Stream<Item = Result<LedgerOutput, Error>>, Error> {
+        self.aggregate(
+            [
+                doc! { "$match": {
+                    "metadata.booked.milestone_index": { "$gte": start_index, "$lte": end_index }
+                } },
+                doc! { "$sort": { "metadata.booked.milestone_index": 1, "_id": 1 } },
+                doc! { "$project": {
+                    "output_id": "$_id",
+                    "block_id": "$metadata.block_id",
+                    "booked": "$metadata.booked",
+                    "output": "$output",
+                    "rent_structure": "$details.rent_structure",
+                } },
+            ],
+            None,
+        )
+        .await
+    }
+
     /// Get all consumed [`LedgerSpent`]s for the given milestone.
     pub async fn get_consumed_outputs(
         &self,
@@ -416,6 +866,26 @@ impl OutputCollection {
         .await
     }
 
+    /// Gets the distinct milestones at which the outputs created by a transaction were booked, ordered by milestone
+    /// index. For a consistent ledger this contains at most a single entry.
+    pub async fn get_transaction_booked_milestones(
+        &self,
+        transaction_id: &TransactionId,
+    ) -> Result<Vec<MilestoneIndexTimestamp>, Error> {
+        self.aggregate(
+            [
+                doc! { "$match": { "_id.transaction_id": transaction_id } },
+                doc! { "$group": { "_id": "$metadata.booked" } },
+                doc! { "$sort": { "_id.milestone_index": 1 } },
+                doc! { "$replaceWith": "$_id" },
+            ],
+            None,
+        )
+        .await?
+        .try_collect()
+        .await
+    }
+
     /// Sums the amounts of all outputs owned by the given [`Address`](crate::model::utxo::Address).
     pub async fn get_address_balance(
         &self,
@@ -450,6 +920,46 @@ impl OutputCollection {
             .await
     }
 
+    /// Sums the genesis outputs (those booked at milestone `0`) that were claimed up to and including the given
+    /// milestone. The result can optionally be restricted to a single owning address or output kind.
+    pub async fn get_claimed_tokens(
+        &self,
+        index: MilestoneIndex,
+        address: Option<Address>,
+        kind: Option<&str>,
+    ) -> Result<ClaimedTokensResult, Error> {
+        let mut match_doc = doc! {
+            "metadata.booked.milestone_index": 0,
+            "metadata.spent_metadata.spent.milestone_index": { "$lte": index },
+        };
+        if let Some(address) = address {
+            match_doc.insert("details.address", address);
+        }
+        if let Some(kind) = kind {
+            match_doc.insert("output.kind", kind);
+        }
+        Ok(self
+            .aggregate(
+                [
+                    doc! { "$match": match_doc },
+                    doc! { "$group": {
+                        "_id": null,
+                        "count": { "$sum": 1 },
+                        "amount": { "$sum": { "$toDecimal": "$output.amount" } },
+                    } },
+                    doc! { "$project": {
+                        "count": 1,
+                        "amount": { "$toString": "$amount" },
+                    } },
+                ],
+                None,
+            )
+            .await?
+            .try_next()
+            .await?
+            .unwrap_or_default())
+    }
+
     /// Returns the changes to the UTXO ledger (as consumed and created output ids) that were applied at the given
     /// `index`. It returns `None` if the provided `index` is out of bounds (beyond Chronicle's ledger index). If
     /// the associated milestone did not perform any changes to the ledger, the returned `Vec`s will be empty.
@@ -496,17 +1006,26 @@ impl OutputCollection {
         &self,
         start_date: time::Date,
         end_date: time::Date,
+    ) -> Result<usize, Error> {
+        self.get_address_activity_count(
+            MilestoneTimestamp::from(start_date.midnight().assume_utc()),
+            MilestoneTimestamp::from(end_date.midnight().assume_utc()),
+        )
+        .await
+    }
+
+    /// Get the number of addresses that owned an output which was created or consumed from `start_timestamp`
+    /// (inclusive) to `end_timestamp` (exclusive).
+    pub async fn get_address_activity_count(
+        &self,
+        start_timestamp: MilestoneTimestamp,
+        end_timestamp: MilestoneTimestamp,
     ) -> Result<usize, Error> {
         #[derive(Deserialize)]
         struct Res {
             count: usize,
         }
 
-        let (start_timestamp, end_timestamp) = (
-            MilestoneTimestamp::from(start_date.midnight().assume_utc()),
-            MilestoneTimestamp::from(end_date.midnight().assume_utc()),
-        );
-
         Ok(self
             .aggregate::<Res>(
                 [
@@ -536,6 +1055,101 @@ impl OutputCollection {
             .await?
             .unwrap_or_default())
     }
+
+    /// Like [`get_address_activity_count`](Self::get_address_activity_count), but also counts the active addresses by
+    /// the kind of the outputs that they owned. An address that owned several outputs of the same kind is only counted
+    /// once for that kind, so the counts per kind can add up to more than the total if an address owned several kinds.
+    pub async fn get_address_activity_count_by_kind(
+        &self,
+        start_timestamp: MilestoneTimestamp,
+        end_timestamp: MilestoneTimestamp,
+    ) -> Result<AddressActivityCountResult, Error> {
+        Ok(self
+            .aggregate(
+                [
+                    doc! { "$match": { "$or": [
+                        { "metadata.booked.milestone_timestamp": {
+                            "$gte": start_timestamp,
+                            "$lt": end_timestamp
+                        } },
+                        { "metadata.spent_metadata.spent.milestone_timestamp": {
+                            "$gte": start_timestamp,
+                            "$lt": end_timestamp
+                        } },
+                    ] } },
+                    doc! { "$group": {
+                        "_id": { "address": "$details.address", "kind": "$output.kind" },
+                    } },
+                    doc! { "$facet": {
+                        "total": [
+                            { "$group": { "_id": "$_id.address" } },
+                            { "$count": "count" },
+                        ],
+                        "by_kind": [
+                            { "$group": { "_id": "$_id.kind", "count": { "$sum": 1 } } },
+                            { "$sort": { "_id": 1 } },
+                            { "$project": { "_id": 0, "kind": "$_id", "count": 1 } },
+                        ],
+                    } },
+                    doc! { "$project": {
+                        "count": { "$ifNull": [ { "$arrayElemAt": [ "$total.count", 0 ] }, 0 ] },
+                        "by_kind": 1,
+                    } },
+                ],
+                None,
+            )
+            .await?
+            .try_next()
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Stream the number of outputs that each address owned and that were created or consumed from `start_timestamp`
+    /// (inclusive) to `end_timestamp` (exclusive), ordered by [`Address`] and starting at the optional `cursor`.
+    pub async fn get_address_activity(
+        &self,
+        start_timestamp: MilestoneTimestamp,
+        end_timestamp: MilestoneTimestamp,
+        page_size: usize,
+        cursor: Option<Address>,
+    ) -> Result<impl Stream<Item = Result<AddressActivityResult, Error>>, Error> {
+        let in_range = |field: &str| {
+            doc! { "$and": [
+                { "$gte": [ field, start_timestamp ] },
+                { "$lt": [ field, end_timestamp ] },
+            ] }
+        };
+        let mut pipeline = vec![
+            doc! { "$match": {
+                "details.address": { "$ne": null },
+                "$or": [
+                    { "metadata.booked.milestone_timestamp": {
+                        "$gte": start_timestamp,
+                        "$lt": end_timestamp
+                    } },
+                    { "metadata.spent_metadata.spent.milestone_timestamp": {
+                        "$gte": start_timestamp,
+                        "$lt": end_timestamp
+                    } },
+                ],
+            } },
+            doc! { "$group": {
+                "_id": "$details.address",
+                "created_count": { "$sum": {
+                    "$cond": [ in_range("$metadata.booked.milestone_timestamp"), 1, 0 ]
+                } },
+                "consumed_count": { "$sum": {
+                    "$cond": [ in_range("$metadata.spent_metadata.spent.milestone_timestamp"), 1, 0 ]
+                } },
+            } },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+        if let Some(address) = cursor {
+            pipeline.push(doc! { "$match": { "_id": { "$gte": address } } });
+        }
+        pipeline.push(doc! { "$limit": page_size as i64 });
+        self.aggregate(pipeline, None).await
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -566,6 +1180,89 @@ pub struct DistributionStat {
     pub total_balance: String,
 }
 
+/// Returns the default boundaries of the amount distribution, which are the powers of ten from 10 up to 10^16.
+pub fn default_amount_boundaries() -> Vec<u64> {
+    (1..=16).map(|exp| 10_u64.pow(exp)).collect()
+}
+
+/// The number and value of the unspent outputs whose amounts fall into a range.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmountBucket {
+    /// The smallest amount in the bucket.
+    pub start: u64,
+    /// The amount at which the next bucket starts, or `None` for the last bucket, which has no upper bound.
+    pub end: Option<u64>,
+    /// The number of outputs in the bucket.
+    pub output_count: u64,
+    /// The summed amount of the outputs in the bucket.
+    pub total_amount: String,
+}
+
+/// The unspent outputs, grouped by their amounts.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmountDistribution {
+    /// The buckets in ascending order, including the empty ones.
+    pub buckets: Vec<AmountBucket>,
+}
+
+/// Counts used to check the consistency of the output collection.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct OutputHealth {
+    /// The number of stored outputs.
+    pub total: usize,
+    /// The number of outputs that were spent.
+    pub spent: usize,
+    /// The number of outputs that were spent at a milestone before the one that booked them, which indicates an
+    /// ingestion bug.
+    pub spent_before_booked: usize,
+}
+
+/// The rent-relevant bytes of all unspent outputs of one kind.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct StorageDepositStat {
+    pub kind: String,
+    pub count: usize,
+    pub key_bytes: u64,
+    pub data_bytes: u64,
+}
+
+impl StorageDepositStat {
+    /// Computes the storage deposit that is locked by these bytes under the given rent structure.
+    pub fn storage_deposit(&self, rent_structure: &RentStructure) -> u64 {
+        rent_structure.v_byte_cost as u64
+            * (self.key_bytes * rent_structure.v_byte_factor_key as u64
+                + self.data_bytes * rent_structure.v_byte_factor_data as u64)
+    }
+}
+
+/// The number of active addresses, in total and by the kind of the outputs that they owned.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct AddressActivityCountResult {
+    pub count: usize,
+    /// Kinds that no active address owned are omitted.
+    pub by_kind: Vec<AddressActivityKindCount>,
+}
+
+/// The number of active addresses that owned outputs of one kind.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[allow(missing_docs)]
+pub struct AddressActivityKindCount {
+    pub kind: String,
+    pub count: usize,
+}
+
+/// The rent-relevant bytes of all unspent outputs, and the storage deposit they require.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub struct StorageDepositAnalyticsResult {
+    pub output_count: usize,
+    pub key_bytes: u64,
+    pub data_bytes: u64,
+    pub storage_deposit: u64,
+}
+
 impl OutputCollection {
     /// Create richest address statistics.
     pub async fn get_richest_addresses(
@@ -600,6 +1297,141 @@ impl OutputCollection {
         Ok(RichestAddresses { top })
     }
 
+    /// Sums the amounts of all outputs that were unspent at the ledger index. The sum is computed as a decimal, so
+    /// that it cannot overflow even if the data is inconsistent.
+    pub async fn get_total_supply(&self, ledger_index: MilestoneIndex) -> Result<TotalSupplyResult, Error> {
+        Ok(self
+            .aggregate(
+                [
+                    doc! { "$match": {
+                        "metadata.booked.milestone_index": { "$lte": ledger_index },
+                        "metadata.spent_metadata.spent.milestone_index": { "$not": { "$lte": ledger_index } }
+                    } },
+                    doc! { "$group": {
+                        "_id": null,
+                        "count": { "$sum": 1 },
+                        "amount": { "$sum": { "$toDecimal": "$output.amount" } },
+                    } },
+                    doc! { "$project": {
+                        "count": 1,
+                        "amount": { "$toString": "$amount" },
+                    } },
+                ],
+                None,
+            )
+            .await?
+            .try_next()
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Splits the amounts of all outputs that were unspent at the ledger index by whether their timelock expires after
+    /// the ledger timestamp. Outputs without a timelock are unlocked.
+    pub async fn get_locked_supply(
+        &self,
+        ledger_index: MilestoneIndex,
+        ledger_timestamp: MilestoneTimestamp,
+    ) -> Result<LockedSupplyResult, Error> {
+        Ok(self
+            .aggregate(
+                [
+                    doc! { "$match": {
+                        "metadata.booked.milestone_index": { "$lte": ledger_index },
+                        "metadata.spent_metadata.spent.milestone_index": { "$not": { "$lte": ledger_index } }
+                    } },
+                    // A missing timelock compares less than any timestamp.
+                    doc! { "$set": { "is_locked": {
+                        "$gt": [ "$output.timelock_unlock_condition.timestamp", ledger_timestamp ]
+                    } } },
+                    doc! { "$group": {
+                        "_id": null,
+                        "locked_amount": { "$sum": {
+                            "$cond": [ "$is_locked", { "$toDecimal": "$output.amount" }, 0 ]
+                        } },
+                        "unlocked_amount": { "$sum": {
+                            "$cond": [ "$is_locked", 0, { "$toDecimal": "$output.amount" } ]
+                        } },
+                    } },
+                    doc! { "$project": {
+                        "locked_amount": { "$toString": "$locked_amount" },
+                        "unlocked_amount": { "$toString": "$unlocked_amount" },
+                    } },
+                ],
+                None,
+            )
+            .await?
+            .try_next()
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Gets the token scheme of a foundry at the ledger index, or `None` if the foundry did not exist at that time.
+    pub async fn get_foundry_supply(
+        &self,
+        foundry_id: FoundryId,
+        ledger_index: MilestoneIndex,
+    ) -> Result<Option<FoundrySupplyResult>, Error> {
+        self.aggregate(
+            [
+                doc! { "$match": {
+                    "output.kind": FoundryOutput::KIND,
+                    "details.indexed_id": foundry_id,
+                    "metadata.booked.milestone_index": { "$lte": ledger_index },
+                    "metadata.spent_metadata.spent.milestone_index": { "$not": { "$lte": ledger_index } }
+                } },
+                doc! { "$project": {
+                    "output_id": "$_id",
+                    "token_scheme": "$output.token_scheme",
+                } },
+            ],
+            None,
+        )
+        .await?
+        .try_next()
+        .await
+    }
+
+    /// Counts the distinct transactions that booked outputs from `start_index` to `end_index` (inclusive), grouped into
+    /// intervals of `interval` milestones that begin at `start_index`. A transaction is counted in the interval of the
+    /// milestone that booked its outputs. Intervals without transactions are omitted.
+    pub async fn get_transaction_counts(
+        &self,
+        start_index: MilestoneIndex,
+        end_index: MilestoneIndex,
+        interval: u32,
+    ) -> Result<Vec<TransactionCountResult>, Error> {
+        self.aggregate(
+            [
+                doc! { "$match": {
+                    "metadata.booked.milestone_index": { "$gte": start_index, "$lte": end_index }
+                } },
+                doc! { "$group": {
+                    "_id": {
+                        "start_index": { "$subtract": [
+                            "$metadata.booked.milestone_index",
+                            { "$mod": [ { "$subtract": [ "$metadata.booked.milestone_index", start_index ] }, interval ] },
+                        ] },
+                        "transaction_id": "$_id.transaction_id",
+                    },
+                } },
+                doc! { "$group": {
+                    "_id": "$_id.start_index",
+                    "count": { "$sum": 1 },
+                } },
+                doc! { "$sort": { "_id": 1 } },
+                doc! { "$project": {
+                    "_id": 0,
+                    "start_index": "$_id",
+                    "count": 1,
+                } },
+            ],
+            None,
+        )
+        .await?
+        .try_collect()
+        .await
+    }
+
     /// Create token distribution statistics.
     pub async fn get_token_distribution(&self, ledger_index: MilestoneIndex) -> Result<TokenDistribution, Error> {
         let distribution = self
@@ -634,4 +1466,203 @@ impl OutputCollection {
             .await?;
         Ok(TokenDistribution { distribution })
     }
+
+    /// Groups the outputs that were unspent at the ledger index by their amounts. The `boundaries` must be strictly
+    /// ascending, greater than zero and no larger than [`i64::MAX`]. They split the amounts into the buckets
+    /// `[0, b0)`, `[b0, b1)`, up to `[bn, ∞)`, so an amount that equals a boundary is counted in the bucket that
+    /// starts there.
+    pub async fn get_amount_distribution(
+        &self,
+        ledger_index: MilestoneIndex,
+        boundaries: &[u64],
+    ) -> Result<AmountDistribution, Error> {
+        #[derive(Deserialize)]
+        struct BucketResult {
+            #[serde(rename = "_id")]
+            start: i64,
+            output_count: u64,
+            total_amount: String,
+        }
+
+        let starts = std::iter::once(0).chain(boundaries.iter().copied()).collect::<Vec<_>>();
+        let output = doc! {
+            "output_count": { "$sum": 1 },
+            "total_amount": { "$sum": { "$toDecimal": "$output.amount" } },
+        };
+        let grouping = match boundaries.last() {
+            // Amounts that are not below any boundary end up in the default bucket, which is identified by the last
+            // boundary so that it reads like the others.
+            Some(&last) => doc! { "$bucket": {
+                "groupBy": { "$toDecimal": "$output.amount" },
+                "boundaries": starts.iter().map(|&start| start as i64).collect::<Vec<_>>(),
+                "default": last as i64,
+                "output": output,
+            } },
+            // `$bucket` needs at least two boundaries, so a single bucket is a plain group.
+            None => {
+                let mut group = doc! { "_id": 0_i64 };
+                group.extend(output);
+                doc! { "$group": group }
+            }
+        };
+
+        let mut results = self
+            .aggregate::<BucketResult>(
+                [
+                    doc! { "$match": {
+                        "metadata.booked.milestone_index": { "$lte": ledger_index },
+                        "metadata.spent_metadata.spent.milestone_index": { "$not": { "$lte": ledger_index } }
+                    } },
+                    grouping,
+                    doc! { "$project": {
+                        "output_count": 1,
+                        "total_amount": { "$toString": "$total_amount" },
+                    } },
+                ],
+                None,
+            )
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .map(|res| (res.start as u64, res))
+            .collect::<std::collections::HashMap<_, _>>();
+
+        let buckets = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let res = results.remove(&start);
+                AmountBucket {
+                    start,
+                    end: starts.get(i + 1).copied(),
+                    output_count: res.as_ref().map_or(0, |res| res.output_count),
+                    total_amount: res.map_or_else(|| "0".to_string(), |res| res.total_amount),
+                }
+            })
+            .collect();
+        Ok(AmountDistribution { buckets })
+    }
+
+    /// Counts the outputs that were unspent at the ledger index, without reading them.
+    pub async fn count_unspent_outputs(&self, ledger_index: MilestoneIndex) -> Result<u64, Error> {
+        self.collection()
+            .count_documents(
+                doc! {
+                    "metadata.booked.milestone_index": { "$lte": ledger_index },
+                    "metadata.spent_metadata.spent.milestone_index": { "$not": { "$lte": ledger_index } }
+                },
+                None,
+            )
+            .await
+    }
+
+    /// Sums the rent-relevant bytes of the outputs that are unspent at the given ledger index, grouped by output kind.
+    /// Kinds without any unspent outputs are omitted.
+    pub async fn get_storage_deposit_by_kind(
+        &self,
+        ledger_index: MilestoneIndex,
+    ) -> Result<Vec<StorageDepositStat>, Error> {
+        self.aggregate(
+            [
+                doc! { "$match": {
+                    "metadata.booked.milestone_index": { "$lte": ledger_index },
+                    "metadata.spent_metadata.spent.milestone_index": { "$not": { "$lte": ledger_index } }
+                } },
+                doc! { "$group" : {
+                    "_id": "$output.kind",
+                    "count": { "$sum": 1 },
+                    "key_bytes": { "$sum": "$details.rent_structure.num_key_bytes" },
+                    "data_bytes": { "$sum": "$details.rent_structure.num_data_bytes" },
+                } },
+                doc! { "$sort": { "_id": 1 } },
+                doc! { "$project": {
+                    "_id": 0,
+                    "kind": "$_id",
+                    "count": 1,
+                    "key_bytes": 1,
+                    "data_bytes": 1,
+                } },
+            ],
+            None,
+        )
+        .await?
+        .try_collect()
+        .await
+    }
+
+    /// Sums the rent-relevant bytes of the outputs that are unspent at the given ledger index, and computes the storage
+    /// deposit they require under the given rent structure, which should be the one in effect at that ledger index.
+    pub async fn get_storage_deposit_analytics(
+        &self,
+        ledger_index: MilestoneIndex,
+        rent_structure: &RentStructure,
+    ) -> Result<StorageDepositAnalyticsResult, Error> {
+        Ok(self.get_storage_deposit_by_kind(ledger_index).await?.into_iter().fold(
+            StorageDepositAnalyticsResult::default(),
+            |mut res, stat| {
+                res.output_count += stat.count;
+                res.key_bytes += stat.key_bytes;
+                res.data_bytes += stat.data_bytes;
+                res.storage_deposit += stat.storage_deposit(rent_structure);
+                res
+            },
+        ))
+    }
+
+    /// Counts the spent outputs and the outputs whose spent milestone precedes their booked milestone.
+    pub async fn get_output_health(&self) -> Result<OutputHealth, Error> {
+        Ok(self
+            .aggregate(
+                [doc! { "$group": {
+                    "_id": null,
+                    "total": { "$sum": 1 },
+                    "spent": { "$sum": {
+                        "$cond": [ { "$gt": [ "$metadata.spent_metadata", null ] }, 1, 0 ]
+                    } },
+                    "spent_before_booked": { "$sum": {
+                        "$cond": [
+                            { "$and": [
+                                { "$gt": [ "$metadata.spent_metadata", null ] },
+                                { "$lt": [
+                                    "$metadata.spent_metadata.spent.milestone_index",
+                                    "$metadata.booked.milestone_index"
+                                ] },
+                            ] },
+                            1,
+                            0
+                        ]
+                    } },
+                } }],
+                None,
+            )
+            .await?
+            .try_next()
+            .await?
+            .unwrap_or_default())
+    }
+}
+
+/// Orders the outputs of a chain, such as those of an NFT or an alias, so that each output is followed by the output
+/// that the transaction spending it created. A chain can move several times within a milestone, so the order of the
+/// booking milestones is not enough. The `records` must be sorted by booking milestone, and the first one starts the
+/// chain.
+fn order_by_spending_transaction<T>(
+    mut records: Vec<T>,
+    chain: impl Fn(&T) -> (&OutputId, Option<&SpentMetadata>),
+) -> Vec<T> {
+    let mut ordered: Vec<T> = Vec::with_capacity(records.len());
+    while !records.is_empty() {
+        let next = ordered
+            .last()
+            .and_then(|previous| chain(previous).1)
+            .and_then(|spent| {
+                records
+                    .iter()
+                    .position(|record| chain(record).0.transaction_id == spent.transaction_id)
+            })
+            .unwrap_or(0);
+        ordered.push(records.remove(next));
+    }
+    ordered
 }
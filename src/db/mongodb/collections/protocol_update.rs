@@ -1,11 +1,7 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use mongodb::{
-    bson::doc,
-    error::Error,
-    options::{FindOneOptions, UpdateOptions},
-};
+use mongodb::{bson::doc, error::Error, options::FindOneOptions};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -85,12 +81,11 @@ impl ProtocolUpdateCollection {
     ) -> Result<(), Error> {
         let params = self.get_protocol_parameters_for_ledger_index(ledger_index).await?;
         if !matches!(params, Some(params) if params.parameters == parameters) {
-            self.update_one(
+            self.upsert_one(
                 doc! { "_id": ledger_index },
                 doc! { "$set": {
                     "parameters": mongodb::bson::to_bson(&parameters)?
                 } },
-                UpdateOptions::builder().upsert(true).build(),
             )
             .await?;
         }
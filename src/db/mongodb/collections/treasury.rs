@@ -1,6 +1,8 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::ops::RangeInclusive;
+
 use mongodb::{
     bson::doc,
     error::Error,
@@ -93,6 +95,16 @@ impl TreasuryCollection {
         Ok(())
     }
 
+    /// Removes the treasury updates of the milestones in `range`, and returns how many were removed.
+    #[instrument(skip_all, err, level = "trace")]
+    pub async fn revert_milestones(&self, range: RangeInclusive<MilestoneIndex>) -> Result<u64, Error> {
+        let (start, end) = range.into_inner();
+        Ok(self
+            .delete_many(doc! { "_id": { "$gte": start, "$lte": end } }, None)
+            .await?
+            .deleted_count)
+    }
+
     /// Returns the current state of the treasury.
     pub async fn get_latest_treasury(&self) -> Result<Option<TreasuryResult>, Error> {
         self.find_one(doc! {}, FindOneOptions::builder().sort(doc! { "_id": -1 }).build())
@@ -3,15 +3,185 @@
 
 //! Holds the `MongoDb` config and its defaults.
 
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
 use mongodb::{
     error::Error,
-    options::{ConnectionString, HostInfo},
+    options::{Acknowledgment, ClientOptions, ConnectionString, HostInfo, Tls, TlsOptions, WriteConcern},
 };
+use thiserror::Error;
 
 /// The default connection string of the database.
 pub const DEFAULT_CONN_STR: &str = "mongodb://localhost:27017";
 /// The default name of the database to connect to.
 pub const DEFAULT_DATABASE_NAME: &str = "chronicle";
+/// The default duration after which a database query is logged as slow.
+pub const DEFAULT_SLOW_QUERY_THRESHOLD: &str = "10s";
+/// The maximum length of a database name in bytes.
+const MAX_DATABASE_NAME_LEN: usize = 63;
+/// Characters that MongoDb does not allow in database names on any platform.
+const INVALID_DATABASE_NAME_CHARS: &[char] = &['/', '\\', '.', ' ', '"', '$', '*', '<', '>', ':', '|', '?', '\0'];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum InvalidDatabaseName {
+    #[error("database name must not be empty")]
+    Empty,
+    #[error("database name `{0}` is longer than {MAX_DATABASE_NAME_LEN} bytes")]
+    TooLong(String),
+    #[error("database name `{0}` contains the invalid character `{1}`")]
+    InvalidChar(String, char),
+}
+
+/// Checks that `name` can be used as a MongoDb database name.
+pub fn validate_database_name(name: &str) -> Result<(), InvalidDatabaseName> {
+    if name.is_empty() {
+        Err(InvalidDatabaseName::Empty)
+    } else if name.len() > MAX_DATABASE_NAME_LEN {
+        Err(InvalidDatabaseName::TooLong(name.to_string()))
+    } else if let Some(c) = name.chars().find(|c| INVALID_DATABASE_NAME_CHARS.contains(c)) {
+        Err(InvalidDatabaseName::InvalidChar(name.to_string(), c))
+    } else {
+        Ok(())
+    }
+}
+
+/// The acknowledgment of writes that is requested from MongoDb.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WriteAcknowledgment {
+    /// The write must be acknowledged by the given number of replica set members.
+    Nodes(u32),
+    /// The write must be acknowledged by a majority of the voting replica set members.
+    Majority,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid write concern `{0}`: expected `majority` or a number of nodes")]
+#[allow(missing_docs)]
+pub struct InvalidWriteAcknowledgment(pub String);
+
+impl FromStr for WriteAcknowledgment {
+    type Err = InvalidWriteAcknowledgment;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "majority" => Ok(Self::Majority),
+            _ => s
+                .parse()
+                .map(Self::Nodes)
+                .map_err(|_| InvalidWriteAcknowledgment(s.to_string())),
+        }
+    }
+}
+
+impl From<WriteAcknowledgment> for Acknowledgment {
+    fn from(value: WriteAcknowledgment) -> Self {
+        match value {
+            WriteAcknowledgment::Nodes(n) => Self::Nodes(n),
+            WriteAcknowledgment::Majority => Self::Majority,
+        }
+    }
+}
+
+/// The write concern that is applied to all writes. Options that are not set fall back to the connection string and
+/// then to the server's defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WriteConcernConfig {
+    /// The acknowledgment that is requested for every write (`w`).
+    pub w: Option<WriteAcknowledgment>,
+    /// Whether writes must be written to the on-disk journal before they are acknowledged (`j`).
+    pub journal: Option<bool>,
+    /// How long to wait for the acknowledgment before the write fails (`wtimeout`).
+    pub w_timeout: Option<Duration>,
+}
+
+impl WriteConcernConfig {
+    /// Overrides the options of `write_concern` that are set in this config.
+    pub fn apply(&self, write_concern: Option<WriteConcern>) -> Option<WriteConcern> {
+        if *self == Self::default() {
+            return write_concern;
+        }
+        let mut write_concern = write_concern.unwrap_or_default();
+        if let Some(w) = self.w {
+            write_concern.w = Some(w.into());
+        }
+        if let Some(journal) = self.journal {
+            write_concern.journal = Some(journal);
+        }
+        if let Some(w_timeout) = self.w_timeout {
+            write_concern.w_timeout = Some(w_timeout);
+        }
+        Some(write_concern)
+    }
+}
+
+/// The TLS options of the connection. If none of them are set, TLS is configured by the connection string alone, and
+/// is disabled by default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// The certificate authorities that the server certificate is checked against (`tlsCAFile`).
+    pub ca_file: Option<PathBuf>,
+    /// The client certificate and private key that are presented to the server (`tlsCertificateKeyFile`).
+    pub cert_key_file: Option<PathBuf>,
+    /// Whether invalid server certificates are accepted (`tlsAllowInvalidCertificates`). Only use this for testing.
+    pub allow_invalid_certificates: bool,
+}
+
+impl TlsConfig {
+    /// Enables TLS with the options that are set in this config, overriding those of `tls`.
+    pub fn apply(&self, tls: Option<Tls>) -> Option<Tls> {
+        if *self == Self::default() {
+            return tls;
+        }
+        let mut options = match tls {
+            Some(Tls::Enabled(options)) => options,
+            _ => TlsOptions::default(),
+        };
+        if let Some(ca_file) = &self.ca_file {
+            options.ca_file_path = Some(ca_file.clone());
+        }
+        if let Some(cert_key_file) = &self.cert_key_file {
+            options.cert_key_file_path = Some(cert_key_file.clone());
+        }
+        if self.allow_invalid_certificates {
+            options.allow_invalid_certificates = Some(true);
+        }
+        Some(Tls::Enabled(options))
+    }
+
+    /// The configured files, so that their existence can be checked at startup.
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.ca_file.iter().chain(&self.cert_key_file)
+    }
+}
+
+/// The sizing of the connection pool. Options that are not set fall back to the connection string and then to the
+/// driver's defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// The number of connections per server that are kept open even while they are idle (`minPoolSize`).
+    pub min_pool_size: Option<u32>,
+    /// The largest number of connections per server. Further operations wait for a connection to become available
+    /// (`maxPoolSize`).
+    pub max_pool_size: Option<u32>,
+    /// How long a connection may stay idle before it is closed (`maxIdleTimeMS`).
+    pub max_idle_time: Option<Duration>,
+}
+
+impl PoolConfig {
+    /// Overrides the pool options of `options` that are set in this config.
+    pub fn apply(&self, options: &mut ClientOptions) {
+        if let Some(min_pool_size) = self.min_pool_size {
+            options.min_pool_size = Some(min_pool_size);
+        }
+        if let Some(max_pool_size) = self.max_pool_size {
+            options.max_pool_size = Some(max_pool_size);
+        }
+        if let Some(max_idle_time) = self.max_idle_time {
+            options.max_idle_time = Some(max_idle_time);
+        }
+    }
+}
 
 /// The [`super::MongoDb`] config.
 #[must_use]
@@ -21,6 +191,18 @@ pub struct MongoDbConfig {
     pub conn_str: String,
     /// The name of the database to connect to.
     pub database_name: String,
+    /// Queries that take longer than this are logged as slow. Zero disables the slow query log.
+    pub slow_query_threshold: Duration,
+    /// The write concern of all writes to the database.
+    pub write_concern: WriteConcernConfig,
+    /// The TLS options of the connection.
+    pub tls: TlsConfig,
+    /// The sizing of the connection pool.
+    pub pool: PoolConfig,
+    /// The zstd level with which the raw bytes of new blocks are compressed, or `None` to store them uncompressed.
+    /// Blocks are readable regardless of this setting.
+    #[cfg(feature = "compression")]
+    pub block_compression_level: Option<i32>,
 }
 
 impl MongoDbConfig {
@@ -40,6 +222,136 @@ impl Default for MongoDbConfig {
         Self {
             conn_str: DEFAULT_CONN_STR.to_string(),
             database_name: DEFAULT_DATABASE_NAME.to_string(),
+            slow_query_threshold: DEFAULT_SLOW_QUERY_THRESHOLD
+                .parse::<humantime::Duration>()
+                .unwrap()
+                .into(),
+            write_concern: Default::default(),
+            tls: Default::default(),
+            pool: Default::default(),
+            #[cfg(feature = "compression")]
+            block_compression_level: None,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn database_name_validation() {
+        assert_eq!(validate_database_name("chronicle-testnet"), Ok(()));
+        assert_eq!(validate_database_name(""), Err(InvalidDatabaseName::Empty));
+        assert!(matches!(
+            validate_database_name(&"a".repeat(64)),
+            Err(InvalidDatabaseName::TooLong(_))
+        ));
+        assert_eq!(
+            validate_database_name("main.net"),
+            Err(InvalidDatabaseName::InvalidChar("main.net".to_string(), '.'))
+        );
+    }
+
+    #[test]
+    fn write_concern_options_are_applied() {
+        assert_eq!(WriteConcernConfig::default().apply(None), None);
+
+        let config = WriteConcernConfig {
+            w: Some("majority".parse().unwrap()),
+            journal: Some(true),
+            w_timeout: Some(Duration::from_secs(5)),
+        };
+        let write_concern = config.apply(None).unwrap();
+        assert_eq!(write_concern.w, Some(Acknowledgment::Majority));
+        assert_eq!(write_concern.journal, Some(true));
+        assert_eq!(write_concern.w_timeout, Some(Duration::from_secs(5)));
+
+        // Options that are not set keep the value of the connection string.
+        let config = WriteConcernConfig {
+            journal: Some(false),
+            ..Default::default()
+        };
+        let write_concern = config
+            .apply(Some(WriteConcern::builder().w(Acknowledgment::Nodes(2)).build()))
+            .unwrap();
+        assert_eq!(write_concern.w, Some(Acknowledgment::Nodes(2)));
+        assert_eq!(write_concern.journal, Some(false));
+
+        assert_eq!("2".parse(), Ok(WriteAcknowledgment::Nodes(2)));
+        assert_eq!(
+            "all".parse::<WriteAcknowledgment>(),
+            Err(InvalidWriteAcknowledgment("all".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn pool_options_are_applied() {
+        let mut options = ClientOptions::parse(DEFAULT_CONN_STR).await.unwrap();
+        PoolConfig::default().apply(&mut options);
+        assert_eq!(options.min_pool_size, None);
+        assert_eq!(options.max_pool_size, None);
+        assert_eq!(options.max_idle_time, None);
+
+        let config = PoolConfig {
+            min_pool_size: Some(5),
+            max_pool_size: Some(50),
+            max_idle_time: Some(Duration::from_secs(60)),
+        };
+        config.apply(&mut options);
+        assert_eq!(options.min_pool_size, Some(5));
+        assert_eq!(options.max_pool_size, Some(50));
+        assert_eq!(options.max_idle_time, Some(Duration::from_secs(60)));
+
+        // Options that are not set keep the value of the connection string.
+        let mut options = ClientOptions::parse("mongodb://localhost:27017/?minPoolSize=2&maxIdleTimeMS=1000")
+            .await
+            .unwrap();
+        let config = PoolConfig {
+            max_pool_size: Some(20),
+            ..Default::default()
+        };
+        config.apply(&mut options);
+        assert_eq!(options.min_pool_size, Some(2));
+        assert_eq!(options.max_pool_size, Some(20));
+        assert_eq!(options.max_idle_time, Some(Duration::from_secs(1)));
+    }
+
+    #[tokio::test]
+    async fn tls_options_are_applied() {
+        let options = mongodb::options::ClientOptions::parse(DEFAULT_CONN_STR).await.unwrap();
+        assert!(options.tls.is_none());
+        assert!(TlsConfig::default().apply(options.tls).is_none());
+
+        let config = TlsConfig {
+            ca_file: Some("/etc/ssl/mongodb-ca.pem".into()),
+            cert_key_file: Some("/etc/ssl/chronicle.pem".into()),
+            allow_invalid_certificates: true,
+        };
+        let options = match config.apply(None) {
+            Some(Tls::Enabled(options)) => options,
+            tls => panic!("expected TLS to be enabled, but found {tls:?}"),
+        };
+        assert_eq!(options.ca_file_path, Some("/etc/ssl/mongodb-ca.pem".into()));
+        assert_eq!(options.cert_key_file_path, Some("/etc/ssl/chronicle.pem".into()));
+        assert_eq!(options.allow_invalid_certificates, Some(true));
+
+        // Options that are not set keep the value of the connection string.
+        let options = mongodb::options::ClientOptions::parse(
+            "mongodb://localhost:27017/?tls=true&tlsCAFile=/etc/ssl/ca.pem&tlsAllowInvalidCertificates=false",
+        )
+        .await
+        .unwrap();
+        let config = TlsConfig {
+            cert_key_file: Some("/etc/ssl/chronicle.pem".into()),
+            ..Default::default()
+        };
+        let options = match config.apply(options.tls) {
+            Some(Tls::Enabled(options)) => options,
+            tls => panic!("expected TLS to be enabled, but found {tls:?}"),
+        };
+        assert_eq!(options.ca_file_path, Some("/etc/ssl/ca.pem".into()));
+        assert_eq!(options.cert_key_file_path, Some("/etc/ssl/chronicle.pem".into()));
+        assert_eq!(options.allow_invalid_certificates, Some(false));
+    }
+}
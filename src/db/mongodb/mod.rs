@@ -12,7 +12,7 @@ use std::collections::{HashMap, HashSet};
 
 use config::MongoDbConfig;
 use mongodb::{
-    bson::{doc, Document},
+    bson::{doc, Bson, Document},
     error::Error,
     options::ClientOptions,
     Client,
@@ -20,11 +20,35 @@ use mongodb::{
 
 pub use self::collection::{InsertIgnoreDuplicatesExt, MongoDbCollection, MongoDbCollectionExt};
 
+/// The indexes of a collection, split by whether they were created or already existed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexReport {
+    /// The names of the indexes that were created.
+    pub created: Vec<String>,
+    /// The names of the indexes that already existed.
+    pub present: Vec<String>,
+}
+
+/// The number of documents in a collection and the space that they take up.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CollectionStats {
+    /// The number of documents.
+    pub count: u64,
+    /// The uncompressed size of the documents in bytes.
+    pub size: u64,
+    /// The storage allocated for the documents in bytes.
+    pub storage_size: u64,
+    /// The storage allocated for the indexes in bytes.
+    pub index_size: u64,
+}
+
 /// A handle to the underlying `MongoDB` database.
 #[derive(Clone, Debug)]
 pub struct MongoDb {
     pub(crate) database_name: String,
     pub(crate) client: mongodb::Client,
+    #[cfg(feature = "compression")]
+    pub(crate) block_compression_level: Option<i32>,
 }
 
 impl MongoDb {
@@ -33,12 +57,19 @@ impl MongoDb {
         let mut client_options = ClientOptions::parse(&config.conn_str).await?;
 
         client_options.app_name = Some(crate::CHRONICLE_APP_NAME.to_string());
+        client_options.write_concern = config.write_concern.apply(client_options.write_concern.take());
+        client_options.tls = config.tls.apply(client_options.tls.take());
+        config.pool.apply(&mut client_options);
 
         let client = Client::with_options(client_options)?;
 
+        collection::set_slow_query_threshold(config.slow_query_threshold);
+
         Ok(Self {
             database_name: config.database_name.clone(),
             client,
+            #[cfg(feature = "compression")]
+            block_compression_level: config.block_compression_level,
         })
     }
 
@@ -47,12 +78,23 @@ impl MongoDb {
         self.client.database(&self.database_name)
     }
 
-    /// Creates a collection if it does not exist.
-    pub async fn create_indexes<T: MongoDbCollection + Send + Sync>(&self) -> Result<(), Error> {
+    /// Creates a collection and its indexes if they do not exist.
+    pub async fn create_indexes<T: MongoDbCollection + Send + Sync>(&self) -> Result<IndexReport, Error> {
         let collection = self.collection::<T>();
         collection.create_collection(self).await?;
+        let existing = self
+            .db()
+            .collection::<Document>(T::NAME)
+            .list_index_names()
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
         collection.create_indexes().await?;
-        Ok(())
+        let (present, created) = T::indexes()
+            .into_iter()
+            .filter_map(|index| index.options.and_then(|options| options.name))
+            .partition(|name| existing.contains(name));
+        Ok(IndexReport { created, present })
     }
 
     /// Gets a collection of the provided type.
@@ -105,14 +147,44 @@ impl MongoDb {
                 .get("storageSize")
                 .unwrap()
             {
-                mongodb::bson::Bson::Int32(i) => *i as u64,
-                mongodb::bson::Bson::Int64(i) => *i as u64,
-                mongodb::bson::Bson::Double(f) => *f as u64,
+                Bson::Int32(i) => *i as u64,
+                Bson::Int64(i) => *i as u64,
+                Bson::Double(f) => *f as u64,
                 _ => unreachable!(),
             },
         )
     }
 
+    /// Returns the number of documents and the storage size of a collection. A collection that does not exist yet is
+    /// reported as empty.
+    pub async fn collection_stats<T: MongoDbCollection>(&self) -> Result<CollectionStats, Error> {
+        // Depending on the server version, `collStats` either fails or reports zeros for a missing collection.
+        if self
+            .db()
+            .list_collection_names(doc! { "name": T::NAME })
+            .await?
+            .is_empty()
+        {
+            return Ok(CollectionStats::default());
+        }
+        let stats = self
+            .db()
+            .run_command(doc! { "collStats": T::NAME, "scale": 1 }, None)
+            .await?;
+        let get = |field: &str| match stats.get(field) {
+            Some(Bson::Int32(i)) => *i as u64,
+            Some(Bson::Int64(i)) => *i as u64,
+            Some(Bson::Double(f)) => *f as u64,
+            _ => 0,
+        };
+        Ok(CollectionStats {
+            count: get("count"),
+            size: get("size"),
+            storage_size: get("storageSize"),
+            index_size: get("totalIndexSize"),
+        })
+    }
+
     /// Returns the names of all available databases.
     pub async fn get_databases(&self) -> Result<Vec<String>, Error> {
         self.client.list_database_names(None, None).await
@@ -4,7 +4,7 @@
 use inx::proto;
 use iota_types::block as iota;
 
-use super::{InxError, RawMessage};
+use super::{DecodeStage, InxError, RawMessage};
 use crate::{
     maybe_missing,
     model::{
@@ -65,14 +65,16 @@ impl TryFrom<inx::proto::BlockMetadata> for BlockMetadataMessage {
         let inclusion_state = value.ledger_inclusion_state().into();
         let conflict_reason = value.conflict_reason().into();
 
+        let block_id: BlockId = maybe_missing!(value.block_id).try_into()?;
         let parents = value
             .parents
             .into_iter()
             .map(TryInto::try_into)
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: InxError| e.decoding(format!("block {}", block_id.to_hex()), DecodeStage::BlockMetadata))?;
 
         Ok(Self {
-            block_id: maybe_missing!(value.block_id).try_into()?,
+            block_id,
             parents: parents.into_boxed_slice(),
             is_solid: value.solid,
             should_promote: value.should_promote,
@@ -158,3 +160,31 @@ impl From<BlockMetadataMessage> for BlockMetadata {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn malformed_metadata_error_contains_block_id() {
+        let block_id = BlockId([1; BlockId::LENGTH]);
+        let metadata = proto::BlockMetadata {
+            block_id: Some(block_id.into()),
+            parents: vec![proto::BlockId { id: vec![0; 3] }],
+            ..Default::default()
+        };
+
+        let err = BlockMetadataMessage::try_from(metadata).unwrap_err();
+        assert!(matches!(
+            err,
+            InxError::Decode {
+                stage: DecodeStage::BlockMetadata,
+                ..
+            }
+        ));
+        assert_eq!(err.category(), "invalid byte length");
+        let message = err.to_string();
+        assert!(message.contains(&block_id.to_hex()), "{message}");
+        assert!(message.contains("invalid byte length"), "{message}");
+    }
+}
@@ -5,7 +5,7 @@ use futures::stream::{Stream, StreamExt};
 use inx::{client::InxClient, proto};
 
 use super::{
-    block::BlockWithMetadataMessage,
+    block::{BlockMetadataMessage, BlockWithMetadataMessage},
     ledger::UnspentOutputMessage,
     milestone::{MilestoneAndProtocolParametersMessage, MilestoneMessage},
     node::NodeConfigurationMessage,
@@ -112,6 +112,32 @@ impl Inx {
             .map(unpack_proto_msg))
     }
 
+    /// Convenience wrapper that listens to blocks becoming solid as a stream of
+    /// [`BlockMetadataMessages`](BlockMetadataMessage).
+    pub async fn listen_to_solid_blocks(
+        &mut self,
+    ) -> Result<impl Stream<Item = Result<BlockMetadataMessage, InxError>>, InxError> {
+        Ok(self
+            .inx
+            .listen_to_solid_blocks(proto::NoParams {})
+            .await?
+            .into_inner()
+            .map(unpack_proto_msg))
+    }
+
+    /// Convenience wrapper that listens to blocks being referenced by a milestone as a stream of
+    /// [`BlockMetadataMessages`](BlockMetadataMessage).
+    pub async fn listen_to_referenced_blocks(
+        &mut self,
+    ) -> Result<impl Stream<Item = Result<BlockMetadataMessage, InxError>>, InxError> {
+        Ok(self
+            .inx
+            .listen_to_referenced_blocks(proto::NoParams {})
+            .await?
+            .into_inner()
+            .map(unpack_proto_msg))
+    }
+
     /// Convenience wrapper that reads the information for a given milestone.
     pub async fn read_milestone(&mut self, request: MilestoneRequest) -> Result<MilestoneMessage, InxError> {
         MilestoneMessage::try_from(
@@ -1,12 +1,22 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::fmt::Display;
+
 use thiserror::Error;
 
 /// The different errors that can happen with INX.
 #[derive(Debug, Error)]
 #[allow(missing_docs)]
 pub enum InxError {
+    #[error("failed to decode {stage} of {subject} ({}): {source}", source.category())]
+    Decode {
+        subject: String,
+        stage: DecodeStage,
+        source: Box<InxError>,
+        /// The bytes that could not be decoded, if the failing part was transmitted as raw bytes.
+        raw: Option<Vec<u8>>,
+    },
     #[error("expected {expected} bytes but received {actual}")]
     InvalidByteLength { actual: usize, expected: usize },
     #[error("{0}")]
@@ -18,3 +28,58 @@ pub enum InxError {
     #[error(transparent)]
     TonicError(#[from] tonic::transport::Error),
 }
+
+impl InxError {
+    /// Adds the block or milestone that was being decoded, and the part of it that failed, to the error.
+    pub fn decoding(self, subject: impl Display, stage: DecodeStage) -> Self {
+        Self::Decode {
+            subject: subject.to_string(),
+            stage,
+            source: Box::new(self),
+            raw: None,
+        }
+    }
+
+    /// Keeps the bytes that could not be decoded with a [`Decode`](Self::Decode) error.
+    pub(crate) fn with_raw(mut self, data: Vec<u8>) -> Self {
+        if let Self::Decode { raw, .. } = &mut self {
+            *raw = Some(data);
+        }
+        self
+    }
+
+    /// A short, stable description of what went wrong, which can be used to group errors.
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::Decode { source, .. } => source.category(),
+            Self::InvalidByteLength { .. } => "invalid byte length",
+            Self::InvalidRawBytes(_) => "invalid raw bytes",
+            Self::MissingField(_) => "missing field",
+            Self::StatusCode(_) => "grpc status",
+            Self::TonicError(_) => "transport",
+        }
+    }
+}
+
+/// The part of an INX message that could not be decoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum DecodeStage {
+    Block,
+    BlockMetadata,
+    MilestoneInfo,
+    MilestonePayload,
+    ProtocolParameters,
+}
+
+impl Display for DecodeStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Block => "block",
+            Self::BlockMetadata => "block metadata",
+            Self::MilestoneInfo => "milestone info",
+            Self::MilestonePayload => "milestone payload",
+            Self::ProtocolParameters => "protocol parameters",
+        })
+    }
+}
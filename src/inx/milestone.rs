@@ -4,7 +4,7 @@
 use inx::proto;
 use iota_types::block as iota;
 
-use super::{raw::RawMessage, InxError, RawProtocolParametersMessage};
+use super::{raw::RawMessage, DecodeStage, InxError, RawProtocolParametersMessage};
 use crate::{
     maybe_missing,
     model::{payload::MilestoneId, tangle::MilestoneIndex},
@@ -86,9 +86,16 @@ impl TryFrom<proto::MilestoneInfo> for MilestoneInfoMessage {
     type Error = InxError;
 
     fn try_from(value: proto::MilestoneInfo) -> Result<Self, Self::Error> {
+        let milestone_index: MilestoneIndex = value.milestone_index.into();
         Ok(MilestoneInfoMessage {
-            milestone_id: value.milestone_id.map(TryInto::try_into).transpose()?,
-            milestone_index: value.milestone_index.into(),
+            milestone_id: value
+                .milestone_id
+                .map(TryInto::try_into)
+                .transpose()
+                .map_err(|e: InxError| {
+                    e.decoding(format!("milestone {milestone_index}"), DecodeStage::MilestoneInfo)
+                })?,
+            milestone_index,
             milestone_timestamp: value.milestone_timestamp,
         })
     }
@@ -17,7 +17,7 @@ mod request;
 pub use self::{
     block::{BlockMessage, BlockMetadataMessage, BlockWithMetadataMessage},
     client::Inx,
-    error::InxError,
+    error::{DecodeStage, InxError},
     ledger::{LedgerUpdateMessage, MarkerMessage, UnspentOutputMessage},
     milestone::MilestoneAndProtocolParametersMessage,
     node::{NodeConfigurationMessage, NodeStatusMessage},
@@ -1,12 +1,12 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::marker::PhantomData;
+use std::{fmt::Display, marker::PhantomData};
 
 use inx::proto;
 use packable::{Packable, PackableExt};
 
-use super::InxError;
+use super::{DecodeStage, InxError};
 
 /// Represents a type as raw bytes.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -36,6 +36,17 @@ impl<T: Packable> RawMessage<T> {
         let unpacked = T::unpack_unverified(self.data).map_err(|e| InxError::InvalidRawBytes(format!("{e:?}")))?;
         Ok(unpacked)
     }
+
+    /// Like [`inner_unverified`](Self::inner_unverified), but reports a failure as a [`InxError::Decode`] error of the
+    /// `stage` of `subject`, which keeps the raw bytes for debugging.
+    pub fn decode_unverified(self, subject: impl Display, stage: DecodeStage) -> Result<T, InxError> {
+        match T::unpack_unverified(&self.data) {
+            Ok(unpacked) => Ok(unpacked),
+            Err(e) => Err(InxError::InvalidRawBytes(format!("{e:?}"))
+                .decoding(subject, stage)
+                .with_raw(self.data)),
+        }
+    }
 }
 
 impl<T: Packable> From<Vec<u8>> for RawMessage<T> {
@@ -16,6 +16,22 @@ pub struct SyncMetrics {
     pub time: DateTime<Utc>,
     pub milestone_index: MilestoneIndex,
     pub milestone_time: u64,
+    /// The number of blocks that were received for the milestone.
+    pub block_count: u64,
+    /// The number of milestones the node has confirmed beyond this one.
+    pub milestone_lag: u64,
+    #[influxdb(tag)]
+    pub chronicle_version: String,
+}
+
+/// A sample of the number of items waiting in one of the internal queues.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, InfluxDbWriteable)]
+#[allow(missing_docs)]
+pub struct QueueMetrics {
+    pub time: DateTime<Utc>,
+    pub depth: u64,
+    #[influxdb(tag)]
+    pub queue: String,
     #[influxdb(tag)]
     pub chronicle_version: String,
 }
@@ -35,6 +51,10 @@ impl InfluxDbMeasurement for SyncMetrics {
     const NAME: &'static str = "sync_metrics";
 }
 
+impl InfluxDbMeasurement for QueueMetrics {
+    const NAME: &'static str = "queue_metrics";
+}
+
 #[cfg(feature = "analytics")]
 impl InfluxDbMeasurement for AnalyticsMetrics {
     const NAME: &'static str = "analytics_metrics";
@@ -143,6 +143,12 @@ mod rand {
             rand_block_id().into()
         }
 
+        /// Generates a random [`BlockId`] from the given source of randomness. Using a seeded `rng` makes the result
+        /// reproducible.
+        pub fn rand_with(rng: &mut impl ::rand::Rng) -> Self {
+            Self(rng.gen())
+        }
+
         /// Generates multiple random [`BlockIds`](BlockId).
         pub fn rand_many(len: usize) -> impl Iterator<Item = Self> {
             rand_block_ids(len).into_iter().map(Into::into)
@@ -65,5 +65,11 @@ mod rand {
         pub fn rand() -> Self {
             rand_milestone_id().into()
         }
+
+        /// Generates a random [`MilestoneId`] from the given source of randomness. Using a seeded `rng` makes the
+        /// result reproducible.
+        pub fn rand_with(rng: &mut impl ::rand::Rng) -> Self {
+            Self(rng.gen())
+        }
     }
 }
@@ -240,6 +240,12 @@ mod rand {
         pub fn rand() -> Self {
             Self(rand_bytes_array())
         }
+
+        /// Generates a random [`TransactionId`] from the given source of randomness. Using a seeded `rng` makes the
+        /// result reproducible.
+        pub fn rand_with(rng: &mut impl ::rand::Rng) -> Self {
+            Self(rng.gen())
+        }
     }
 
     impl TransactionEssence {
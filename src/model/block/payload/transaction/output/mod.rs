@@ -314,6 +314,12 @@ mod rand {
         pub fn rand(ctx: &iota_types::block::protocol::ProtocolParameters) -> Self {
             rand_number_range(iota::Output::AMOUNT_MIN..ctx.token_supply()).into()
         }
+
+        /// Generates a random [`TokenAmount`] from the given source of randomness. Using a seeded `rng` makes the
+        /// result reproducible.
+        pub fn rand_with(rng: &mut impl ::rand::Rng, ctx: &iota_types::block::protocol::ProtocolParameters) -> Self {
+            rng.gen_range(iota::Output::AMOUNT_MIN..ctx.token_supply()).into()
+        }
     }
 
     impl OutputId {
@@ -321,6 +327,15 @@ mod rand {
         pub fn rand() -> Self {
             rand_output_id().into()
         }
+
+        /// Generates a random [`OutputId`] from the given source of randomness. Using a seeded `rng` makes the result
+        /// reproducible.
+        pub fn rand_with(rng: &mut impl ::rand::Rng) -> Self {
+            Self {
+                transaction_id: TransactionId::rand_with(rng),
+                index: rng.gen_range(iota::OUTPUT_INDEX_RANGE),
+            }
+        }
     }
 
     impl Output {
@@ -376,6 +391,24 @@ mod test {
         from_bson::<OutputId>(bson).unwrap();
     }
 
+    #[test]
+    fn test_rand_with_seed_is_reproducible() {
+        use ::rand::{rngs::StdRng, SeedableRng};
+
+        let ctx = iota_types::block::protocol::protocol_parameters();
+        let generate = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (
+                OutputId::rand_with(&mut rng),
+                TokenAmount::rand_with(&mut rng, &ctx),
+                crate::model::BlockId::rand_with(&mut rng),
+            )
+        };
+
+        assert_eq!(generate(42), generate(42));
+        assert_ne!(generate(42), generate(43));
+    }
+
     #[test]
     fn test_basic_output_bson() {
         let ctx = iota_types::block::protocol::protocol_parameters();
@@ -3,6 +3,10 @@
 
 use std::{
     pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
 };
 
@@ -42,15 +46,48 @@ impl<'a, I: InputSource> Milestone<'a, I> {
     }
 }
 
+/// The number of milestones that were fetched ahead of time and are waiting to be yielded by a [`MilestoneStream`].
+#[derive(Clone, Debug, Default)]
+pub struct QueueDepth(Arc<AtomicUsize>);
+
+impl QueueDepth {
+    /// Returns the current number of waiting milestones.
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn decrement(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 #[allow(missing_docs)]
 pub struct MilestoneStream<'a, I: InputSource> {
     pub(super) inner: BoxStream<'a, Result<Milestone<'a, I>, I::Error>>,
+    pub(super) queue_depth: QueueDepth,
+}
+
+impl<'a, I: InputSource> MilestoneStream<'a, I> {
+    /// Returns a handle to the number of milestones that are ready but were not yet consumed. A persistently high depth
+    /// means that applying the milestones is slower than fetching them.
+    pub fn queue_depth(&self) -> QueueDepth {
+        self.queue_depth.clone()
+    }
 }
 
 impl<'a, I: InputSource> Stream for MilestoneStream<'a, I> {
     type Item = Result<Milestone<'a, I>, I::Error>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().inner).poll_next(cx)
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_next(cx);
+        if let Poll::Ready(Some(Ok(_))) = poll {
+            this.queue_depth.decrement();
+        }
+        poll
     }
 }
@@ -12,7 +12,7 @@ use futures::{StreamExt, TryStreamExt};
 
 pub use self::{
     ledger_updates::LedgerUpdateStore,
-    milestone_stream::{Milestone, MilestoneStream},
+    milestone_stream::{Milestone, MilestoneStream, QueueDepth},
     sources::{BlockData, InputSource, MilestoneData},
 };
 use crate::model::tangle::MilestoneIndex;
@@ -42,15 +42,29 @@ impl<I: InputSource + Sync> Tangle<I> {
     pub async fn milestone_stream(
         &self,
         range: impl RangeBounds<MilestoneIndex> + Send,
+    ) -> Result<MilestoneStream<'_, I>, I::Error> {
+        self.buffered_milestone_stream(range, 1).await
+    }
+
+    /// Returns a stream of milestones for a given range, fetching the ledger updates of up to `fetch_concurrency`
+    /// milestones at the same time. The milestones are still yielded in order.
+    pub async fn buffered_milestone_stream(
+        &self,
+        range: impl RangeBounds<MilestoneIndex> + Send,
+        fetch_concurrency: usize,
     ) -> Result<MilestoneStream<'_, I>, I::Error> {
         let stream = self.source.milestone_stream(range).await?;
+        let queue_depth = QueueDepth::default();
+        let fetched = queue_depth.clone();
         Ok(MilestoneStream {
             inner: stream
-                .and_then(|data| {
+                .map(move |data| {
                     #[allow(clippy::borrow_deref_ref)]
                     let source = &self.source;
+                    let fetched = fetched.clone();
                     async move {
-                        Ok(Milestone {
+                        let data = data?;
+                        let milestone = Milestone {
                             ledger_updates: source.ledger_updates(data.at.milestone_index).await?,
                             source,
                             milestone_id: data.milestone_id,
@@ -58,10 +72,134 @@ impl<I: InputSource + Sync> Tangle<I> {
                             payload: data.payload,
                             protocol_params: data.protocol_params,
                             node_config: data.node_config,
-                        })
+                        };
+                        fetched.increment();
+                        Ok(milestone)
                     }
                 })
+                .buffered(fetch_concurrency.max(1))
                 .boxed(),
+            queue_depth,
         })
     }
 }
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use std::{sync::Mutex, time::Duration};
+
+    use async_trait::async_trait;
+    use futures::stream::BoxStream;
+
+    use super::*;
+    use crate::{
+        model::{
+            node::{BaseToken, NodeConfiguration},
+            payload::{MilestoneId, MilestonePayload},
+            tangle::MilestoneIndexTimestamp,
+        },
+        tangle::sources::memory::InMemoryInputSourceError,
+    };
+
+    /// A source that takes longer to fetch the ledger updates of earlier milestones.
+    struct SlowSource {
+        milestones: Vec<MilestoneData>,
+        fetched: Mutex<Vec<MilestoneIndex>>,
+    }
+
+    #[async_trait]
+    impl InputSource for SlowSource {
+        type Error = InMemoryInputSourceError;
+
+        async fn milestone_stream(
+            &self,
+            range: impl RangeBounds<MilestoneIndex> + Send,
+        ) -> Result<BoxStream<Result<MilestoneData, Self::Error>>, Self::Error> {
+            let milestones = self
+                .milestones
+                .iter()
+                .filter(|data| range.contains(&data.at.milestone_index))
+                .cloned()
+                .map(Ok)
+                .collect::<Vec<_>>();
+            Ok(Box::pin(futures::stream::iter(milestones)))
+        }
+
+        async fn cone_stream(
+            &self,
+            _index: MilestoneIndex,
+        ) -> Result<BoxStream<Result<BlockData, Self::Error>>, Self::Error> {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+
+        async fn ledger_updates(&self, index: MilestoneIndex) -> Result<LedgerUpdateStore, Self::Error> {
+            tokio::time::sleep(Duration::from_millis(20 * (10 - index.0 as u64))).await;
+            self.fetched.lock().unwrap().push(index);
+            Ok(LedgerUpdateStore::init(Vec::new(), Vec::new()))
+        }
+    }
+
+    fn slow_source() -> SlowSource {
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+        SlowSource {
+            milestones: (1..=5)
+                .map(|index| MilestoneData {
+                    milestone_id: MilestoneId::rand(),
+                    at: MilestoneIndexTimestamp {
+                        milestone_index: index.into(),
+                        milestone_timestamp: (1000 + index).into(),
+                    },
+                    payload: MilestonePayload::rand(&protocol_params),
+                    protocol_params: protocol_params.clone().into(),
+                    node_config: NodeConfiguration {
+                        milestone_public_key_count: 0,
+                        milestone_key_ranges: Box::new([]),
+                        base_token: BaseToken {
+                            name: String::new(),
+                            ticker_symbol: String::new(),
+                            unit: String::new(),
+                            subunit: String::new(),
+                            decimals: 0,
+                            use_metric_prefix: false,
+                        },
+                    },
+                })
+                .collect(),
+            fetched: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn buffered_milestones_are_yielded_in_order() {
+        let tangle = Tangle::from(slow_source());
+
+        let applied = tangle
+            .buffered_milestone_stream(.., 5)
+            .await
+            .unwrap()
+            .map_ok(|milestone| milestone.at.milestone_index)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        // The fetches complete in reverse order, but the milestones are still applied in order.
+        assert_eq!(applied, [1, 2, 3, 4, 5]);
+        assert_eq!(*tangle.source.fetched.lock().unwrap(), [5, 4, 3, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn queue_depth_counts_waiting_milestones() {
+        let tangle = Tangle::from(slow_source());
+        let mut stream = tangle.buffered_milestone_stream(.., 5).await.unwrap();
+        let queue_depth = stream.queue_depth();
+        assert_eq!(queue_depth.get(), 0);
+
+        // The first milestone is fetched last, so the others are waiting once it is yielded.
+        let first = stream.try_next().await.unwrap().unwrap();
+        assert_eq!(first.at.milestone_index, 1);
+        assert_eq!(queue_depth.get(), 4);
+
+        while stream.try_next().await.unwrap().is_some() {}
+        assert_eq!(queue_depth.get(), 0);
+    }
+}
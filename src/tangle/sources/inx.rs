@@ -9,7 +9,7 @@ use thiserror::Error;
 
 use super::{BlockData, InputSource, MilestoneData};
 use crate::{
-    inx::{Inx, InxError, MarkerMessage, MilestoneRangeRequest},
+    inx::{DecodeStage, Inx, InxError, MarkerMessage, MilestoneRangeRequest},
     model::tangle::{MilestoneIndex, MilestoneIndexTimestamp},
     tangle::ledger_updates::LedgerUpdateStore,
 };
@@ -43,8 +43,11 @@ impl InputSource for Inx {
                     let mut inx = inx.clone();
                     async move {
                         let node_config = inx.read_node_configuration().await?.into();
-                        let payload = if let iota_types::block::payload::Payload::Milestone(payload) =
-                            msg.milestone.milestone.inner_unverified()?
+                        let milestone_index = msg.milestone.milestone_info.milestone_index;
+                        let payload = if let iota_types::block::payload::Payload::Milestone(payload) = msg
+                            .milestone
+                            .milestone
+                            .decode_unverified(format!("milestone {milestone_index}"), DecodeStage::MilestonePayload)?
                         {
                             payload.into()
                         } else {
@@ -59,7 +62,14 @@ impl InputSource for Inx {
                                 milestone_timestamp: msg.milestone.milestone_info.milestone_timestamp.into(),
                             },
                             payload,
-                            protocol_params: msg.current_protocol_parameters.params.inner_unverified()?.into(),
+                            protocol_params: msg
+                                .current_protocol_parameters
+                                .params
+                                .decode_unverified(
+                                    format!("milestone {milestone_index}"),
+                                    DecodeStage::ProtocolParameters,
+                                )?
+                                .into(),
                             node_config,
                         })
                     }
@@ -79,7 +89,11 @@ impl InputSource for Inx {
                 .and_then(|msg| async move {
                     Ok(BlockData {
                         block_id: msg.metadata.block_id,
-                        block: msg.block.clone().inner_unverified()?.into(),
+                        block: msg
+                            .block
+                            .clone()
+                            .decode_unverified(format!("block {}", msg.metadata.block_id.to_hex()), DecodeStage::Block)?
+                            .into(),
                         raw: msg.block.data(),
                         metadata: msg.metadata.into(),
                     })
@@ -5,10 +5,13 @@ mod common;
 
 #[cfg(feature = "rand")]
 mod test_rand {
-    use std::{collections::HashSet, fs::File, io::BufReader};
+    use std::{collections::HashSet, fs::File, io::BufReader, str::FromStr};
 
     use chronicle::{
-        db::{mongodb::collections::BlockCollection, MongoDbCollectionExt},
+        db::{
+            mongodb::collections::{BlockCollection, BlockStatus, BlockWithMetadataResult},
+            MongoDb, MongoDbCollection, MongoDbCollectionExt,
+        },
         model::{
             metadata::{BlockMetadata, ConflictReason, LedgerInclusionState},
             payload::Payload,
@@ -29,6 +32,9 @@ mod test_rand {
         metadata: BlockMetadata,
     }
 
+    #[cfg(feature = "compression")]
+    use chronicle::db::MongoDbConfig;
+
     use super::common::{setup_collection, setup_database, teardown};
 
     #[tokio::test]
@@ -99,6 +105,21 @@ mod test_rand {
                 Some(metadata),
             );
         }
+
+        for (block_id, block, _, metadata) in &blocks {
+            assert_eq!(
+                block_collection.get_block_with_metadata(block_id).await.unwrap(),
+                Some(BlockWithMetadataResult {
+                    block: block.clone(),
+                    metadata: metadata.clone(),
+                }),
+            );
+        }
+
+        // An id that was never stored.
+        let unknown = BlockId::from_str("0x52fdfc072182654f163f5f0f9a621d729566c74d10037c4d7bbb0407d1e2c649").unwrap();
+        assert!(blocks.iter().all(|(block_id, ..)| block_id != &unknown));
+        assert_eq!(block_collection.get_block_with_metadata(&unknown).await.unwrap(), None);
         teardown(db).await;
     }
 
@@ -293,4 +314,224 @@ mod test_rand {
 
         teardown(db).await;
     }
+
+    #[tokio::test]
+    async fn test_block_status_updates() {
+        let db = setup_database("test-block-status").await.unwrap();
+        let block_collection = setup_collection::<BlockCollection>(&db).await.unwrap();
+
+        let block_id = BlockId::rand();
+        let block = Block::rand_no_payload();
+        let metadata = |is_solid, referenced_by_milestone_index: u32| BlockMetadata {
+            parents: block.parents.clone(),
+            is_solid,
+            should_promote: false,
+            should_reattach: false,
+            referenced_by_milestone_index: referenced_by_milestone_index.into(),
+            milestone_index: referenced_by_milestone_index.into(),
+            inclusion_state: LedgerInclusionState::NoTransaction,
+            conflict_reason: ConflictReason::None,
+            white_flag_index: 0,
+        };
+
+        block_collection
+            .insert_blocks_with_metadata([(block_id, block.clone(), vec![0; 100], metadata(false, 0))])
+            .await
+            .unwrap();
+        assert_eq!(stored_status(&db, &block_id).await, BlockStatus::Pending);
+
+        assert!(block_collection
+            .update_block_metadata(&block_id, metadata(true, 0))
+            .await
+            .unwrap());
+        assert_eq!(stored_status(&db, &block_id).await, BlockStatus::Solid);
+        assert_eq!(
+            block_collection.get_block_metadata(&block_id).await.unwrap(),
+            Some(metadata(true, 0))
+        );
+
+        assert!(block_collection
+            .update_block_metadata(&block_id, metadata(true, 5))
+            .await
+            .unwrap());
+        assert_eq!(stored_status(&db, &block_id).await, BlockStatus::Referenced);
+        assert_eq!(
+            block_collection.get_block_metadata(&block_id).await.unwrap(),
+            Some(metadata(true, 5))
+        );
+
+        // A notification that arrives late does not move the block back.
+        assert!(!block_collection
+            .update_block_metadata(&block_id, metadata(true, 0))
+            .await
+            .unwrap());
+        assert_eq!(stored_status(&db, &block_id).await, BlockStatus::Referenced);
+        assert_eq!(
+            block_collection.get_block_metadata(&block_id).await.unwrap(),
+            Some(metadata(true, 5))
+        );
+
+        // Blocks that are not stored yet are not created.
+        assert!(!block_collection
+            .update_block_metadata(&BlockId::rand(), metadata(true, 0))
+            .await
+            .unwrap());
+        assert_eq!(block_collection.count().await.unwrap(), 1);
+
+        teardown(db).await;
+    }
+
+    async fn stored_status(db: &MongoDb, block_id: &BlockId) -> BlockStatus {
+        let doc = db
+            .db()
+            .collection::<mongodb::bson::Document>(BlockCollection::NAME)
+            .find_one(mongodb::bson::doc! { "_id": block_id }, None)
+            .await
+            .unwrap()
+            .unwrap();
+        mongodb::bson::from_bson(doc.get("status").unwrap().clone()).unwrap()
+    }
+
+    #[cfg(feature = "compression")]
+    fn compressible_block(white_flag_index: u32) -> (BlockId, Block, Vec<u8>, BlockMetadata) {
+        let block = Block::rand_no_payload();
+        (
+            BlockId::rand(),
+            block.clone(),
+            vec![white_flag_index as u8; 1000],
+            BlockMetadata {
+                parents: block.parents,
+                is_solid: true,
+                should_promote: false,
+                should_reattach: false,
+                referenced_by_milestone_index: 1.into(),
+                milestone_index: 0.into(),
+                inclusion_state: LedgerInclusionState::NoTransaction,
+                conflict_reason: ConflictReason::None,
+                white_flag_index,
+            },
+        )
+    }
+
+    #[cfg(feature = "compression")]
+    /// Connects to the same database as `db`, but compresses the raw bytes of new blocks.
+    async fn connect_compressed(db: &MongoDb) -> MongoDb {
+        let mut config = MongoDbConfig {
+            database_name: db.name().to_string(),
+            block_compression_level: Some(3),
+            ..Default::default()
+        };
+        if let Ok(conn_str) = std::env::var("MONGODB_CONN_STR") {
+            config.conn_str = conn_str;
+        }
+        MongoDb::connect(&config).await.unwrap()
+    }
+
+    #[cfg(feature = "compression")]
+    async fn stored_raw(db: &MongoDb, block_id: &BlockId) -> (usize, Option<bool>) {
+        let doc = db
+            .db()
+            .collection::<mongodb::bson::Document>(BlockCollection::NAME)
+            .find_one(mongodb::bson::doc! { "_id": block_id }, None)
+            .await
+            .unwrap()
+            .unwrap();
+        (
+            doc.get_binary_generic("raw").unwrap().len(),
+            doc.get_bool("raw_compressed").ok(),
+        )
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_compressed_blocks() {
+        let db = setup_database("test-compressed-blocks").await.unwrap();
+        setup_collection::<BlockCollection>(&db).await.unwrap();
+        let compressed_db = connect_compressed(&db).await;
+
+        let blocks = (0..3).map(compressible_block).collect::<Vec<_>>();
+        compressed_db
+            .collection::<BlockCollection>()
+            .insert_blocks_with_metadata(blocks.clone())
+            .await
+            .unwrap();
+
+        // Blocks are decompressed regardless of the setting of the handle that reads them.
+        let block_collection = db.collection::<BlockCollection>();
+        for (block_id, _, raw, _) in &blocks {
+            let (len, compressed) = stored_raw(&db, block_id).await;
+            assert!(len < raw.len());
+            assert_eq!(compressed, Some(true));
+            assert_eq!(
+                block_collection.get_block_raw(block_id).await.unwrap().as_ref(),
+                Some(raw)
+            );
+        }
+
+        let cone = block_collection
+            .get_referenced_blocks_in_white_flag_order_stream(1.into())
+            .await
+            .unwrap()
+            .map_ok(|(block_id, _, raw, _)| (block_id, raw))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            cone,
+            blocks
+                .iter()
+                .map(|(block_id, _, raw, _)| (*block_id, raw.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        teardown(db).await;
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_uncompressed_blocks_remain_readable() {
+        let db = setup_database("test-uncompressed-blocks").await.unwrap();
+        let block_collection = setup_collection::<BlockCollection>(&db).await.unwrap();
+        let file = File::open("tests/data/blocks_ms_2418807.json").unwrap();
+        let test_data: mongodb::bson::Bson = serde_json::from_reader(BufReader::new(file)).unwrap();
+        let blocks: Vec<BlockTestData> = mongodb::bson::from_bson(test_data).unwrap();
+        let blocks = blocks
+            .into_iter()
+            .map(
+                |BlockTestData {
+                     block_id,
+                     raw,
+                     metadata,
+                 }| {
+                    let block = iota_types::block::Block::unpack_unverified(raw.clone()).unwrap().into();
+                    (block_id, block, raw, metadata)
+                },
+            )
+            .collect::<Vec<_>>();
+
+        // Blocks that were stored before compression was supported lack the flag.
+        block_collection
+            .insert_blocks_with_metadata(blocks.clone())
+            .await
+            .unwrap();
+
+        let compressed_db = connect_compressed(&db).await;
+        let compressed_collection = compressed_db.collection::<BlockCollection>();
+        for (block_id, block, raw, metadata) in &blocks {
+            assert_eq!(stored_raw(&db, block_id).await, (raw.len(), None));
+            assert_eq!(
+                compressed_collection.get_block_raw(block_id).await.unwrap().as_ref(),
+                Some(raw)
+            );
+            assert_eq!(
+                compressed_collection.get_block_with_metadata(block_id).await.unwrap(),
+                Some(BlockWithMetadataResult {
+                    block: block.clone(),
+                    metadata: metadata.clone(),
+                }),
+            );
+        }
+
+        teardown(db).await;
+    }
 }
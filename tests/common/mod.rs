@@ -1,10 +1,48 @@
 // Copyright 2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::ops::Deref;
+
 use chronicle::db::{MongoDb, MongoDbCollection, MongoDbConfig};
 
+/// A test database that is dropped once it goes out of scope, even if the test panics before calling [`teardown`].
+pub struct TestDb {
+    db: Option<MongoDb>,
+    config: MongoDbConfig,
+}
+
+impl Deref for TestDb {
+    type Target = MongoDb;
+
+    fn deref(&self) -> &Self::Target {
+        // Unwrap: The database is only taken by `teardown`, which consumes the guard.
+        self.db.as_ref().unwrap()
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        if self.db.take().is_none() {
+            return;
+        }
+        // The runtime of the test may be blocked by this call, so the database is dropped by a fresh client on a
+        // separate thread. Errors are only reported, since panicking while unwinding would abort the test run.
+        let config = self.config.clone();
+        let res = std::thread::spawn(move || -> eyre::Result<()> {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            runtime.block_on(async { Ok(MongoDb::connect(&config).await?.drop().await?) })
+        })
+        .join();
+        match res {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => eprintln!("failed to drop test database `{}`: {err}", self.config.database_name),
+            Err(_) => eprintln!("failed to drop test database `{}`", self.config.database_name),
+        }
+    }
+}
+
 #[allow(unused)]
-pub async fn setup_database(database_name: impl ToString) -> eyre::Result<MongoDb> {
+pub async fn setup_database(database_name: impl ToString) -> eyre::Result<TestDb> {
     dotenvy::dotenv().ok();
 
     let mut test_config = MongoDbConfig {
@@ -18,7 +56,10 @@ pub async fn setup_database(database_name: impl ToString) -> eyre::Result<MongoD
 
     let db = MongoDb::connect(&test_config).await?;
     db.clear().await?;
-    Ok(db)
+    Ok(TestDb {
+        db: Some(db),
+        config: test_config,
+    })
 }
 
 #[allow(unused)]
@@ -27,7 +68,38 @@ pub async fn setup_collection<T: MongoDbCollection + Send + Sync>(db: &MongoDb)
     Ok(db.collection::<T>())
 }
 
+/// Creates an output with a random id, which was booked in a random block at the given milestone.
+#[cfg(feature = "rand")]
+#[allow(unused)]
+pub fn ledger_output(
+    output: chronicle::model::utxo::Output,
+    milestone_index: u32,
+    milestone_timestamp: u32,
+) -> chronicle::model::ledger::LedgerOutput {
+    use chronicle::model::{
+        ledger::{LedgerOutput, RentStructureBytes},
+        tangle::MilestoneIndexTimestamp,
+        utxo::OutputId,
+        BlockId,
+    };
+
+    LedgerOutput {
+        output_id: OutputId::rand(),
+        rent_structure: RentStructureBytes {
+            num_key_bytes: 0,
+            num_data_bytes: 100,
+        },
+        output,
+        block_id: BlockId::rand(),
+        booked: MilestoneIndexTimestamp {
+            milestone_index: milestone_index.into(),
+            milestone_timestamp: milestone_timestamp.into(),
+        },
+    }
+}
+
 #[allow(unused)]
-pub async fn teardown(db: MongoDb) {
-    db.drop().await.unwrap();
+pub async fn teardown(mut db: TestDb) {
+    // Unwrap: The database is only taken here.
+    db.db.take().unwrap().drop().await.unwrap();
 }
@@ -0,0 +1,47 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+mod common;
+
+#[cfg(all(feature = "rand", feature = "inx"))]
+mod test_rand {
+    use chronicle::{
+        db::{
+            mongodb::collections::{DeadLetterCollection, DeadLetterDocument},
+            MongoDbCollection,
+        },
+        inx::{DecodeStage, RawMessage},
+    };
+    use mongodb::bson::doc;
+
+    use super::common::{setup_collection, setup_database, teardown};
+
+    #[tokio::test]
+    async fn test_dead_letters() {
+        let db = setup_database("test-dead-letters").await.unwrap();
+        let collection = setup_collection::<DeadLetterCollection>(&db).await.unwrap();
+
+        // A block that ends after its protocol version.
+        let malformed = vec![2];
+        let err = RawMessage::<iota_types::block::Block>::from(malformed.clone())
+            .decode_unverified("block 0x00", DecodeStage::Block)
+            .unwrap_err();
+        let dead_letter = DeadLetterDocument::from_error(&err).unwrap();
+        collection.insert_dead_letter(&dead_letter).await.unwrap();
+
+        let dead_letters = collection.get_dead_letters(10).await.unwrap();
+        assert_eq!(dead_letters, vec![dead_letter]);
+        assert_eq!(dead_letters[0].subject, "block 0x00");
+        assert_eq!(dead_letters[0].stage, "block");
+        assert_eq!(dead_letters[0].raw.as_deref(), Some(malformed.as_slice()));
+
+        let stats = db
+            .db()
+            .run_command(doc! { "collStats": DeadLetterCollection::NAME }, None)
+            .await
+            .unwrap();
+        assert!(stats.get_bool("capped").unwrap());
+
+        teardown(db).await;
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+mod common;
+
+#[cfg(feature = "rand")]
+mod test_rand {
+    use std::collections::HashSet;
+
+    use chronicle::db::{
+        mongodb::collections::{BlockCollection, LedgerUpdateCollection, MilestoneCollection, OutputCollection},
+        MongoDb, MongoDbCollection,
+    };
+
+    use super::common::{setup_database, teardown};
+
+    fn index_names<T: MongoDbCollection>() -> Vec<String> {
+        T::indexes()
+            .into_iter()
+            .map(|index| {
+                index
+                    .options
+                    .and_then(|options| options.name)
+                    .expect("index is not named")
+            })
+            .collect()
+    }
+
+    async fn assert_idempotent<T: MongoDbCollection + Send + Sync>(db: &MongoDb) {
+        let names = index_names::<T>();
+        assert!(!names.is_empty(), "{} has no indexes", T::NAME);
+
+        let first = db.create_indexes::<T>().await.unwrap();
+        assert_eq!(first.created, names, "{}", T::NAME);
+        assert!(first.present.is_empty(), "{}", T::NAME);
+
+        let second = db.create_indexes::<T>().await.unwrap();
+        assert!(second.created.is_empty(), "{}", T::NAME);
+        assert_eq!(second.present, names, "{}", T::NAME);
+
+        let existing = &db.get_index_names().await.unwrap()[T::NAME];
+        let expected = names.into_iter().chain(["_id_".to_owned()]).collect::<HashSet<_>>();
+        assert_eq!(existing, &expected, "{}", T::NAME);
+    }
+
+    #[tokio::test]
+    async fn test_create_indexes_twice() {
+        let db = setup_database("test-create-indexes-twice").await.unwrap();
+
+        assert_idempotent::<OutputCollection>(&db).await;
+        assert_idempotent::<BlockCollection>(&db).await;
+        assert_idempotent::<LedgerUpdateCollection>(&db).await;
+        assert_idempotent::<MilestoneCollection>(&db).await;
+
+        teardown(db).await;
+    }
+
+    #[test]
+    fn test_milestone_lookups_are_indexed() {
+        let keys = OutputCollection::indexes()
+            .into_iter()
+            .flat_map(|index| index.keys.into_iter().map(|(key, _)| key))
+            .collect::<HashSet<_>>();
+        assert!(keys.contains("metadata.booked.milestone_index"));
+        assert!(keys.contains("metadata.spent_metadata.spent.milestone_index"));
+    }
+}
@@ -6,11 +6,23 @@ mod common;
 #[cfg(feature = "rand")]
 mod test_rand {
     use chronicle::{
-        db::mongodb::collections::MilestoneCollection,
-        model::payload::{MilestoneId, MilestonePayload},
+        db::{
+            mongodb::collections::{
+                LedgerUpdateCollection, MilestoneCollection, MilestonePayloadPart, OutputCollection, OutputHealth,
+            },
+            MongoDbCollectionExt,
+        },
+        model::{
+            ledger::LedgerSpent,
+            metadata::SpentMetadata,
+            payload::{MilestoneId, MilestonePayload, TransactionId},
+            tangle::MilestoneIndexTimestamp,
+            utxo::Output,
+            BlockId,
+        },
     };
 
-    use super::common::{setup_collection, setup_database, teardown};
+    use super::common::{ledger_output, setup_collection, setup_database, teardown};
 
     #[tokio::test]
     async fn test_milestones() {
@@ -58,4 +70,216 @@ mod test_rand {
 
         teardown(db).await;
     }
+
+    #[tokio::test]
+    async fn test_partial_milestone_payloads() {
+        let db = setup_database("test-partial-milestone-payloads").await.unwrap();
+        let milestone_collection = setup_collection::<MilestoneCollection>(&db).await.unwrap();
+
+        let milestone = MilestonePayload::rand(&iota_types::block::protocol::protocol_parameters());
+        let milestone_id = MilestoneId::rand();
+
+        milestone_collection
+            .insert_milestone(
+                milestone_id,
+                milestone.essence.index,
+                milestone.essence.timestamp,
+                milestone.clone(),
+            )
+            .await
+            .unwrap();
+
+        let mut expected = milestone.clone();
+        expected.essence.metadata = Vec::new();
+        expected.signatures = Box::new([]);
+
+        let omitted = [MilestonePayloadPart::Metadata, MilestonePayloadPart::Signatures];
+        assert_eq!(
+            milestone_collection
+                .get_partial_milestone_payload_by_id(&milestone_id, &omitted)
+                .await
+                .unwrap()
+                .as_ref(),
+            Some(&expected)
+        );
+
+        assert_eq!(
+            milestone_collection
+                .get_partial_milestone_payload(milestone.essence.index, &omitted)
+                .await
+                .unwrap()
+                .as_ref(),
+            Some(&expected)
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_milestone_block_ids() {
+        let db = setup_database("test-milestone-block-ids").await.unwrap();
+        let milestone_collection = setup_collection::<MilestoneCollection>(&db).await.unwrap();
+
+        let milestone = MilestonePayload::rand(&iota_types::block::protocol::protocol_parameters());
+        let index = milestone.essence.index;
+        milestone_collection
+            .insert_milestone(
+                MilestoneId::rand(),
+                index,
+                milestone.essence.timestamp,
+                milestone.clone(),
+            )
+            .await
+            .unwrap();
+
+        // The block is not known until a later milestone references it.
+        assert_eq!(milestone_collection.get_milestone_block_id(index).await.unwrap(), None);
+
+        let block_id = BlockId::rand();
+        milestone_collection
+            .insert_milestone_block_ids([(index, block_id)])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            milestone_collection.get_milestone_block_id(index).await.unwrap(),
+            Some(block_id)
+        );
+        assert_eq!(
+            milestone_collection
+                .get_milestone_payload(index)
+                .await
+                .unwrap()
+                .as_ref(),
+            Some(&milestone)
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_reinsert_milestone() {
+        let db = setup_database("test-reinsert-milestone").await.unwrap();
+        let milestone_collection = setup_collection::<MilestoneCollection>(&db).await.unwrap();
+
+        let milestone = MilestonePayload::rand(&iota_types::block::protocol::protocol_parameters());
+        let milestone_id = MilestoneId::rand();
+        let index = milestone.essence.index;
+        let block_id = BlockId::rand();
+
+        milestone_collection
+            .insert_milestone(milestone_id, index, milestone.essence.timestamp, milestone.clone())
+            .await
+            .unwrap();
+        milestone_collection
+            .insert_milestone_block_ids([(index, block_id)])
+            .await
+            .unwrap();
+
+        // Re-applying the milestone after an interrupted sync must neither fail nor change the stored milestone.
+        milestone_collection
+            .insert_milestone(milestone_id, index, milestone.essence.timestamp, milestone.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(milestone_collection.count().await.unwrap(), 1);
+        assert_eq!(
+            milestone_collection.get_milestone_id(index).await.unwrap(),
+            Some(milestone_id)
+        );
+        assert_eq!(
+            milestone_collection
+                .get_milestone_payload(index)
+                .await
+                .unwrap()
+                .as_ref(),
+            Some(&milestone)
+        );
+        assert_eq!(
+            milestone_collection.get_milestone_block_id(index).await.unwrap(),
+            Some(block_id)
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_apply_milestone_twice() {
+        let db = setup_database("test-apply-milestone-twice").await.unwrap();
+        let milestone_collection = setup_collection::<MilestoneCollection>(&db).await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+        let ledger_collection = setup_collection::<LedgerUpdateCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+        let milestone = MilestonePayload::rand(&protocol_params);
+        let milestone_id = MilestoneId::rand();
+        let at = MilestoneIndexTimestamp {
+            milestone_index: milestone.essence.index,
+            milestone_timestamp: milestone.essence.timestamp,
+        };
+
+        let created = std::iter::repeat_with(|| Output::rand_basic(&protocol_params))
+            .take(6)
+            .map(|output| ledger_output(output, at.milestone_index.0, at.milestone_timestamp.0))
+            .collect::<Vec<_>>();
+        let consumed = created
+            .iter()
+            .take(2)
+            .cloned()
+            .map(|output| LedgerSpent {
+                output,
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: at,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let (milestone_collection, output_collection, ledger_collection) =
+            (&milestone_collection, &output_collection, &ledger_collection);
+        let (created, consumed, milestone) = (&created, &consumed, &milestone);
+        let apply = || async move {
+            output_collection.insert_unspent_outputs(created).await.unwrap();
+            ledger_collection.insert_unspent_ledger_updates(created).await.unwrap();
+            output_collection.update_spent_outputs(consumed).await.unwrap();
+            ledger_collection.insert_spent_ledger_updates(consumed).await.unwrap();
+            milestone_collection
+                .insert_milestone(
+                    milestone_id,
+                    at.milestone_index,
+                    at.milestone_timestamp,
+                    milestone.clone(),
+                )
+                .await
+                .unwrap();
+        };
+        let expected = OutputHealth {
+            total: 6,
+            spent: 2,
+            spent_before_booked: 0,
+        };
+
+        assert!(!milestone_collection
+            .is_milestone_applied(at.milestone_index)
+            .await
+            .unwrap());
+        apply().await;
+        assert!(milestone_collection
+            .is_milestone_applied(at.milestone_index)
+            .await
+            .unwrap());
+        assert!(!milestone_collection
+            .is_milestone_applied(at.milestone_index + 1)
+            .await
+            .unwrap());
+
+        // A replayed milestone is skipped once its marker exists, but if it is applied again before that, the outputs
+        // must still be neither inserted nor spent twice.
+        apply().await;
+        assert_eq!(output_collection.get_output_health().await.unwrap(), expected);
+        assert_eq!(ledger_collection.count().await.unwrap(), 8);
+        assert_eq!(milestone_collection.count().await.unwrap(), 1);
+
+        teardown(db).await;
+    }
 }
@@ -5,19 +5,31 @@ mod common;
 
 #[cfg(feature = "rand")]
 mod test_rand {
+    use std::collections::HashMap;
+
     use chronicle::{
-        db::mongodb::collections::{OutputCollection, OutputMetadataResult, OutputWithMetadataResult},
+        db::mongodb::collections::{
+            AddressActivityCountResult, AddressActivityKindCount, AddressActivityResult, AliasTransition, AmountBucket,
+            AmountDistribution, BasicOutputsQuery, ClaimedTokensResult, LockedSupplyResult, OutputCollection,
+            OutputHealth, OutputMetadataResult, OutputWithMetadataResult, StorageDepositAnalyticsResult,
+            StorageDepositStat, TotalSupplyResult, TransactionCountResult,
+        },
         model::{
             ledger::{LedgerOutput, LedgerSpent, RentStructureBytes},
             metadata::SpentMetadata,
             payload::TransactionId,
-            tangle::MilestoneIndexTimestamp,
-            utxo::{Output, OutputId},
-            BlockId,
+            tangle::{MilestoneIndexTimestamp, MilestoneTimestamp},
+            utxo::{
+                Address, AliasId, AliasOutput, BasicOutput, FoundryId, FoundryOutput, NftId, NftOutput, Output,
+                OutputId, TokenAmount, TokenScheme,
+            },
+            RentStructure,
         },
     };
+    use futures::TryStreamExt;
+    use primitive_types::U256;
 
-    use super::common::{setup_collection, setup_database, teardown};
+    use super::common::{ledger_output, setup_collection, setup_database, teardown};
 
     #[tokio::test]
     async fn test_outputs() {
@@ -28,19 +40,7 @@ mod test_rand {
 
         let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
             .take(100)
-            .map(|output| LedgerOutput {
-                output_id: OutputId::rand(),
-                rent_structure: RentStructureBytes {
-                    num_key_bytes: 0,
-                    num_data_bytes: 100,
-                },
-                output,
-                block_id: BlockId::rand(),
-                booked: MilestoneIndexTimestamp {
-                    milestone_index: 1.into(),
-                    milestone_timestamp: 12345.into(),
-                },
-            })
+            .map(|output| ledger_output(output, 1, 12345))
             .collect::<Vec<_>>();
 
         output_collection.insert_unspent_outputs(&outputs).await.unwrap();
@@ -169,4 +169,1547 @@ mod test_rand {
 
         teardown(db).await;
     }
+
+    #[tokio::test]
+    async fn test_transaction_booked_milestones() {
+        let db = setup_database("test-transaction-booked-milestones").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let transaction_id = TransactionId::rand();
+        let booked = MilestoneIndexTimestamp {
+            milestone_index: 5.into(),
+            milestone_timestamp: 12345.into(),
+        };
+
+        let outputs = (0..3)
+            .map(|index| LedgerOutput {
+                output_id: OutputId { transaction_id, index },
+                ..ledger_output(Output::rand(&protocol_params), 5, 12345)
+            })
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        assert_eq!(
+            output_collection
+                .get_transaction_booked_milestones(&transaction_id)
+                .await
+                .unwrap(),
+            vec![booked],
+        );
+        assert!(output_collection
+            .get_transaction_booked_milestones(&TransactionId::rand())
+            .await
+            .unwrap()
+            .is_empty());
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_unspent_output_ids_by_kind() {
+        let db = setup_database("test-unspent-output-ids-by-kind").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(100)
+            .map(|output| ledger_output(output, 1, 12345))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        for kind in ["basic", "alias", "foundry", "nft"] {
+            let mut expected = outputs
+                .iter()
+                .filter(|o| o.output.kind() == kind)
+                .map(|o| o.output_id)
+                .collect::<Vec<_>>();
+            expected.sort_by_key(|id| (id.transaction_id.0, id.index));
+
+            let mut found = output_collection
+                .get_unspent_output_ids_by_kind(kind, 1.into(), 1000, None)
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            found.sort_by_key(|id| (id.transaction_id.0, id.index));
+
+            assert_eq!(found, expected);
+        }
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_output_history() {
+        let db = setup_database("test-output-history").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(2)
+            .map(|output| ledger_output(output, 1, 12345))
+            .collect::<Vec<_>>();
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let spent = LedgerSpent {
+            output: outputs[0].clone(),
+            spent_metadata: SpentMetadata {
+                transaction_id: TransactionId::rand(),
+                spent: MilestoneIndexTimestamp {
+                    milestone_index: 2.into(),
+                    milestone_timestamp: 23456.into(),
+                },
+            },
+        };
+        output_collection.update_spent_outputs([&spent]).await.unwrap();
+
+        let history = |output: &LedgerOutput, spent_metadata| OutputMetadataResult {
+            output_id: output.output_id,
+            block_id: output.block_id,
+            booked: output.booked,
+            spent_metadata,
+        };
+
+        assert_eq!(
+            output_collection
+                .get_output_metadata(&outputs[0].output_id, 2.into())
+                .await
+                .unwrap(),
+            Some(history(&outputs[0], Some(spent.spent_metadata)))
+        );
+        // The output was not spent yet at the earlier ledger index.
+        assert_eq!(
+            output_collection
+                .get_output_metadata(&outputs[0].output_id, 1.into())
+                .await
+                .unwrap(),
+            Some(history(&outputs[0], None))
+        );
+        assert_eq!(
+            output_collection
+                .get_output_metadata(&outputs[1].output_id, 2.into())
+                .await
+                .unwrap(),
+            Some(history(&outputs[1], None))
+        );
+
+        assert_eq!(
+            output_collection
+                .get_output_metadata(&OutputId::rand(), 2.into())
+                .await
+                .unwrap(),
+            None
+        );
+        // Outputs booked after the ledger index are unknown as well.
+        assert_eq!(
+            output_collection
+                .get_output_metadata(&outputs[1].output_id, 0.into())
+                .await
+                .unwrap(),
+            None
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_claimed_tokens_by_address() {
+        let db = setup_database("test-claimed-tokens-by-address").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(100)
+            .map(|output| ledger_output(output, 0, 12345))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let outputs = outputs
+            .into_iter()
+            .map(|output| LedgerSpent {
+                output,
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: 1.into(),
+                        milestone_timestamp: 23456.into(),
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+
+        output_collection.update_spent_outputs(&outputs).await.unwrap();
+
+        let address = *outputs
+            .iter()
+            .find_map(|o| o.output.owning_address())
+            .expect("random outputs should have an owning address");
+        let claimed = outputs
+            .iter()
+            .filter(|o| o.output.owning_address() == Some(&address))
+            .collect::<Vec<_>>();
+
+        // The UTXO set shrinks as the outputs are spent.
+        assert_eq!(output_collection.count_unspent_outputs(0.into()).await.unwrap(), 100);
+        assert_eq!(output_collection.count_unspent_outputs(1.into()).await.unwrap(), 0);
+
+        assert_eq!(
+            output_collection
+                .get_claimed_tokens(1.into(), Some(address), None)
+                .await
+                .unwrap(),
+            ClaimedTokensResult {
+                count: claimed.len(),
+                amount: claimed.iter().map(|o| o.output.amount().0).sum::<u64>().to_string(),
+            },
+        );
+
+        // Nothing was claimed before the outputs were spent.
+        assert_eq!(
+            output_collection
+                .get_claimed_tokens(0.into(), Some(address), None)
+                .await
+                .unwrap(),
+            ClaimedTokensResult::default(),
+        );
+
+        assert_eq!(
+            output_collection
+                .get_claimed_tokens(1.into(), Some(Address::rand_ed25519()), None)
+                .await
+                .unwrap(),
+            ClaimedTokensResult::default(),
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_genesis_output_ids() {
+        let db = setup_database("test-genesis-output-ids").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        assert!(output_collection
+            .get_genesis_output_ids(100, None)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .is_empty());
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(100)
+            .enumerate()
+            .map(|(i, output)| ledger_output(output, (i % 2) as u32, 12345))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let mut expected = outputs
+            .iter()
+            .filter(|o| o.booked.milestone_index == 0)
+            .map(|o| o.output_id)
+            .collect::<Vec<_>>();
+        expected.sort_by_key(|id| (id.transaction_id.0, id.index));
+
+        let first_page = output_collection
+            .get_genesis_output_ids(30, None)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let second_page = output_collection
+            .get_genesis_output_ids(100, Some(first_page[29]))
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let mut found = first_page[..29].to_vec();
+        found.extend(second_page);
+        found.sort_by_key(|id| (id.transaction_id.0, id.index));
+
+        assert_eq!(found, expected);
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_storage_deposit_by_kind() {
+        let db = setup_database("test-storage-deposit-by-kind").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(100)
+            .enumerate()
+            .map(|(i, output)| LedgerOutput {
+                rent_structure: RentStructureBytes {
+                    num_key_bytes: i as u64,
+                    num_data_bytes: 10 * i as u64,
+                },
+                ..ledger_output(output, 1, 12345)
+            })
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let stats = output_collection.get_storage_deposit_by_kind(1.into()).await.unwrap();
+
+        for kind in ["alias", "basic", "foundry", "nft"] {
+            let of_kind = outputs.iter().filter(|o| o.output.kind() == kind).collect::<Vec<_>>();
+            let expected = StorageDepositStat {
+                kind: kind.to_string(),
+                count: of_kind.len(),
+                key_bytes: of_kind.iter().map(|o| o.rent_structure.num_key_bytes).sum(),
+                data_bytes: of_kind.iter().map(|o| o.rent_structure.num_data_bytes).sum(),
+            };
+            if of_kind.is_empty() {
+                assert!(stats.iter().all(|s| s.kind != kind));
+            } else {
+                assert_eq!(stats.iter().find(|s| s.kind == kind), Some(&expected));
+            }
+        }
+
+        let stat = StorageDepositStat {
+            key_bytes: 10,
+            data_bytes: 100,
+            ..Default::default()
+        };
+        let rent_structure = RentStructure {
+            v_byte_cost: 100,
+            v_byte_factor_data: 1,
+            v_byte_factor_key: 10,
+        };
+        assert_eq!(stat.storage_deposit(&rent_structure), 20_000);
+
+        // Nothing was unspent before the outputs were booked.
+        assert!(output_collection
+            .get_storage_deposit_by_kind(0.into())
+            .await
+            .unwrap()
+            .is_empty());
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_storage_deposit_analytics() {
+        let db = setup_database("test-storage-deposit-analytics").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(4)
+            .enumerate()
+            .map(|(i, output)| LedgerOutput {
+                rent_structure: RentStructureBytes {
+                    num_key_bytes: 10,
+                    num_data_bytes: 100,
+                },
+                ..ledger_output(output, i as u32 + 1, 12345)
+            })
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let rent_structure = RentStructure {
+            v_byte_cost: 100,
+            v_byte_factor_data: 1,
+            v_byte_factor_key: 10,
+        };
+
+        // Only the outputs booked at or before the ledger index are included.
+        assert_eq!(
+            output_collection
+                .get_storage_deposit_analytics(2.into(), &rent_structure)
+                .await
+                .unwrap(),
+            StorageDepositAnalyticsResult {
+                output_count: 2,
+                key_bytes: 20,
+                data_bytes: 200,
+                storage_deposit: 40_000,
+            }
+        );
+
+        // The deposit follows the rent structure it is given.
+        let doubled_cost = RentStructure {
+            v_byte_cost: 200,
+            ..rent_structure
+        };
+        assert_eq!(
+            output_collection
+                .get_storage_deposit_analytics(4.into(), &doubled_cost)
+                .await
+                .unwrap()
+                .storage_deposit,
+            160_000
+        );
+
+        assert_eq!(
+            output_collection
+                .get_storage_deposit_analytics(0.into(), &rent_structure)
+                .await
+                .unwrap(),
+            StorageDepositAnalyticsResult::default()
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_output_health() {
+        let db = setup_database("test-output-health").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        assert_eq!(
+            output_collection.get_output_health().await.unwrap(),
+            OutputHealth::default()
+        );
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(10)
+            .map(|output| ledger_output(output, 5, 12345))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        // Spend four outputs, one of them at a milestone before it was booked.
+        let spent = outputs
+            .into_iter()
+            .take(4)
+            .enumerate()
+            .map(|(i, output)| LedgerSpent {
+                output,
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: if i == 0 { 2.into() } else { 6.into() },
+                        milestone_timestamp: 23456.into(),
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        assert_eq!(
+            output_collection.get_output_health().await.unwrap(),
+            OutputHealth {
+                total: 10,
+                spent: 4,
+                spent_before_booked: 1,
+            }
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_count_indexed_outputs() {
+        let db = setup_database("test-count-indexed-outputs").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(50)
+            .map(|output| ledger_output(output, 1, 12345))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let expected = outputs.iter().filter(|o| o.output.kind() == "basic").count() as u64;
+
+        let total = output_collection
+            .count_indexed_outputs(BasicOutputsQuery::default(), false, 1.into(), None)
+            .await
+            .unwrap();
+        assert_eq!(total, expected);
+
+        // Outputs booked after the ledger index are not counted.
+        let total = output_collection
+            .count_indexed_outputs(BasicOutputsQuery::default(), false, 0.into(), None)
+            .await
+            .unwrap();
+        assert_eq!(total, 0);
+
+        if expected > 1 {
+            let total = output_collection
+                .count_indexed_outputs(BasicOutputsQuery::default(), false, 1.into(), Some(expected - 1))
+                .await
+                .unwrap();
+            assert_eq!(total, expected - 1);
+        }
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_output_metadata_by_ids() {
+        let db = setup_database("test-output-metadata-by-ids").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(5)
+            .enumerate()
+            .map(|(i, output)| ledger_output(output, i as u32 + 1, 12345))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        // Two known outputs, one that is booked after the ledger index, and one that is unknown.
+        let output_ids = vec![
+            outputs[0].output_id,
+            outputs[2].output_id,
+            outputs[4].output_id,
+            OutputId::rand(),
+        ];
+
+        let mut found = output_collection
+            .get_output_metadata_by_ids(&output_ids, 3.into())
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        found.sort_by_key(|res| res.booked.milestone_index);
+
+        assert_eq!(
+            found,
+            vec![
+                OutputMetadataResult {
+                    output_id: outputs[0].output_id,
+                    block_id: outputs[0].block_id,
+                    booked: outputs[0].booked,
+                    spent_metadata: None,
+                },
+                OutputMetadataResult {
+                    output_id: outputs[2].output_id,
+                    block_id: outputs[2].block_id,
+                    booked: outputs[2].booked,
+                    spent_metadata: None,
+                },
+            ]
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_total_supply() {
+        let db = setup_database("test-total-supply").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(100)
+            .map(|output| ledger_output(output, 1, 12345))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        // Spend some of the outputs, which removes them from the supply.
+        let spent = outputs[..10]
+            .iter()
+            .cloned()
+            .map(|output| LedgerSpent {
+                output,
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: 2.into(),
+                        milestone_timestamp: 23456.into(),
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        let supply = |outputs: &[LedgerOutput]| outputs.iter().map(|o| o.output.amount().0 as u128).sum::<u128>();
+
+        assert_eq!(
+            output_collection.get_total_supply(1.into()).await.unwrap(),
+            TotalSupplyResult {
+                count: 100,
+                amount: supply(&outputs).to_string(),
+            }
+        );
+        assert_eq!(
+            output_collection.get_total_supply(2.into()).await.unwrap(),
+            TotalSupplyResult {
+                count: 90,
+                amount: supply(&outputs[10..]).to_string(),
+            }
+        );
+        assert_eq!(
+            output_collection.get_total_supply(0.into()).await.unwrap(),
+            TotalSupplyResult::default()
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_locked_supply() {
+        let db = setup_database("test-locked-supply").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(100)
+            .map(|output| ledger_output(output, 1, 12345))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        // Outputs that are booked before and spent after a milestone are part of its supply.
+        let spent = outputs[..10]
+            .iter()
+            .cloned()
+            .map(|output| LedgerSpent {
+                output,
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: 3.into(),
+                        milestone_timestamp: 34567.into(),
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        let timelock = |output: &LedgerOutput| {
+            mongodb::bson::to_document(&output.output)
+                .unwrap()
+                .get_document("timelock_unlock_condition")
+                .ok()
+                .map(|condition| {
+                    mongodb::bson::from_bson::<MilestoneTimestamp>(condition.get("timestamp").unwrap().clone()).unwrap()
+                })
+        };
+        // Split the random timelocks in the middle, so that both sums are likely to be non-zero.
+        let mut timelocks = outputs.iter().filter_map(timelock).collect::<Vec<_>>();
+        timelocks.sort();
+        let ledger_timestamp = timelocks.get(timelocks.len() / 2).copied().unwrap_or(12345.into());
+
+        let locked_supply = |outputs: &[LedgerOutput]| {
+            let (locked, unlocked) = outputs
+                .iter()
+                .partition::<Vec<_>, _>(|o| timelock(o).map_or(false, |t| t > ledger_timestamp));
+            let sum = |outputs: Vec<&LedgerOutput>| outputs.iter().map(|o| o.output.amount().0 as u128).sum::<u128>();
+            LockedSupplyResult {
+                locked_amount: sum(locked).to_string(),
+                unlocked_amount: sum(unlocked).to_string(),
+            }
+        };
+
+        for index in [1, 2] {
+            assert_eq!(
+                output_collection
+                    .get_locked_supply(index.into(), ledger_timestamp)
+                    .await
+                    .unwrap(),
+                locked_supply(&outputs),
+            );
+        }
+        assert_eq!(
+            output_collection
+                .get_locked_supply(3.into(), ledger_timestamp)
+                .await
+                .unwrap(),
+            locked_supply(&outputs[10..]),
+        );
+        assert_eq!(
+            output_collection
+                .get_locked_supply(0.into(), ledger_timestamp)
+                .await
+                .unwrap(),
+            LockedSupplyResult::default(),
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_outputs_in_milestone_range() {
+        let db = setup_database("test-outputs-in-milestone-range").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = (0..50)
+            .map(|i| ledger_output(Output::rand(&protocol_params), i % 5, 12345 + i % 5))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let mut expected = outputs
+            .iter()
+            .filter(|o| (1..=3).contains(&o.booked.milestone_index.0))
+            .cloned()
+            .collect::<Vec<_>>();
+        expected.sort_by_key(|o| {
+            (
+                o.booked.milestone_index,
+                o.output_id.transaction_id.0,
+                o.output_id.index,
+            )
+        });
+
+        let streamed = output_collection
+            .get_outputs_in_milestone_range(1.into(), 3.into())
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(streamed, expected);
+
+        let streamed = output_collection
+            .get_outputs_in_milestone_range(5.into(), 10.into())
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert!(streamed.is_empty());
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_revert_spent_outputs() {
+        let db = setup_database("test-revert-spent-outputs").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(30)
+            .map(|output| ledger_output(output, 1, 12345))
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        // The first ten outputs are spent at milestone 2, the next ten at milestone 3.
+        let spent = outputs[..20]
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, output)| LedgerSpent {
+                output,
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: (2 + i as u32 / 10).into(),
+                        milestone_timestamp: (23456 + i as u32 / 10).into(),
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        assert_eq!(output_collection.revert_spent_outputs(3.into()).await.unwrap(), 10);
+        // Reverting again does not change anything.
+        assert_eq!(output_collection.revert_spent_outputs(3.into()).await.unwrap(), 0);
+
+        for (i, output) in outputs.iter().enumerate() {
+            let metadata = output_collection
+                .get_output_metadata(&output.output_id, 3.into())
+                .await
+                .unwrap()
+                .unwrap();
+            if i < 10 {
+                assert_eq!(metadata.spent_metadata, Some(spent[i].spent_metadata));
+            } else {
+                assert_eq!(metadata.spent_metadata, None);
+            }
+        }
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_revert_milestones() {
+        let db = setup_database("test-revert-milestones").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        // Ten outputs are created at each of the milestones 1 to 3.
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(30)
+            .enumerate()
+            .map(|(i, output)| ledger_output(output, 1 + i as u32 / 10, 12345 + i as u32 / 10))
+            .collect::<Vec<_>>();
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        // Of the outputs created at milestone 2, five are spent at milestone 2 and five at milestone 4. Five outputs
+        // of milestone 1 are spent at milestone 2.
+        let spent_at = |i: usize| match i {
+            0..=4 | 10..=14 => Some(2),
+            15..=19 => Some(4),
+            _ => None,
+        };
+        let spent = outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, output)| {
+                spent_at(i).map(|index: u32| LedgerSpent {
+                    output: output.clone(),
+                    spent_metadata: SpentMetadata {
+                        transaction_id: TransactionId::rand(),
+                        spent: MilestoneIndexTimestamp {
+                            milestone_index: index.into(),
+                            milestone_timestamp: (23456 + index).into(),
+                        },
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        // Ten spends are reverted, and the five outputs of milestone 2 that remain unspent afterwards are removed.
+        assert_eq!(
+            output_collection.revert_milestones(2.into()..=2.into()).await.unwrap(),
+            15
+        );
+
+        for (i, output) in outputs.iter().enumerate() {
+            let metadata = output_collection
+                .get_output_metadata(&output.output_id, 4.into())
+                .await
+                .unwrap();
+            match i {
+                // Outputs of milestone 1 are kept, and are unspent again.
+                0..=9 => assert_eq!(metadata.unwrap().spent_metadata, None),
+                // Outputs of milestone 2 are removed, unless a later milestone spent them.
+                10..=14 => assert!(metadata.is_none()),
+                15..=19 => assert_eq!(
+                    metadata.unwrap().spent_metadata.unwrap().spent.milestone_index,
+                    4.into()
+                ),
+                _ => assert_eq!(metadata.unwrap().spent_metadata, None),
+            }
+        }
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_nft_history() {
+        let db = setup_database("test-nft-history").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let owners = std::iter::repeat_with(Address::rand_ed25519)
+            .take(3)
+            .collect::<Vec<_>>();
+        let transfers = std::iter::repeat_with(TransactionId::rand).take(2).collect::<Vec<_>>();
+        let minted = OutputId::rand();
+        let nft_id = NftId::from(minted);
+        let nft_output = |output_id, address, nft_id| {
+            let mut nft = NftOutput::rand(&protocol_params);
+            nft.nft_id = nft_id;
+            nft.address_unlock_condition.address = address;
+            let (milestone_index, milestone_timestamp) = if output_id == minted { (1, 12345) } else { (2, 23456) };
+            LedgerOutput {
+                output_id,
+                ..ledger_output(Output::Nft(nft), milestone_index, milestone_timestamp)
+            }
+        };
+
+        // The NFT is minted at milestone 1, and is transferred twice at milestone 2. The last transfer is inserted
+        // first, so that the order of the documents does not match the order of the transfers.
+        let outputs = vec![
+            nft_output(minted, owners[0], NftId::implicit()),
+            nft_output(
+                OutputId {
+                    transaction_id: transfers[1],
+                    index: 0,
+                },
+                owners[2],
+                nft_id,
+            ),
+            nft_output(
+                OutputId {
+                    transaction_id: transfers[0],
+                    index: 1,
+                },
+                owners[1],
+                nft_id,
+            ),
+            nft_output(OutputId::rand(), owners[0], NftId::from(OutputId::rand())),
+        ];
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let spent = [(0, transfers[0]), (2, transfers[1])]
+            .into_iter()
+            .map(|(i, transaction_id)| LedgerSpent {
+                output: outputs[i].clone(),
+                spent_metadata: SpentMetadata {
+                    transaction_id,
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: 2.into(),
+                        milestone_timestamp: 23456.into(),
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        let history = output_collection.get_nft_history(nft_id, 2.into()).await.unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|record| (record.output_id, record.address))
+                .collect::<Vec<_>>(),
+            vec![
+                (outputs[0].output_id, owners[0]),
+                (outputs[2].output_id, owners[1]),
+                (outputs[1].output_id, owners[2]),
+            ]
+        );
+        assert_eq!(history[0].spent_metadata, Some(spent[0].spent_metadata));
+        assert_eq!(history[1].spent_metadata, Some(spent[1].spent_metadata));
+        assert_eq!(history[2].spent_metadata, None);
+
+        // Before the transfers, the NFT only had the owner it was minted for.
+        let history = output_collection.get_nft_history(nft_id, 1.into()).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].address, owners[0]);
+        assert_eq!(history[0].spent_metadata, None);
+
+        assert!(output_collection
+            .get_nft_history(NftId::from(OutputId::rand()), 2.into())
+            .await
+            .unwrap()
+            .is_empty());
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_alias_history() {
+        let db = setup_database("test-alias-history").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let controllers = std::iter::repeat_with(Address::rand_ed25519)
+            .take(2)
+            .collect::<Vec<_>>();
+        let governors = std::iter::repeat_with(Address::rand_ed25519)
+            .take(2)
+            .collect::<Vec<_>>();
+        let transitions = std::iter::repeat_with(TransactionId::rand).take(2).collect::<Vec<_>>();
+        let created = OutputId::rand();
+        let alias_id = AliasId::from(created);
+        let alias_output = |output_id, alias_id, state_index, owners: usize| {
+            let mut alias = AliasOutput::rand(&protocol_params);
+            alias.alias_id = alias_id;
+            alias.state_index = state_index;
+            alias.state_controller_address_unlock_condition.address = controllers[owners];
+            alias.governor_address_unlock_condition.address = governors[owners];
+            let (milestone_index, milestone_timestamp) = if output_id == created { (1, 12345) } else { (2, 23456) };
+            LedgerOutput {
+                output_id,
+                ..ledger_output(Output::Alias(alias), milestone_index, milestone_timestamp)
+            }
+        };
+
+        // The alias is created at milestone 1. At milestone 2, the state controller increments the state index and the
+        // governor then replaces both addresses. The governance transition is inserted first, so that the order of the
+        // documents does not match the order of the transitions.
+        let outputs = vec![
+            alias_output(created, AliasId::implicit(), 0, 0),
+            alias_output(
+                OutputId {
+                    transaction_id: transitions[1],
+                    index: 0,
+                },
+                alias_id,
+                1,
+                1,
+            ),
+            alias_output(
+                OutputId {
+                    transaction_id: transitions[0],
+                    index: 1,
+                },
+                alias_id,
+                1,
+                0,
+            ),
+            alias_output(OutputId::rand(), AliasId::from(OutputId::rand()), 0, 0),
+        ];
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let spent = [(0, transitions[0]), (2, transitions[1])]
+            .into_iter()
+            .map(|(i, transaction_id)| LedgerSpent {
+                output: outputs[i].clone(),
+                spent_metadata: SpentMetadata {
+                    transaction_id,
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: 2.into(),
+                        milestone_timestamp: 23456.into(),
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        let history = output_collection.get_alias_history(alias_id, 2.into()).await.unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|record| (
+                    record.output_id,
+                    record.state_index,
+                    record.state_controller,
+                    record.governor
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (outputs[0].output_id, 0, controllers[0], governors[0]),
+                (outputs[2].output_id, 1, controllers[0], governors[0]),
+                (outputs[1].output_id, 1, controllers[1], governors[1]),
+            ]
+        );
+        assert_eq!(
+            history
+                .iter()
+                .enumerate()
+                .map(|(i, record)| record.transition(i.checked_sub(1).map(|previous| &history[previous])))
+                .collect::<Vec<_>>(),
+            vec![
+                AliasTransition::Creation,
+                AliasTransition::State,
+                AliasTransition::Governance
+            ]
+        );
+        assert_eq!(history[0].spent_metadata, Some(spent[0].spent_metadata));
+        assert_eq!(history[1].spent_metadata, Some(spent[1].spent_metadata));
+        assert_eq!(history[2].spent_metadata, None);
+
+        // Before the transitions, the alias only had the output that created it.
+        let history = output_collection.get_alias_history(alias_id, 1.into()).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].state_index, 0);
+        assert_eq!(history[0].spent_metadata, None);
+
+        assert!(output_collection
+            .get_alias_history(AliasId::from(OutputId::rand()), 2.into())
+            .await
+            .unwrap()
+            .is_empty());
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_foundry_supply() {
+        let db = setup_database("test-foundry-supply").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let foundry_id = FoundryId::rand();
+        let foundry_output = |milestone_index: u32, minted: u64, melted: u64| {
+            let mut foundry = FoundryOutput::rand(&protocol_params);
+            foundry.foundry_id = foundry_id;
+            foundry.token_scheme = TokenScheme::Simple {
+                minted_tokens: U256::from(minted).into(),
+                melted_tokens: U256::from(melted).into(),
+                maximum_supply: U256::from(10_000).into(),
+            };
+            ledger_output(Output::Foundry(foundry), milestone_index, 12345 + milestone_index)
+        };
+
+        // The foundry mints 500 tokens at milestone 1, and then mints another 500 and melts 250 at milestone 2.
+        let outputs = vec![foundry_output(1, 500, 0), foundry_output(2, 1000, 250)];
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+        output_collection
+            .update_spent_outputs(&[LedgerSpent {
+                output: outputs[0].clone(),
+                spent_metadata: SpentMetadata {
+                    transaction_id: outputs[1].output_id.transaction_id,
+                    spent: outputs[1].booked,
+                },
+            }])
+            .await
+            .unwrap();
+
+        let supply = output_collection
+            .get_foundry_supply(foundry_id, 1.into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(supply.output_id, outputs[0].output_id);
+        assert_eq!(supply.circulating_supply(), U256::from(500));
+
+        let supply = output_collection
+            .get_foundry_supply(foundry_id, 2.into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(supply.output_id, outputs[1].output_id);
+        assert_eq!(supply.circulating_supply(), U256::from(750));
+
+        assert_eq!(
+            output_collection
+                .get_foundry_supply(FoundryId::rand(), 2.into())
+                .await
+                .unwrap(),
+            None
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_transaction_counts() {
+        let db = setup_database("test-transaction-counts").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        // Milestone `n` books `n` transactions with two outputs each.
+        let outputs = (1..=5u32)
+            .flat_map(|milestone_index| (0..milestone_index).map(move |_| (milestone_index, TransactionId::rand())))
+            .flat_map(|(milestone_index, transaction_id)| {
+                (0..2).map(move |index| (milestone_index, OutputId { transaction_id, index }))
+            })
+            .map(|(milestone_index, output_id)| LedgerOutput {
+                output_id,
+                ..ledger_output(Output::rand(&protocol_params), milestone_index, 12345 + milestone_index)
+            })
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        assert_eq!(
+            output_collection
+                .get_transaction_counts(1.into(), 5.into(), 1)
+                .await
+                .unwrap(),
+            (1..=5)
+                .map(|index| TransactionCountResult {
+                    start_index: index.into(),
+                    count: index as usize,
+                })
+                .collect::<Vec<_>>()
+        );
+
+        // Intervals begin at the start index, and each transaction is counted once in the interval of the milestone
+        // that booked it.
+        assert_eq!(
+            output_collection
+                .get_transaction_counts(2.into(), 5.into(), 2)
+                .await
+                .unwrap(),
+            vec![
+                TransactionCountResult {
+                    start_index: 2.into(),
+                    count: 5,
+                },
+                TransactionCountResult {
+                    start_index: 4.into(),
+                    count: 9,
+                },
+            ]
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_reapply_ledger_updates() {
+        let db = setup_database("test-reapply-ledger-updates").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(10)
+            .map(|output| ledger_output(output, 5, 12345))
+            .collect::<Vec<_>>();
+        let spent = outputs
+            .iter()
+            .take(4)
+            .cloned()
+            .map(|output| LedgerSpent {
+                output,
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: 6.into(),
+                        milestone_timestamp: 23456.into(),
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        let expected = OutputHealth {
+            total: 10,
+            spent: 4,
+            spent_before_booked: 0,
+        };
+        assert_eq!(output_collection.get_output_health().await.unwrap(), expected);
+
+        // Re-applying both milestones, as a resumed sync does, must converge to the same state. In particular, the
+        // outputs that were spent must not become unspent again.
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+        assert_eq!(output_collection.get_output_health().await.unwrap(), expected);
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+        assert_eq!(output_collection.get_output_health().await.unwrap(), expected);
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_address_activity_pages() {
+        let db = setup_database("test-address-activity-pages").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let outputs = std::iter::repeat_with(|| Output::rand(&protocol_params))
+            .take(20)
+            .map(|output| ledger_output(output, 1, 12345))
+            .collect::<Vec<_>>();
+        let spent = outputs
+            .iter()
+            .take(5)
+            .cloned()
+            .map(|output| LedgerSpent {
+                output,
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: 2.into(),
+                        milestone_timestamp: 23456.into(),
+                    },
+                },
+            })
+            .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        let activity = |page_size, cursor| {
+            let output_collection = &output_collection;
+            async move {
+                output_collection
+                    .get_address_activity(MilestoneTimestamp(12345), MilestoneTimestamp(23457), page_size, cursor)
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<AddressActivityResult>>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let all = activity(1000, None).await;
+        let owned = |outputs: &[LedgerOutput]| outputs.iter().filter(|o| o.output.owning_address().is_some()).count();
+        assert_eq!(all.iter().map(|a| a.created_count).sum::<usize>(), owned(&outputs));
+        assert_eq!(
+            all.iter().map(|a| a.consumed_count).sum::<usize>(),
+            owned(&outputs[..5])
+        );
+
+        // A page starts at the address of its cursor and follows the same order.
+        assert!(all.len() > 4);
+        assert_eq!(activity(3, Some(all[1].address)).await, all[1..4]);
+
+        // Outputs outside of the time range are not taken into account.
+        assert!(output_collection
+            .get_address_activity(MilestoneTimestamp(0), MilestoneTimestamp(12345), 1000, None)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .is_empty());
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_address_activity_count_by_kind() {
+        let db = setup_database("test-address-activity-count-by-kind").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let address = Address::rand_ed25519();
+        let basic = |address| {
+            let mut output = BasicOutput::rand(&protocol_params);
+            output.address_unlock_condition.address = address;
+            Output::Basic(output)
+        };
+        let mut nft = NftOutput::rand(&protocol_params);
+        nft.address_unlock_condition.address = address;
+
+        // The address owns two basic outputs and an NFT, next to two other addresses.
+        let outputs = [
+            basic(address),
+            basic(address),
+            Output::Nft(nft),
+            basic(Address::rand_ed25519()),
+            Output::Alias(AliasOutput::rand(&protocol_params)),
+        ]
+        .into_iter()
+        .map(|output| ledger_output(output, 1, 12345))
+        .collect::<Vec<_>>();
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        let kind_count = |kind: &str, count| AddressActivityKindCount {
+            kind: kind.to_string(),
+            count,
+        };
+        assert_eq!(
+            output_collection
+                .get_address_activity_count_by_kind(MilestoneTimestamp(12345), MilestoneTimestamp(12346))
+                .await
+                .unwrap(),
+            AddressActivityCountResult {
+                count: 3,
+                by_kind: vec![
+                    kind_count(AliasOutput::KIND, 1),
+                    kind_count(BasicOutput::KIND, 2),
+                    kind_count(NftOutput::KIND, 1),
+                ],
+            }
+        );
+
+        assert_eq!(
+            output_collection
+                .get_address_activity_count_by_kind(MilestoneTimestamp(0), MilestoneTimestamp(12345))
+                .await
+                .unwrap(),
+            AddressActivityCountResult::default()
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_outputs_for_address() {
+        let db = setup_database("test-outputs-for-address").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        let address = Address::rand_ed25519();
+        let basic = |address| {
+            let mut output = BasicOutput::rand(&protocol_params);
+            output.address_unlock_condition.address = address;
+            Output::Basic(output)
+        };
+
+        // The address owns four outputs, next to one of another address.
+        let outputs = [
+            basic(address),
+            basic(address),
+            basic(address),
+            basic(address),
+            basic(Address::rand_ed25519()),
+        ]
+        .into_iter()
+        .map(|output| ledger_output(output, 1, 12345))
+        .collect::<Vec<_>>();
+
+        // Two outputs are spent as of milestone 3, and one only afterwards.
+        let spend = |output: &LedgerOutput, milestone_index: u32| LedgerSpent {
+            output: output.clone(),
+            spent_metadata: SpentMetadata {
+                transaction_id: TransactionId::rand(),
+                spent: MilestoneIndexTimestamp {
+                    milestone_index: milestone_index.into(),
+                    milestone_timestamp: (12345 + milestone_index).into(),
+                },
+            },
+        };
+        let spent = vec![spend(&outputs[0], 2), spend(&outputs[2], 3), spend(&outputs[3], 5)];
+
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+        output_collection.update_spent_outputs(&spent).await.unwrap();
+
+        let outputs_for_address = |include_spent, page_size, cursor| {
+            let output_collection = &output_collection;
+            async move {
+                output_collection
+                    .get_outputs_for_address(&address, include_spent, 3.into(), page_size, cursor)
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<OutputWithMetadataResult>>()
+                    .await
+                    .unwrap()
+            }
+        };
+        let ids = |results: &[OutputWithMetadataResult]| {
+            results
+                .iter()
+                .map(|result| result.metadata.output_id)
+                .collect::<Vec<_>>()
+        };
+
+        let spent_metadata = |results: &[OutputWithMetadataResult]| {
+            results
+                .iter()
+                .map(|result| (result.metadata.output_id, result.metadata.spent_metadata))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let all = outputs_for_address(true, 100, None).await;
+        assert_eq!(
+            spent_metadata(&all),
+            HashMap::from([
+                (outputs[0].output_id, Some(spent[0].spent_metadata)),
+                (outputs[1].output_id, None),
+                (outputs[2].output_id, Some(spent[1].spent_metadata)),
+                // The output was still unspent at the ledger index.
+                (outputs[3].output_id, None),
+            ])
+        );
+        assert!(all
+            .iter()
+            .all(|result| outputs.iter().any(|output| output.output == result.output)));
+
+        let unspent = outputs_for_address(false, 100, None).await;
+        assert_eq!(
+            spent_metadata(&unspent),
+            HashMap::from([(outputs[1].output_id, None), (outputs[3].output_id, None)])
+        );
+        // Filtering does not change the order.
+        assert_eq!(
+            ids(&unspent),
+            ids(&all)
+                .into_iter()
+                .filter(|id| *id == outputs[1].output_id || *id == outputs[3].output_id)
+                .collect::<Vec<_>>()
+        );
+
+        // A page starts at the output id of its cursor.
+        assert_eq!(ids(&outputs_for_address(true, 2, None).await), ids(&all[..2]));
+        assert_eq!(
+            ids(&outputs_for_address(true, 2, Some(all[2].metadata.output_id)).await),
+            ids(&all[2..])
+        );
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_amount_distribution() {
+        let db = setup_database("test-amount-distribution").await.unwrap();
+        let output_collection = setup_collection::<OutputCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+
+        // The amounts at the boundaries are counted in the bucket that starts there.
+        let outputs = [5, 10, 11, 99, 100, 1000, 5000]
+            .into_iter()
+            .map(|amount| {
+                let mut output = BasicOutput::rand(&protocol_params);
+                output.amount = TokenAmount(amount);
+                ledger_output(Output::Basic(output), 1, 12345)
+            })
+            .collect::<Vec<_>>();
+        output_collection.insert_unspent_outputs(&outputs).await.unwrap();
+
+        // Spending the output with amount 99 removes it from the distribution.
+        output_collection
+            .update_spent_outputs(&[LedgerSpent {
+                output: outputs[3].clone(),
+                spent_metadata: SpentMetadata {
+                    transaction_id: TransactionId::rand(),
+                    spent: MilestoneIndexTimestamp {
+                        milestone_index: 2.into(),
+                        milestone_timestamp: 23456.into(),
+                    },
+                },
+            }])
+            .await
+            .unwrap();
+
+        let bucket = |start, end, output_count, total_amount: u64| AmountBucket {
+            start,
+            end,
+            output_count,
+            total_amount: total_amount.to_string(),
+        };
+
+        assert_eq!(
+            output_collection
+                .get_amount_distribution(1.into(), &[10, 100, 1000])
+                .await
+                .unwrap(),
+            AmountDistribution {
+                buckets: vec![
+                    bucket(0, Some(10), 1, 5),
+                    bucket(10, Some(100), 3, 120),
+                    bucket(100, Some(1000), 1, 100),
+                    bucket(1000, None, 2, 6000),
+                ]
+            }
+        );
+        assert_eq!(
+            output_collection
+                .get_amount_distribution(2.into(), &[10, 20, 100])
+                .await
+                .unwrap(),
+            AmountDistribution {
+                buckets: vec![
+                    bucket(0, Some(10), 1, 5),
+                    bucket(10, Some(20), 2, 21),
+                    bucket(20, Some(100), 0, 0),
+                    bucket(100, None, 3, 6100),
+                ]
+            }
+        );
+        // Without boundaries, all outputs are in a single bucket.
+        assert_eq!(
+            output_collection.get_amount_distribution(2.into(), &[]).await.unwrap(),
+            AmountDistribution {
+                buckets: vec![bucket(0, None, 6, 6126)]
+            }
+        );
+
+        teardown(db).await;
+    }
 }
@@ -0,0 +1,68 @@
+// Copyright 2023 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+mod common;
+
+#[cfg(feature = "rand")]
+mod test_rand {
+    use chronicle::{
+        db::{
+            mongodb::collections::{MilestoneCollection, TreasuryCollection},
+            CollectionStats,
+        },
+        model::payload::{MilestoneId, MilestonePayload},
+    };
+
+    use super::common::{setup_collection, setup_database, teardown};
+
+    #[tokio::test]
+    async fn test_database_is_dropped_on_panic() {
+        let res = tokio::spawn(async {
+            let db = setup_database("test-dropped-on-panic").await.unwrap();
+            // Creating the indexes makes sure the database actually exists.
+            setup_collection::<MilestoneCollection>(&db).await.unwrap();
+            panic!("deliberate test failure");
+        })
+        .await;
+        assert!(res.unwrap_err().is_panic());
+
+        let db = setup_database("test-dropped-on-panic-check").await.unwrap();
+        let databases = db.get_databases().await.unwrap();
+        assert!(!databases.iter().any(|name| name == "test-dropped-on-panic"));
+
+        teardown(db).await;
+    }
+
+    #[tokio::test]
+    async fn test_collection_stats() {
+        let db = setup_database("test-collection-stats").await.unwrap();
+        let milestone_collection = setup_collection::<MilestoneCollection>(&db).await.unwrap();
+
+        let protocol_params = iota_types::block::protocol::protocol_parameters();
+        for index in 1..=3u32 {
+            let milestone = MilestonePayload::rand(&protocol_params);
+            milestone_collection
+                .insert_milestone(
+                    MilestoneId::rand(),
+                    index.into(),
+                    milestone.essence.timestamp,
+                    milestone,
+                )
+                .await
+                .unwrap();
+        }
+
+        let stats = db.collection_stats::<MilestoneCollection>().await.unwrap();
+        assert_eq!(stats.count, 3);
+        assert!(stats.size > 0);
+        assert!(stats.index_size > 0);
+
+        // The treasury collection was never created.
+        assert_eq!(
+            db.collection_stats::<TreasuryCollection>().await.unwrap(),
+            CollectionStats::default()
+        );
+
+        teardown(db).await;
+    }
+}